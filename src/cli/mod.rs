@@ -0,0 +1,129 @@
+pub mod output;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use crate::cli::output::{render, OutputFormat, Render, Table};
+use crate::errors::codes::ErrorCode;
+use crate::errors::GfcError;
+use crate::models::compose_file::ComposeFile;
+use crate::models::project::{ManifestFormat, ProjectFile};
+use crate::usecases::validation::{
+    resolve_compose_file, validate_compose_file, validate_create_project_params,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "gfc", version, about = "GitOps for docker compose projects")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (the default when no subcommand is given)
+    Serve,
+    /// Validate a project file, and optionally its compose file, without a running server
+    Validate(ValidateArgs),
+    /// Watch and operate projects of a running server from the terminal
+    #[cfg(feature = "tui")]
+    Tui(tui::TuiArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// Path to the project file (YAML, JSON, or TOML)
+    #[arg(short = 'f', long = "file")]
+    pub file: PathBuf,
+    /// Repository checkout the project's `source.path` is resolved against
+    #[arg(long)]
+    pub compose: Option<PathBuf>,
+    /// Output format
+    #[arg(short = 'o', long, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    pub project_file: String,
+    pub compose_file: Option<String>,
+    pub valid: bool,
+    pub code: Option<ErrorCode>,
+    pub error: Option<String>,
+}
+
+impl Render for ValidationReport {
+    fn to_table(&self) -> Table {
+        let status = match &self.error {
+            Some(error) => format!("invalid ({})", error),
+            None => "valid".to_string(),
+        };
+
+        Table::new(&["file", "status"])
+            .row(vec![self.project_file.clone(), status.clone()])
+            .row(vec![
+                self.compose_file.clone().unwrap_or_else(|| "-".to_string()),
+                match self.compose_file {
+                    Some(_) => status,
+                    None => "skipped".to_string(),
+                },
+            ])
+    }
+}
+
+/// Run `gfc validate`. The process exit code is the [`ErrorCode`] of the first failure.
+pub fn run_validate(args: &ValidateArgs) -> ExitCode {
+    let report = match validate(args) {
+        Ok(compose_file) => ValidationReport {
+            project_file: args.file.display().to_string(),
+            compose_file: compose_file.map(|path| path.display().to_string()),
+            valid: true,
+            code: None,
+            error: None,
+        },
+        Err(e) => ValidationReport {
+            project_file: args.file.display().to_string(),
+            compose_file: None,
+            valid: false,
+            code: Some(e.code()),
+            error: Some(e.to_string()),
+        },
+    };
+
+    print_output(&report, args.output);
+    report
+        .code
+        .map_or(ExitCode::SUCCESS, |code| ExitCode::from(code.exit_code()))
+}
+
+fn validate(args: &ValidateArgs) -> Result<Option<PathBuf>, GfcError> {
+    let project_file = load_project_file(&args.file)?;
+    validate_create_project_params(&project_file)?;
+
+    let Some(repository_dir) = &args.compose else {
+        return Ok(None);
+    };
+    let compose_path = resolve_compose_file(repository_dir, &project_file.source.path)?;
+    validate_compose_file(&ComposeFile::from_path(&compose_path)?)?;
+
+    Ok(Some(compose_path))
+}
+
+/// Files without a recognised extension are read as YAML.
+fn load_project_file(path: &Path) -> Result<ProjectFile, GfcError> {
+    let format = ManifestFormat::from_path(path).unwrap_or(ManifestFormat::Yaml);
+    let content = fs::read_to_string(path)?;
+    Ok(format.parse(&content)?)
+}
+
+fn print_output<T: Render>(value: &T, format: OutputFormat) {
+    match render(value, format) {
+        Ok(output) => print!("{}", output),
+        Err(e) => eprintln!("Failed to render output: {}", e),
+    }
+}
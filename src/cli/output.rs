@@ -0,0 +1,109 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+
+use crate::errors::GfcError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    #[default]
+    Table,
+}
+
+/// Output that can be printed as JSON, YAML, or a plain aligned table.
+pub trait Render: Serialize {
+    fn to_table(&self) -> Table;
+}
+
+#[derive(Debug, Default)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|header| header.to_uppercase()).collect(),
+            rows: vec![],
+        }
+    }
+
+    pub fn row(mut self, cells: Vec<String>) -> Self {
+        self.rows.push(cells);
+        self
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .map(String::len)
+                    .fold(header.len(), usize::max)
+            })
+            .collect::<Vec<_>>();
+
+        for line in std::iter::once(&self.headers).chain(self.rows.iter()) {
+            let cells = line
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", cells.join("  ").trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn render<T: Render>(value: &T, format: OutputFormat) -> Result<String, GfcError> {
+    match format {
+        OutputFormat::Json => Ok(format!("{}\n", serde_json::to_string_pretty(value)?)),
+        OutputFormat::Yaml => Ok(serde_yaml::to_string(value)?),
+        OutputFormat::Table => Ok(value.to_table().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Service {
+        name: String,
+        replicas: u32,
+    }
+
+    impl Render for Service {
+        fn to_table(&self) -> Table {
+            Table::new(&["name", "replicas"])
+                .row(vec![self.name.clone(), self.replicas.to_string()])
+                .row(vec!["a".to_string(), String::new()])
+        }
+    }
+
+    #[test]
+    fn given_each_format_when_rendered_then_print_it_in_that_format() {
+        let service = Service {
+            name: "web".to_string(),
+            replicas: 2,
+        };
+
+        let json = render(&service, OutputFormat::Json).unwrap();
+        let yaml = render(&service, OutputFormat::Yaml).unwrap();
+        let table = render(&service, OutputFormat::Table).unwrap();
+
+        assert_eq!(json, "{\n  \"name\": \"web\",\n  \"replicas\": 2\n}\n");
+        assert_eq!(yaml, "name: web\nreplicas: 2\n");
+        assert_eq!(table, "NAME  REPLICAS\nweb   2\na\n");
+    }
+}
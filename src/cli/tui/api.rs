@@ -0,0 +1,187 @@
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader, Read};
+use std::time::Duration;
+use ureq::{Agent, RequestBuilder};
+
+use crate::models::docker_compose::ProjectEvent;
+use crate::models::project::Project;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The bits of the HTTP API the TUI uses.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    base_url: String,
+    agent: Agent,
+    token: Option<String>,
+}
+
+/// Responses are wrapped in `{"results": [...]}`, or `{"error": "..."}` on failure, which
+/// is not always sent with an error status.
+#[derive(Debug, Default, Deserialize)]
+struct Envelope<T> {
+    results: Option<Vec<T>>,
+    error: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str) -> Self {
+        let agent = Agent::config_builder()
+            .timeout_global(Some(REQUEST_TIMEOUT))
+            .http_status_as_error(false)
+            .build()
+            .into();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent,
+            token: None,
+        }
+    }
+
+    /// Send `token` as a bearer token with every request.
+    pub fn with_token(self, token: Option<String>) -> Self {
+        Self { token, ..self }
+    }
+
+    pub fn projects(&self) -> Result<Vec<Project>> {
+        let mut response = self
+            .authorized(self.agent.get(self.url("/projects?humanize=true")))
+            .call()?;
+        let envelope: Envelope<Project> = response.body_mut().read_json()?;
+        match envelope {
+            Envelope {
+                error: Some(error), ..
+            } => Err(anyhow!(error)),
+            Envelope { results, .. } => Ok(results.unwrap_or_default()),
+        }
+    }
+
+    pub fn sync(&self, name: &str) -> Result<()> {
+        self.post(&format!("/projects/{}/sync", name))
+    }
+
+    pub fn pause(&self, name: &str) -> Result<()> {
+        self.post(&format!("/projects/{}/pause", name))
+    }
+
+    pub fn unpause(&self, name: &str) -> Result<()> {
+        self.post(&format!("/projects/{}/unpause", name))
+    }
+
+    /// Follow `GET /projects/{name}/events`. The iterator ends when the server closes
+    /// the stream.
+    pub fn events(&self, name: &str) -> Result<impl Iterator<Item = Result<ProjectEvent>>> {
+        let response = self
+            .authorized(
+                self.agent
+                    .get(self.url(&format!("/projects/{}/events", name))),
+            )
+            .header("Accept", "text/event-stream")
+            .config()
+            .timeout_global(None)
+            .build()
+            .call()?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to follow events of {}: {}",
+                name,
+                response.status()
+            ));
+        }
+        Ok(SseData::new(response.into_body().into_reader()).map(|data| parse(&data?)))
+    }
+
+    fn post(&self, path: &str) -> Result<()> {
+        let mut response = self
+            .authorized(self.agent.post(self.url(path)))
+            .send_empty()?;
+        let status = response.status();
+        let envelope: Envelope<serde_json::Value> =
+            response.body_mut().read_json().unwrap_or_default();
+        match envelope.error {
+            Some(error) => Err(anyhow!(error)),
+            None if !status.is_success() => Err(anyhow!("{} {}", status, path)),
+            None => Ok(()),
+        }
+    }
+
+    fn authorized<B>(&self, request: RequestBuilder<B>) -> RequestBuilder<B> {
+        match &self.token {
+            Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+fn parse<T: DeserializeOwned>(data: &str) -> Result<T> {
+    Ok(serde_json::from_str(data)?)
+}
+
+/// The `data` of each server-sent event, with the lines of multi-line data joined.
+/// Comments, such as keep-alives, and other fields are skipped.
+pub struct SseData<R> {
+    lines: std::io::Lines<BufReader<R>>,
+}
+
+impl<R: Read> SseData<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: BufReader::new(reader).lines(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for SseData<R> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut data: Option<String> = None;
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.is_empty() {
+                if let Some(data) = data.take() {
+                    return Some(Ok(data));
+                }
+                continue;
+            }
+            let Some(value) = line.strip_prefix("data:") else {
+                continue;
+            };
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match &mut data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => data = Some(value.to_string()),
+            }
+        }
+        data.map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_event_stream_when_iterated_then_yield_data_of_each_event() {
+        let stream =
+            ": keep-alive\n\ndata: {\"a\":1}\n\nevent: message\ndata: first\ndata: second\n\n";
+
+        let actual = SseData::new(stream.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(actual, ["{\"a\":1}", "first\nsecond"]);
+    }
+}
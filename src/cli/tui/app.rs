@@ -0,0 +1,222 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::VecDeque;
+
+use crate::models::docker_compose::ProjectEvent;
+use crate::models::project::Project;
+
+/// Lines kept in the event pane, oldest dropped first.
+const MAX_EVENT_LINES: usize = 200;
+
+/// What the TUI shows, updated from key presses and from what the server sends.
+#[derive(Debug, Default)]
+pub struct App {
+    pub projects: Vec<Project>,
+    pub selected: usize,
+    /// Events of the followed project, oldest first.
+    pub events: VecDeque<String>,
+    /// The project whose events are shown.
+    pub following: Option<String>,
+    /// The outcome of the last action, or why the last refresh failed.
+    pub notice: Option<String>,
+}
+
+pub enum Message {
+    Key(KeyEvent),
+    Projects(Result<Vec<Project>, String>),
+    Event {
+        project: String,
+        event: ProjectEvent,
+    },
+    Notice(String),
+    /// The terminal was resized, so the next draw fills it.
+    Resize,
+}
+
+/// Work the event loop does on the app's behalf.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Refresh,
+    /// Follow the events of this project instead.
+    Follow(String),
+    Sync(String),
+    /// Unpause the project's containers.
+    Start(String),
+    /// Pause the project's containers.
+    Stop(String),
+}
+
+impl App {
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::Key(key) => self.on_key(key),
+            Message::Projects(Ok(projects)) => {
+                let selected = self.selected_name().map(str::to_string);
+                self.projects = projects;
+                self.selected = selected
+                    .and_then(|name| self.projects.iter().position(|p| p.name == name))
+                    .unwrap_or(0)
+                    .min(self.projects.len().saturating_sub(1));
+                self.follow_selected()
+            }
+            Message::Projects(Err(e)) => {
+                self.notice = Some(format!("Failed to list projects: {}", e));
+                None
+            }
+            Message::Event { project, event } => {
+                if self.following.as_deref() != Some(project.as_str()) {
+                    return None;
+                }
+                self.push_event(format_event(&event));
+                // A container starting or dying changes the project's status.
+                Some(Action::Refresh)
+            }
+            Message::Notice(notice) => {
+                self.notice = Some(notice);
+                None
+            }
+            Message::Resize => None,
+        }
+    }
+
+    pub fn selected_name(&self) -> Option<&str> {
+        self.projects
+            .get(self.selected)
+            .map(|project| project.name.as_str())
+    }
+
+    fn on_key(&mut self, key: KeyEvent) -> Option<Action> {
+        let name = self.selected_name().map(str::to_string);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Action::Quit)
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                self.follow_selected()
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.projects.len().saturating_sub(1));
+                self.follow_selected()
+            }
+            KeyCode::Char('r') => Some(Action::Refresh),
+            KeyCode::Char('s') => name.map(Action::Sync),
+            KeyCode::Char('t') => name.map(Action::Start),
+            KeyCode::Char('x') => name.map(Action::Stop),
+            _ => None,
+        }
+    }
+
+    fn follow_selected(&mut self) -> Option<Action> {
+        let name = self.selected_name()?.to_string();
+        if self.following.as_ref() == Some(&name) {
+            return None;
+        }
+        self.following = Some(name.clone());
+        self.events.clear();
+        Some(Action::Follow(name))
+    }
+
+    fn push_event(&mut self, line: String) {
+        if self.events.len() == MAX_EVENT_LINES {
+            self.events.pop_front();
+        }
+        self.events.push_back(line);
+    }
+}
+
+fn format_event(event: &ProjectEvent) -> String {
+    let source = event.service.as_deref().unwrap_or(&event.container);
+    let exit_code = event
+        .exit_code
+        .map(|code| format!(" (exit code {})", code))
+        .unwrap_or_default();
+    format!(
+        "{} {} {}{}",
+        event.time.format("%H:%M:%S"),
+        source,
+        event.action,
+        exit_code
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::git::GitSource;
+    use crate::models::project::ProjectStatus;
+    use chrono::{TimeZone, Utc};
+
+    fn project(name: &str) -> Project {
+        Project {
+            name: name.to_string(),
+            source: GitSource::default(),
+            labels: Default::default(),
+            status: ProjectStatus::Exited,
+            last_updated_at: Some("2024-01-01T00:00:00Z".to_string()),
+            last_updated_ago: None,
+            drifted: false,
+            environment_of: None,
+            warnings: vec![],
+        }
+    }
+
+    fn key(code: KeyCode) -> Message {
+        Message::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn given_projects_when_moving_down_then_follow_and_act_on_the_selected_one() {
+        let mut app = App::default();
+
+        let first = app.update(Message::Projects(Ok(vec![project("app"), project("db")])));
+        let moved = app.update(key(KeyCode::Down));
+        let past_end = app.update(key(KeyCode::Down));
+        let sync = app.update(key(KeyCode::Char('s')));
+
+        assert_eq!(first, Some(Action::Follow("app".to_string())));
+        assert_eq!(moved, Some(Action::Follow("db".to_string())));
+        assert_eq!(past_end, None);
+        assert_eq!(sync, Some(Action::Sync("db".to_string())));
+    }
+
+    #[test]
+    fn given_refreshed_projects_when_selected_one_moved_then_keep_it_selected() {
+        let mut app = App::default();
+        app.update(Message::Projects(Ok(vec![project("app"), project("db")])));
+        app.update(key(KeyCode::Down));
+
+        let actual = app.update(Message::Projects(Ok(vec![
+            project("api"),
+            project("app"),
+            project("db"),
+        ])));
+
+        assert_eq!(actual, None);
+        assert_eq!(app.selected_name(), Some("db"));
+    }
+
+    #[test]
+    fn given_event_of_followed_project_when_received_then_show_it_and_refresh() {
+        let mut app = App::default();
+        app.update(Message::Projects(Ok(vec![project("app")])));
+        let event = |project: &str| Message::Event {
+            project: project.to_string(),
+            event: ProjectEvent {
+                container: "app-web-1".to_string(),
+                service: Some("web".to_string()),
+                action: "die".to_string(),
+                exit_code: Some(137),
+                time: Utc.with_ymd_and_hms(2024, 1, 1, 12, 30, 0).unwrap(),
+            },
+        };
+
+        let stale = app.update(event("db"));
+        let actual = app.update(event("app"));
+
+        assert_eq!(stale, None);
+        assert_eq!(actual, Some(Action::Refresh));
+        assert_eq!(app.events, ["12:30:00 web die (exit code 137)"]);
+    }
+}
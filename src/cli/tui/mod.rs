@@ -0,0 +1,173 @@
+mod api;
+mod app;
+mod ui;
+
+use anyhow::Result;
+use clap::Args;
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::cli::tui::api::ApiClient;
+use crate::cli::tui::app::{Action, App, Message};
+
+#[derive(Debug, Args)]
+pub struct TuiArgs {
+    /// Base URL of the gfc server
+    #[arg(long, default_value = "http://127.0.0.1:3000")]
+    pub url: String,
+    /// Seconds between refreshes of the project list; container events of the selected
+    /// project refresh it sooner
+    #[arg(long, default_value_t = 5)]
+    pub refresh_secs: u64,
+    /// Bearer token, for servers with tenants
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+/// Run `gfc tui` until the user quits. Everything that talks to the server runs on its
+/// own thread and reports back to the draw loop as a [`Message`].
+pub fn run(args: &TuiArgs) -> Result<ExitCode> {
+    let api = ApiClient::new(&args.url).with_token(args.token.clone());
+    let (sender, messages) = mpsc::channel();
+    spawn_input(sender.clone());
+    let refresh = spawn_refresher(
+        api.clone(),
+        sender.clone(),
+        Duration::from_secs(args.refresh_secs.max(1)),
+    );
+
+    let mut terminal = ratatui::init();
+    let result = Session {
+        api,
+        sender,
+        refresh,
+        followed: Arc::default(),
+    }
+    .run(&mut terminal, messages, &args.url);
+    ratatui::restore();
+    result.map(|()| ExitCode::SUCCESS)
+}
+
+struct Session {
+    api: ApiClient,
+    sender: Sender<Message>,
+    /// Asks the refresher for a refresh ahead of its interval.
+    refresh: Sender<()>,
+    /// Bumped whenever another project is followed, which stops the previous stream at
+    /// its next event.
+    followed: Arc<AtomicU64>,
+}
+
+impl Session {
+    fn run(
+        &self,
+        terminal: &mut DefaultTerminal,
+        messages: Receiver<Message>,
+        server: &str,
+    ) -> Result<()> {
+        let mut app = App::default();
+        loop {
+            terminal.draw(|frame| ui::draw(frame, &app, server))?;
+            let Ok(message) = messages.recv() else {
+                return Ok(());
+            };
+            match app.update(message) {
+                Some(Action::Quit) => return Ok(()),
+                Some(action) => self.perform(action),
+                None => {}
+            }
+        }
+    }
+
+    fn perform(&self, action: Action) {
+        match action {
+            Action::Quit => {}
+            Action::Refresh => {
+                let _ = self.refresh.send(());
+            }
+            Action::Follow(name) => self.follow(name),
+            Action::Sync(name) => self.act(name, "Sync", ApiClient::sync),
+            Action::Start(name) => self.act(name, "Start", ApiClient::unpause),
+            Action::Stop(name) => self.act(name, "Stop", ApiClient::pause),
+        }
+    }
+
+    fn follow(&self, name: String) {
+        let generation = self.followed.fetch_add(1, Ordering::Relaxed) + 1;
+        let (api, sender, followed) =
+            (self.api.clone(), self.sender.clone(), self.followed.clone());
+        thread::spawn(move || {
+            let events = match api.events(&name) {
+                Ok(events) => events,
+                Err(e) => {
+                    let _ = sender.send(Message::Notice(e.to_string()));
+                    return;
+                }
+            };
+            for event in events {
+                if followed.load(Ordering::Relaxed) != generation {
+                    break;
+                }
+                let message = match event {
+                    Ok(event) => Message::Event {
+                        project: name.clone(),
+                        event,
+                    },
+                    Err(e) => Message::Notice(format!("Lost events of {}: {}", name, e)),
+                };
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn act(&self, name: String, what: &'static str, call: fn(&ApiClient, &str) -> Result<()>) {
+        let (api, sender, refresh) = (self.api.clone(), self.sender.clone(), self.refresh.clone());
+        thread::spawn(move || {
+            let notice = match call(&api, &name) {
+                Ok(()) => format!("{} of {} requested", what, name),
+                Err(e) => format!("{} of {} failed: {}", what, name, e),
+            };
+            let _ = sender.send(Message::Notice(notice));
+            let _ = refresh.send(());
+        });
+    }
+}
+
+fn spawn_input(sender: Sender<Message>) {
+    thread::spawn(move || loop {
+        let message = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => Message::Key(key),
+            Ok(Event::Resize(..)) => Message::Resize,
+            Ok(_) => continue,
+            Err(_) => break,
+        };
+        if sender.send(message).is_err() {
+            break;
+        }
+    });
+}
+
+/// List projects every `interval`, or sooner when asked through the returned sender.
+fn spawn_refresher(api: ApiClient, sender: Sender<Message>, interval: Duration) -> Sender<()> {
+    let (refresh, requests) = mpsc::channel();
+    thread::spawn(move || loop {
+        let projects = api.projects().map_err(|e| e.to_string());
+        if sender.send(Message::Projects(projects)).is_err() {
+            break;
+        }
+        if let Err(mpsc::RecvTimeoutError::Disconnected) = requests.recv_timeout(interval) {
+            break;
+        }
+        // Requests that piled up during the listing are served by the next one.
+        while requests.try_recv().is_ok() {}
+    });
+    refresh
+}
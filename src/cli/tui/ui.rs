@@ -0,0 +1,91 @@
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+
+use crate::cli::tui::app::App;
+use crate::models::project::ProjectStatus;
+
+const HELP: &str = "↑/↓ select  s sync  t start  x stop  r refresh  q quit";
+
+pub fn draw(frame: &mut Frame, app: &App, server: &str) {
+    let [projects_area, events_area, footer_area] = Layout::vertical([
+        Constraint::Min(5),
+        Constraint::Length(12),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let rows = app.projects.iter().map(|project| {
+        Row::new(vec![
+            project.name.clone(),
+            project.status.to_string(),
+            if project.drifted { "yes" } else { "" }.to_string(),
+            project
+                .last_updated_ago
+                .clone()
+                .or_else(|| project.last_updated_at.clone())
+                .unwrap_or_default(),
+        ])
+        .style(Style::default().fg(status_color(&project.status)))
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+            Constraint::Length(8),
+            Constraint::Percentage(30),
+        ],
+    )
+    .header(
+        Row::new(["NAME", "STATUS", "DRIFTED", "UPDATED"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" gfc · {} ", server)),
+    );
+    let mut state = TableState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(table, projects_area, &mut state);
+
+    // The newest events that fit, oldest at the top.
+    let visible = events_area.height.saturating_sub(2) as usize;
+    let events = app
+        .events
+        .iter()
+        .skip(app.events.len().saturating_sub(visible))
+        .map(|line| ListItem::new(line.as_str()));
+    let title = match &app.following {
+        Some(name) => format!(" events · {} ", name),
+        None => " events ".to_string(),
+    };
+    frame.render_widget(
+        List::new(events).block(Block::default().borders(Borders::ALL).title(title)),
+        events_area,
+    );
+
+    let footer = match &app.notice {
+        Some(notice) => Line::from(format!("{}  ·  {}", notice, HELP)),
+        None => Line::from(HELP),
+    };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+}
+
+/// The colours of the status badges.
+fn status_color(status: &ProjectStatus) -> Color {
+    match status {
+        ProjectStatus::Running { running, total } if running == total => Color::Green,
+        ProjectStatus::Running { .. } => Color::Yellow,
+        ProjectStatus::Paused => Color::Blue,
+        ProjectStatus::Exited
+        | ProjectStatus::DeleteFailed
+        | ProjectStatus::DeploymentFailed { .. } => Color::Red,
+        ProjectStatus::Deleting | ProjectStatus::CreationInProgress | ProjectStatus::Cancelled => {
+            Color::Gray
+        }
+    }
+}
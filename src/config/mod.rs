@@ -1,34 +1,685 @@
-use serde::Deserialize;
-use std::{fs::File, io::Read, path::Path};
+use serde::{Deserialize, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+use std::{fs::File, io::Read};
 use thiserror::Error;
 
+use crate::models::network::Subnet;
+use crate::models::retry::RetryPolicy;
+
 #[derive(Debug, Error)]
 pub enum ConfigError {
     #[error("Failed to open config file: {0}")]
     Io(#[from] std::io::Error),
     #[error("Failed to parse YAML: {0}")]
     Yaml(#[from] serde_yaml::Error),
+    #[error("Environment variable {0} is not set")]
+    MissingEnv(String),
+    #[error("Credentials for {0} have neither token nor token_env")]
+    MissingToken(String),
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Serve the gRPC API on this port as well, when set.
+    pub grpc_port: Option<u16>,
+    /// Upper bound for the `X-Request-Timeout` header.
+    #[serde(default = "default_max_request_timeout_secs")]
+    pub max_request_timeout_secs: u64,
+    /// Compress responses with gzip or brotli when the client accepts it.
+    #[serde(default = "default_compression")]
+    pub compression: bool,
+    /// Requests whose body takes longer than this to arrive are rejected.
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// Serve HTTPS instead of plain HTTP, when set.
+    pub tls: Option<TlsConfig>,
+}
+
+/// PEM files for the HTTPS listener.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+fn default_max_request_timeout_secs() -> u64 {
+    60
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+fn default_compression() -> bool {
+    true
+}
+
+fn default_read_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct ResourcesConfig {
     pub projects_dir: String,
     pub repositories_dir: String,
+    /// Previously deployed revisions kept checked out per project for instant rollback.
+    #[serde(default = "default_retained_revisions")]
+    pub retained_revisions: usize,
+    /// Values for compose secrets that declare no source of their own.
+    #[serde(default = "default_secrets_dir")]
+    pub secrets_dir: String,
+    /// Files that only exist while a project is up, such as materialized secrets.
+    #[serde(default = "default_runtime_dir")]
+    pub runtime_dir: String,
+    /// Compose projects gfc doesn't manage, one directory each, listed read-only at
+    /// `GET /compose-projects` when set.
+    #[serde(default)]
+    pub compose_projects_dir: Option<String>,
+    /// SQLite database of past deployments and unfinished jobs. `history.db` next to
+    /// `projects_dir` when unset. Unused without the `sqlite-store` feature.
+    #[serde(default)]
+    pub history_db: Option<String>,
+}
+
+fn default_retained_revisions() -> usize {
+    2
+}
+
+fn default_secrets_dir() -> String {
+    "resources/secrets".to_string()
+}
+
+fn default_runtime_dir() -> String {
+    "resources/runtime".to_string()
+}
+
+/// Periodic sync of every git-backed project with its remote.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ReconcilerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_reconcile_interval_secs")]
+    pub interval_secs: u64,
+    /// Also sync projects whose containers drifted from their compose file.
+    #[serde(default)]
+    pub correct_drift: bool,
+    /// Converge every project once when gfc starts, whether or not `enabled` is set.
+    #[serde(default = "default_reconcile_on_startup")]
+    pub on_startup: bool,
+}
+
+fn default_reconcile_interval_secs() -> u64 {
+    300
+}
+
+fn default_reconcile_on_startup() -> bool {
+    true
+}
+
+impl Default for ReconcilerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_reconcile_interval_secs(),
+            correct_drift: false,
+            on_startup: default_reconcile_on_startup(),
+        }
+    }
+}
+
+/// Periodic check of the images of projects with `watch_images` for new digests on their
+/// registries.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ImageWatchConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_image_watch_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_image_watch_interval_secs() -> u64 {
+    900
+}
+
+impl Default for ImageWatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_image_watch_interval_secs(),
+        }
+    }
+}
+
+/// Periodic removal of images no managed project uses any more: dangling images, and
+/// tags of the repositories managed projects run that none of them runs now. Images
+/// created within `retention_secs` are kept.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ImageGcConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_image_gc_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_image_gc_retention_secs")]
+    pub retention_secs: u64,
+}
+
+fn default_image_gc_interval_secs() -> u64 {
+    86400
+}
+
+fn default_image_gc_retention_secs() -> u64 {
+    7 * 86400
+}
+
+impl Default for ImageGcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_image_gc_interval_secs(),
+            retention_secs: default_image_gc_retention_secs(),
+        }
+    }
+}
+
+/// Disk each project may take up with its checkouts and named volumes.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct DiskQuotaConfig {
+    /// `None` for no limit. A project's own `disk_quota_bytes` wins over it.
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub enforcement: QuotaEnforcement,
+}
+
+/// What syncing a project over its disk quota does.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaEnforcement {
+    /// Sync anyway, and record a warning in the project's activity.
+    #[default]
+    Warn,
+    /// Refuse to sync.
+    Refuse,
+}
+
+/// Anonymous usage reports, off unless `enabled` is set. A report holds the gfc version,
+/// the number of projects and the deployment backend, never names, URLs or addresses. With
+/// no `endpoint` reports are only printed.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct UsageStatsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default = "default_usage_stats_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_usage_stats_interval_secs() -> u64 {
+    86400
+}
+
+impl Default for UsageStatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            interval_secs: default_usage_stats_interval_secs(),
+        }
+    }
+}
+
+/// Address pools project networks get their subnets from, in the shape of the Docker
+/// daemon's `default-address-pools`. With none, Docker picks subnets itself.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct NetworksConfig {
+    #[serde(default)]
+    pub pools: Vec<AddressPool>,
+}
+
+/// `base` is split into subnets with a prefix length of `size`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct AddressPool {
+    pub base: Subnet,
+    pub size: u8,
+}
+
+/// Pull request previews publish their services on host ports from `ports` instead of
+/// those in the compose file, which the project they preview already holds.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PreviewsConfig {
+    #[serde(default = "default_preview_ports")]
+    pub ports: PortRange,
+}
+
+/// Host ports from `start` to `end`, both included.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+fn default_preview_ports() -> PortRange {
+    PortRange {
+        start: 20000,
+        end: 29999,
+    }
+}
+
+impl Default for PreviewsConfig {
+    fn default() -> Self {
+        Self {
+            ports: default_preview_ports(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct GitConfig {
+    /// Clone the whole history of repositories instead of only their newest commit.
+    #[serde(default)]
+    pub full_clone: bool,
+    /// Kill git commands, e.g. a clone stuck on a dead connection, after this long. Ten
+    /// minutes when unset.
+    pub timeout_secs: Option<u64>,
+    /// Keep one clone per remote and branch in this directory, which the checkouts of the
+    /// projects tracking them borrow objects from and pull through, e.g. for a monorepo
+    /// deployed as several projects with different `source.path`s. Unset to clone every
+    /// project on its own.
+    pub shared_clones_dir: Option<String>,
+    #[serde(default)]
+    pub ssh: SshConfig,
+    #[serde(default)]
+    pub credentials: Vec<GitCredential>,
+}
+
+/// HTTPS credentials for remotes whose URL starts with `url`. The longest match wins, so
+/// one entry can cover a whole host and another a single repository.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct GitCredential {
+    pub url: String,
+    /// Most hosts accept any username along with a personal access token.
+    #[serde(default = "default_credential_username")]
+    pub username: String,
+    #[serde(default, serialize_with = "redact_option")]
+    pub token: Option<String>,
+    /// Environment variable holding the token, to keep it out of the config file.
+    pub token_env: Option<String>,
+}
+
+fn default_credential_username() -> String {
+    "gfc".to_string()
+}
+
+impl GitCredential {
+    pub fn token(&self) -> Result<String, ConfigError> {
+        resolve_token(&self.token, &self.token_env, &self.url)
+    }
+}
+
+/// A token given inline, or read from the environment variable named by `token_env`.
+fn resolve_token(
+    token: &Option<String>,
+    token_env: &Option<String>,
+    owner: &str,
+) -> Result<String, ConfigError> {
+    match (token, token_env) {
+        (Some(token), _) => Ok(token.clone()),
+        (None, Some(name)) => {
+            std::env::var(name).map_err(|_| ConfigError::MissingEnv(name.clone()))
+        }
+        (None, None) => Err(ConfigError::MissingToken(owner.to_string())),
+    }
+}
+
+/// Tenants of a shared deployment, each with a workspace of its own that only its API
+/// token reaches. Once any are set, every API request needs a bearer token: a tenant's,
+/// or the operator's for the main workspace and host-wide endpoints. Webhooks keep
+/// authenticating with their signatures and only reach the main workspace.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct TenancyConfig {
+    #[serde(default, serialize_with = "redact_option")]
+    pub operator_token: Option<String>,
+    /// Environment variable holding the operator token.
+    pub operator_token_env: Option<String>,
+    #[serde(default)]
+    pub tenants: Vec<TenantConfig>,
+}
+
+impl TenancyConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.tenants.is_empty()
+    }
+
+    pub fn operator_token(&self) -> Result<String, ConfigError> {
+        resolve_token(
+            &self.operator_token,
+            &self.operator_token_env,
+            "the operator",
+        )
+    }
+
+    /// Refuse tenants and an operator that would be unreachable for want of a token.
+    pub fn check(&self) -> Result<(), ConfigError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        self.operator_token()?;
+        for tenant in &self.tenants {
+            tenant.token()?;
+        }
+        Ok(())
+    }
+}
+
+/// A tenant's workspace. Its secrets and runtime files go in a directory named after it
+/// under the main `secrets_dir` and `runtime_dir`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct TenantConfig {
+    pub name: String,
+    pub projects_dir: String,
+    pub repositories_dir: String,
+    #[serde(default, serialize_with = "redact_option")]
+    pub token: Option<String>,
+    /// Environment variable holding the token.
+    pub token_env: Option<String>,
+    /// Forge webhooks that sync the tenant's projects. Deliveries are told apart by the
+    /// secret they are signed with, so it must differ from the main workspace's.
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+}
+
+impl TenantConfig {
+    pub fn token(&self) -> Result<String, ConfigError> {
+        resolve_token(
+            &self.token,
+            &self.token_env,
+            &format!("tenant {}", self.name),
+        )
+    }
+
+    /// The tenant's resources, with what it doesn't set taken from `main`.
+    pub fn resources(&self, main: &ResourcesConfig) -> ResourcesConfig {
+        ResourcesConfig {
+            projects_dir: self.projects_dir.clone(),
+            repositories_dir: self.repositories_dir.clone(),
+            secrets_dir: Path::new(&main.secrets_dir)
+                .join(&self.name)
+                .display()
+                .to_string(),
+            runtime_dir: Path::new(&main.runtime_dir)
+                .join(&self.name)
+                .display()
+                .to_string(),
+            compose_projects_dir: None,
+            history_db: None,
+            ..main.clone()
+        }
+    }
+}
+
+/// How git reaches repositories over SSH.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct SshConfig {
+    #[serde(default)]
+    pub host_key_checking: HostKeyChecking,
+    /// Host keys are checked against this file instead of `~/.ssh/known_hosts`.
+    pub known_hosts_file: Option<String>,
+}
+
+/// What to do with a host key that isn't known yet.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HostKeyChecking {
+    /// Refuse to connect.
+    #[default]
+    Strict,
+    /// Remember it on first use, and refuse if it changes later.
+    AcceptNew,
+    /// Connect without checking host keys at all.
+    Off,
+}
+
+impl HostKeyChecking {
+    /// The value of ssh's `StrictHostKeyChecking` option.
+    pub fn as_ssh_option(&self) -> &'static str {
+        match self {
+            HostKeyChecking::Strict => "yes",
+            HostKeyChecking::AcceptNew => "accept-new",
+            HostKeyChecking::Off => "no",
+        }
+    }
+}
+
+/// How files a project commits encrypted with sops are decrypted.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct SopsConfig {
+    #[serde(default = "default_sops_binary")]
+    pub binary: String,
+    /// Passed to sops as `SOPS_AGE_KEY_FILE`. Without it, sops finds keys on its own.
+    pub age_key_file: Option<String>,
+}
+
+fn default_sops_binary() -> String {
+    "sops".to_string()
+}
+
+impl Default for SopsConfig {
+    fn default() -> Self {
+        Self {
+            binary: default_sops_binary(),
+            age_key_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct WebhooksConfig {
+    pub github: Option<WebhookSecretConfig>,
+    pub gitlab: Option<WebhookSecretConfig>,
+    pub gitea: Option<WebhookSecretConfig>,
+    #[serde(default)]
+    pub rules: Vec<WebhookRule>,
+}
+
+/// Sends pushes to projects whose source URL is not one the forge reports for the pushed
+/// repository, such as a mirror. Projects still match pushes to their own URL as well.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct WebhookRule {
+    /// Glob matched against each URL the forge reports for the repository.
+    pub repository: String,
+    /// Glob matched against the pushed branch or tag. Any push matches when unset.
+    pub branch: Option<String>,
+    pub projects: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct WebhookSecretConfig {
+    #[serde(serialize_with = "redact")]
+    pub secret: String,
+}
+
+const REDACTED: &str = "********";
+
+/// Secrets never leave the process, e.g. through `GET /admin/config`.
+fn redact<S: Serializer>(_secret: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+fn redact_option<S: Serializer>(secret: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match secret {
+        Some(_) => serializer.serialize_some(REDACTED),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Resource profile. `low_memory` trades speed for a small, bounded footprint on hosts
+/// such as a Raspberry Pi.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    #[default]
+    Standard,
+    LowMemory,
+}
+
+/// Limits derived from a [`Profile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileLimits {
+    /// Captured stdout or stderr of a single command beyond this is discarded.
+    pub max_command_output_bytes: usize,
+    /// Jobs of each queue running at once, e.g. deployments; the rest wait queued.
+    pub max_concurrent_jobs: usize,
+    /// Finished jobs kept around for `GET /jobs/{id}`.
+    pub max_finished_jobs: usize,
+    /// Pull known images while the repository is cloned, rather than leaving it to
+    /// `compose up`.
+    pub pull_during_clone: bool,
+}
+
+impl Profile {
+    pub fn limits(&self) -> ProfileLimits {
+        match self {
+            Profile::Standard => ProfileLimits {
+                max_command_output_bytes: 16 * 1024 * 1024,
+                max_concurrent_jobs: 8,
+                max_finished_jobs: 500,
+                pull_during_clone: true,
+            },
+            Profile::LowMemory => ProfileLimits {
+                max_command_output_bytes: 256 * 1024,
+                max_concurrent_jobs: 1,
+                max_finished_jobs: 50,
+                pull_during_clone: false,
+            },
+        }
+    }
+}
+
+/// How gfc runs the docker CLI.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct ComposeConfig {
+    /// Kill docker commands, e.g. an `up` stuck pulling an image, after this long. Half
+    /// an hour when unset.
+    pub timeout_secs: Option<u64>,
+}
+
+/// Background job settings that override the profile's.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct JobsConfig {
+    /// Clones and `compose up`s running at once, across all projects.
+    pub max_parallel_deployments: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct Config {
     pub server: ServerConfig,
     pub resources: ResourcesConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub profile: Profile,
+    #[serde(default)]
+    pub reconciler: ReconcilerConfig,
+    #[serde(default)]
+    pub image_watch: ImageWatchConfig,
+    #[serde(default)]
+    pub image_gc: ImageGcConfig,
+    #[serde(default)]
+    pub disk_quota: DiskQuotaConfig,
+    #[serde(default)]
+    pub usage_stats: UsageStatsConfig,
+    #[serde(default)]
+    pub networks: NetworksConfig,
+    #[serde(default)]
+    pub previews: PreviewsConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub sops: SopsConfig,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub compose: ComposeConfig,
+    #[serde(default)]
+    pub tenancy: TenancyConfig,
+    /// A [`Bootstrap`](crate::models::bootstrap::Bootstrap) file applied on every startup.
+    #[serde(default)]
+    pub bootstrap: Option<String>,
+}
+
+impl ServerConfig {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+            grpc_port: None,
+            max_request_timeout_secs: default_max_request_timeout_secs(),
+            compression: default_compression(),
+            read_timeout_secs: default_read_timeout_secs(),
+            tls: None,
+        }
+    }
+}
+
+impl ResourcesConfig {
+    pub fn new(projects_dir: &str, repositories_dir: &str) -> Self {
+        Self {
+            projects_dir: projects_dir.to_string(),
+            repositories_dir: repositories_dir.to_string(),
+            retained_revisions: default_retained_revisions(),
+            secrets_dir: default_secrets_dir(),
+            runtime_dir: default_runtime_dir(),
+            compose_projects_dir: None,
+            history_db: None,
+        }
+    }
+
+    pub fn history_db_path(&self) -> PathBuf {
+        match &self.history_db {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(&self.projects_dir).with_file_name("history.db"),
+        }
+    }
 }
 
 impl Config {
+    /// A configuration with no webhooks set up, for embedding without a config file.
+    pub fn new(server: ServerConfig, resources: ResourcesConfig) -> Self {
+        Self {
+            server,
+            resources,
+            webhooks: WebhooksConfig::default(),
+            profile: Profile::default(),
+            reconciler: ReconcilerConfig::default(),
+            image_watch: ImageWatchConfig::default(),
+            image_gc: ImageGcConfig::default(),
+            disk_quota: DiskQuotaConfig::default(),
+            usage_stats: UsageStatsConfig::default(),
+            networks: NetworksConfig::default(),
+            previews: PreviewsConfig::default(),
+            git: GitConfig::default(),
+            sops: SopsConfig::default(),
+            retry: RetryPolicy::default(),
+            jobs: JobsConfig::default(),
+            compose: ComposeConfig::default(),
+            tenancy: TenancyConfig::default(),
+            bootstrap: None,
+        }
+    }
+
+    /// The profile's limits, with what `jobs` overrides applied.
+    pub fn limits(&self) -> ProfileLimits {
+        let limits = self.profile.limits();
+        ProfileLimits {
+            max_concurrent_jobs: self
+                .jobs
+                .max_parallel_deployments
+                .unwrap_or(limits.max_concurrent_jobs),
+            ..limits
+        }
+    }
+
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
         let mut file: File = File::open(path)?;
         let mut contents = String::new();
@@ -67,6 +718,35 @@ mod tests {
         assert_eq!(config.resources.repositories_dir, "/tmp/repos");
     }
 
+    #[test]
+    fn given_low_memory_profile_when_loaded_then_limits_are_lowered() {
+        let yaml = "server:\n  host: 127.0.0.1\n  port: 8080\nresources:\n  projects_dir: /tmp/projects\n  repositories_dir: /tmp/repos\nprofile: low_memory\n";
+        let mut tmpfile = NamedTempFile::new().unwrap();
+        write!(tmpfile, "{}", yaml).unwrap();
+
+        let config = Config::from_file(tmpfile.path()).unwrap();
+
+        assert_eq!(config.profile, Profile::LowMemory);
+        assert_eq!(config.profile.limits().max_concurrent_jobs, 1);
+        assert!(!config.profile.limits().pull_during_clone);
+    }
+
+    #[test]
+    fn given_webhook_secret_when_serialized_then_secret_is_redacted() {
+        let mut config = Config::new(
+            ServerConfig::new("127.0.0.1", 8080),
+            ResourcesConfig::new("/tmp/projects", "/tmp/repos"),
+        );
+        config.webhooks.github = Some(WebhookSecretConfig {
+            secret: "hunter2".to_string(),
+        });
+
+        let actual = serde_json::to_value(&config).unwrap();
+
+        assert_eq!(actual["webhooks"]["github"]["secret"], REDACTED);
+        assert_eq!(actual["resources"]["retained_revisions"], 2);
+    }
+
     #[test]
     fn given_invalid_yaml_when_loaded_then_returns_error() {
         let yaml = "not: valid: yaml";
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+/// Stable error categories shared by the API and the CLI. The serialized name and the
+/// exit code are part of the public contract; add new categories instead of renumbering.
+///
+/// | code         | exit |
+/// |--------------|------|
+/// | `internal`   | 1    |
+/// | `validation` | 3    |
+/// | `not_found`  | 4    |
+/// | `config`     | 5    |
+/// | `git`        | 6    |
+/// | `compose`    | 7    |
+/// | `io`         | 8    |
+/// | `docker`     | 9    |
+/// | `conflict`   | 10   |
+///
+/// Exit code 2 is left to the argument parser for usage errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Internal,
+    Validation,
+    NotFound,
+    Config,
+    Git,
+    Compose,
+    Io,
+    Docker,
+    Conflict,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Internal => "internal",
+            ErrorCode::Validation => "validation",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Config => "config",
+            ErrorCode::Git => "git",
+            ErrorCode::Compose => "compose",
+            ErrorCode::Io => "io",
+            ErrorCode::Docker => "docker",
+            ErrorCode::Conflict => "conflict",
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ErrorCode::Internal => 1,
+            ErrorCode::Validation => 3,
+            ErrorCode::NotFound => 4,
+            ErrorCode::Config => 5,
+            ErrorCode::Git => 6,
+            ErrorCode::Compose => 7,
+            ErrorCode::Io => 8,
+            ErrorCode::Docker => 9,
+            ErrorCode::Conflict => 10,
+        }
+    }
+}
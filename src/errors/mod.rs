@@ -0,0 +1,68 @@
+pub mod codes;
+
+use thiserror::Error;
+
+use crate::config::ConfigError;
+use crate::errors::codes::ErrorCode;
+use crate::models::compose_file::ComposeFileError;
+use crate::models::project::ManifestError;
+#[cfg(feature = "docker-api")]
+use crate::repositories::docker_client::DockerClientError;
+use crate::repositories::docker_compose_client::DockerComposeError;
+use crate::usecases::project::ProjectUsecaseError;
+use crate::usecases::validation::ValidationError;
+
+/// Top-level error for entry points (CLI commands, server startup) that need to report
+/// a stable [`ErrorCode`] regardless of which layer failed.
+#[derive(Debug, Error)]
+pub enum GfcError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error(transparent)]
+    Manifest(#[from] ManifestError),
+    #[error(transparent)]
+    ComposeFile(#[from] ComposeFileError),
+    #[error(transparent)]
+    DockerCompose(#[from] DockerComposeError),
+    #[cfg(feature = "docker-api")]
+    #[error(transparent)]
+    DockerClient(#[from] DockerClientError),
+    #[error(transparent)]
+    Project(#[from] ProjectUsecaseError),
+    #[error("Failed to read file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Failed to serialize output: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl GfcError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            GfcError::Config(_) => ErrorCode::Config,
+            GfcError::Validation(_) | GfcError::Manifest(_) => ErrorCode::Validation,
+            GfcError::ComposeFile(ComposeFileError::Io(_)) | GfcError::Io(_) => ErrorCode::Io,
+            GfcError::ComposeFile(_) => ErrorCode::Validation,
+            GfcError::DockerCompose(
+                DockerComposeError::DirectoryNotFound
+                | DockerComposeError::DockerComposeFileDoesNotExist,
+            ) => ErrorCode::NotFound,
+            GfcError::DockerCompose(
+                DockerComposeError::InvalidConfig(_) | DockerComposeError::InvalidImageReference(_),
+            ) => ErrorCode::Validation,
+            GfcError::DockerCompose(_) => ErrorCode::Compose,
+            #[cfg(feature = "docker-api")]
+            GfcError::DockerClient(DockerClientError::NotFound(_)) => ErrorCode::NotFound,
+            #[cfg(feature = "docker-api")]
+            GfcError::DockerClient(DockerClientError::Conflict(_)) => ErrorCode::Conflict,
+            #[cfg(feature = "docker-api")]
+            GfcError::DockerClient(_) => ErrorCode::Docker,
+            GfcError::Project(ProjectUsecaseError::InvalidProject(_)) => ErrorCode::Validation,
+            GfcError::Project(ProjectUsecaseError::ProjectNotFound(_)) => ErrorCode::NotFound,
+            GfcError::Project(_) | GfcError::Yaml(_) | GfcError::Json(_) => ErrorCode::Internal,
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::time::Duration;
+use tonic::metadata::MetadataMap;
+use tonic::{Request, Response, Status};
+
+use crate::models::export::{ImportStatus, ImportedProject};
+use crate::models::git::{GitSource, SshKey};
+use crate::models::project::{ManifestFormat, Project, ProjectFile};
+use crate::repositories::blocking;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::deadline::Deadline;
+use crate::usecases::project::{ProjectUsecase, ProjectUsecaseError};
+
+pub mod proto {
+    tonic::include_proto!("gfc.v1");
+}
+
+use proto::project_service_server::{ProjectService, ProjectServiceServer};
+
+/// gRPC front end for [`ProjectUsecase`], served next to the HTTP API.
+pub struct GrpcProjectService<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    usecase: ProjectUsecase<C, G>,
+    max_request_timeout: Duration,
+}
+
+impl<C, G> GrpcProjectService<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    /// `max_request_timeout` caps the deadline a client asks for with `grpc-timeout`, as
+    /// `server.max_request_timeout_secs` does for HTTP.
+    pub fn new(usecase: ProjectUsecase<C, G>, max_request_timeout: Duration) -> Self {
+        Self {
+            usecase,
+            max_request_timeout,
+        }
+    }
+
+    /// Deadline requested through `grpc-timeout`, capped at `max_request_timeout`. Requests
+    /// without it have no deadline.
+    fn deadline(&self, metadata: &MetadataMap) -> Result<Deadline, String> {
+        let Some(value) = metadata.get(GRPC_TIMEOUT_HEADER) else {
+            return Ok(Deadline::none());
+        };
+
+        let timeout = value
+            .to_str()
+            .ok()
+            .and_then(parse_grpc_timeout)
+            .ok_or_else(|| format!("Invalid {} header", GRPC_TIMEOUT_HEADER))?;
+        Ok(Deadline::after(timeout.min(self.max_request_timeout)))
+    }
+
+    pub fn into_server(self) -> ProjectServiceServer<Self> {
+        ProjectServiceServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl<C, G> ProjectService for GrpcProjectService<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    async fn list_projects(
+        &self,
+        request: Request<proto::ListProjectsRequest>,
+    ) -> Result<Response<proto::ListProjectsResponse>, Status> {
+        let deadline = self
+            .deadline(request.metadata())
+            .map_err(Status::invalid_argument)?;
+        let usecase = self.usecase.clone();
+        let projects = blocking::run(move || usecase.resolve_projects(&deadline))
+            .await
+            .map_err(interrupted)??;
+
+        Ok(Response::new(proto::ListProjectsResponse {
+            projects: projects.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_project(
+        &self,
+        request: Request<proto::GetProjectRequest>,
+    ) -> Result<Response<proto::ProjectManifest>, Status> {
+        let usecase = self.usecase.clone();
+        let name = request.into_inner().name;
+        let project_file = blocking::run(move || usecase.find_project_file(&name))
+            .await
+            .map_err(interrupted)??;
+
+        Ok(Response::new(project_file.into()))
+    }
+
+    /// A manifest with `environments` creates a project per environment, as over HTTP.
+    async fn create_project(
+        &self,
+        request: Request<proto::CreateProjectRequest>,
+    ) -> Result<Response<proto::CreateProjectResponse>, Status> {
+        let project_file =
+            requested_project(request.into_inner()).map_err(Status::invalid_argument)?;
+        let usecase = self.usecase.clone();
+        if !project_file.environments.is_empty() {
+            let created = blocking::run(move || usecase.create_environments(project_file))
+                .await
+                .map_err(interrupted)??;
+            return Ok(Response::new(proto::CreateProjectResponse {
+                job_id: String::new(),
+                environments: created.into_iter().map(Into::into).collect(),
+            }));
+        }
+        let job = blocking::run(move || usecase.create_project(project_file))
+            .await
+            .map_err(interrupted)??;
+
+        Ok(Response::new(proto::CreateProjectResponse {
+            job_id: job.id,
+            environments: vec![],
+        }))
+    }
+
+    async fn sync_project(
+        &self,
+        request: Request<proto::SyncProjectRequest>,
+    ) -> Result<Response<proto::SyncProjectResponse>, Status> {
+        let usecase = self.usecase.clone();
+        let name = request.into_inner().name;
+        let job = blocking::run(move || usecase.sync_project(&name))
+            .await
+            .map_err(interrupted)??;
+
+        Ok(Response::new(proto::SyncProjectResponse { job_id: job.id }))
+    }
+
+    async fn delete_project(
+        &self,
+        request: Request<proto::DeleteProjectRequest>,
+    ) -> Result<Response<proto::DeleteProjectResponse>, Status> {
+        let usecase = self.usecase.clone();
+        let name = request.into_inner().name;
+        let job = blocking::run(move || usecase.delete_project(&name))
+            .await
+            .map_err(interrupted)??;
+
+        Ok(Response::new(proto::DeleteProjectResponse {
+            job_id: job.id,
+        }))
+    }
+}
+
+/// The project a create request describes, through either its `project` or its
+/// `manifest`, or why it describes none.
+fn requested_project(request: proto::CreateProjectRequest) -> Result<ProjectFile, String> {
+    match (request.project, request.manifest.is_empty()) {
+        (Some(project), true) => Ok(project.into()),
+        (None, false) => {
+            let format = match request.manifest_format.as_str() {
+                "" => ManifestFormat::Yaml,
+                name => ManifestFormat::from_extension(name)
+                    .ok_or_else(|| format!("Unsupported manifest format: {}", name))?,
+            };
+            format.parse(&request.manifest).map_err(|e| e.to_string())
+        }
+        (Some(_), false) => Err("set either project or manifest, not both".to_string()),
+        (None, true) => Err("project or manifest is required".to_string()),
+    }
+}
+
+const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+/// Parse a `grpc-timeout` value: at most eight digits followed by a unit, one of `H`, `M`,
+/// `S`, `m`, `u` or `n`.
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    let split = value.len().checked_sub(1)?;
+    let (amount, unit) = (value.get(..split)?, value.get(split..)?);
+    if amount.is_empty() || amount.len() > 8 || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let amount = amount.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+fn interrupted(e: tokio::task::JoinError) -> Status {
+    Status::internal(e.to_string())
+}
+
+impl From<ProjectUsecaseError> for Status {
+    fn from(value: ProjectUsecaseError) -> Self {
+        let message = value.to_string();
+        match value {
+            ProjectUsecaseError::ProjectNotFound(_)
+            | ProjectUsecaseError::ServiceNotFound(_)
+            | ProjectUsecaseError::JobNotFound(_) => Status::not_found(message),
+            ProjectUsecaseError::InvalidProject(_) | ProjectUsecaseError::InvalidSelector(_) => {
+                Status::invalid_argument(message)
+            }
+            ProjectUsecaseError::DeadlineExceeded(_) => Status::deadline_exceeded(message),
+            ProjectUsecaseError::PreflightFailed(_) => Status::already_exists(message),
+            ProjectUsecaseError::ProjectDeleting(_) | ProjectUsecaseError::ProjectBusy { .. } => {
+                Status::failed_precondition(message)
+            }
+            _ => Status::internal(message),
+        }
+    }
+}
+
+impl From<GitSource> for proto::GitSource {
+    fn from(value: GitSource) -> Self {
+        proto::GitSource {
+            url: value.url,
+            branch: value.branch,
+            path: value.path,
+            extra_paths: value.extra_paths,
+            tag_pattern: value.tag_pattern.unwrap_or_default(),
+            ssh_key_path: match &value.ssh_key {
+                Some(SshKey::Path(path)) => path.clone(),
+                _ => String::new(),
+            },
+            ssh_key_secret: match &value.ssh_key {
+                Some(SshKey::Secret(name)) => name.clone(),
+                _ => String::new(),
+            },
+        }
+    }
+}
+
+impl From<proto::GitSource> for GitSource {
+    fn from(value: proto::GitSource) -> Self {
+        GitSource {
+            url: value.url,
+            branch: value.branch,
+            path: value.path,
+            extra_paths: value.extra_paths,
+            tag_pattern: Some(value.tag_pattern).filter(|pattern| !pattern.is_empty()),
+            ssh_key: match (value.ssh_key_path, value.ssh_key_secret) {
+                (path, _) if !path.is_empty() => Some(SshKey::Path(path)),
+                (_, name) if !name.is_empty() => Some(SshKey::Secret(name)),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl From<Project> for proto::Project {
+    fn from(value: Project) -> Self {
+        proto::Project {
+            name: value.name,
+            source: Some(value.source.into()),
+            status: value.status.to_string(),
+            last_updated_at: value.last_updated_at.unwrap_or_default(),
+            drifted: value.drifted,
+        }
+    }
+}
+
+impl From<ImportedProject> for proto::CreatedProject {
+    fn from(value: ImportedProject) -> Self {
+        proto::CreatedProject {
+            name: value.name,
+            status: match value.status {
+                ImportStatus::Queued => "queued",
+                ImportStatus::Skipped => "skipped",
+                ImportStatus::Failed => "failed",
+                ImportStatus::Removed => "removed",
+            }
+            .to_string(),
+            job_id: value.job_id.unwrap_or_default(),
+            error: value.error.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ProjectFile> for proto::ProjectManifest {
+    fn from(value: ProjectFile) -> Self {
+        proto::ProjectManifest {
+            name: value.name,
+            source: Some(value.source.into()),
+        }
+    }
+}
+
+impl From<proto::ProjectManifest> for ProjectFile {
+    fn from(value: proto::ProjectManifest) -> Self {
+        ProjectFile {
+            name: value.name,
+            source: value.source.map(Into::into).unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_each_unit_when_parse_grpc_timeout_then_return_duration() {
+        assert_eq!(parse_grpc_timeout("2H"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_grpc_timeout("3M"), Some(Duration::from_secs(180)));
+        assert_eq!(parse_grpc_timeout("10S"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_grpc_timeout("500m"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_grpc_timeout("250u"), Some(Duration::from_micros(250)));
+        assert_eq!(parse_grpc_timeout("99n"), Some(Duration::from_nanos(99)));
+    }
+
+    #[test]
+    fn given_malformed_value_when_parse_grpc_timeout_then_return_none() {
+        assert_eq!(parse_grpc_timeout(""), None);
+        assert_eq!(parse_grpc_timeout("S"), None);
+        assert_eq!(parse_grpc_timeout("10"), None);
+        assert_eq!(parse_grpc_timeout("10s"), None);
+        assert_eq!(parse_grpc_timeout("123456789S"), None);
+        assert_eq!(parse_grpc_timeout("-1S"), None);
+    }
+}
@@ -0,0 +1,14 @@
+use axum::extract::State;
+use axum::Json;
+
+use crate::config::Config;
+use crate::handlers::tenancy::Operator;
+use crate::models::response::GenericResponse;
+
+/// The configuration the server is running with, defaults filled in and secrets redacted.
+pub async fn get_config(
+    _operator: Operator,
+    State(config): State<Config>,
+) -> Json<GenericResponse<Config>> {
+    Json(GenericResponse::result(config))
+}
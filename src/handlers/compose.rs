@@ -0,0 +1,22 @@
+use axum::extract::State;
+use axum::response::Response;
+
+use crate::handlers::negotiation::ResponseFormat;
+use crate::handlers::project::HandlerError;
+use crate::handlers::tenancy::Operator;
+use crate::repositories::blocking;
+use crate::repositories::compose_client::ComposeClient;
+use crate::usecases::compose::ComposeUsecase;
+
+/// Not found unless `resources.compose_projects_dir` is set.
+pub async fn get_compose_projects<C>(
+    _operator: Operator,
+    State(usecase): State<ComposeUsecase<C>>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+{
+    let projects = blocking::run(move || usecase.list_compose_projects()).await??;
+    Ok(format.respond(projects))
+}
@@ -0,0 +1,63 @@
+use axum::http::header::{ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::handlers::negotiation::ResponseFormat;
+
+/// Strong ETag for one representation of a resource. JSON and YAML bodies differ, so the
+/// format is part of the tag.
+pub fn entity_tag(hash: &str, format: ResponseFormat) -> String {
+    match format {
+        ResponseFormat::Json => format!("\"{}\"", hash),
+        ResponseFormat::Yaml => format!("\"{}-yaml\"", hash),
+    }
+}
+
+/// Whether `If-None-Match` lists `etag`, or is `*`. Weak tags compare equal to their
+/// strong form, as `If-None-Match` uses weak comparison.
+pub fn none_match_hit(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+pub fn not_modified(etag: &str) -> Response {
+    (StatusCode::NOT_MODIFIED, [(ETAG, etag.to_string())]).into_response()
+}
+
+pub fn with_etag(mut response: Response, etag: &str) -> Response {
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        response.headers_mut().insert(ETAG, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with_if_none_match(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn given_matching_tag_in_list_when_none_match_hit_then_return_true() {
+        let headers = headers_with_if_none_match("\"other\", W/\"abc\"");
+
+        assert!(none_match_hit(&headers, "\"abc\""));
+    }
+
+    #[test]
+    fn given_stale_tag_when_none_match_hit_then_return_false() {
+        let headers = headers_with_if_none_match("\"abc\"");
+
+        assert!(!none_match_hit(&headers, "\"abc-yaml\""));
+        assert!(!none_match_hit(&HeaderMap::new(), "\"abc\""));
+    }
+}
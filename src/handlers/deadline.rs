@@ -0,0 +1,39 @@
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use std::time::Duration;
+
+use crate::config::ServerConfig;
+use crate::usecases::deadline::{parse_timeout, Deadline};
+
+pub const REQUEST_TIMEOUT_HEADER: &str = "X-Request-Timeout";
+
+/// Deadline requested through `X-Request-Timeout`, capped at
+/// `server.max_request_timeout_secs`. Requests without the header have no deadline.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestDeadline(pub Deadline);
+
+impl<S> FromRequestParts<S> for RequestDeadline
+where
+    S: Send + Sync,
+    ServerConfig: FromRef<S>,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(REQUEST_TIMEOUT_HEADER) else {
+            return Ok(Self(Deadline::none()));
+        };
+
+        let timeout = value.to_str().ok().and_then(parse_timeout).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid {} header", REQUEST_TIMEOUT_HEADER),
+            )
+        })?;
+        let max_timeout =
+            Duration::from_secs(ServerConfig::from_ref(state).max_request_timeout_secs);
+
+        Ok(Self(Deadline::after(timeout.min(max_timeout))))
+    }
+}
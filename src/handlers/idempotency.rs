@@ -0,0 +1,151 @@
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::models::job::Job;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+const MAX_KEY_LENGTH: usize = 255;
+const MAX_ENTRIES: usize = 1000;
+const ENTRY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Key from the `Idempotency-Key` header, when the client sent one.
+#[derive(Debug, Clone, Default)]
+pub struct IdempotencyKey(pub Option<String>);
+
+impl<S> FromRequestParts<S> for IdempotencyKey
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(Self(None));
+        };
+
+        value
+            .to_str()
+            .ok()
+            .map(str::trim)
+            .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LENGTH)
+            .map(|key| Self(Some(key.to_string())))
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid {} header", IDEMPOTENCY_KEY_HEADER),
+                )
+            })
+    }
+}
+
+/// Jobs submitted under an idempotency key, so a retried create or sync gets the job the
+/// first attempt enqueued instead of a second deployment. Keys are scoped per workspace,
+/// operation and project, and forgotten after a day or once the cache is full.
+#[derive(Debug, Clone, Default)]
+pub struct IdempotencyCache {
+    entries: Arc<Mutex<HashMap<String, (Instant, Job)>>>,
+}
+
+impl IdempotencyCache {
+    /// Return the job stored for `key` in `scope`, or run `submit` and remember its job.
+    /// Failed submissions are not remembered, so they can be retried. Without a key,
+    /// `submit` always runs.
+    pub fn submit_once<E>(
+        &self,
+        key: &IdempotencyKey,
+        scope: &str,
+        submit: impl FnOnce() -> Result<Job, E>,
+    ) -> Result<Job, E> {
+        let Some(key) = &key.0 else {
+            return submit();
+        };
+        let cache_key = format!("{}\n{}", scope, key);
+
+        // Held across `submit` so concurrent retries cannot both get through.
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, (stored_at, _)| now.duration_since(*stored_at) < ENTRY_TTL);
+        if let Some((_, job)) = entries.get(&cache_key) {
+            return Ok(job.clone());
+        }
+
+        let job = submit()?;
+        if entries.len() >= MAX_ENTRIES {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, (stored_at, _))| *stored_at)
+                .map(|(cache_key, _)| cache_key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(cache_key, (now, job.clone()));
+        Ok(job)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::job::{JobKind, JobStatus};
+    use chrono::Utc;
+
+    fn make_job(id: &str) -> Job {
+        Job {
+            id: id.to_string(),
+            kind: JobKind::SyncProject,
+            project: "demo".to_string(),
+            status: JobStatus::Queued,
+            error: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_ago: None,
+            updated_ago: None,
+        }
+    }
+
+    #[test]
+    fn given_repeated_key_when_submit_once_then_submit_runs_once() {
+        let cache = IdempotencyCache::default();
+        let key = IdempotencyKey(Some("ci-run-42".to_string()));
+
+        let first = cache.submit_once(&key, "sync/demo", || Ok::<_, ()>(make_job("1")));
+        let second = cache.submit_once(&key, "sync/demo", || Ok::<_, ()>(make_job("2")));
+
+        assert_eq!(first.unwrap().id, "1");
+        assert_eq!(second.unwrap().id, "1");
+    }
+
+    #[test]
+    fn given_no_key_or_other_scope_when_submit_once_then_submit_runs_again() {
+        let cache = IdempotencyCache::default();
+        let key = IdempotencyKey(Some("ci-run-42".to_string()));
+        cache
+            .submit_once(&key, "sync/demo", || Ok::<_, ()>(make_job("1")))
+            .unwrap();
+
+        let other_scope = cache.submit_once(&key, "sync/other", || Ok::<_, ()>(make_job("2")));
+        let no_key = cache.submit_once(&IdempotencyKey(None), "sync/demo", || {
+            Ok::<_, ()>(make_job("3"))
+        });
+
+        assert_eq!(other_scope.unwrap().id, "2");
+        assert_eq!(no_key.unwrap().id, "3");
+    }
+
+    #[test]
+    fn given_failed_submission_when_retried_then_submit_runs_again() {
+        let cache = IdempotencyCache::default();
+        let key = IdempotencyKey(Some("ci-run-42".to_string()));
+        let _ = cache.submit_once(&key, "sync/demo", || Err::<Job, _>("busy"));
+
+        let retried = cache.submit_once(&key, "sync/demo", || Ok::<_, &str>(make_job("2")));
+
+        assert_eq!(retried.unwrap().id, "2");
+    }
+}
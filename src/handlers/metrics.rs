@@ -0,0 +1,96 @@
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::time::Instant;
+
+use crate::usecases::job::JobManager;
+use crate::usecases::metrics::{RequestMetrics, RouteKey};
+
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Time every routed request. Only requests that matched a route are recorded, and under
+/// their route template, so label cardinality stays bounded.
+pub async fn record_request_metrics(
+    State(metrics): State<RequestMetrics>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(matched_path) = matched_path else {
+        return next.run(request).await;
+    };
+
+    let method = request.method().to_string();
+    let trace_id = trace_id_from_headers(request.headers());
+    let started_at = Instant::now();
+    let response = next.run(request).await;
+
+    metrics.observe(
+        RouteKey {
+            method,
+            route: matched_path.as_str().to_string(),
+            status: response.status().as_u16(),
+        },
+        started_at.elapsed(),
+        trace_id.as_deref(),
+    );
+    response
+}
+
+pub async fn get_metrics(
+    State(metrics): State<RequestMetrics>,
+    State(jobs): State<JobManager>,
+) -> Response {
+    (
+        [(CONTENT_TYPE, OPENMETRICS_CONTENT_TYPE)],
+        metrics.render(&jobs.queue_stats()),
+    )
+        .into_response()
+}
+
+/// The trace ID from a W3C `traceparent` header (`00-<trace-id>-<parent-id>-<flags>`).
+/// The all-zero trace ID is invalid by spec and ignored.
+fn trace_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    let traceparent = headers.get(TRACEPARENT_HEADER)?.to_str().ok()?;
+    let trace_id = traceparent.split('-').nth(1)?;
+    let valid = trace_id.len() == 32
+        && trace_id.chars().all(|c| c.is_ascii_hexdigit())
+        && trace_id.chars().any(|c| c != '0');
+    valid.then(|| trace_id.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers_with_traceparent(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TRACEPARENT_HEADER, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn given_valid_traceparent_when_trace_id_from_headers_then_return_trace_id() {
+        let headers =
+            headers_with_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+
+        let actual = trace_id_from_headers(&headers);
+
+        assert_eq!(actual, Some("4bf92f3577b34da6a3ce929d0e0e4736".to_string()));
+    }
+
+    #[test]
+    fn given_zero_or_malformed_traceparent_when_trace_id_from_headers_then_return_none() {
+        let zero =
+            headers_with_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01");
+        let malformed = headers_with_traceparent("not-a-traceparent");
+
+        assert_eq!(trace_id_from_headers(&zero), None);
+        assert_eq!(trace_id_from_headers(&malformed), None);
+        assert_eq!(trace_id_from_headers(&HeaderMap::new()), None);
+    }
+}
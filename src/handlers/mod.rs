@@ -1 +1,11 @@
+pub mod admin;
+pub mod compose;
+pub mod conditional;
+pub mod deadline;
+pub mod idempotency;
+#[cfg(feature = "telemetry")]
+pub mod metrics;
+pub mod negotiation;
 pub mod project;
+pub mod tenancy;
+pub mod webhook;
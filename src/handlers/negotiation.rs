@@ -0,0 +1,102 @@
+use axum::extract::FromRequestParts;
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use std::convert::Infallible;
+
+const YAML_MEDIA_TYPES: &[&str] = &["application/yaml", "application/x-yaml", "text/yaml"];
+
+/// Response format picked from the `Accept` header. The first supported media type wins;
+/// anything else, including no header at all, gets JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    Yaml,
+}
+
+impl ResponseFormat {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|media_range| {
+                media_range
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_ascii_lowercase()
+            })
+            .find_map(|media_type| match media_type.as_str() {
+                "application/json" => Some(ResponseFormat::Json),
+                yaml if YAML_MEDIA_TYPES.contains(&yaml) => Some(ResponseFormat::Yaml),
+                _ => None,
+            })
+            .unwrap_or(ResponseFormat::Json)
+    }
+
+    pub fn respond<T: Serialize>(self, body: T) -> Response {
+        match self {
+            ResponseFormat::Json => Json(body).into_response(),
+            ResponseFormat::Yaml => match serde_yaml::to_string(&body) {
+                Ok(yaml) => yaml_response(yaml),
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            },
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for ResponseFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_headers(&parts.headers))
+    }
+}
+
+/// Serve YAML text as is, e.g. a compose file that should keep its comments.
+pub fn yaml_response(yaml: String) -> Response {
+    ([(CONTENT_TYPE, "application/yaml")], yaml).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers_with_accept(accept: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static(accept));
+        headers
+    }
+
+    #[test]
+    fn given_yaml_accept_header_when_from_headers_then_return_yaml() {
+        let headers = headers_with_accept("application/yaml;q=0.9, */*;q=0.1");
+
+        assert_eq!(ResponseFormat::from_headers(&headers), ResponseFormat::Yaml);
+    }
+
+    #[test]
+    fn given_json_listed_before_yaml_when_from_headers_then_return_json() {
+        let headers = headers_with_accept("application/json, application/yaml");
+
+        assert_eq!(ResponseFormat::from_headers(&headers), ResponseFormat::Json);
+    }
+
+    #[test]
+    fn given_no_accept_header_when_from_headers_then_return_json() {
+        assert_eq!(
+            ResponseFormat::from_headers(&HeaderMap::new()),
+            ResponseFormat::Json
+        );
+    }
+}
@@ -1,30 +1,125 @@
 use anyhow::{Error, Result};
+use axum::body::{Body, Bytes};
+use axum::extract::{FromRequest, Path, Query, Request};
+use axum::http::header::{
+    CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, LOCATION, X_CONTENT_TYPE_OPTIONS,
+};
+use axum::http::HeaderMap;
 use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::IntoResponse;
 use axum::response::Response;
 use axum::{extract::State, Json};
+use chrono::Utc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::time::Duration;
 
-use crate::models::project::{Project, ProjectFile};
-use crate::models::response::{GenericResponse, ResponseStatus};
+use crate::config::Config;
+use crate::handlers::conditional::{entity_tag, none_match_hit, not_modified, with_etag};
+use crate::handlers::deadline::RequestDeadline;
+use crate::handlers::idempotency::{IdempotencyCache, IdempotencyKey};
+use crate::handlers::negotiation::{yaml_response, ResponseFormat};
+use crate::handlers::tenancy::{Operator, Workspace};
+use crate::models::activity::ActivityQuery;
+use crate::models::badge::Badge;
+use crate::models::deployment::DeploymentsQuery;
+use crate::models::docker_compose::{ExecOutput, ExecRequest};
+use crate::models::export::{
+    ImportQuery, ImportedProject, PortainerImportRequest, WorkspaceExport,
+};
+use crate::models::humanize::HumanizeQuery;
+use crate::models::job::Job;
+use crate::models::project::{
+    FromComposeQuery, ListProjectsQuery, ManifestError, ManifestFormat, MigrateToGitRequest,
+    ProjectFile, SelectorQuery,
+};
+use crate::models::response::GenericResponse;
+use crate::models::selector::LabelSelector;
+use crate::models::system::{ImageGcReport, SystemInfo};
+use crate::models::validation::ProjectValidation;
+use crate::repositories::blocking;
 use crate::repositories::compose_client::ComposeClient;
 use crate::repositories::git::GitClient;
-use crate::usecases::project::ProjectUsecase;
+use crate::usecases::compose::ComposeUsecaseError;
+use crate::usecases::deadline::Deadline;
+use crate::usecases::project::{ProjectUsecase, ProjectUsecaseError};
+use crate::usecases::tenancy::Caller;
+
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+/// Projects resolved ahead of a slow client before resolution pauses.
+const NDJSON_BUFFERED_LINES: usize = 16;
+/// Events held for a slow client before reading from docker pauses.
+const PROJECT_EVENTS_BUFFERED: usize = 16;
 
 pub struct HandlerError(Error);
 
 impl IntoResponse for HandlerError {
     fn into_response(self) -> Response {
-        (
-            StatusCode::OK,
-            Json(GenericResponse::<String>::error(format!(
-                "Something went wrong: {}",
-                self.0
-            ))),
-        )
-            .into_response()
+        if let Some(ComposeUsecaseError::NotConfigured) = self.0.downcast_ref() {
+            return error_response(StatusCode::NOT_FOUND, self.0.to_string());
+        }
+        let status = if let Some(error) = self.0.downcast_ref::<ProjectUsecaseError>() {
+            project_error_status(error)
+        } else if self.0.is::<ManifestError>() {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+
+        error_response(status, format!("Something went wrong: {}", self.0))
     }
 }
 
+/// Every variant is listed, so a new one has to be given a status here.
+fn project_error_status(error: &ProjectUsecaseError) -> StatusCode {
+    match error {
+        ProjectUsecaseError::ProjectNotFound(_)
+        | ProjectUsecaseError::ServiceNotFound(_)
+        | ProjectUsecaseError::JobNotFound(_)
+        | ProjectUsecaseError::DeploymentNotFound(_)
+        | ProjectUsecaseError::FileNotFound(_) => StatusCode::NOT_FOUND,
+        ProjectUsecaseError::InvalidProject(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ProjectUsecaseError::InvalidFilePath(_)
+        | ProjectUsecaseError::InvalidSelector(_)
+        | ProjectUsecaseError::UnsupportedExportVersion(_) => StatusCode::BAD_REQUEST,
+        ProjectUsecaseError::FileNotServed(_) => StatusCode::FORBIDDEN,
+        ProjectUsecaseError::FileTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        ProjectUsecaseError::PreflightFailed(_)
+        | ProjectUsecaseError::ProjectDeleting(_)
+        | ProjectUsecaseError::ProjectBusy { .. }
+        | ProjectUsecaseError::JobFinished(_)
+        | ProjectUsecaseError::RollbackUnavailable(_)
+        | ProjectUsecaseError::DeploymentNotPending(_)
+        | ProjectUsecaseError::DiffUnavailable(_) => StatusCode::CONFLICT,
+        ProjectUsecaseError::DeadlineExceeded(_) => StatusCode::GATEWAY_TIMEOUT,
+        ProjectUsecaseError::QuotaExceeded(_) => StatusCode::INSUFFICIENT_STORAGE,
+        ProjectUsecaseError::CreateProjectFailed(_)
+        | ProjectUsecaseError::ListProjectsFailed(_)
+        | ProjectUsecaseError::ReadActivityFailed(_)
+        | ProjectUsecaseError::ReadStatusFailed(_)
+        | ProjectUsecaseError::PauseFailed(_)
+        | ProjectUsecaseError::PortainerImportFailed(_)
+        | ProjectUsecaseError::MigrationFailed(_)
+        | ProjectUsecaseError::SecretFailed(_)
+        | ProjectUsecaseError::PruneNetworksFailed(_)
+        | ProjectUsecaseError::ScheduleFailed(_)
+        | ProjectUsecaseError::ReadComposeFileFailed(_)
+        | ProjectUsecaseError::ExecFailed(_)
+        | ProjectUsecaseError::ReadHistoryFailed(_)
+        | ProjectUsecaseError::ApprovalFailed(_)
+        | ProjectUsecaseError::DiffFailed(_)
+        | ProjectUsecaseError::DeleteProjectFailed(_)
+        | ProjectUsecaseError::PruneFailed(_)
+        | ProjectUsecaseError::DiskUsageFailed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(GenericResponse::<String>::error(message))).into_response()
+}
+
 impl<E> From<E> for HandlerError
 where
     E: Into<anyhow::Error>,
@@ -34,23 +129,944 @@ where
     }
 }
 
+/// A project file in any supported manifest format, chosen by the request's
+/// `Content-Type`. Requests without one are read as JSON.
+pub struct Manifest(pub ProjectFile);
+
+impl<S> FromRequest<S> for Manifest
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/json")
+            .to_string();
+        let format = ManifestFormat::from_content_type(&content_type).ok_or_else(|| {
+            error_response(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Unsupported content type: {}", content_type),
+            )
+        })?;
+        let body = String::from_request(req, state)
+            .await
+            .map_err(|e| error_response(e.status(), e.body_text()))?;
+
+        format
+            .parse(&body)
+            .map(Self)
+            .map_err(|e| HandlerError::from(e).into_response())
+    }
+}
+
 pub async fn get_projects<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    RequestDeadline(deadline): RequestDeadline,
+    Query(query): Query<ListProjectsQuery>,
+    format: ResponseFormat,
+    headers: HeaderMap,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let selector = query
+        .selector
+        .as_deref()
+        .unwrap_or_default()
+        .parse::<LabelSelector>()
+        .map_err(ProjectUsecaseError::from)?;
+    if query.format.as_deref() == Some("ndjson") {
+        return Ok(stream_projects(usecase, deadline, query.humanize, selector));
+    }
+
+    let humanize = query.humanize;
+    let (projects, hash) =
+        blocking::run(move || usecase.list_projects_with_etag(&deadline, humanize, &selector))
+            .await??;
+    let etag = entity_tag(&hash, format);
+    if none_match_hit(&headers, &etag) {
+        return Ok(not_modified(&etag));
+    }
+
+    Ok(with_etag(format.respond(projects), &etag))
+}
+
+/// One project per line, each written as soon as its status is resolved. A failure ends
+/// the stream with an `{"error": ...}` line, since the status code has already been sent.
+fn stream_projects<C, G>(
+    usecase: ProjectUsecase<C, G>,
+    deadline: Deadline,
+    humanize: bool,
+    selector: LabelSelector,
+) -> Response
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let (sender, receiver) = tokio::sync::mpsc::channel::<String>(NDJSON_BUFFERED_LINES);
+
+    blocking::spawn(move || {
+        let projects = match usecase.resolve_projects_lazily(&deadline, &selector) {
+            Ok(projects) => projects,
+            Err(e) => {
+                let _ = sender.blocking_send(ndjson_error(&e));
+                return;
+            }
+        };
+
+        for project in projects {
+            let (line, failed) = match project {
+                Ok(mut project) => {
+                    if humanize {
+                        project.humanize(Utc::now());
+                    }
+                    (ndjson_line(&project), false)
+                }
+                Err(e) => (ndjson_error(&e), true),
+            };
+            // A send error means the client went away.
+            if sender.blocking_send(line).is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    let lines = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let line = receiver.recv().await?;
+        Some((Ok::<_, Infallible>(line), receiver))
+    });
+    (
+        [(CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+fn ndjson_line<T: Serialize>(value: &T) -> String {
+    match serde_json::to_string(value) {
+        Ok(json) => format!("{}\n", json),
+        Err(e) => ndjson_error(&e),
+    }
+}
+
+fn ndjson_error(error: &dyn std::fmt::Display) -> String {
+    format!("{}\n", serde_json::json!({ "error": error.to_string() }))
+}
+
+/// YAML responses are the bare project file, so they can be saved and re-applied as is.
+pub async fn get_project_manifest<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let project_file = blocking::run(move || usecase.find_project_file(&name))
+        .await??
+        .redacted();
+    Ok(match format {
+        ResponseFormat::Json => format.respond(GenericResponse::result(project_file)),
+        ResponseFormat::Yaml => format.respond(project_file),
+    })
+}
+
+pub async fn get_project_status<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    RequestDeadline(deadline): RequestDeadline,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let status = blocking::run(move || usecase.project_status(&deadline, &name)).await??;
+    Ok(format.respond(status))
+}
+
+/// Server-sent events, one JSON `ProjectEvent` per message, until the client goes away.
+pub async fn get_project_events<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let events = blocking::run(move || usecase.project_events(&name)).await??;
+    let (sender, receiver) = tokio::sync::mpsc::channel(PROJECT_EVENTS_BUFFERED);
+
+    // A plain thread rather than the blocking pool, since it lives as long as the
+    // connection. It notices a closed connection at the next event.
+    std::thread::spawn(move || {
+        for event in events {
+            if sender.blocking_send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    let events = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Event::default().json_data(event), receiver))
+    });
+    Ok(Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+/// Always answers with an image, so a broken embed in a README still shows something.
+/// Unknown projects get a grey `not found` badge with a 404. Image proxies such as
+/// GitHub's revalidate on every view, so the badge carries an ETag over its SVG and an
+/// unchanged one is answered with a bodiless 304.
+pub async fn get_project_badge<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Response
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let summary = {
+        let name = name.clone();
+        blocking::run(move || usecase.project_status_summary(&name)).await
+    };
+    let (status, badge) = match summary {
+        Ok(Ok(project_status)) => (StatusCode::OK, Badge::for_status(&name, &project_status)),
+        Ok(Err(ProjectUsecaseError::ProjectNotFound(_))) => {
+            (StatusCode::NOT_FOUND, Badge::not_found(&name))
+        }
+        Ok(Err(_)) | Err(_) => (StatusCode::OK, Badge::unknown(&name)),
+    };
+    let svg = badge.render_svg();
+    let etag = format!("\"{}\"", hex::encode(Sha256::digest(svg.as_bytes())));
+    let cache_control = [(CACHE_CONTROL, "no-cache, max-age=0")];
+    if status == StatusCode::OK && none_match_hit(&headers, &etag) {
+        return (cache_control, not_modified(&etag)).into_response();
+    }
+
+    let response = (
+        status,
+        cache_control,
+        [(CONTENT_TYPE, "image/svg+xml")],
+        svg,
+    )
+        .into_response();
+    with_etag(response, &etag)
+}
+
+/// YAML responses are the compose file text itself, comments included.
+pub async fn get_project_compose<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    RequestDeadline(deadline): RequestDeadline,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let compose_file =
+        blocking::run(move || usecase.project_compose_file(&deadline, &name)).await??;
+    Ok(match format {
+        ResponseFormat::Json => {
+            format.respond(GenericResponse::result(compose_file.document().clone()))
+        }
+        ResponseFormat::Yaml => yaml_response(compose_file.source().to_string()),
+    })
+}
+
+/// A manifest with `environments` creates a project per environment, answered with each
+/// of them as imports are.
+pub async fn create_project<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    caller: Caller,
+    State(idempotency): State<IdempotencyCache>,
+    key: IdempotencyKey,
+    Manifest(project_file): Manifest,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    // Creating environments skips those that exist, so it needs no idempotency key.
+    if !project_file.environments.is_empty() {
+        let created = blocking::run(move || usecase.create_environments(project_file)).await??;
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(GenericResponse::results(created)),
+        )
+            .into_response());
+    }
+    let job = blocking::run(move || {
+        let scope = format!("{}/create/{}", caller, project_file.name);
+        let job = idempotency.submit_once(&key, &scope, || usecase.create_project(project_file))?;
+        Ok::<_, Error>(latest(&usecase, job))
+    })
+    .await??;
+    Ok(job_accepted(job))
+}
+
+/// The body is the compose file itself; the project name comes from `?name=`.
+pub async fn create_project_from_compose<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    caller: Caller,
+    State(idempotency): State<IdempotencyCache>,
+    key: IdempotencyKey,
+    Query(query): Query<FromComposeQuery>,
+    compose: String,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let job = blocking::run(move || {
+        let scope = format!("{}/create/{}", caller, query.name);
+        let job = idempotency.submit_once(&key, &scope, || {
+            usecase.create_inline_project(&query.name, &compose)
+        })?;
+        Ok::<_, Error>(latest(&usecase, job))
+    })
+    .await??;
+    Ok(job_accepted(job))
+}
+
+/// Sync every project the `selector` query parameter selects, answered with the job
+/// queued for each.
+pub async fn sync_selected_projects<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Query(query): Query<SelectorQuery>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let selector = query
+        .selector
+        .parse::<LabelSelector>()
+        .map_err(ProjectUsecaseError::from)?;
+    let queued = blocking::run(move || usecase.sync_selected(&selector)).await??;
+    Ok((StatusCode::ACCEPTED, Json(GenericResponse::results(queued))).into_response())
+}
+
+pub async fn migrate_to_git<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    Json(request): Json<MigrateToGitRequest>,
+) -> Result<Json<GenericResponse<ProjectFile>>, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let project_file = blocking::run(move || usecase.migrate_to_git(&name, &request)).await??;
+    Ok(Json(GenericResponse::result(project_file)))
+}
+
+pub async fn validate_project<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    RequestDeadline(deadline): RequestDeadline,
+    Manifest(project_file): Manifest,
+) -> Result<Json<GenericResponse<ProjectValidation>>, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let validation =
+        blocking::run(move || usecase.validate_project(&deadline, &project_file)).await??;
+    Ok(Json(GenericResponse::result(validation)))
+}
+
+pub async fn prune_orphans<C, G>(
+    _operator: Operator,
+    State(usecase): State<ProjectUsecase<C, G>>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let report = blocking::run(move || usecase.prune_orphans()).await??;
+    Ok(format.respond(GenericResponse::result(report)))
+}
+
+/// What `POST /maintenance/images/prune` would remove, under the configured retention.
+pub async fn get_unused_images<C, G>(
+    _operator: Operator,
+    State(usecase): State<ProjectUsecase<C, G>>,
+    State(config): State<Config>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let retention = Duration::from_secs(config.image_gc.retention_secs);
+    let removed = blocking::run(move || usecase.unused_images(retention)).await??;
+    Ok(format.respond(GenericResponse::result(ImageGcReport {
+        removed,
+        failed: vec![],
+    })))
+}
+
+pub async fn prune_unused_images<C, G>(
+    _operator: Operator,
     State(usecase): State<ProjectUsecase<C, G>>,
-) -> Result<Json<GenericResponse<Project>>, HandlerError>
+    State(config): State<Config>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let retention = Duration::from_secs(config.image_gc.retention_secs);
+    let report = blocking::run(move || usecase.collect_unused_images(retention)).await??;
+    Ok(format.respond(GenericResponse::result(report)))
+}
+
+pub async fn pause_project<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let project = blocking::run(move || usecase.pause_project(&name)).await??;
+    Ok(format.respond(project))
+}
+
+pub async fn unpause_project<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let project = blocking::run(move || usecase.unpause_project(&name)).await??;
+    Ok(format.respond(project))
+}
+
+pub async fn sync_project<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    caller: Caller,
+    State(idempotency): State<IdempotencyCache>,
+    key: IdempotencyKey,
+    Path(name): Path<String>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let job = blocking::run(move || {
+        let scope = format!("{}/sync/{}", caller, name);
+        let job = idempotency.submit_once(&key, &scope, || usecase.sync_project(&name))?;
+        Ok::<_, Error>(latest(&usecase, job))
+    })
+    .await??;
+    Ok(job_accepted(job))
+}
+
+pub async fn rollback_project<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    caller: Caller,
+    State(idempotency): State<IdempotencyCache>,
+    key: IdempotencyKey,
+    Path((name, id)): Path<(String, i64)>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let job = blocking::run(move || {
+        let scope = format!("{}/rollback/{}/{}", caller, name, id);
+        let job = idempotency.submit_once(&key, &scope, || usecase.rollback_project(&name, id))?;
+        Ok::<_, Error>(latest(&usecase, job))
+    })
+    .await??;
+    Ok(job_accepted(job))
+}
+
+pub async fn approve_deployment<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    caller: Caller,
+    State(idempotency): State<IdempotencyCache>,
+    key: IdempotencyKey,
+    Path((name, id)): Path<(String, i64)>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let job = blocking::run(move || {
+        let scope = format!("{}/approve/{}/{}", caller, name, id);
+        let job =
+            idempotency.submit_once(&key, &scope, || usecase.approve_deployment(&name, id))?;
+        Ok::<_, Error>(latest(&usecase, job))
+    })
+    .await??;
+    Ok(job_accepted(job))
+}
+
+pub async fn delete_project<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    caller: Caller,
+    State(idempotency): State<IdempotencyCache>,
+    key: IdempotencyKey,
+    Path(name): Path<String>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let job = blocking::run(move || {
+        let scope = format!("{}/delete/{}", caller, name);
+        let job = idempotency.submit_once(&key, &scope, || usecase.delete_project(&name))?;
+        Ok::<_, Error>(latest(&usecase, job))
+    })
+    .await??;
+    Ok(job_accepted(job))
+}
+
+/// A replayed job may have moved on since it was cached. Jobs that have already been
+/// dropped from the job list are returned as last seen.
+fn latest<C, G>(usecase: &ProjectUsecase<C, G>, job: Job) -> Job
 where
     C: ComposeClient + Send + Sync,
     G: GitClient + Send + Sync,
 {
-    Ok(Json(usecase.list_projects()?))
+    usecase.job(&job.id).unwrap_or(job)
 }
 
-pub async fn create_project<C, G>(
-    State(usecase): State<ProjectUsecase<C, G>>,
-    Json(project_file): Json<ProjectFile>,
-) -> Result<Json<GenericResponse<ResponseStatus>>, HandlerError>
+pub async fn get_job<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(id): Path<String>,
+    Query(query): Query<HumanizeQuery>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync,
+    G: GitClient + Send + Sync,
+{
+    let job = humanized(blocking::run(move || usecase.job(&id)).await??, &query);
+    Ok(format.respond(GenericResponse::result(job)))
+}
+
+/// A running job is returned while its command is being killed, and shows as cancelled
+/// once it has stopped.
+pub async fn cancel_job<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(id): Path<String>,
+    Query(query): Query<HumanizeQuery>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync,
+    G: GitClient + Send + Sync,
+{
+    let job = humanized(
+        blocking::run(move || usecase.cancel_job(&id)).await??,
+        &query,
+    );
+    Ok(format.respond(GenericResponse::result(job)))
+}
+
+fn humanized(mut job: Job, query: &HumanizeQuery) -> Job {
+    if query.humanize {
+        job.humanize(Utc::now());
+    }
+    job
+}
+
+pub async fn get_job_queues<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    format: ResponseFormat,
+) -> Response
 where
     C: ComposeClient + Send + Sync,
     G: GitClient + Send + Sync,
 {
-    Ok(Json(usecase.create_project(project_file)?))
+    format.respond(GenericResponse::results(usecase.job_queues()))
+}
+
+/// The bundle is served as a download and can be posted to `/import` unchanged.
+pub async fn export_workspace<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let export = blocking::run(move || usecase.export_workspace()).await??;
+    Ok((
+        [(
+            CONTENT_DISPOSITION,
+            "attachment; filename=\"gfc-export.json\"",
+        )],
+        Json(export),
+    )
+        .into_response())
+}
+
+pub async fn import_workspace<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Query(query): Query<ImportQuery>,
+    Json(export): Json<WorkspaceExport>,
+) -> Result<Json<GenericResponse<ImportedProject>>, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let imported = blocking::run(move || usecase.import_workspace(export, query.deploy)).await??;
+    Ok(Json(GenericResponse::results(imported)))
+}
+
+pub async fn import_portainer_stacks<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Json(request): Json<PortainerImportRequest>,
+) -> Result<Json<GenericResponse<ImportedProject>>, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let imported = blocking::run(move || usecase.import_portainer_stacks(&request)).await??;
+    Ok(Json(GenericResponse::results(imported)))
+}
+
+pub async fn get_system_info<C, G>(
+    _operator: Operator,
+    State(usecase): State<ProjectUsecase<C, G>>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let info: SystemInfo = blocking::run(move || usecase.system_info()).await??;
+    Ok(format.respond(GenericResponse::result(info)))
+}
+
+/// `202 Accepted`, pointing at the job to poll for the outcome.
+fn job_accepted(job: Job) -> Response {
+    let location = format!("/jobs/{}", job.id);
+    (
+        StatusCode::ACCEPTED,
+        [(LOCATION, location)],
+        Json(GenericResponse::result(job)),
+    )
+        .into_response()
+}
+
+pub async fn get_project_activity<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    Query(query): Query<ActivityQuery>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let activity = blocking::run(move || usecase.project_activity(&name, &query)).await??;
+    Ok(format.respond(activity))
+}
+
+pub async fn get_project_deployments<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    Query(query): Query<DeploymentsQuery>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let deployments = blocking::run(move || usecase.project_deployments(&name, &query)).await??;
+    Ok(format.respond(deployments))
+}
+
+pub async fn get_scheduled_actions<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let actions = blocking::run(move || usecase.scheduled_actions(&name)).await??;
+    Ok(format.respond(GenericResponse::results(actions)))
+}
+
+pub async fn get_project_disk_usage<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let usage = blocking::run(move || usecase.disk_usage(&name)).await??;
+    Ok(format.respond(GenericResponse::result(usage)))
+}
+
+/// What a sync would change, to review before syncing.
+pub async fn get_project_diff<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+    RequestDeadline(deadline): RequestDeadline,
+    format: ResponseFormat,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let diff = blocking::run(move || usecase.diff_project(&deadline, &name)).await??;
+    Ok(format.respond(GenericResponse::result(diff)))
+}
+
+/// Served with `nosniff` and, outside JSON and YAML, as plain text, so a page checked
+/// into the repository cannot run in the API's origin.
+pub async fn get_repository_file<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path((name, path)): Path<(String, String)>,
+) -> Result<Response, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let contents = {
+        let path = path.clone();
+        blocking::run(move || usecase.repository_file(&name, &path)).await??
+    };
+    let content_type = match std::str::from_utf8(&contents) {
+        Err(_) => "application/octet-stream",
+        Ok(_) => match path.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("json") => "application/json",
+            Some("yml" | "yaml") => "application/yaml",
+            _ => "text/plain; charset=utf-8",
+        },
+    };
+
+    Ok((
+        [
+            (CONTENT_TYPE, content_type),
+            (X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        contents,
+    )
+        .into_response())
+}
+
+pub async fn list_secrets<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path(name): Path<String>,
+) -> Result<Json<GenericResponse<String>>, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let secrets = blocking::run(move || usecase.list_secrets(&name)).await??;
+    Ok(Json(GenericResponse::results(secrets)))
+}
+
+/// The body is the secret's value, stored as is.
+pub async fn put_secret<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path((name, secret)): Path<(String, String)>,
+    value: Bytes,
+) -> Result<StatusCode, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    blocking::run(move || usecase.put_secret(&name, &secret, &value)).await??;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_secret<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path((name, secret)): Path<(String, String)>,
+) -> Result<StatusCode, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    blocking::run(move || usecase.delete_secret(&name, &secret)).await??;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn exec_in_service<C, G>(
+    Workspace(usecase): Workspace<C, G>,
+    Path((name, service)): Path<(String, String)>,
+    Json(request): Json<ExecRequest>,
+) -> Result<Json<GenericResponse<ExecOutput>>, HandlerError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    let output =
+        blocking::run(move || usecase.exec_in_service(&name, &service, &request)).await??;
+    Ok(Json(GenericResponse::result(output)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::selector::SelectorError;
+    use crate::usecases::preflight::PreflightReport;
+    use crate::usecases::validation::ValidationError;
+
+    fn status_of<E: Into<Error>>(error: E) -> StatusCode {
+        HandlerError::from(error).into_response().status()
+    }
+
+    #[test]
+    fn given_missing_project_service_job_deployment_or_file_when_responding_then_return_not_found()
+    {
+        for error in [
+            ProjectUsecaseError::ProjectNotFound("shop".to_string()),
+            ProjectUsecaseError::ServiceNotFound("shop/web".to_string()),
+            ProjectUsecaseError::JobNotFound("job-1".to_string()),
+            ProjectUsecaseError::DeploymentNotFound("shop/7".to_string()),
+            ProjectUsecaseError::FileNotFound("README.md".to_string()),
+        ] {
+            assert_eq!(status_of(error), StatusCode::NOT_FOUND);
+        }
+        assert_eq!(
+            status_of(ComposeUsecaseError::NotConfigured),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn given_invalid_project_when_responding_then_return_unprocessable_entity() {
+        let error = ProjectUsecaseError::InvalidProject(ValidationError::EmptySourceUrl);
+
+        assert_eq!(status_of(error), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn given_malformed_request_when_responding_then_return_bad_request() {
+        for error in [
+            ProjectUsecaseError::InvalidFilePath("../etc/passwd".to_string()),
+            ProjectUsecaseError::InvalidSelector(SelectorError::InvalidRequirement(
+                "=".to_string(),
+            )),
+            ProjectUsecaseError::UnsupportedExportVersion(99),
+        ] {
+            assert_eq!(status_of(error), StatusCode::BAD_REQUEST);
+        }
+        let manifest = ManifestFormat::Json.parse("{").unwrap_err();
+        assert_eq!(status_of(manifest), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn given_secret_or_oversized_file_when_responding_then_return_forbidden_or_too_large() {
+        assert_eq!(
+            status_of(ProjectUsecaseError::FileNotServed(".env".to_string())),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status_of(ProjectUsecaseError::FileTooLarge {
+                path: "dump.sql".to_string(),
+                limit: 1,
+            }),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[test]
+    fn given_project_in_wrong_state_when_responding_then_return_conflict() {
+        for error in [
+            ProjectUsecaseError::PreflightFailed(PreflightReport(vec![])),
+            ProjectUsecaseError::ProjectDeleting("shop".to_string()),
+            ProjectUsecaseError::ProjectBusy {
+                name: "shop".to_string(),
+                operation: "deployment".to_string(),
+            },
+            ProjectUsecaseError::JobFinished("job-1".to_string()),
+            ProjectUsecaseError::RollbackUnavailable("shop".to_string()),
+            ProjectUsecaseError::DeploymentNotPending("shop/7".to_string()),
+            ProjectUsecaseError::DiffUnavailable("shop".to_string()),
+        ] {
+            assert_eq!(status_of(error), StatusCode::CONFLICT);
+        }
+    }
+
+    #[test]
+    fn given_deadline_or_quota_exceeded_when_responding_then_return_timeout_or_insufficient_storage(
+    ) {
+        assert_eq!(
+            status_of(ProjectUsecaseError::DeadlineExceeded("listing".to_string())),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+        assert_eq!(
+            status_of(ProjectUsecaseError::QuotaExceeded("shop".to_string())),
+            StatusCode::INSUFFICIENT_STORAGE
+        );
+    }
+
+    #[test]
+    fn given_failed_operation_or_unknown_error_when_responding_then_return_internal_server_error() {
+        assert_eq!(
+            status_of(ProjectUsecaseError::CreateProjectFailed(
+                "clone".to_string()
+            )),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            status_of(ComposeUsecaseError::ListFailed("io".to_string())),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(
+            status_of(anyhow::anyhow!("unexpected")),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
 }
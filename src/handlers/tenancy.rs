@@ -0,0 +1,94 @@
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use crate::models::response::GenericResponse;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::ProjectUsecase;
+use crate::usecases::tenancy::{Caller, Tenancy, TenancyError, Workspaces};
+
+impl IntoResponse for TenancyError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            TenancyError::MissingToken | TenancyError::InvalidToken => StatusCode::UNAUTHORIZED,
+            TenancyError::OperatorOnly => StatusCode::FORBIDDEN,
+        };
+
+        (
+            status,
+            Json(GenericResponse::<String>::error(self.to_string())),
+        )
+            .into_response()
+    }
+}
+
+/// The workspace of the caller the request's bearer token belongs to.
+pub struct Workspace<C, G>(pub ProjectUsecase<C, G>)
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static;
+
+impl<S, C, G> FromRequestParts<S> for Workspace<C, G>
+where
+    S: Send + Sync,
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    Tenancy: FromRef<S>,
+    Workspaces<C, G>: FromRef<S>,
+    ProjectUsecase<C, G>: Clone,
+{
+    type Rejection = TenancyError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let caller = caller(parts, state)?;
+        Ok(Self(Workspaces::from_ref(state).of(&caller)?))
+    }
+}
+
+/// Admits only the operator, for endpoints that reach past a single workspace, such as
+/// the Docker host or the server's configuration.
+pub struct Operator;
+
+impl<S> FromRequestParts<S> for Operator
+where
+    S: Send + Sync,
+    Tenancy: FromRef<S>,
+{
+    type Rejection = TenancyError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match caller(parts, state)? {
+            Caller::Operator => Ok(Self),
+            Caller::Tenant(_) => Err(TenancyError::OperatorOnly),
+        }
+    }
+}
+
+/// Who the request's bearer token belongs to.
+impl<S> FromRequestParts<S> for Caller
+where
+    S: Send + Sync,
+    Tenancy: FromRef<S>,
+{
+    type Rejection = TenancyError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        caller(parts, state)
+    }
+}
+
+fn caller<S>(parts: &Parts, state: &S) -> Result<Caller, TenancyError>
+where
+    Tenancy: FromRef<S>,
+{
+    let token = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    Tenancy::from_ref(state).authenticate(token)
+}
@@ -0,0 +1,139 @@
+use axum::body::Bytes;
+use axum::extract::{Path, Query};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{extract::State, Json};
+
+use crate::handlers::tenancy::Operator;
+use crate::models::response::GenericResponse;
+use crate::models::webhook::{DryRunQuery, WebhookMatch};
+use crate::repositories::blocking;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::ProjectUsecaseError;
+use crate::usecases::webhook::{WebhookError, WebhookWorkspaces};
+
+impl IntoResponse for WebhookError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            WebhookError::NotConfigured(_) => StatusCode::NOT_FOUND,
+            WebhookError::MissingSignature
+            | WebhookError::InvalidSignature
+            | WebhookError::InvalidToken => StatusCode::UNAUTHORIZED,
+            WebhookError::InvalidPayload(_) | WebhookError::UnknownForge(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            WebhookError::Project(ProjectUsecaseError::ProjectNotFound(_)) => StatusCode::NOT_FOUND,
+            WebhookError::Project(_) | WebhookError::Interrupted(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        (
+            status,
+            Json(GenericResponse::<String>::error(self.to_string())),
+        )
+            .into_response()
+    }
+}
+
+pub async fn github_webhook<C, G>(
+    State(workspaces): State<WebhookWorkspaces<C, G>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<GenericResponse<String>>, WebhookError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let event = header_value(&headers, "X-GitHub-Event")
+        .unwrap_or_default()
+        .to_string();
+    let signature = header_value(&headers, "X-Hub-Signature-256").map(str::to_string);
+
+    Ok(Json(GenericResponse::results(
+        blocking::run(move || workspaces.handle_github(&event, signature.as_deref(), &body))
+            .await??,
+    )))
+}
+
+pub async fn gitlab_webhook<C, G>(
+    State(workspaces): State<WebhookWorkspaces<C, G>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<GenericResponse<String>>, WebhookError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let token = header_value(&headers, "X-Gitlab-Token").map(str::to_string);
+
+    Ok(Json(GenericResponse::results(
+        blocking::run(move || workspaces.handle_gitlab(token.as_deref(), &body)).await??,
+    )))
+}
+
+/// Forgejo sends its own signature header alongside the Gitea one; either is accepted.
+pub async fn gitea_webhook<C, G>(
+    State(workspaces): State<WebhookWorkspaces<C, G>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<GenericResponse<String>>, WebhookError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let event = header_value(&headers, "X-Gitea-Event")
+        .or_else(|| header_value(&headers, "X-Forgejo-Event"))
+        .unwrap_or_default()
+        .to_string();
+    let signature = header_value(&headers, "X-Gitea-Signature")
+        .or_else(|| header_value(&headers, "X-Forgejo-Signature"))
+        .map(str::to_string);
+
+    Ok(Json(GenericResponse::results(
+        blocking::run(move || workspaces.handle_gitea(&event, signature.as_deref(), &body))
+            .await??,
+    )))
+}
+
+/// For CI systems without a dedicated integration. The body is signed like GitHub's,
+/// with the project's own secret, and sent as `X-Gfc-Signature-256: sha256=<hex>`.
+pub async fn generic_webhook<C, G>(
+    State(workspaces): State<WebhookWorkspaces<C, G>>,
+    Path(project): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<GenericResponse<String>>, WebhookError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let signature = header_value(&headers, "X-Gfc-Signature-256").map(str::to_string);
+
+    Ok(Json(GenericResponse::results(
+        blocking::run(move || workspaces.handle_generic(&project, signature.as_deref(), &body))
+            .await??,
+    )))
+}
+
+/// Reports which projects a push payload would sync, through their own source or the
+/// configured rules. Nothing is verified or synced, so only the operator may ask.
+pub async fn dry_run_webhook<C, G>(
+    _operator: Operator,
+    State(workspaces): State<WebhookWorkspaces<C, G>>,
+    Query(query): Query<DryRunQuery>,
+    body: Bytes,
+) -> Result<Json<GenericResponse<WebhookMatch>>, WebhookError>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    Ok(Json(GenericResponse::results(
+        blocking::run(move || workspaces.main().dry_run(&query.forge, &body)).await??,
+    )))
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
@@ -1,26 +1,432 @@
+//! GitOps for docker compose projects.
+//!
+//! The HTTP server started by [`init`] is one way to drive gfc. The engine underneath is
+//! plain library code: build a [`ProjectUsecase`] from a compose client, a git client
+//! and a [`ResourcesConfig`](config::ResourcesConfig), and call it directly. Nothing
+//! reads a config file unless asked to.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use gfc::config::ResourcesConfig;
+//! use gfc::repositories::docker_compose_client::DockerComposeClient;
+//! use gfc::repositories::git::GitClientImpl;
+//! use gfc::usecases::deadline::Deadline;
+//! use gfc::usecases::project::ProjectUsecase;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let usecase = ProjectUsecase::new(
+//!     Arc::new(DockerComposeClient::new()?),
+//!     Arc::new(GitClientImpl::default()),
+//!     ResourcesConfig::new("/srv/gfc/projects", "/srv/gfc/repositories"),
+//! );
+//! for project in usecase.resolve_projects(&Deadline::none())? {
+//!     println!("{}: {}", project.name, project.status);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To serve the HTTP API from an existing program, pass a [`Config`] to [`serve`], or
+//! mount the router from [`build_app_with`] in an axum app of your own.
+
+pub mod cli;
 pub mod config;
+pub mod errors;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod handlers;
 pub mod models;
 pub mod repositories;
 pub mod usecases;
 
 use anyhow::Result;
-use axum::routing::{get, post};
+use axum::extract::FromRef;
+#[cfg(feature = "telemetry")]
+use axum::middleware;
+use axum::routing::{delete, get, post, put};
 use axum::Router;
-use std::sync::Arc;
+#[cfg(feature = "grpc")]
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tower_http::compression::predicate::{NotForContentType, Predicate};
+use tower_http::compression::{CompressionLayer, DefaultPredicate};
+use tower_http::timeout::RequestBodyTimeoutLayer;
 
-use crate::config::Config;
-use crate::handlers::project::{create_project, get_projects};
+use crate::config::{Config, ResourcesConfig, ServerConfig, TlsConfig};
+#[cfg(feature = "grpc")]
+use crate::grpc::GrpcProjectService;
+use crate::handlers::admin::get_config;
+use crate::handlers::compose::get_compose_projects;
+use crate::handlers::idempotency::IdempotencyCache;
+#[cfg(feature = "telemetry")]
+use crate::handlers::metrics::{get_metrics, record_request_metrics};
+use crate::handlers::project::{
+    approve_deployment, cancel_job, create_project, create_project_from_compose, delete_project,
+    delete_secret, exec_in_service, export_workspace, get_job, get_job_queues,
+    get_project_activity, get_project_badge, get_project_compose, get_project_deployments,
+    get_project_diff, get_project_disk_usage, get_project_events, get_project_manifest,
+    get_project_status, get_projects, get_repository_file, get_scheduled_actions, get_system_info,
+    get_unused_images, import_portainer_stacks, import_workspace, list_secrets, migrate_to_git,
+    pause_project, prune_orphans, prune_unused_images, put_secret, rollback_project, sync_project,
+    sync_selected_projects, unpause_project, validate_project,
+};
+use crate::handlers::webhook::{
+    dry_run_webhook, generic_webhook, gitea_webhook, github_webhook, gitlab_webhook,
+};
+use crate::models::bootstrap::Bootstrap;
+use crate::repositories::blocking;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::credentials::CredentialCipher;
 use crate::repositories::docker_compose_client::DockerComposeClient;
-use crate::repositories::git::GitClientImpl;
+use crate::repositories::git::{GitClient, GitClientImpl};
+use crate::repositories::usage_reporter::{LogUsageReporter, UsageReporter};
+use crate::usecases::compose::ComposeUsecase;
+use crate::usecases::image_gc::ImageCollector;
+use crate::usecases::image_watch::ImageWatcher;
+#[cfg(feature = "telemetry")]
+use crate::usecases::job::JobManager;
+#[cfg(feature = "telemetry")]
+use crate::usecases::metrics::RequestMetrics;
 use crate::usecases::project::ProjectUsecase;
+use crate::usecases::reconciler::{converge, Reconciler};
+use crate::usecases::schedule::Scheduler;
+use crate::usecases::tenancy::{Tenancy, Workspaces};
+use crate::usecases::usage::UsageStatsReporter;
+use crate::usecases::webhook::{WebhookUsecase, WebhookWorkspaces};
+
+#[derive(Debug, Clone)]
+pub struct AppState<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    pub project_usecase: ProjectUsecase<C, G>,
+    /// The webhooks of `workspaces`.
+    pub webhook_workspaces: WebhookWorkspaces<C, G>,
+    pub compose_usecase: ComposeUsecase<C>,
+    pub server_config: ServerConfig,
+    /// The full configuration as loaded, for `GET /admin/config`.
+    pub config: Config,
+    pub idempotency: IdempotencyCache,
+    pub tenancy: Tenancy,
+    /// `project_usecase` and those of the tenants.
+    pub workspaces: Workspaces<C, G>,
+    #[cfg(feature = "telemetry")]
+    pub request_metrics: RequestMetrics,
+}
+
+#[cfg(feature = "telemetry")]
+impl<C, G> FromRef<AppState<C, G>> for RequestMetrics
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.request_metrics.clone()
+    }
+}
+
+#[cfg(feature = "telemetry")]
+impl<C, G> FromRef<AppState<C, G>> for JobManager
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.project_usecase.jobs.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for ServerConfig
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.server_config.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for IdempotencyCache
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.idempotency.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for Tenancy
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.tenancy.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for Workspaces<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    Workspaces<C, G>: Clone,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.workspaces.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for Config
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.config.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for ProjectUsecase<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.project_usecase.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for ComposeUsecase<C>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.compose_usecase.clone()
+    }
+}
+
+impl<C, G> FromRef<AppState<C, G>> for WebhookWorkspaces<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    WebhookWorkspaces<C, G>: Clone,
+{
+    fn from_ref(state: &AppState<C, G>) -> Self {
+        state.webhook_workspaces.clone()
+    }
+}
+
+/// The clients and configuration the app is assembled from. `init` wires in the docker
+/// and git CLIs; tests and embedders can pass in-memory fakes instead.
+#[derive(Debug, Clone)]
+pub struct AppDependencies<C, G> {
+    pub compose_client: Arc<C>,
+    pub git_client: Arc<G>,
+    pub config: Config,
+    /// Seals stored secrets, e.g. the cipher for the key in `GFC_MASTER_KEY`. With `None`
+    /// they are written unsealed.
+    pub secrets_cipher: Option<CredentialCipher>,
+}
+
+impl<C, G> AppState<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    pub fn new(dependencies: AppDependencies<C, G>) -> Self {
+        let AppDependencies {
+            compose_client,
+            git_client,
+            config,
+            secrets_cipher,
+        } = dependencies;
+        let compose_usecase = ComposeUsecase::new(
+            Arc::clone(&compose_client),
+            config.resources.compose_projects_dir.as_ref(),
+        );
+        let tenants = config
+            .tenancy
+            .tenants
+            .iter()
+            .map(|tenant| (tenant.name.clone(), tenant.resources(&config.resources)))
+            .collect::<Vec<_>>();
+        let all_resources = std::iter::once(&config.resources)
+            .chain(tenants.iter().map(|(_, resources)| resources))
+            .cloned()
+            .collect::<Vec<_>>();
+        let allocating = Arc::new(Mutex::new(()));
+        let workspace = |resources: &ResourcesConfig| {
+            let neighbours = all_resources
+                .iter()
+                .filter(|other| *other != resources)
+                .cloned()
+                .collect();
+            ProjectUsecase::new(
+                Arc::clone(&compose_client),
+                Arc::clone(&git_client),
+                resources.clone(),
+            )
+            .with_limits(config.limits())
+            .with_address_pools(config.networks.pools.clone())
+            .with_preview_ports(config.previews.ports)
+            .with_secrets_cipher(secrets_cipher.clone())
+            .with_sops(config.sops.clone())
+            .with_retry_policy(config.retry.clone())
+            .with_disk_quota(config.disk_quota.clone())
+            .with_neighbours(neighbours, Arc::clone(&allocating))
+        };
+        let project_usecase = workspace(&config.resources);
+        let tenant_usecases = tenants
+            .iter()
+            .map(|(name, resources)| (name.clone(), workspace(resources)))
+            .collect::<Vec<_>>();
+        let webhook_workspaces = WebhookWorkspaces::new(
+            WebhookUsecase::new(project_usecase.clone(), config.webhooks.clone()),
+            config
+                .tenancy
+                .tenants
+                .iter()
+                .zip(&tenant_usecases)
+                .map(|(tenant, (_, usecase))| {
+                    WebhookUsecase::new(usecase.clone(), tenant.webhooks.clone())
+                })
+                .collect(),
+        );
+        let workspaces = Workspaces::new(
+            project_usecase.clone(),
+            tenant_usecases.into_iter().collect(),
+        );
+        let tenancy = Tenancy::new(&config.tenancy);
+
+        Self {
+            project_usecase,
+            webhook_workspaces,
+            compose_usecase,
+            server_config: config.server.clone(),
+            config,
+            idempotency: IdempotencyCache::default(),
+            tenancy,
+            workspaces,
+            #[cfg(feature = "telemetry")]
+            request_metrics: RequestMetrics::default(),
+        }
+    }
+}
 
+/// Run the server with the configuration in `config/default.yaml`.
 pub async fn init() -> Result<()> {
-    let config = load_config("config/default.yaml")?;
-    let project_usecase = create_project_usecase(&config)?;
-    let app = build_app(project_usecase);
+    serve(load_config("config/default.yaml")?).await
+}
+
+/// Run the HTTP server, and the gRPC server when `server.grpc_port` is set, until the
+/// listener fails.
+pub async fn serve(config: Config) -> Result<()> {
+    // Refuse a malformed master key, rather than storing secrets unsealed.
+    let secrets_cipher = CredentialCipher::from_env()?;
+    // Refuse tenants that no token could reach, and a gRPC API that would bypass theirs.
+    config.tenancy.check()?;
+    if config.tenancy.is_enabled() && config.server.grpc_port.is_some() {
+        return Err(anyhow::anyhow!(
+            "server.grpc_port cannot be set along with tenants, as the gRPC API takes no tokens"
+        ));
+    }
+    let mut compose_client = DockerComposeClient::new()?
+        .with_output_limit(config.profile.limits().max_command_output_bytes);
+    if let Some(timeout_secs) = config.compose.timeout_secs {
+        compose_client = compose_client.with_timeout(Duration::from_secs(timeout_secs));
+    }
+    let mut git_client = GitClientImpl::with_depth((!config.git.full_clone).then_some(1))
+        .with_ssh(config.git.ssh.clone())
+        .with_credentials(config.git.credentials.clone());
+    if let Some(timeout_secs) = config.git.timeout_secs {
+        git_client = git_client.with_timeout(Duration::from_secs(timeout_secs));
+    }
+    if let Some(shared_clones_dir) = &config.git.shared_clones_dir {
+        git_client = git_client.with_shared_clones(shared_clones_dir);
+    }
+    let state = AppState::new(AppDependencies {
+        compose_client: Arc::new(compose_client),
+        git_client: Arc::new(git_client),
+        config: config.clone(),
+        secrets_cipher,
+    });
+    if let Some(grpc_port) = config.server.grpc_port {
+        serve_grpc(&config.server, grpc_port, state.project_usecase.clone())?;
+    }
+
+    if let Some(path) = &config.bootstrap {
+        let project_usecase = state.project_usecase.clone();
+        let path = path.clone();
+        blocking::run(move || bootstrap(&project_usecase, &path)).await??;
+    }
+
+    for project_usecase in state.workspaces.all() {
+        let on_startup = config.reconciler.on_startup;
+        let recovering = project_usecase.clone();
+        blocking::spawn(move || {
+            for job in recovering.resume_interrupted_jobs() {
+                println!("Resumed interrupted {:?} of {}", job.kind, job.project);
+            }
+            if on_startup {
+                let synced = converge(&recovering);
+                if !synced.is_empty() {
+                    println!("Converging projects at startup: {}", synced.join(", "));
+                }
+            }
+        });
+        Scheduler::new(project_usecase.clone()).spawn();
+        if config.reconciler.enabled {
+            Reconciler::new(
+                project_usecase.clone(),
+                Duration::from_secs(config.reconciler.interval_secs),
+            )
+            .with_drift_correction(config.reconciler.correct_drift)
+            .spawn();
+        }
+        if config.image_watch.enabled {
+            ImageWatcher::new(
+                project_usecase.clone(),
+                Duration::from_secs(config.image_watch.interval_secs),
+            )
+            .spawn();
+        }
+    }
+    // One collector sees every workspace: the main one's neighbours are the tenants'.
+    if config.image_gc.enabled {
+        ImageCollector::new(
+            state.project_usecase.clone(),
+            Duration::from_secs(config.image_gc.interval_secs),
+            Duration::from_secs(config.image_gc.retention_secs),
+        )
+        .spawn();
+    }
+    if config.usage_stats.enabled {
+        UsageStatsReporter::new(
+            state.project_usecase.clone(),
+            usage_reporter(config.usage_stats.endpoint.as_deref()),
+            "docker-compose",
+            Duration::from_secs(config.usage_stats.interval_secs),
+        )
+        .spawn();
+    }
+
+    let app = build_app(state);
 
     let address = format!("{}:{}", config.server.host, config.server.port);
+    if let Some(tls) = &config.server.tls {
+        // Bound with std so host names resolve as they do for plain HTTP.
+        let listener = std::net::TcpListener::bind(&address)?;
+        println!("Server running at https://{}", address);
+        return serve_tls(listener, tls, app).await;
+    }
+
     let listener = tokio::net::TcpListener::bind(&address).await?;
     println!("Server running at http://{}", address);
 
@@ -28,6 +434,83 @@ pub async fn init() -> Result<()> {
     Ok(())
 }
 
+#[cfg(not(feature = "tls"))]
+async fn serve_tls(_listener: std::net::TcpListener, _tls: &TlsConfig, _app: Router) -> Result<()> {
+    Err(anyhow::anyhow!(
+        "server.tls is set, but gfc was built without the tls feature"
+    ))
+}
+
+#[cfg(feature = "tls")]
+async fn serve_tls(listener: std::net::TcpListener, tls: &TlsConfig, app: Router) -> Result<()> {
+    // ring, as ureq uses. Rustls can't pick one by itself once a dependency enables
+    // another provider. An error means one was installed already.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let rustls_config =
+        axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path).await?;
+    listener.set_nonblocking(true)?;
+
+    axum_server::from_tcp_rustls(listener, rustls_config)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+#[cfg(not(feature = "grpc"))]
+fn serve_grpc(
+    _server: &ServerConfig,
+    _port: u16,
+    _project_usecase: ProjectUsecase<DockerComposeClient, GitClientImpl>,
+) -> Result<()> {
+    println!("server.grpc_port is set, but gfc was built without the grpc feature");
+    Ok(())
+}
+
+#[cfg(feature = "grpc")]
+fn serve_grpc(
+    server: &ServerConfig,
+    port: u16,
+    project_usecase: ProjectUsecase<DockerComposeClient, GitClientImpl>,
+) -> Result<()> {
+    let address: SocketAddr = format!("{}:{}", server.host, port).parse()?;
+    let service = GrpcProjectService::new(
+        project_usecase,
+        Duration::from_secs(server.max_request_timeout_secs),
+    )
+    .into_server();
+
+    tokio::spawn(async move {
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(service)
+            .serve(address)
+            .await
+        {
+            println!("gRPC server stopped: {}", e);
+        }
+    });
+    println!("gRPC server running at {}", address);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "usage-stats"))]
+fn usage_reporter(endpoint: Option<&str>) -> Box<dyn UsageReporter> {
+    if endpoint.is_some() {
+        println!("usage_stats.endpoint is set, but gfc was built without the usage-stats feature; reports are only printed");
+    }
+    Box::new(LogUsageReporter)
+}
+
+#[cfg(feature = "usage-stats")]
+fn usage_reporter(endpoint: Option<&str>) -> Box<dyn UsageReporter> {
+    match endpoint {
+        Some(endpoint) => Box::new(crate::repositories::usage_reporter::HttpUsageReporter::new(
+            endpoint,
+        )),
+        None => Box::new(LogUsageReporter),
+    }
+}
+
 fn load_config<P>(path: P) -> Result<Config>
 where
     P: AsRef<std::path::Path>,
@@ -36,22 +519,227 @@ where
     Ok(config)
 }
 
-fn create_project_usecase(
-    config: &Config,
-) -> Result<ProjectUsecase<DockerComposeClient, GitClientImpl>> {
-    let docker_compose_client = Arc::new(DockerComposeClient::new()?);
-    let git_client = Arc::new(GitClientImpl);
+/// Apply the bootstrap file at `path`. A file that cannot be read or applied stops the
+/// server from starting; a project that fails to start is only reported.
+fn bootstrap<C, G>(project_usecase: &ProjectUsecase<C, G>, path: &str) -> Result<()>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read bootstrap file {}: {}", path, e))?;
+    let bootstrap: Bootstrap = serde_yaml::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Invalid bootstrap file {}: {}", path, e))?;
+    for project in project_usecase.apply_bootstrap(bootstrap)? {
+        match project.error {
+            Some(e) => println!("Bootstrap of {} failed: {}", project.name, e),
+            None => println!("Bootstrap of {}: {:?}", project.name, project.status),
+        }
+    }
+    Ok(())
+}
+
+/// Assemble the HTTP app from the given clients, without binding a listener.
+pub fn build_app_with<C, G>(dependencies: AppDependencies<C, G>) -> Router
+where
+    C: ComposeClient + Clone + Send + Sync + 'static,
+    G: GitClient + Clone + Send + Sync + 'static,
+{
+    build_app(AppState::new(dependencies))
+}
 
-    Ok(ProjectUsecase::new(
-        docker_compose_client,
-        git_client,
-        config.resources.clone(),
-    ))
+fn build_app<C, G>(state: AppState<C, G>) -> Router
+where
+    C: ComposeClient + Clone + Send + Sync + 'static,
+    G: GitClient + Clone + Send + Sync + 'static,
+{
+    let router = Router::new()
+        .route("/projects", get(get_projects::<C, G>))
+        .route("/projects", post(create_project::<C, G>))
+        .route("/projects/validate", post(validate_project::<C, G>))
+        .route("/projects/sync", post(sync_selected_projects::<C, G>))
+        .route("/projects/{name}", delete(delete_project::<C, G>))
+        .route(
+            "/projects/from-compose",
+            post(create_project_from_compose::<C, G>),
+        )
+        .route(
+            "/projects/{name}/activity",
+            get(get_project_activity::<C, G>),
+        )
+        .route(
+            "/projects/{name}/deployments",
+            get(get_project_deployments::<C, G>),
+        )
+        .route(
+            "/projects/{name}/scheduled-actions",
+            get(get_scheduled_actions::<C, G>),
+        )
+        .route(
+            "/projects/{name}/disk-usage",
+            get(get_project_disk_usage::<C, G>),
+        )
+        .route("/projects/{name}/diff", get(get_project_diff::<C, G>))
+        .route(
+            "/projects/{name}/deployments/{id}/rollback",
+            post(rollback_project::<C, G>),
+        )
+        .route(
+            "/projects/{name}/deployments/{id}/approve",
+            post(approve_deployment::<C, G>),
+        )
+        .route(
+            "/projects/{name}/manifest",
+            get(get_project_manifest::<C, G>),
+        )
+        .route("/projects/{name}/compose", get(get_project_compose::<C, G>))
+        .route("/projects/{name}/status", get(get_project_status::<C, G>))
+        .route("/projects/{name}/badge.svg", get(get_project_badge::<C, G>))
+        .route("/projects/{name}/events", get(get_project_events::<C, G>))
+        .route("/projects/{name}/sync", post(sync_project::<C, G>))
+        .route("/projects/{name}/migrate", post(migrate_to_git::<C, G>))
+        .route(
+            "/projects/{name}/files/{*path}",
+            get(get_repository_file::<C, G>),
+        )
+        .route("/projects/{name}/secrets", get(list_secrets::<C, G>))
+        .route(
+            "/projects/{name}/secrets/{secret}",
+            put(put_secret::<C, G>).delete(delete_secret::<C, G>),
+        )
+        .route("/projects/{name}/pause", post(pause_project::<C, G>))
+        .route("/projects/{name}/unpause", post(unpause_project::<C, G>))
+        .route(
+            "/projects/{name}/services/{service}/exec",
+            post(exec_in_service::<C, G>),
+        )
+        .route("/compose-projects", get(get_compose_projects::<C>))
+        .route("/jobs/queues", get(get_job_queues::<C, G>))
+        .route(
+            "/jobs/{id}",
+            get(get_job::<C, G>).delete(cancel_job::<C, G>),
+        )
+        .route("/maintenance/prune", post(prune_orphans::<C, G>))
+        .route("/maintenance/images", get(get_unused_images::<C, G>))
+        .route(
+            "/maintenance/images/prune",
+            post(prune_unused_images::<C, G>),
+        )
+        .route("/system/info", get(get_system_info::<C, G>))
+        .route("/export", get(export_workspace::<C, G>))
+        .route("/import", post(import_workspace::<C, G>))
+        .route("/import/portainer", post(import_portainer_stacks::<C, G>))
+        .route("/admin/config", get(get_config))
+        .route("/webhooks/github", post(github_webhook::<C, G>))
+        .route("/webhooks/gitlab", post(gitlab_webhook::<C, G>))
+        .route("/webhooks/gitea", post(gitea_webhook::<C, G>))
+        .route("/webhooks/generic/{project}", post(generic_webhook::<C, G>))
+        .route("/webhooks/dry-run", post(dry_run_webhook::<C, G>));
+
+    #[cfg(feature = "telemetry")]
+    let router = router
+        .route_layer(middleware::from_fn_with_state(
+            state.request_metrics.clone(),
+            record_request_metrics,
+        ))
+        .route("/metrics", get(get_metrics));
+
+    let router = router.layer(RequestBodyTimeoutLayer::new(Duration::from_secs(
+        state.server_config.read_timeout_secs,
+    )));
+    let router = match state.server_config.compression {
+        // Streamed NDJSON would otherwise sit in the compressor's buffer.
+        true => router.layer(CompressionLayer::new().compress_when(
+            DefaultPredicate::new().and(NotForContentType::const_new("application/x-ndjson")),
+        )),
+        false => router,
+    };
+
+    router.with_state(state)
 }
 
-fn build_app(project_usecase: ProjectUsecase<DockerComposeClient, GitClientImpl>) -> Router {
-    Router::new()
-        .route("/projects", get(get_projects))
-        .route("/projects", post(create_project))
-        .with_state(project_usecase)
+#[cfg(all(test, feature = "tls", feature = "usage-stats"))]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// A self-signed certificate for `localhost`, written to `dir`.
+    fn self_signed_certificate(dir: &Path) -> TlsConfig {
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        let status = Command::new("openssl")
+            .args([
+                "req",
+                "-x509",
+                "-newkey",
+                "ec",
+                "-pkeyopt",
+                "ec_paramgen_curve:prime256v1",
+            ])
+            .args(["-nodes", "-days", "1", "-subj", "/CN=localhost"])
+            .args(["-addext", "subjectAltName=DNS:localhost"])
+            .args(["-addext", "basicConstraints=critical,CA:FALSE"])
+            .arg("-keyout")
+            .arg(&key_path)
+            .arg("-out")
+            .arg(&cert_path)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+        TlsConfig {
+            cert_path: cert_path.display().to_string(),
+            key_path: key_path.display().to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn given_tls_config_when_served_then_answer_over_https_only() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let tls = self_signed_certificate(dir.path());
+        let cert = std::fs::read(&tls.cert_path).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = Router::new().route("/ping", get(|| async { "pong" }));
+        tokio::spawn(async move { serve_tls(listener, &tls, app).await });
+
+        let (https, http) = blocking::run(move || {
+            let roots =
+                ureq::tls::RootCerts::new_with_certs(&[
+                    ureq::tls::Certificate::from_pem(&cert).unwrap()
+                ]);
+            let agent: ureq::Agent = ureq::Agent::config_builder()
+                .tls_config(ureq::tls::TlsConfig::builder().root_certs(roots).build())
+                .timeout_global(Some(Duration::from_secs(10)))
+                .build()
+                .into();
+            let https = agent
+                .get(format!("https://localhost:{}/ping", port))
+                .call()
+                .and_then(|mut response| response.body_mut().read_to_string());
+            let http = agent.get(format!("http://localhost:{}/ping", port)).call();
+            (https, http)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(https.unwrap(), "pong");
+        assert!(http.is_err());
+    }
+
+    #[tokio::test]
+    async fn given_missing_certificate_when_served_then_fail_to_start() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tls = TlsConfig {
+            cert_path: dir.path().join("cert.pem").display().to_string(),
+            key_path: dir.path().join("key.pem").display().to_string(),
+        };
+
+        let actual = serve_tls(listener, &tls, Router::new()).await;
+
+        assert!(actual.is_err());
+    }
 }
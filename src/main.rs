@@ -1,6 +1,18 @@
 use anyhow::Result;
+use clap::Parser;
+use std::process::ExitCode;
+
+use gfc::cli::{Cli, Command};
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    gfc::init().await
+async fn main() -> Result<ExitCode> {
+    match Cli::parse().command {
+        Some(Command::Validate(args)) => Ok(gfc::cli::run_validate(&args)),
+        #[cfg(feature = "tui")]
+        Some(Command::Tui(args)) => gfc::cli::tui::run(&args),
+        Some(Command::Serve) | None => {
+            gfc::init().await?;
+            Ok(ExitCode::SUCCESS)
+        }
+    }
 }
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+use crate::models::humanize;
+
+pub const DEFAULT_PER_PAGE: usize = 20;
+pub const MAX_PER_PAGE: usize = 100;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Deployment,
+    StatusTransition,
+    ManualAction,
+    Notification,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ActivityEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ActivityKind,
+    pub message: String,
+    /// `timestamp` relative to now, with `?humanize=true`. Never stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ago: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+    #[serde(default)]
+    pub humanize: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ActivityPage {
+    pub entries: Vec<ActivityEntry>,
+    pub page: usize,
+    pub per_page: usize,
+    pub total: usize,
+}
+
+impl ActivityPage {
+    /// Newest entries first. Pages start at 1; `per_page` is capped at [`MAX_PER_PAGE`].
+    pub fn paginate(mut entries: Vec<ActivityEntry>, query: &ActivityQuery) -> Self {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query
+            .per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE);
+        let total = entries.len();
+
+        entries.sort_by_key(|entry| Reverse(entry.timestamp));
+        let now = Utc::now();
+        let entries = entries
+            .into_iter()
+            .skip((page - 1) * per_page)
+            .take(per_page)
+            .map(|entry| match query.humanize {
+                true => ActivityEntry {
+                    ago: Some(humanize::relative(entry.timestamp, now)),
+                    ..entry
+                },
+                false => entry,
+            })
+            .collect();
+
+        Self {
+            entries,
+            page,
+            per_page,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn make_entry(epoch: i64) -> ActivityEntry {
+        ActivityEntry {
+            timestamp: Utc.timestamp_opt(epoch, 0).unwrap(),
+            kind: ActivityKind::Deployment,
+            message: epoch.to_string(),
+            ago: None,
+        }
+    }
+
+    #[test]
+    fn given_unordered_entries_when_paginate_then_return_newest_first() {
+        let entries = vec![make_entry(1), make_entry(3), make_entry(2)];
+        let query = ActivityQuery {
+            page: None,
+            per_page: None,
+            humanize: false,
+        };
+
+        let actual = ActivityPage::paginate(entries, &query);
+
+        let messages: Vec<_> = actual.entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["3", "2", "1"]);
+        assert_eq!(actual.total, 3);
+    }
+
+    #[test]
+    fn given_second_page_when_paginate_then_skip_first_page() {
+        let entries = (1..=5).map(make_entry).collect();
+        let query = ActivityQuery {
+            page: Some(2),
+            per_page: Some(2),
+            humanize: false,
+        };
+
+        let actual = ActivityPage::paginate(entries, &query);
+
+        let messages: Vec<_> = actual.entries.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, vec!["3", "2"]);
+        assert_eq!(actual.page, 2);
+    }
+}
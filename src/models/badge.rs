@@ -0,0 +1,113 @@
+use crate::models::project::ProjectStatus;
+
+const CHARACTER_WIDTH: usize = 7;
+const HORIZONTAL_PADDING: usize = 10;
+
+/// A flat, shields.io-style badge: a grey label on the left, a coloured message on the
+/// right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Badge {
+    pub label: String,
+    pub message: String,
+    pub color: &'static str,
+}
+
+impl Badge {
+    pub fn for_status(label: &str, status: &ProjectStatus) -> Self {
+        let color = match status {
+            ProjectStatus::Running { running, total } if running == total => "#4c1",
+            ProjectStatus::Running { .. } => "#dfb317",
+            ProjectStatus::Paused => "#007ec6",
+            ProjectStatus::Exited
+            | ProjectStatus::DeleteFailed
+            | ProjectStatus::DeploymentFailed { .. } => "#e05d44",
+            ProjectStatus::Deleting
+            | ProjectStatus::CreationInProgress
+            | ProjectStatus::Cancelled => "#9f9f9f",
+        };
+        // The reason a deployment failed is too long for a badge.
+        let message = match status {
+            ProjectStatus::DeploymentFailed { .. } => "deployment failed".to_string(),
+            status => status.to_string().to_lowercase(),
+        };
+
+        Self {
+            label: label.to_string(),
+            message,
+            color,
+        }
+    }
+
+    /// For when the status could not be read, e.g. while docker is unreachable.
+    pub fn unknown(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            message: "unknown".to_string(),
+            color: "#9f9f9f",
+        }
+    }
+
+    pub fn not_found(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            message: "not found".to_string(),
+            color: "#9f9f9f",
+        }
+    }
+
+    /// Text widths are estimated from the character count, which is close enough for the
+    /// short labels badges carry.
+    pub fn render_svg(&self) -> String {
+        let label = escape_xml(&self.label);
+        let message = escape_xml(&self.message);
+        let label_width = text_width(&self.label);
+        let message_width = text_width(&self.message);
+        let width = label_width + message_width;
+        let label_x = label_width / 2;
+        let message_x = label_width + message_width / 2;
+
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##,
+            color = self.color,
+        )
+    }
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().count() * CHARACTER_WIDTH + HORIZONTAL_PADDING
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_partially_running_project_when_for_status_then_badge_is_yellow() {
+        let status = ProjectStatus::Running {
+            running: 1,
+            total: 2,
+        };
+
+        let actual = Badge::for_status("demo", &status);
+
+        assert_eq!(actual.message, "running (1/2)");
+        assert_eq!(actual.color, "#dfb317");
+    }
+
+    #[test]
+    fn given_label_with_markup_when_render_svg_then_label_is_escaped() {
+        let badge = Badge::not_found("<demo>");
+
+        let actual = badge.render_svg();
+
+        assert!(actual.contains("&lt;demo&gt;: not found"));
+        assert!(!actual.contains("<demo>"));
+    }
+}
@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+use crate::models::project::ProjectFile;
+
+/// A whole gfc instance as one file, applied on every startup when `bootstrap` is set in
+/// the config. Applying it again changes nothing: projects that exist are left as they
+/// are, and a secret is only written when its value changed.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Bootstrap {
+    /// Project manifests, as `POST /projects` accepts them. Each is cloned and deployed.
+    #[serde(default)]
+    pub projects: Vec<ProjectFile>,
+    #[serde(default)]
+    pub secrets: Vec<BootstrapSecret>,
+}
+
+/// A value for a compose secret, read from where the file points instead of written in it.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct BootstrapSecret {
+    pub project: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub value: SecretReference,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretReference {
+    /// An environment variable of the gfc process.
+    FromEnv(String),
+    /// A file on the gfc host, e.g. one mounted by the orchestrator.
+    FromFile(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_projects_and_secret_references_when_parsed_then_read_both() {
+        let yaml = r#"
+projects:
+  - name: web
+    source:
+      url: https://example.com/web.git
+      branch: main
+      path: docker-compose.yml
+secrets:
+  - project: web
+    name: db_password
+    from_env: WEB_DB_PASSWORD
+  - project: web
+    name: tls_key
+    from_file: /run/secrets/web.key
+"#;
+
+        let actual: Bootstrap = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(actual.projects[0].name, "web");
+        assert_eq!(
+            actual.secrets[0].value,
+            SecretReference::FromEnv("WEB_DB_PASSWORD".to_string())
+        );
+        assert_eq!(
+            actual.secrets[1].value,
+            SecretReference::FromFile("/run/secrets/web.key".to_string())
+        );
+    }
+}
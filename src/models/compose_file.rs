@@ -0,0 +1,325 @@
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::models::device::DeviceReservation;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PublishedPort {
+    pub port: u16,
+    pub protocol: String,
+}
+
+#[derive(Debug, Error)]
+pub enum ComposeFileError {
+    #[error("Failed to read compose file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse compose file: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Compose file must be a mapping at the top level")]
+    NotAMapping,
+}
+
+/// A compose file read as a YAML tree instead of a typed struct, so keys gfc does not
+/// know about (`x-*` extension fields, newer compose options) can still be read. gfc never
+/// rewrites it: the source is kept as it was, comments and anchors included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComposeFile {
+    source: String,
+    document: Value,
+}
+
+impl ComposeFile {
+    pub fn parse(source: &str) -> Result<Self, ComposeFileError> {
+        let document: Value = serde_yaml::from_str(source)?;
+        if !document.is_mapping() {
+            return Err(ComposeFileError::NotAMapping);
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            document,
+        })
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ComposeFileError> {
+        let source = fs::read_to_string(path)?;
+        Self::parse(&source)
+    }
+
+    pub fn service_names(&self) -> Vec<String> {
+        self.services()
+            .map(|services| {
+                services
+                    .keys()
+                    .filter_map(|key| key.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn service(&self, name: &str) -> Option<&Value> {
+        self.services().and_then(|services| services.get(name))
+    }
+
+    /// Images referenced by services, without duplicates, in service order.
+    pub fn images(&self) -> Vec<String> {
+        let mut images = Vec::new();
+        for name in self.service_names() {
+            let image = self
+                .service(&name)
+                .and_then(|service| service.get("image"))
+                .and_then(Value::as_str);
+            if let Some(image) = image.filter(|image| !images.iter().any(|i| i == image)) {
+                images.push(image.to_string());
+            }
+        }
+        images
+    }
+
+    /// Fixed `container_name`s set on services.
+    pub fn container_names(&self) -> Vec<String> {
+        self.service_names()
+            .iter()
+            .filter_map(|name| self.service(name)?.get("container_name")?.as_str())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Devices services reserve under `deploy.resources.reservations.devices`.
+    pub fn device_reservations(&self) -> Vec<DeviceReservation> {
+        self.service_names()
+            .iter()
+            .flat_map(|name| {
+                self.service(name)
+                    .and_then(|service| {
+                        service
+                            .get("deploy")?
+                            .get("resources")?
+                            .get("reservations")?
+                            .get("devices")?
+                            .as_sequence()
+                    })
+                    .into_iter()
+                    .flatten()
+                    .map(move |device| DeviceReservation::parse(name, device))
+            })
+            .collect()
+    }
+
+    /// Host ports the services publish. Ports left to docker to pick, and ports that use
+    /// variable interpolation, are not included.
+    pub fn published_ports(&self) -> Vec<PublishedPort> {
+        self.service_names()
+            .iter()
+            .filter_map(|name| self.service(name)?.get("ports")?.as_sequence())
+            .flatten()
+            .flat_map(parse_published_ports)
+            .collect()
+    }
+
+    /// Networks compose creates for the file and picks subnets for: `default` when a
+    /// service joins no network explicitly, then top-level networks that are neither
+    /// external nor given subnets of their own.
+    pub fn created_networks(&self) -> Vec<String> {
+        let joins_default = self.service_names().iter().any(|name| {
+            self.service(name).is_some_and(|service| {
+                service.get("networks").is_none() && service.get("network_mode").is_none()
+            })
+        });
+        let declared = self
+            .document
+            .get("networks")
+            .and_then(Value::as_mapping)
+            .into_iter()
+            .flatten()
+            .filter(|(_, network)| {
+                let external = network
+                    .get("external")
+                    .is_some_and(|external| external.as_bool() != Some(false));
+                let has_subnets = network
+                    .get("ipam")
+                    .and_then(|ipam| ipam.get("config"))
+                    .is_some();
+                !external && !has_subnets
+            })
+            .filter_map(|(name, _)| name.as_str().map(str::to_string));
+
+        joins_default
+            .then(|| "default".to_string())
+            .into_iter()
+            .chain(declared.filter(|name| name != "default"))
+            .collect()
+    }
+
+    /// Top-level `x-*` keys, in the order they appear in the source.
+    pub fn extension_fields(&self) -> Vec<String> {
+        self.document
+            .as_mapping()
+            .map(|root| {
+                root.keys()
+                    .filter_map(Value::as_str)
+                    .filter(|key| key.starts_with("x-"))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn document(&self) -> &Value {
+        &self.document
+    }
+
+    /// The file as it was read.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn services(&self) -> Option<&Mapping> {
+        self.document.get("services").and_then(Value::as_mapping)
+    }
+}
+
+/// Short syntax is `[[IP:]HOST:]CONTAINER[/PROTOCOL]`, where HOST may be a range; long
+/// syntax is a mapping with `published` and `protocol`.
+fn parse_published_ports(port: &Value) -> Vec<PublishedPort> {
+    let (host_ports, protocol) = match port {
+        Value::String(short) => {
+            let (mapping, protocol) = short.split_once('/').unwrap_or((short.as_str(), "tcp"));
+            let Some((host, _container)) = mapping.rsplit_once(':') else {
+                return vec![];
+            };
+            let host_ports = host.rsplit_once(':').map_or(host, |(_ip, port)| port);
+            (host_ports.to_string(), protocol.to_string())
+        }
+        Value::Mapping(long) => {
+            let published = match long.get("published") {
+                Some(Value::Number(number)) => number.to_string(),
+                Some(Value::String(published)) => published.clone(),
+                _ => return vec![],
+            };
+            let protocol = long
+                .get("protocol")
+                .and_then(Value::as_str)
+                .unwrap_or("tcp");
+            (published, protocol.to_string())
+        }
+        _ => return vec![],
+    };
+
+    let (start, end) = host_ports
+        .split_once('-')
+        .unwrap_or((host_ports.as_str(), host_ports.as_str()));
+    match (start.parse::<u16>(), end.parse::<u16>()) {
+        (Ok(start), Ok(end)) => (start..=end)
+            .map(|port| PublishedPort {
+                port,
+                protocol: protocol.clone(),
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::device::DeviceCount;
+
+    const SOURCE: &str = r#"# shared settings
+x-common: &common
+  restart: always
+
+services:
+  web:
+    <<: *common
+    image: nginx:latest
+    labels:
+      - "team=web"
+  worker:
+    image: busybox:latest
+    labels:
+      team: jobs
+"#;
+
+    #[test]
+    fn given_comments_and_anchors_when_parsed_then_source_is_kept_verbatim() {
+        let compose_file = ComposeFile::parse(SOURCE).unwrap();
+
+        assert_eq!(compose_file.source(), SOURCE);
+        assert_eq!(compose_file.extension_fields(), vec!["x-common"]);
+        assert_eq!(compose_file.service_names(), vec!["web", "worker"]);
+    }
+
+    #[test]
+    fn given_short_and_long_port_syntax_when_published_ports_then_return_host_ports() {
+        let compose_file = ComposeFile::parse(
+            r#"
+services:
+  web:
+    image: nginx
+    ports:
+      - "8080:80"
+      - "127.0.0.1:5353:53/udp"
+      - "9000-9001:9000-9001"
+      - "3000"
+      - target: 443
+        published: 8443
+"#,
+        )
+        .unwrap();
+
+        let actual = compose_file
+            .published_ports()
+            .into_iter()
+            .map(|p| (p.port, p.protocol))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![
+                (8080, "tcp".to_string()),
+                (5353, "udp".to_string()),
+                (9000, "tcp".to_string()),
+                (9001, "tcp".to_string()),
+                (8443, "tcp".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn given_non_mapping_document_when_parse_then_return_error() {
+        let actual = ComposeFile::parse("- just\n- a list\n");
+
+        assert!(matches!(actual, Err(ComposeFileError::NotAMapping)));
+    }
+
+    #[test]
+    fn given_declared_and_external_networks_when_created_networks_then_return_those_compose_creates(
+    ) {
+        let compose_file = ComposeFile::parse(
+            "services:\n  web:\n    image: nginx\n  db:\n    image: postgres\n    networks: [backend]\nnetworks:\n  backend: {}\n  proxy:\n    external: true\n  fixed:\n    ipam:\n      config:\n        - subnet: 172.30.0.0/24\n",
+        )
+        .unwrap();
+
+        assert_eq!(compose_file.created_networks(), vec!["default", "backend"]);
+    }
+
+    #[test]
+    fn given_gpu_reservations_when_device_reservations_then_return_count_or_ids() {
+        let compose_file = ComposeFile::parse(
+            "services:\n  train:\n    image: pytorch\n    deploy:\n      resources:\n        reservations:\n          devices:\n            - driver: nvidia\n              count: 2\n              capabilities: [gpu]\n  infer:\n    image: triton\n    deploy:\n      resources:\n        reservations:\n          devices:\n            - device_ids: [\"0\"]\n              capabilities: [gpu, utility]\n",
+        )
+        .unwrap();
+
+        let actual = compose_file.device_reservations();
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].service, "train");
+        assert_eq!(actual[0].count, Some(DeviceCount::Exactly(2)));
+        assert_eq!(actual[1].count, None);
+        assert_eq!(actual[1].device_ids, vec!["0"]);
+        assert!(actual.iter().all(DeviceReservation::is_gpu));
+    }
+}
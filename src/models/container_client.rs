@@ -1,3 +1,50 @@
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+use crate::models::device::{DeviceCount, DeviceReservation};
+use crate::models::docker_compose::{Container, ContainerState, HealthStatus, CONFIG_HASH_LABEL};
+
+/// What [`ContainerClient::create_container`] creates a container from, the same things a
+/// compose service sets.
+///
+/// [`ContainerClient::create_container`]: crate::repositories::container_client::ContainerClient::create_container
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CreateContainerConfig {
+    pub name: String,
+    pub image: String,
+    pub env: BTreeMap<String, String>,
+    pub labels: BTreeMap<String, String>,
+    pub ports: Vec<PortMapping>,
+    pub devices: Vec<DeviceReservation>,
+}
+
+/// A container port published on the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortMapping {
+    pub container_port: u16,
+    /// `None` lets Docker pick a free port.
+    pub host_port: Option<u16>,
+    pub host_ip: Option<String>,
+    pub protocol: String,
+}
+
+#[derive(Debug, Error)]
+#[error("Unknown state for {name}: {state}")]
+pub struct UnknownStateError {
+    pub name: String,
+    pub state: String,
+}
+
+/// A change to a container reported by the daemon, such as `start`, `die` or `destroy`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerEvent {
+    pub id: String,
+    pub name: Option<String>,
+    pub action: String,
+    pub time: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug)]
 pub struct ContainerCreateResponse {
     pub id: String,
@@ -23,3 +70,218 @@ impl From<bollard::models::ContainerSummary> for ContainerInfo {
         }
     }
 }
+
+impl From<bollard::models::EventMessage> for ContainerEvent {
+    fn from(value: bollard::models::EventMessage) -> Self {
+        let actor = value.actor.unwrap_or_default();
+        ContainerEvent {
+            id: actor.id.unwrap_or_default(),
+            name: actor
+                .attributes
+                .and_then(|attributes| attributes.get("name").cloned()),
+            action: value.action.unwrap_or_default(),
+            time: value
+                .time
+                .and_then(|time| Utc.timestamp_opt(time, 0).single()),
+        }
+    }
+}
+
+impl From<&CreateContainerConfig> for bollard::container::Config<String> {
+    fn from(value: &CreateContainerConfig) -> Self {
+        let port_key = |port: &PortMapping| format!("{}/{}", port.container_port, port.protocol);
+        let mut port_bindings = HashMap::<String, Option<Vec<_>>>::new();
+        for port in &value.ports {
+            port_bindings
+                .entry(port_key(port))
+                .or_default()
+                .get_or_insert_with(Vec::new)
+                .push(bollard::models::PortBinding {
+                    host_ip: port.host_ip.clone(),
+                    host_port: port.host_port.map(|host_port| host_port.to_string()),
+                });
+        }
+
+        bollard::container::Config {
+            image: Some(value.image.clone()),
+            env: Some(
+                value
+                    .env
+                    .iter()
+                    .map(|(key, value)| format!("{}={}", key, value))
+                    .collect(),
+            ),
+            labels: Some(value.labels.clone().into_iter().collect()),
+            exposed_ports: Some(
+                value
+                    .ports
+                    .iter()
+                    .map(|port| (port_key(port), HashMap::new()))
+                    .collect(),
+            ),
+            host_config: Some(bollard::models::HostConfig {
+                port_bindings: Some(port_bindings),
+                device_requests: (!value.devices.is_empty())
+                    .then(|| value.devices.iter().map(Into::into).collect()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&DeviceReservation> for bollard::models::DeviceRequest {
+    fn from(value: &DeviceReservation) -> Self {
+        bollard::models::DeviceRequest {
+            driver: value.driver.clone(),
+            count: value.count.map(|count| match count {
+                DeviceCount::All => -1,
+                DeviceCount::Exactly(count) => i64::from(count),
+            }),
+            device_ids: (!value.device_ids.is_empty()).then(|| value.device_ids.clone()),
+            capabilities: Some(vec![value.capabilities.clone()]),
+            options: Some(value.options.clone().into_iter().collect()),
+        }
+    }
+}
+
+impl TryFrom<bollard::models::ContainerInspectResponse> for Container {
+    type Error = UnknownStateError;
+
+    fn try_from(value: bollard::models::ContainerInspectResponse) -> Result<Self, Self::Error> {
+        let name = value
+            .name
+            .map(|name| name.trim_start_matches('/').to_string())
+            .unwrap_or_default();
+        let labels = value
+            .config
+            .and_then(|config| config.labels)
+            .unwrap_or_default();
+        let state = value.state.unwrap_or_default();
+        let status = state
+            .status
+            .map(|status| status.to_string())
+            .unwrap_or_default();
+
+        Ok(Container {
+            state: ContainerState::parse(&status).ok_or_else(|| UnknownStateError {
+                name: name.clone(),
+                state: status.clone(),
+            })?,
+            health: state
+                .health
+                .and_then(|health| health.status)
+                .and_then(|status| HealthStatus::parse(status.as_ref())),
+            exit_code: state.exit_code,
+            restart_count: value
+                .restart_count
+                .and_then(|count| u64::try_from(count).ok()),
+            oom_killed: state.oom_killed,
+            service: labels.get("com.docker.compose.service").cloned(),
+            config_hash: labels.get(CONFIG_HASH_LABEL).cloned(),
+            name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_published_ports_and_env_when_converted_then_bollard_config_binds_them() {
+        let config = CreateContainerConfig {
+            name: "web".to_string(),
+            image: "nginx:latest".to_string(),
+            env: BTreeMap::from([("MODE".to_string(), "production".to_string())]),
+            ports: vec![PortMapping {
+                container_port: 80,
+                host_port: Some(8080),
+                host_ip: None,
+                protocol: "tcp".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let actual = bollard::container::Config::<String>::from(&config);
+
+        assert_eq!(actual.image.as_deref(), Some("nginx:latest"));
+        assert_eq!(actual.env, Some(vec!["MODE=production".to_string()]));
+        let bindings = actual.host_config.unwrap().port_bindings.unwrap();
+        assert_eq!(
+            bindings["80/tcp"].as_ref().unwrap()[0].host_port.as_deref(),
+            Some("8080")
+        );
+        assert!(actual.exposed_ports.unwrap().contains_key("80/tcp"));
+    }
+
+    #[test]
+    fn given_event_message_when_converted_then_container_event_has_name_and_action() {
+        let message = bollard::models::EventMessage {
+            action: Some("die".to_string()),
+            actor: Some(bollard::models::EventActor {
+                id: Some("4f2a".to_string()),
+                attributes: Some(HashMap::from([("name".to_string(), "web".to_string())])),
+            }),
+            time: Some(1_700_000_000),
+            ..Default::default()
+        };
+
+        let actual = ContainerEvent::from(message);
+
+        assert_eq!(actual.id, "4f2a");
+        assert_eq!(actual.name.as_deref(), Some("web"));
+        assert_eq!(actual.action, "die");
+        assert_eq!(
+            actual.time.map(|time| time.timestamp()),
+            Some(1_700_000_000)
+        );
+    }
+
+    #[test]
+    fn given_inspected_unhealthy_container_when_converted_then_report_health_exit_code_and_restarts(
+    ) {
+        let response = bollard::models::ContainerInspectResponse {
+            name: Some("/shop-web-1".to_string()),
+            restart_count: Some(3),
+            state: Some(bollard::models::ContainerState {
+                status: Some(bollard::models::ContainerStateStatusEnum::RESTARTING),
+                exit_code: Some(137),
+                oom_killed: Some(true),
+                health: Some(bollard::models::Health {
+                    status: Some(bollard::models::HealthStatusEnum::UNHEALTHY),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            config: Some(bollard::models::ContainerConfig {
+                labels: Some(HashMap::from([(
+                    "com.docker.compose.service".to_string(),
+                    "web".to_string(),
+                )])),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let actual = Container::try_from(response).unwrap();
+
+        assert_eq!(actual.name, "shop-web-1");
+        assert_eq!(actual.state, ContainerState::Restarting);
+        assert_eq!(actual.health, Some(HealthStatus::Unhealthy));
+        assert_eq!(actual.exit_code, Some(137));
+        assert_eq!(actual.restart_count, Some(3));
+        assert_eq!(actual.oom_killed, Some(true));
+        assert_eq!(actual.service.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn given_inspected_container_in_unknown_state_when_converted_then_fail() {
+        let response = bollard::models::ContainerInspectResponse {
+            name: Some("/shop-web-1".to_string()),
+            ..Default::default()
+        };
+
+        assert!(Container::try_from(response).is_err());
+    }
+}
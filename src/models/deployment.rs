@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LIMIT: usize = 20;
+pub const MAX_LIMIT: usize = 100;
+
+/// What started a deployment.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentTrigger {
+    /// The project's first deployment.
+    Create,
+    /// A sync requested through the API.
+    Manual,
+    Webhook,
+    Reconciler,
+    /// A redeployment of the revision of an earlier deployment.
+    Rollback,
+    /// New digests of the services' images found by the image watcher.
+    ImageUpdate,
+    /// A sync among the project's scheduled actions.
+    Schedule,
+    /// A redeployment when gfc started, of a project that was not running as deployed or
+    /// whose deployment a restart interrupted.
+    Startup,
+}
+
+impl DeploymentTrigger {
+    pub const ALL: [DeploymentTrigger; 8] = [
+        DeploymentTrigger::Create,
+        DeploymentTrigger::Manual,
+        DeploymentTrigger::Webhook,
+        DeploymentTrigger::Reconciler,
+        DeploymentTrigger::Rollback,
+        DeploymentTrigger::ImageUpdate,
+        DeploymentTrigger::Schedule,
+        DeploymentTrigger::Startup,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeploymentTrigger::Create => "create",
+            DeploymentTrigger::Manual => "manual",
+            DeploymentTrigger::Webhook => "webhook",
+            DeploymentTrigger::Reconciler => "reconciler",
+            DeploymentTrigger::Rollback => "rollback",
+            DeploymentTrigger::ImageUpdate => "image_update",
+            DeploymentTrigger::Schedule => "schedule",
+            DeploymentTrigger::Startup => "startup",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentOutcome {
+    Succeeded,
+    Failed,
+    Cancelled,
+    /// New commits of a project whose manifest requires approval, waiting to be approved.
+    Pending,
+    /// Approved and being deployed; completed in place once the deployment finishes.
+    Approved,
+    /// A pending deployment that newer commits replaced before it was approved.
+    Superseded,
+}
+
+impl DeploymentOutcome {
+    pub const ALL: [DeploymentOutcome; 6] = [
+        DeploymentOutcome::Succeeded,
+        DeploymentOutcome::Failed,
+        DeploymentOutcome::Cancelled,
+        DeploymentOutcome::Pending,
+        DeploymentOutcome::Approved,
+        DeploymentOutcome::Superseded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeploymentOutcome::Succeeded => "succeeded",
+            DeploymentOutcome::Failed => "failed",
+            DeploymentOutcome::Cancelled => "cancelled",
+            DeploymentOutcome::Pending => "pending",
+            DeploymentOutcome::Approved => "approved",
+            DeploymentOutcome::Superseded => "superseded",
+        }
+    }
+}
+
+/// One finished deployment of a project, from acquiring its lease to its outcome.
+///
+/// Deployments awaiting approval are recorded as [`DeploymentOutcome::Pending`] when the
+/// new commits are found, and completed in place once approved and deployed.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Deployment {
+    /// Assigned by the history, in the order deployments were recorded.
+    pub id: i64,
+    pub project: String,
+    /// The commit deployed, or attempted. Unset for inline projects.
+    pub revision: Option<String>,
+    pub trigger: DeploymentTrigger,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub outcome: DeploymentOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// For rollbacks, the ID of the deployment whose revision was redeployed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollback_of: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeploymentsQuery {
+    /// Newest deployments first; [`DEFAULT_LIMIT`] when unset, capped at [`MAX_LIMIT`].
+    pub limit: Option<usize>,
+}
+
+impl DeploymentsQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
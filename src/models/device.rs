@@ -0,0 +1,89 @@
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+
+/// A `deploy.resources.reservations.devices` entry of a compose service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceReservation {
+    pub service: String,
+    pub driver: Option<String>,
+    /// `None` when specific `device_ids` are asked for instead.
+    pub count: Option<DeviceCount>,
+    pub device_ids: Vec<String>,
+    pub capabilities: Vec<String>,
+    pub options: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceCount {
+    All,
+    Exactly(u32),
+}
+
+/// A GPU on the host, as listed by `nvidia-smi`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gpu {
+    pub index: String,
+    pub uuid: String,
+}
+
+impl DeviceReservation {
+    /// Compose reserves every matching device when neither `count` nor `device_ids` is
+    /// set, so `count` defaults to `all` here.
+    pub fn parse(service: &str, device: &Value) -> DeviceReservation {
+        let strings = |key: &str| -> Vec<String> {
+            device
+                .get(key)
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(|value| match value {
+                    Value::String(value) => Some(value.clone()),
+                    Value::Number(value) => Some(value.to_string()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let device_ids = strings("device_ids");
+        let count = match device.get("count") {
+            Some(Value::Number(count)) => count
+                .as_u64()
+                .map(|count| DeviceCount::Exactly(count as u32)),
+            Some(Value::String(count)) if count == "all" => Some(DeviceCount::All),
+            Some(Value::String(count)) => count.parse().ok().map(DeviceCount::Exactly),
+            _ if device_ids.is_empty() => Some(DeviceCount::All),
+            _ => None,
+        };
+        let options = device
+            .get("options")
+            .and_then(Value::as_mapping)
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, value)| {
+                Some((key.as_str()?.to_string(), value.as_str()?.to_string()))
+            })
+            .collect();
+
+        DeviceReservation {
+            service: service.to_string(),
+            driver: device
+                .get("driver")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            count,
+            device_ids,
+            capabilities: strings("capabilities"),
+            options,
+        }
+    }
+
+    /// NVIDIA GPUs are the only devices gfc keeps an inventory of.
+    pub fn is_gpu(&self) -> bool {
+        self.capabilities
+            .iter()
+            .any(|capability| capability == "gpu")
+            && self
+                .driver
+                .as_deref()
+                .is_none_or(|driver| driver == "nvidia")
+    }
+}
@@ -1,12 +1,35 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+use crate::models::project::ProjectStatus;
+
+/// Label compose sets to a hash of the service's configuration when creating a container.
+pub const CONFIG_HASH_LABEL: &str = "com.docker.compose.config-hash";
+
+const DEFAULT_EXEC_TIMEOUT_SECS: u64 = 30;
+const MAX_EXEC_TIMEOUT_SECS: u64 = 600;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
 pub struct Container {
     pub name: String,
     pub state: ContainerState,
+    /// `None` when the service has no healthcheck.
+    pub health: Option<HealthStatus>,
+    pub exit_code: Option<i64>,
+    /// Only filled in by detailed lookups, which need an extra `docker inspect`.
+    pub restart_count: Option<u64>,
+    /// Whether the kernel killed the container for running out of memory. Only filled
+    /// in by detailed lookups.
+    pub oom_killed: Option<bool>,
+    /// `None` for containers compose did not create.
+    pub service: Option<String>,
+    /// The container's `com.docker.compose.config-hash` label.
+    pub config_hash: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum ContainerState {
     Created,
     Dead,
@@ -17,6 +40,132 @@ pub enum ContainerState {
     Running,
 }
 
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectStatusDetail {
+    pub name: String,
+    pub status: ProjectStatus,
+    pub containers: Vec<Container>,
+    /// Containers that died in a way the status alone doesn't tell, even if they were
+    /// restarted since.
+    pub failures: Vec<ContainerFailure>,
+}
+
+/// A container that was killed for running out of memory, or keeps dying and being
+/// restarted.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct ContainerFailure {
+    pub container: String,
+    pub exit_code: Option<i64>,
+    pub reason: String,
+}
+
+/// A named volume of a compose project.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct VolumeUsage {
+    pub name: String,
+    /// `None` when the engine could not tell, e.g. for volume drivers other than `local`.
+    pub bytes: Option<u64>,
+}
+
+/// A compose project the engine knows about, as listed by `docker compose ls`.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct ComposeStack {
+    pub name: String,
+    /// e.g. `running(2)`, or `exited(1)`.
+    pub status: String,
+    /// Absolute paths, as seen by whoever started the stack.
+    pub config_files: Vec<String>,
+}
+
+/// A lifecycle event of one of a project's containers, as reported by `docker events`.
+#[derive(Debug, Clone, Deserialize, Serialize, Eq, PartialEq)]
+pub struct ProjectEvent {
+    pub container: String,
+    pub service: Option<String>,
+    /// e.g. `start`, `die`, or `health_status: unhealthy`.
+    pub action: String,
+    /// Set for `die` events.
+    pub exit_code: Option<i64>,
+    pub time: DateTime<Utc>,
+}
+
+/// A directory with a compose file that gfc lists but doesn't manage.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct ComposeProject {
+    pub name: String,
+    pub path: String,
+    pub status: ProjectStatus,
+}
+
+/// An image in the engine's local store, once per tag, as listed by `docker image ls`.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct LocalImage {
+    pub id: String,
+    /// `None` for dangling images, which lost their tag to a newer image.
+    pub repository: Option<String>,
+    pub tag: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// As docker prints it, e.g. `187MB`.
+    pub size: String,
+}
+
+impl LocalImage {
+    /// What to remove it by: `repository:tag`, or the id for a dangling image, so that
+    /// removing one tag of an image leaves its other tags alone.
+    pub fn reference(&self) -> String {
+        match (&self.repository, &self.tag) {
+            (Some(repository), Some(tag)) => format!("{}:{}", repository, tag),
+            _ => self.id.clone(),
+        }
+    }
+}
+
+/// A network compose created for a project, as listed by `docker network inspect`.
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct ComposeNetwork {
+    pub name: String,
+    /// The `com.docker.compose.project` label.
+    pub project: String,
+    /// Containers with an endpoint on the network. Stopped containers have none.
+    pub containers: usize,
+}
+
+/// A one-off command to run in a running service container.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecRequest {
+    pub command: Vec<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Eq, PartialEq)]
+pub struct ExecOutput {
+    /// `None` when the command was killed for running past its timeout.
+    pub exit_code: Option<i64>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    /// Output past the configured limit was discarded.
+    pub truncated: bool,
+}
+
+impl ExecRequest {
+    pub fn timeout(&self) -> Duration {
+        let secs = self
+            .timeout_secs
+            .unwrap_or(DEFAULT_EXEC_TIMEOUT_SECS)
+            .min(MAX_EXEC_TIMEOUT_SECS);
+        Duration::from_secs(secs)
+    }
+}
+
 impl ContainerState {
     pub fn to_string(&self) -> &str {
         match self {
@@ -30,3 +179,30 @@ impl ContainerState {
         }
     }
 }
+
+impl ContainerState {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "paused" => Some(ContainerState::Paused),
+            "restarting" => Some(ContainerState::Restarting),
+            "removing" => Some(ContainerState::Removing),
+            "running" => Some(ContainerState::Running),
+            "dead" => Some(ContainerState::Dead),
+            "created" => Some(ContainerState::Created),
+            "exited" => Some(ContainerState::Exited),
+            _ => None,
+        }
+    }
+}
+
+impl HealthStatus {
+    /// Docker reports `""` or `"none"` for containers without a healthcheck.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "starting" => Some(HealthStatus::Starting),
+            "healthy" => Some(HealthStatus::Healthy),
+            "unhealthy" => Some(HealthStatus::Unhealthy),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::project::ProjectFile;
+
+/// Version of the bundle layout written by `GET /export`.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Every project manifest in the workspace, as one document that `POST /import` accepts
+/// back. Manifests are exported as is, webhook secrets included.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkspaceExport {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub projects: Vec<ProjectFile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    /// Run `docker compose up` for imported projects, not just clone them.
+    #[serde(default)]
+    pub deploy: bool,
+}
+
+/// Body of `POST /import/portainer`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PortainerImportRequest {
+    /// Host path of Portainer's `/data` volume, e.g.
+    /// `/var/lib/docker/volumes/portainer_data/_data`.
+    pub data_dir: String,
+    /// Run `docker compose up` for imported stacks. The compose project name stays the
+    /// same, so compose takes over the containers Portainer started, recreating those
+    /// whose configuration now resolves differently.
+    #[serde(default)]
+    pub deploy: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Queued,
+    /// A project with the same name already exists and was left untouched.
+    Skipped,
+    Failed,
+    /// Created for an environment the manifest no longer declares, and queued for deletion.
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ImportedProject {
+    pub name: String,
+    pub status: ImportStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
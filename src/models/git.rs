@@ -1,9 +1,212 @@
+use chrono::{DateTime, Utc};
+use glob::Pattern;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// In manifests, `path` may also be a list of compose files, applied in order as with
+/// repeated `-f` flags. The first one is kept in `path` and the rest in `extra_paths`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(from = "GitSourceFields", into = "GitSourceFields")]
 pub struct GitSource {
     pub url: String,
     pub branch: String,
     /// path to compose.yml file
     pub path: String,
+    /// Compose files applied on top of `path`, e.g. `docker-compose.prod.yml`.
+    pub extra_paths: Vec<String>,
+    /// Track the newest tag matching this glob, e.g. `v1.2.*`, instead of `branch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_pattern: Option<String>,
+    /// Private key for reaching `url` over SSH, for private repositories.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<SshKey>,
+}
+
+/// Where the deploy key of a source comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SshKey {
+    /// A key file on the gfc host.
+    Path(String),
+    /// A secret of the project, stored through the secrets API.
+    Secret(String),
+}
+
+/// [`GitSource`] as written in manifests.
+#[derive(Deserialize, Serialize)]
+struct GitSourceFields {
+    url: String,
+    branch: String,
+    path: ComposePaths,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag_pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ssh_key: Option<SshKey>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ComposePaths {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<GitSourceFields> for GitSource {
+    fn from(value: GitSourceFields) -> Self {
+        let (path, extra_paths) = match value.path {
+            ComposePaths::One(path) => (path, vec![]),
+            ComposePaths::Many(mut paths) if !paths.is_empty() => {
+                let path = paths.remove(0);
+                (path, paths)
+            }
+            ComposePaths::Many(_) => (String::new(), vec![]),
+        };
+        GitSource {
+            url: value.url,
+            branch: value.branch,
+            path,
+            extra_paths,
+            tag_pattern: value.tag_pattern,
+            ssh_key: value.ssh_key,
+        }
+    }
+}
+
+impl From<GitSource> for GitSourceFields {
+    fn from(value: GitSource) -> Self {
+        let path = match value.extra_paths.is_empty() {
+            true => ComposePaths::One(value.path),
+            false => ComposePaths::Many([vec![value.path], value.extra_paths].concat()),
+        };
+        GitSourceFields {
+            url: value.url,
+            branch: value.branch,
+            path,
+            tag_pattern: value.tag_pattern,
+            ssh_key: value.ssh_key,
+        }
+    }
+}
+
+impl GitSource {
+    /// Whether `tag_pattern` selects `tag`. False when no pattern is set or it is invalid.
+    pub fn matches_tag(&self, tag: &str) -> bool {
+        self.tag_pattern
+            .as_deref()
+            .and_then(|pattern| Pattern::new(pattern).ok())
+            .is_some_and(|pattern| pattern.matches(tag))
+    }
+
+    /// The highest version among `tags` that `tag_pattern` selects.
+    pub fn newest_matching_tag<'a, I>(&self, tags: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        tags.into_iter()
+            .filter(|tag| self.matches_tag(tag))
+            .max_by(|a, b| compare_versions(a, b))
+    }
+}
+
+/// A commit of a project's repository.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct Commit {
+    pub revision: String,
+    pub author: String,
+    pub committed_at: DateTime<Utc>,
+    /// The first line of its message.
+    pub subject: String,
+}
+
+/// What syncing a project would change: the commits the remote has beyond the one
+/// deployed, and how the compose configuration differs between the two.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ProjectDiff {
+    pub deployed_revision: String,
+    pub remote_revision: String,
+    /// Newest first.
+    pub commits: Vec<Commit>,
+    /// A unified diff of `docker compose config` from the deployed revision to the
+    /// remote one, with variables and env files left unresolved. Empty when nothing
+    /// changed.
+    pub compose_diff: String,
+}
+
+/// Orders tags like `v1.10.0` after `v1.9.2`, and a pre-release such as `v2.0.0-rc1`
+/// before its release. Parts that aren't numbers compare as text.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |tag: &str| -> (Vec<u64>, Option<String>) {
+        let tag = tag.strip_prefix('v').unwrap_or(tag);
+        let tag = tag.split('+').next().unwrap_or(tag);
+        let (core, pre_release) = match tag.split_once('-') {
+            Some((core, pre_release)) => (core, Some(pre_release.to_string())),
+            None => (tag, None),
+        };
+        let core = core
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        (core, pre_release)
+    };
+    let (a_core, a_pre) = parse(a);
+    let (b_core, b_pre) = parse(b);
+
+    a_core
+        .cmp(&b_core)
+        .then_with(|| match (&a_pre, &b_pre) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a_pre), Some(b_pre)) => a_pre.cmp(b_pre),
+        })
+        .then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_tags_when_newest_matching_tag_then_return_highest_matching_version() {
+        let source = GitSource {
+            tag_pattern: Some("v1.2.*".to_string()),
+            ..Default::default()
+        };
+        let tags = ["v1.2.9", "v1.2.10", "v1.2.11-rc1", "v1.3.0", "latest"];
+
+        let actual = source.newest_matching_tag(tags);
+
+        assert_eq!(actual, Some("v1.2.11-rc1"));
+        assert_eq!(
+            source.newest_matching_tag(["v1.2.9", "v1.2.10"]),
+            Some("v1.2.10")
+        );
+    }
+
+    #[test]
+    fn given_release_and_pre_release_when_compared_then_release_is_newer() {
+        assert_eq!(compare_versions("v2.0.0-rc1", "v2.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+    }
+
+    #[test]
+    fn given_list_of_paths_when_deserialized_then_first_is_path_and_rest_are_extra() {
+        let yaml = "url: https://example.com/app.git\nbranch: main\npath: [docker-compose.yml, docker-compose.prod.yml]\n";
+
+        let actual: GitSource = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(actual.path, "docker-compose.yml");
+        assert_eq!(actual.extra_paths, vec!["docker-compose.prod.yml"]);
+        let round_trip: GitSource =
+            serde_yaml::from_str(&serde_yaml::to_string(&actual).unwrap()).unwrap();
+        assert_eq!(round_trip.extra_paths, actual.extra_paths);
+        assert!(!serde_yaml::to_string(&GitSource::default())
+            .unwrap()
+            .contains('['));
+    }
+
+    #[test]
+    fn given_no_tag_pattern_when_matches_tag_then_return_false() {
+        assert!(!GitSource::default().matches_tag("v1.0.0"));
+    }
 }
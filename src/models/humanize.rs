@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// `?humanize=true` adds relative times such as `3 minutes ago` next to timestamps, for
+/// clients like shell scripts that would rather not do date arithmetic.
+#[derive(Debug, Default, Deserialize)]
+pub struct HumanizeQuery {
+    #[serde(default)]
+    pub humanize: bool,
+}
+
+/// `time` relative to `now` in its largest whole unit, e.g. `3 minutes ago` or
+/// `in 2 hours`. Anything under a minute away is `just now`.
+pub fn relative(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - time).num_seconds();
+    let (count, unit) = match seconds.unsigned_abs() {
+        s if s < 60 => return "just now".to_string(),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3600, "hour"),
+        s if s < 30 * 86_400 => (s / 86_400, "day"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "month"),
+        s => (s / (365 * 86_400), "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    match seconds < 0 {
+        true => format!("in {} {}{}", count, unit, plural),
+        false => format!("{} {}{} ago", count, unit, plural),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn given_times_around_now_when_relative_then_use_largest_whole_unit() {
+        let now = Utc::now();
+
+        let actual = [
+            now - Duration::seconds(30),
+            now - Duration::minutes(3),
+            now - Duration::minutes(61),
+            now - Duration::days(45),
+            now + Duration::hours(2),
+        ]
+        .map(|time| relative(time, now));
+
+        assert_eq!(
+            actual,
+            [
+                "just now",
+                "3 minutes ago",
+                "1 hour ago",
+                "1 month ago",
+                "in 2 hours"
+            ]
+        );
+    }
+}
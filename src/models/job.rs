@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::humanize;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    CreateProject,
+    SyncProject,
+    RollbackProject,
+    /// The deployment of a pending deployment once it was approved.
+    DeployApproved,
+    DeleteProject,
+}
+
+impl JobKind {
+    pub fn queue(&self) -> JobQueue {
+        match self {
+            JobKind::CreateProject
+            | JobKind::SyncProject
+            | JobKind::RollbackProject
+            | JobKind::DeployApproved => JobQueue::Deployments,
+            JobKind::DeleteProject => JobQueue::Teardowns,
+        }
+    }
+}
+
+/// Jobs wait in one of these for a free slot. Each has its own slots, so a backlog of
+/// deployments doesn't hold up deletes.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum JobQueue {
+    Deployments,
+    Teardowns,
+}
+
+impl JobQueue {
+    pub const ALL: [JobQueue; 2] = [JobQueue::Deployments, JobQueue::Teardowns];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobQueue::Deployments => "deployments",
+            JobQueue::Teardowns => "teardowns",
+        }
+    }
+}
+
+/// What a queue is doing right now, and how its jobs turned out since startup.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct QueueStats {
+    pub queue: JobQueue,
+    pub max_parallel: usize,
+    pub queued: usize,
+    pub running: usize,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub project: String,
+    pub status: JobStatus,
+    /// Set once the job has failed, or was cancelled while running.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// `created_at` and `updated_at` relative to now, with `?humanize=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_ago: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_ago: Option<String>,
+}
+
+impl Job {
+    pub fn humanize(&mut self, now: DateTime<Utc>) {
+        self.created_ago = Some(humanize::relative(self.created_at, now));
+        self.updated_ago = Some(humanize::relative(self.updated_at, now));
+    }
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
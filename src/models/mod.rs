@@ -1,5 +1,24 @@
+pub mod activity;
+pub mod badge;
+pub mod bootstrap;
+pub mod compose_file;
+#[cfg(feature = "docker-api")]
 pub mod container_client;
+pub mod deployment;
+pub mod device;
 pub mod docker_compose;
+pub mod export;
 pub mod git;
+pub mod humanize;
+pub mod job;
+pub mod network;
+pub mod preview;
 pub mod project;
 pub mod response;
+pub mod retry;
+pub mod schedule;
+pub mod selector;
+pub mod system;
+pub mod usage;
+pub mod validation;
+pub mod webhook;
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SubnetError {
+    #[error("Invalid subnet, expected e.g. 10.200.0.0/16: {0}")]
+    Invalid(String),
+    #[error("Subnet has host bits set: {0}")]
+    HostBitsSet(String),
+}
+
+/// A service of the project attached to the shared network, where services of other
+/// projects reach it by its aliases.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SharedService {
+    pub service: String,
+    /// DNS names on the shared network; `<project>-<service>` when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// An IPv4 network in CIDR notation, e.g. `10.200.0.0/16`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Subnet {
+    address: Ipv4Addr,
+    prefix_len: u8,
+}
+
+impl Subnet {
+    pub fn overlaps(&self, other: &Subnet) -> bool {
+        let mask = mask(self.prefix_len.min(other.prefix_len));
+        u32::from(self.address) & mask == u32::from(other.address) & mask
+    }
+
+    /// The subnets of `prefix_len` this one divides into, in address order. Empty when
+    /// `prefix_len` is shorter than this subnet's own.
+    pub fn split(&self, prefix_len: u8) -> impl Iterator<Item = Subnet> {
+        let start = u64::from(u32::from(self.address));
+        let count = match (self.prefix_len..=32).contains(&prefix_len) {
+            true => 1u64 << (prefix_len - self.prefix_len),
+            false => 0,
+        };
+        let step = 1u64 << (32 - u32::from(prefix_len.min(32)));
+
+        (0..count).map(move |i| Subnet {
+            address: Ipv4Addr::from((start + i * step) as u32),
+            prefix_len,
+        })
+    }
+}
+
+fn mask(prefix_len: u8) -> u32 {
+    u32::MAX
+        .checked_shl(32 - u32::from(prefix_len))
+        .unwrap_or(0)
+}
+
+impl FromStr for Subnet {
+    type Err = SubnetError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || SubnetError::Invalid(value.to_string());
+        let (address, prefix_len) = value.split_once('/').ok_or_else(invalid)?;
+        let address = address.parse::<Ipv4Addr>().map_err(|_| invalid())?;
+        let prefix_len = prefix_len
+            .parse::<u8>()
+            .ok()
+            .filter(|prefix_len| *prefix_len <= 32)
+            .ok_or_else(invalid)?;
+
+        match u32::from(address) & !mask(prefix_len) {
+            0 => Ok(Subnet {
+                address,
+                prefix_len,
+            }),
+            _ => Err(SubnetError::HostBitsSet(value.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix_len)
+    }
+}
+
+impl TryFrom<String> for Subnet {
+    type Error = SubnetError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Subnet> for String {
+    fn from(value: Subnet) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subnet(value: &str) -> Subnet {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn given_cidr_when_parsed_then_displayed_the_same() {
+        assert_eq!(subnet("10.200.0.0/16").to_string(), "10.200.0.0/16");
+        assert_eq!(
+            "10.200.0.1/16".parse::<Subnet>(),
+            Err(SubnetError::HostBitsSet("10.200.0.1/16".to_string()))
+        );
+        assert!("10.200.0.0/33".parse::<Subnet>().is_err());
+        assert!("10.200.0.0".parse::<Subnet>().is_err());
+    }
+
+    #[test]
+    fn given_nested_and_disjoint_subnets_when_overlaps_then_only_nested_overlap() {
+        let pool = subnet("10.200.0.0/16");
+
+        assert!(pool.overlaps(&subnet("10.200.3.0/24")));
+        assert!(subnet("10.200.3.0/24").overlaps(&pool));
+        assert!(!pool.overlaps(&subnet("10.201.0.0/24")));
+    }
+
+    #[test]
+    fn given_pool_when_split_then_return_subnets_in_address_order() {
+        let actual = subnet("10.200.0.0/22")
+            .split(24)
+            .map(|subnet| subnet.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![
+                "10.200.0.0/24",
+                "10.200.1.0/24",
+                "10.200.2.0/24",
+                "10.200.3.0/24"
+            ]
+        );
+        assert_eq!(subnet("10.200.0.0/24").split(16).count(), 0);
+    }
+}
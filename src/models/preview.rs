@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Opts a project into previews: every pull request against its repository and branch is
+/// deployed as a project of its own, `<project>-pr-<number>`, until it is closed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PreviewSettings {
+    /// Labels added to every service of a preview, e.g. routing rules for a reverse
+    /// proxy. `{name}`, `{project}`, `{number}` and `{branch}` in keys and values are
+    /// replaced with the preview's name, the project's name, the pull request number and its
+    /// branch.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Set on a project gfc created to preview a pull request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Preview {
+    /// The project whose pull request this is.
+    pub of: String,
+    pub number: u64,
+    /// Labels added to every service, rendered from the project's `previews.labels`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Preview {
+    /// The labels the preview's services get: `gfc.preview.of` and `gfc.preview.number`,
+    /// then those of the project's settings.
+    pub fn service_labels(&self) -> BTreeMap<String, String> {
+        let mut labels = BTreeMap::from([
+            ("gfc.preview.of".to_string(), self.of.clone()),
+            ("gfc.preview.number".to_string(), self.number.to_string()),
+        ]);
+        labels.extend(self.labels.clone());
+        labels
+    }
+}
+
+/// `myapp` and 42 -> `myapp-pr-42`.
+pub fn preview_name(project: &str, number: u64) -> String {
+    format!("{}-pr-{}", project, number)
+}
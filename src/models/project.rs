@@ -1,17 +1,683 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Component, Path};
+use thiserror::Error;
 
 use crate::models::git::GitSource;
+use crate::models::humanize;
+use crate::models::network::SharedService;
+use crate::models::preview::{Preview, PreviewSettings};
+use crate::models::retry::RetryPolicy;
+use crate::models::schedule::{ActiveSchedule, ScheduledAction};
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// Extensions project files may use on disk, in discovery order.
+pub const MANIFEST_EXTENSIONS: &[&str] = &["yml", "yaml", "json", "toml"];
+
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    #[error("Failed to parse YAML manifest: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Failed to parse JSON manifest: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to parse TOML manifest: {0}")]
+    Toml(#[from] toml::de::Error),
+}
+
+/// Formats a project file can be written in. All of them parse into the same
+/// [`ProjectFile`]; gfc itself always stores manifests as YAML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl ManifestFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    /// `yml` and `yaml` are both YAML.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "yml" | "yaml" => Some(ManifestFormat::Yaml),
+            "json" => Some(ManifestFormat::Json),
+            "toml" => Some(ManifestFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// Media type parameters such as `charset` are ignored.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next()?.trim().to_ascii_lowercase();
+        match media_type.as_str() {
+            "application/json" => Some(ManifestFormat::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(ManifestFormat::Yaml)
+            }
+            "application/toml" | "text/toml" => Some(ManifestFormat::Toml),
+            _ => None,
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<ProjectFile, ManifestError> {
+        match self {
+            ManifestFormat::Yaml => Ok(serde_yaml::from_str(content)?),
+            ManifestFormat::Json => Ok(serde_json::from_str(content)?),
+            ManifestFormat::Toml => Ok(toml::from_str(content)?),
+        }
+    }
+}
+
+const REDACTED: &str = "********";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ProjectFile {
     pub name: String,
     pub source: GitSource,
+    /// Selectable with `?selector=`, e.g. `team: payments` matches `team=payments`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Free-form notes on the project, e.g. an owner or a runbook link, that gfc keeps
+    /// but never interprets.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<ProjectWebhook>,
+    /// Overrides the global `retry` policy for this project's deployments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Images to start pulling while the repository is cloned.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<ActiveSchedule>,
+    /// Actions to run on cron schedules, e.g. a restart every night.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scheduled_actions: Vec<ScheduledAction>,
+    /// Redeploy when the registry has a new digest for one of the services' images, e.g.
+    /// after a push to `:latest`. Only checked while `image_watch` is enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub watch_images: bool,
+    /// Set `GFC_PROJECT`, `GFC_REVISION` and `GFC_DEPLOYED_AT` in every service's
+    /// environment, so applications can report what they run. As `GFC_DEPLOYED_AT`
+    /// changes, every deployment recreates the containers.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inject_provenance: bool,
+    /// Services to attach to the network gfc shares between projects, created on first
+    /// use, so services of other projects can reach them without an external network set
+    /// up by hand.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shared_services: Vec<SharedService>,
+    /// Deploy pull requests against the project's branch as projects of their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previews: Option<PreviewSettings>,
+    /// Set on the projects gfc creates for those pull requests, which it deletes again
+    /// once they are closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Preview>,
+    /// Deploy the project once per environment instead, each as a project of its own
+    /// named `<name>-<environment>`. Only read when the project is created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environments: Vec<ProjectEnvironment>,
+    /// Set on the projects created for those environments.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_of: Option<EnvironmentOf>,
+    /// Variables compose substitutes into the compose file, written to a `.env` next to
+    /// it before `up`. They win over those of `env_file`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    /// An env file in the repository, e.g. `env/production.env`, that the `.env` starts
+    /// from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+    /// Files in the repository encrypted with sops, decrypted next to themselves before
+    /// `up` with `enc` dropped from their name, e.g. `prod.enc.env` to `prod.env`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sops_files: Vec<String>,
+    /// Paths in the repository, besides the directories of its compose files, whose
+    /// changes redeploy the project on a push, e.g. a `libs/` directory its images are
+    /// built from. Pushes that change nothing the project watches are ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watch_paths: Vec<String>,
+    /// Overrides the global `disk_quota.max_bytes` for this project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_quota_bytes: Option<u64>,
+    /// Whether new commits found by the reconciler or a webhook wait for
+    /// `POST /projects/{name}/deployments/{id}/approve` before they are deployed.
+    #[serde(default, skip_serializing_if = "ApprovalPolicy::is_automatic")]
+    pub approval: ApprovalPolicy,
+    /// The compose file was uploaded instead of cloned. `source` only names the compose
+    /// file, and the project's workspace is not a git checkout.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inline: bool,
+    /// Set while the project is being torn down, and left in place when that fails so
+    /// the project stays listed until a retried delete gets through.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deletion: Option<Deletion>,
+    /// Set from when the project is accepted until its first deployment succeeds, and
+    /// left with the reason when that fails, since nothing else would record it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation: Option<Creation>,
+}
+
+/// Syncs requested through the API deploy the branch as it is either way.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalPolicy {
+    #[default]
+    Automatic,
+    /// Record new commits as a pending deployment instead of deploying them.
+    Required,
+}
+
+impl ApprovalPolicy {
+    pub fn is_automatic(&self) -> bool {
+        *self == ApprovalPolicy::Automatic
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Deletion {
+    /// Why the last attempt stopped, once it has failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Creation {
+    /// Why the first deployment failed, once it has.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether it failed because its job was cancelled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+}
+
+/// An environment of a project, e.g. `staging`, and what it does differently.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProjectEnvironment {
+    pub name: String,
+    /// The branch it deploys, instead of the project's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Variables set on top of the project's `environment`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub environment: BTreeMap<String, String>,
+    /// The env file it starts from, instead of the project's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<String>,
+}
+
+/// The project and environment a project was created for.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct EnvironmentOf {
+    pub project: String,
+    pub environment: String,
+}
+
+/// File name an uploaded compose file is stored under.
+pub const INLINE_COMPOSE_FILE: &str = "docker-compose.yml";
+
+/// Shared secret for `POST /webhooks/generic/{project}`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProjectWebhook {
+    pub secret: String,
+}
+
+impl ProjectFile {
+    /// Manifest for a project whose compose file is uploaded rather than kept in git.
+    pub fn inline(name: &str) -> ProjectFile {
+        ProjectFile {
+            name: name.to_string(),
+            source: GitSource {
+                path: INLINE_COMPOSE_FILE.to_string(),
+                ..Default::default()
+            },
+            inline: true,
+            ..Default::default()
+        }
+    }
+
+    /// What a push must change to redeploy the project: the directory of each compose
+    /// file, its env and sops files, and its `watch_paths`. An empty path stands for the
+    /// whole repository, as for a compose file at its root.
+    pub fn watched_paths(&self) -> Vec<String> {
+        let compose_dirs = std::iter::once(&self.source.path)
+            .chain(&self.source.extra_paths)
+            .map(|path| {
+                let path = Path::new(path);
+                match path.extension().and_then(|extension| extension.to_str()) {
+                    Some("yml" | "yaml") => path.parent().unwrap_or(Path::new("")),
+                    _ => path,
+                }
+            });
+        let files = self
+            .env_file
+            .iter()
+            .chain(&self.sops_files)
+            .chain(&self.watch_paths)
+            .map(Path::new);
+        compose_dirs
+            .chain(files)
+            .map(|path| {
+                path.components()
+                    .filter_map(|component| match component {
+                        Component::Normal(part) => part.to_str(),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect()
+    }
+
+    /// Whether a push changing the files at `changed`, relative to the repository root,
+    /// affects the project.
+    pub fn is_affected_by<S: AsRef<str>>(&self, changed: &[S]) -> bool {
+        let watched = self.watched_paths();
+        changed.iter().any(|file| {
+            let file = file.as_ref();
+            watched.iter().any(|path| {
+                path.is_empty()
+                    || file == path
+                    || file
+                        .strip_prefix(path.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+        })
+    }
+
+    /// Whether the project should be up at `now`, i.e. it has no schedule or its window
+    /// is open. An invalid schedule counts as closed.
+    pub fn is_scheduled_at(&self, now: NaiveDateTime) -> bool {
+        self.schedule
+            .as_ref()
+            .is_none_or(|schedule| schedule.is_active_at(now).unwrap_or(false))
+    }
+
+    /// The status a project being deleted reports instead of that of its containers,
+    /// which may already be gone along with its checkout.
+    pub fn deletion_status(&self) -> Option<ProjectStatus> {
+        self.deletion.as_ref().map(|deletion| match deletion.error {
+            None => ProjectStatus::Deleting,
+            Some(_) => ProjectStatus::DeleteFailed,
+        })
+    }
+
+    /// The status a project being created reports until its first deployment succeeds.
+    pub fn creation_status(&self) -> Option<ProjectStatus> {
+        self.creation
+            .as_ref()
+            .map(|creation| match &creation.error {
+                _ if creation.cancelled => ProjectStatus::Cancelled,
+                None => ProjectStatus::CreationInProgress,
+                Some(reason) => ProjectStatus::DeploymentFailed {
+                    reason: reason.clone(),
+                },
+            })
+    }
+
+    /// The projects the manifest stands for: itself, or one per environment when it
+    /// declares any.
+    pub fn environment_projects(self) -> Vec<ProjectFile> {
+        if self.environments.is_empty() {
+            return vec![self];
+        }
+
+        self.environments
+            .iter()
+            .map(|environment| {
+                let mut variables = self.environment.clone();
+                variables.extend(environment.environment.clone());
+                ProjectFile {
+                    name: format!("{}-{}", self.name, environment.name),
+                    source: GitSource {
+                        branch: environment
+                            .branch
+                            .clone()
+                            .unwrap_or_else(|| self.source.branch.clone()),
+                        ..self.source.clone()
+                    },
+                    environment: variables,
+                    env_file: environment
+                        .env_file
+                        .clone()
+                        .or_else(|| self.env_file.clone()),
+                    environments: vec![],
+                    environment_of: Some(EnvironmentOf {
+                        project: self.name.clone(),
+                        environment: environment.name.clone(),
+                    }),
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// The status recorded in the manifest, which takes precedence over that of the
+    /// containers: a deletion first, then a creation.
+    pub fn lifecycle_status(&self) -> Option<ProjectStatus> {
+        self.deletion_status().or_else(|| self.creation_status())
+    }
+
+    /// A copy that is safe to return from the API.
+    pub fn redacted(&self) -> ProjectFile {
+        let mut project_file = self.clone();
+        if let Some(webhook) = project_file.webhook.as_mut() {
+            webhook.secret = REDACTED.to_string();
+        }
+        project_file
+    }
+}
+
+/// Where an inline project's files should live from now on. With `push`, the uploaded
+/// files are pushed to the remote; otherwise they must already be there.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MigrateToGitRequest {
+    pub url: String,
+    pub branch: String,
+    #[serde(default = "inline_compose_path")]
+    pub path: String,
+    #[serde(default)]
+    pub push: bool,
+}
+
+fn inline_compose_path() -> String {
+    INLINE_COMPOSE_FILE.to_string()
+}
+
+impl MigrateToGitRequest {
+    pub fn source(&self) -> GitSource {
+        GitSource {
+            url: self.url.clone(),
+            branch: self.branch.clone(),
+            path: self.path.clone(),
+            extra_paths: vec![],
+            tag_pattern: None,
+            ssh_key: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FromComposeQuery {
+    pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListProjectsQuery {
+    /// `ndjson` streams projects one per line instead of returning a single document.
+    pub format: Option<String>,
+    #[serde(default)]
+    pub humanize: bool,
+    /// A label selector, e.g. `team=payments,env=prod`, listing only the projects it
+    /// selects.
+    #[serde(default)]
+    pub selector: Option<String>,
+}
+
+/// The projects a bulk action applies to.
+#[derive(Debug, Deserialize)]
+pub struct SelectorQuery {
+    pub selector: String,
+}
+
+/// The job a bulk action queued for one of the projects it selected, or why it could not.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct QueuedJob {
+    pub project: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Project {
     pub name: String,
     pub source: GitSource,
-    pub status: String,
-    pub last_updated_at: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    pub status: ProjectStatus,
+    /// RFC 3339, in UTC. `None` while it can't be read, e.g. from a checkout that is
+    /// still being cloned; `warnings` says why.
+    pub last_updated_at: Option<String>,
+    /// `last_updated_at` relative to now, with `?humanize=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_updated_ago: Option<String>,
+    /// The containers no longer match the compose file: a service was stopped or removed
+    /// by hand, or the file changed since they were created.
+    #[serde(default)]
+    pub drifted: bool,
+    /// Set for projects created for an environment of another.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_of: Option<EnvironmentOf>,
+    /// What could not be looked up for the project, which is listed without it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl Project {
+    pub fn humanize(&mut self, now: DateTime<Utc>) {
+        self.last_updated_ago = self
+            .last_updated_at
+            .as_ref()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| humanize::relative(time.to_utc(), now));
+    }
+}
+
+/// Overall state of a project's containers. Serialized as its display form, e.g.
+/// `Running (2/3)`, so clients that read the status as text keep working.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum ProjectStatus {
+    Running {
+        running: usize,
+        total: usize,
+    },
+    /// Nothing is running and at least one container is paused.
+    Paused,
+    Exited,
+    /// The project is being torn down.
+    Deleting,
+    /// Tearing the project down failed partway; deleting it again retries.
+    DeleteFailed,
+    /// The project was accepted and its first clone and deployment are still running.
+    CreationInProgress,
+    /// The project's first clone or deployment failed; syncing it again retries.
+    DeploymentFailed {
+        reason: String,
+    },
+    /// The project's first clone or deployment was cancelled; syncing it again retries.
+    Cancelled,
+}
+
+const DEPLOYMENT_FAILED_PREFIX: &str = "DeploymentFailed: ";
+
+impl fmt::Display for ProjectStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectStatus::Running { running, total } => {
+                write!(f, "Running ({}/{})", running, total)
+            }
+            ProjectStatus::Paused => write!(f, "Paused"),
+            ProjectStatus::Exited => write!(f, "Exited"),
+            ProjectStatus::Deleting => write!(f, "Deleting"),
+            ProjectStatus::DeleteFailed => write!(f, "DeleteFailed"),
+            ProjectStatus::CreationInProgress => write!(f, "CreationInProgress"),
+            ProjectStatus::DeploymentFailed { reason } => {
+                write!(f, "{}{}", DEPLOYMENT_FAILED_PREFIX, reason)
+            }
+            ProjectStatus::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl From<ProjectStatus> for String {
+    fn from(value: ProjectStatus) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for ProjectStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let running = value
+            .strip_prefix("Running (")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .and_then(|counts| counts.split_once('/'))
+            .and_then(|(running, total)| Some((running.parse().ok()?, total.parse().ok()?)));
+
+        if let Some(reason) = value.strip_prefix(DEPLOYMENT_FAILED_PREFIX) {
+            return Ok(ProjectStatus::DeploymentFailed {
+                reason: reason.to_string(),
+            });
+        }
+
+        match (value.as_str(), running) {
+            ("Paused", _) => Ok(ProjectStatus::Paused),
+            ("Exited", _) => Ok(ProjectStatus::Exited),
+            ("Deleting", _) => Ok(ProjectStatus::Deleting),
+            ("DeleteFailed", _) => Ok(ProjectStatus::DeleteFailed),
+            ("CreationInProgress", _) => Ok(ProjectStatus::CreationInProgress),
+            ("Cancelled", _) => Ok(ProjectStatus::Cancelled),
+            (_, Some((running, total))) => Ok(ProjectStatus::Running { running, total }),
+            _ => Err(format!("Unknown project status: {}", value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_project_in_monorepo_directory_when_files_change_then_only_its_paths_affect_it() {
+        let project_file = ProjectFile {
+            source: GitSource {
+                path: "./apps/api/docker-compose.yml".to_string(),
+                ..Default::default()
+            },
+            env_file: Some("env/api.env".to_string()),
+            watch_paths: vec!["libs/shared/".to_string()],
+            ..Default::default()
+        };
+
+        assert!(project_file.is_affected_by(&["apps/api/src/main.rs"]));
+        assert!(project_file.is_affected_by(&["README.md", "env/api.env"]));
+        assert!(project_file.is_affected_by(&["libs/shared/lib.rs"]));
+        assert!(!project_file.is_affected_by(&["apps/api-gateway/docker-compose.yml"]));
+        assert!(!project_file.is_affected_by(&["apps/web/index.html", "libs/other.rs"]));
+        assert!(ProjectFile::default().is_affected_by(&["anything/at/all"]));
+    }
+
+    #[test]
+    fn given_same_project_in_each_format_when_parse_then_return_same_project_file() {
+        let yaml = "name: app\nsource:\n  url: https://github.com/fpiyapol/gfc.git\n  branch: main\n  path: docker-compose.yml\n";
+        let json = r#"{"name":"app","source":{"url":"https://github.com/fpiyapol/gfc.git","branch":"main","path":"docker-compose.yml"}}"#;
+        let toml = "name = \"app\"\n[source]\nurl = \"https://github.com/fpiyapol/gfc.git\"\nbranch = \"main\"\npath = \"docker-compose.yml\"\n";
+
+        let parsed = [
+            ManifestFormat::Yaml.parse(yaml).unwrap(),
+            ManifestFormat::Json.parse(json).unwrap(),
+            ManifestFormat::Toml.parse(toml).unwrap(),
+        ];
+
+        for project_file in parsed {
+            assert_eq!(project_file.name, "app");
+            assert_eq!(project_file.source.branch, "main");
+            assert_eq!(project_file.source.path, "docker-compose.yml");
+        }
+    }
+
+    #[test]
+    fn given_environments_when_expanded_then_return_a_project_per_environment() {
+        let yaml = "name: app\nsource:\n  url: https://github.com/fpiyapol/gfc.git\n  branch: main\n  path: docker-compose.yml\nenvironment:\n  LOG_LEVEL: info\n  REPLICAS: \"1\"\nenvironments:\n  - name: staging\n    branch: develop\n    environment:\n      LOG_LEVEL: debug\n  - name: prod\n    env_file: env/prod.env\n";
+
+        let actual = ManifestFormat::Yaml
+            .parse(yaml)
+            .unwrap()
+            .environment_projects();
+
+        assert_eq!(actual.len(), 2);
+        let (staging, prod) = (&actual[0], &actual[1]);
+        assert_eq!(staging.name, "app-staging");
+        assert_eq!(staging.source.branch, "develop");
+        assert_eq!(staging.environment["LOG_LEVEL"], "debug");
+        assert_eq!(staging.environment["REPLICAS"], "1");
+        assert!(staging.environments.is_empty());
+        assert_eq!(
+            staging.environment_of,
+            Some(EnvironmentOf {
+                project: "app".to_string(),
+                environment: "staging".to_string(),
+            })
+        );
+        assert_eq!(prod.name, "app-prod");
+        assert_eq!(prod.source.branch, "main");
+        assert_eq!(prod.environment["LOG_LEVEL"], "info");
+        assert_eq!(prod.env_file.as_deref(), Some("env/prod.env"));
+    }
+
+    #[test]
+    fn given_project_without_last_update_when_serialized_then_report_null_and_warning() {
+        let mut project = Project {
+            name: "app".to_string(),
+            source: GitSource::default(),
+            status: ProjectStatus::Exited,
+            labels: BTreeMap::new(),
+            last_updated_at: None,
+            last_updated_ago: None,
+            drifted: false,
+            environment_of: None,
+            warnings: vec!["Failed to read the last commit: not a git repository".to_string()],
+        };
+        project.humanize(Utc::now());
+
+        let actual = serde_json::to_value(&project).unwrap();
+
+        assert_eq!(actual["last_updated_at"], serde_json::Value::Null);
+        assert!(actual.get("last_updated_ago").is_none());
+        assert_eq!(
+            actual["warnings"][0],
+            "Failed to read the last commit: not a git repository"
+        );
+    }
+
+    #[test]
+    fn given_each_status_when_serialized_then_round_trips_through_display_form() {
+        let statuses = [
+            ProjectStatus::Running {
+                running: 2,
+                total: 3,
+            },
+            ProjectStatus::Paused,
+            ProjectStatus::Exited,
+            ProjectStatus::CreationInProgress,
+            ProjectStatus::DeploymentFailed {
+                reason: "pull access denied for app (1/2)".to_string(),
+            },
+            ProjectStatus::Cancelled,
+        ];
+
+        for status in statuses {
+            let json = serde_json::to_string(&status).unwrap();
+            let actual: ProjectStatus = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(json, format!("\"{}\"", status));
+            assert_eq!(actual, status);
+        }
+    }
+
+    #[test]
+    fn given_content_type_with_charset_when_from_content_type_then_ignore_parameters() {
+        let actual = ManifestFormat::from_content_type("application/YAML; charset=utf-8");
+
+        assert_eq!(actual, Some(ManifestFormat::Yaml));
+    }
 }
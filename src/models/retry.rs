@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often a deployment step that failed, such as a clone or `compose up`, is tried
+/// again before the deployment fails. The wait doubles after each attempt, up to
+/// `max_backoff_ms`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first included. `1` turns retries off.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Wait a random amount between half the backoff and all of it, so projects that
+    /// failed together don't all retry at once.
+    #[serde(default = "default_jitter")]
+    pub jitter: bool,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_jitter() -> bool {
+    true
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            jitter: default_jitter(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait after the `attempt`th failed attempt, counting from 1, before jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_attempts_when_backoff_then_double_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3000,
+            ..Default::default()
+        };
+
+        let actual = (1..=5)
+            .map(|attempt| policy.backoff(attempt))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            [500, 1000, 2000, 3000, 3000].map(Duration::from_millis)
+        );
+    }
+}
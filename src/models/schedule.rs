@@ -0,0 +1,307 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+const TIME_FORMAT: &str = "%H:%M";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("Invalid time '{0}', expected HH:MM")]
+    InvalidTime(String),
+    #[error("Schedule start and end must differ")]
+    EmptyWindow,
+    #[error("Invalid cron expression '{0}', expected minute hour day month weekday")]
+    InvalidCron(String),
+}
+
+/// When a project should be up, in the host's local time. Outside the window gfc stops
+/// the stack, and brings it back up when the window opens again. A window whose end is
+/// before its start runs overnight, e.g. `22:00`–`06:00`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ActiveSchedule {
+    /// Days the window opens on. Every day when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    pub start: String,
+    pub end: String,
+}
+
+impl ActiveSchedule {
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        let (start, end) = self.window()?;
+        match start == end {
+            true => Err(ScheduleError::EmptyWindow),
+            false => Ok(()),
+        }
+    }
+
+    pub fn is_active_at(&self, now: NaiveDateTime) -> Result<bool, ScheduleError> {
+        let (start, end) = self.window()?;
+        let time = now.time();
+        let today = now.weekday();
+
+        Ok(match start < end {
+            true => self.opens_on(today) && start <= time && time < end,
+            false => {
+                (self.opens_on(today) && time >= start)
+                    || (self.opens_on(today.pred()) && time < end)
+            }
+        })
+    }
+
+    fn opens_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn window(&self) -> Result<(NaiveTime, NaiveTime), ScheduleError> {
+        Ok((parse_time(&self.start)?, parse_time(&self.end)?))
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, ScheduleError> {
+    NaiveTime::parse_from_str(value, TIME_FORMAT)
+        .map_err(|_| ScheduleError::InvalidTime(value.to_string()))
+}
+
+/// Something to do to a project on a cron schedule, independent of its `schedule`
+/// window.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ScheduledAction {
+    pub action: ScheduledActionKind,
+    /// e.g. `0 3 * * *` for 03:00 every night, or `*/15 * * * *`; see [`CronSchedule`].
+    pub cron: String,
+}
+
+impl ScheduledAction {
+    pub fn schedule(&self) -> Result<CronSchedule, ScheduleError> {
+        self.cron.parse()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledActionKind {
+    /// Pull the repository and redeploy, as `POST /projects/{name}/sync` does.
+    Sync,
+    /// Restart the services' containers without recreating them.
+    Restart,
+    Start,
+    Stop,
+}
+
+/// A scheduled action and when it runs next.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ScheduledActionStatus {
+    pub action: ScheduledActionKind,
+    pub cron: String,
+    /// RFC 3339, in UTC. `None` when the expression is invalid or never fires.
+    pub next_run_at: Option<String>,
+}
+
+/// A five-field cron expression, `minute hour day-of-month month day-of-week`, in the
+/// host's local time. Fields are `*`, values, ranges such as `1-5`, steps such as `*/15`
+/// or `8-18/2`, or lists of those. Day-of-week runs from 0 to 7, both Sunday. As with
+/// cron, a day matches either day field when both are restricted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = ScheduleError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let invalid = || ScheduleError::InvalidCron(expression.to_string());
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(invalid());
+        };
+        let weekdays = parse_cron_field(days_of_week, 0, 7).ok_or_else(invalid)?;
+
+        Ok(Self {
+            minutes: parse_cron_field(minutes, 0, 59).ok_or_else(invalid)?,
+            hours: parse_cron_field(hours, 0, 23).ok_or_else(invalid)?,
+            days_of_month: parse_cron_field(days_of_month, 1, 31).ok_or_else(invalid)?,
+            months: parse_cron_field(months, 1, 12).ok_or_else(invalid)?,
+            // Sunday is both 0 and 7.
+            days_of_week: (weekdays | weekdays >> 7) & 0x7f,
+            any_day_of_month: days_of_month.starts_with('*'),
+            any_day_of_week: days_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl CronSchedule {
+    /// The first minute after `after` the schedule fires at. `None` for expressions that
+    /// never fire, e.g. on February 30th.
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        // Leap days come round at least once in eight years.
+        for date in start.date().iter_days().take(8 * 366) {
+            if !self.fires_on(date) {
+                continue;
+            }
+            for hour in (0..24).filter(|hour| has(self.hours, *hour)) {
+                for minute in (0..60).filter(|minute| has(self.minutes, *minute)) {
+                    let at = date.and_hms_opt(hour, minute, 0)?;
+                    if at >= start {
+                        return Some(at);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Whether the schedule fires after `after` and no later than `until`.
+    pub fn fires_between(&self, after: NaiveDateTime, until: NaiveDateTime) -> bool {
+        self.next_after(after).is_some_and(|at| at <= until)
+    }
+
+    fn fires_on(&self, date: NaiveDate) -> bool {
+        let day_of_month = has(self.days_of_month, date.day());
+        let day_of_week = has(self.days_of_week, date.weekday().num_days_from_sunday());
+        has(self.months, date.month())
+            && match (self.any_day_of_month, self.any_day_of_week) {
+                (true, true) => true,
+                (true, false) => day_of_week,
+                (false, true) => day_of_month,
+                (false, false) => day_of_month || day_of_week,
+            }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// The values a field allows, as a bit set.
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u32>().ok().filter(|s| *s > 0)?)),
+            None => (part, None),
+        };
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+            None => {
+                let value = range.parse().ok()?;
+                (value, step.map_or(value, |_| max))
+            }
+        };
+        if first < min || last > max || first > last {
+            return None;
+        }
+        for value in (first..=last).step_by(step.unwrap_or(1) as usize) {
+            set |= 1 << value;
+        }
+    }
+    Some(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        // 2024-01-01 is a Monday.
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(parse_time(time).unwrap())
+    }
+
+    fn schedule(days: Vec<Weekday>, start: &str, end: &str) -> ActiveSchedule {
+        ActiveSchedule {
+            days,
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn given_weekday_office_hours_when_is_active_at_then_only_active_inside_window() {
+        let office_hours = schedule(
+            vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            "08:00",
+            "20:00",
+        );
+
+        assert!(office_hours.is_active_at(at(1, "08:00")).unwrap());
+        assert!(!office_hours.is_active_at(at(1, "20:00")).unwrap());
+        assert!(!office_hours.is_active_at(at(1, "07:59")).unwrap());
+        assert!(!office_hours.is_active_at(at(6, "12:00")).unwrap());
+    }
+
+    #[test]
+    fn given_overnight_window_when_is_active_at_then_it_spills_into_next_day() {
+        let nightly = schedule(vec![Weekday::Fri], "22:00", "06:00");
+
+        assert!(nightly.is_active_at(at(5, "23:00")).unwrap());
+        assert!(nightly.is_active_at(at(6, "05:59")).unwrap());
+        assert!(!nightly.is_active_at(at(6, "23:00")).unwrap());
+    }
+
+    fn cron(expression: &str) -> CronSchedule {
+        expression.parse().unwrap()
+    }
+
+    #[test]
+    fn given_nightly_cron_when_next_after_then_return_next_occurrence() {
+        let nightly = cron("0 3 * * *");
+
+        assert_eq!(nightly.next_after(at(1, "02:59")), Some(at(1, "03:00")));
+        assert_eq!(nightly.next_after(at(1, "03:00")), Some(at(2, "03:00")));
+    }
+
+    #[test]
+    fn given_step_cron_when_fires_between_then_only_at_multiples() {
+        let quarterly = cron("*/15 * * * *");
+
+        assert_eq!(quarterly.next_after(at(1, "10:01")), Some(at(1, "10:15")));
+        assert!(quarterly.fires_between(at(1, "10:14"), at(1, "10:15")));
+        assert!(!quarterly.fires_between(at(1, "10:15"), at(1, "10:29")));
+    }
+
+    #[test]
+    fn given_both_day_fields_when_next_after_then_either_day_matches() {
+        // The 15th, or any Sunday; 2024-01-07 is the first Sunday.
+        let either = cron("0 12 15 * 7");
+
+        assert_eq!(either.next_after(at(1, "00:00")), Some(at(7, "12:00")));
+        assert_eq!(either.next_after(at(14, "13:00")), Some(at(15, "12:00")));
+    }
+
+    #[test]
+    fn given_malformed_cron_when_parsed_then_return_error() {
+        for expression in ["0 3 * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *"] {
+            assert_eq!(
+                expression.parse::<CronSchedule>(),
+                Err(ScheduleError::InvalidCron(expression.to_string()))
+            );
+        }
+        assert_eq!(cron("0 0 30 2 *").next_after(at(1, "00:00")), None);
+    }
+
+    #[test]
+    fn given_malformed_time_when_validate_then_return_error() {
+        let actual = schedule(vec![], "8am", "20:00").validate();
+
+        assert_eq!(actual, Err(ScheduleError::InvalidTime("8am".to_string())));
+    }
+}
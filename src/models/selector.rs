@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SelectorError {
+    #[error("Invalid label selector requirement '{0}': use key=value, key!=value or key")]
+    InvalidRequirement(String),
+}
+
+/// Selects projects by their labels, e.g. `team=payments,env!=dev,critical`. A project is
+/// selected when it meets every requirement; the empty selector selects every project.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector(Vec<Requirement>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    /// The label is set, to any value.
+    Exists(String),
+}
+
+impl LabelSelector {
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.0.iter().all(|requirement| match requirement {
+            Requirement::Equals(key, value) => labels.get(key) == Some(value),
+            Requirement::NotEquals(key, value) => labels.get(key) != Some(value),
+            Requirement::Exists(key) => labels.contains_key(key),
+        })
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        selector
+            .split(',')
+            .map(str::trim)
+            .filter(|requirement| !requirement.is_empty())
+            .map(|requirement| {
+                let invalid = || SelectorError::InvalidRequirement(requirement.to_string());
+                let parsed = match requirement.split_once("!=") {
+                    Some((key, value)) => {
+                        Requirement::NotEquals(key.trim().to_string(), value.trim().to_string())
+                    }
+                    None => match requirement.split_once('=') {
+                        Some((key, value)) => {
+                            Requirement::Equals(key.trim().to_string(), value.trim().to_string())
+                        }
+                        None => Requirement::Exists(requirement.to_string()),
+                    },
+                };
+                match &parsed {
+                    Requirement::Equals(key, _)
+                    | Requirement::NotEquals(key, _)
+                    | Requirement::Exists(key)
+                        if is_valid_label_key(key) =>
+                    {
+                        Ok(parsed)
+                    }
+                    _ => Err(invalid()),
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(LabelSelector)
+    }
+}
+
+/// Label keys can't hold the characters selectors are written with.
+pub fn is_valid_label_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, ',' | '=' | '!'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn given_selector_when_matching_then_require_every_requirement() {
+        let selector: LabelSelector = "team=payments, env!=dev,critical".parse().unwrap();
+
+        assert!(selector.matches(&labels(&[
+            ("team", "payments"),
+            ("env", "prod"),
+            ("critical", "")
+        ])));
+        assert!(!selector.matches(&labels(&[
+            ("team", "payments"),
+            ("env", "dev"),
+            ("critical", "")
+        ])));
+        assert!(!selector.matches(&labels(&[("team", "payments"), ("env", "prod")])));
+        assert!(LabelSelector::default().matches(&labels(&[])));
+    }
+
+    #[test]
+    fn given_requirement_without_key_when_parsed_then_fail() {
+        let actual = "team=payments,=prod".parse::<LabelSelector>();
+
+        assert_eq!(
+            actual,
+            Err(SelectorError::InvalidRequirement("=prod".to_string()))
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+
+use crate::models::docker_compose::{LocalImage, VolumeUsage};
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SystemInfo {
+    pub gfc_version: String,
+    /// `None` when the docker daemon could not be reached.
+    pub docker_engine_version: Option<String>,
+    pub compose_version: Option<String>,
+    pub managed_projects: usize,
+    pub disk_usage: Vec<DirectoryUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DirectoryUsage {
+    pub name: String,
+    pub path: String,
+    /// `None` when the directory could not be read.
+    pub bytes: Option<u64>,
+}
+
+/// Disk a project takes up, against its quota.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ProjectDiskUsage {
+    /// Its checkout and the revisions kept on standby.
+    pub repository_bytes: u64,
+    pub volumes: Vec<VolumeUsage>,
+    /// Of the checkouts and the volumes whose size is known.
+    pub total_bytes: u64,
+    /// `None` without a quota.
+    pub quota_bytes: Option<u64>,
+    pub over_quota: bool,
+}
+
+/// What was left of deleted projects and has been removed.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Compose projects started from a checkout whose project file is gone.
+    pub stacks: Vec<String>,
+    /// Checkouts, standby ones included, without a project file.
+    pub repositories: Vec<String>,
+    pub networks: Vec<String>,
+}
+
+impl PruneReport {
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty() && self.repositories.is_empty() && self.networks.is_empty()
+    }
+}
+
+/// Images no managed project uses any more; see `GET /maintenance/images`.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct ImageGcReport {
+    /// Removed images, or on a dry run the images that would be.
+    pub removed: Vec<LocalImage>,
+    /// Images that could not be removed, e.g. because a container still uses them, with
+    /// why.
+    pub failed: Vec<String>,
+}
@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+/// Everything an anonymous usage report says. Nothing in it identifies the host, its
+/// projects or their repositories; a field must not be added that would.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UsageStats {
+    /// The gfc version.
+    pub version: String,
+    /// How many projects are managed, without their names.
+    pub projects: usize,
+    /// What deploys projects, e.g. `docker-compose`.
+    pub backend: String,
+}
+
+impl UsageStats {
+    pub fn new(projects: usize, backend: &str) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            projects,
+            backend: backend.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_usage_stats_when_serialized_then_contain_only_version_count_and_backend() {
+        let actual = serde_json::to_value(UsageStats::new(3, "docker-compose")).unwrap();
+
+        let mut keys = actual.as_object().unwrap().keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["backend", "projects", "version"]);
+        assert_eq!(actual["projects"], 3);
+    }
+}
@@ -0,0 +1,98 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Not run because an earlier check failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ValidationCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of a dry run of project creation, one entry per check in the order they ran.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct ProjectValidation {
+    pub valid: bool,
+    pub checks: Vec<ValidationCheck>,
+}
+
+impl ProjectValidation {
+    /// Record the outcome of a check, passing its value on when it succeeded.
+    pub fn check<T>(&mut self, name: &str, result: Result<T, String>) -> Option<T> {
+        let (status, error, value) = match result {
+            Ok(value) => (CheckStatus::Passed, None, Some(value)),
+            Err(error) => (CheckStatus::Failed, Some(error), None),
+        };
+        self.checks.push(ValidationCheck {
+            name: name.to_string(),
+            status,
+            error,
+        });
+        value
+    }
+
+    /// Mark every check in `names` that never ran as skipped, and settle `valid`.
+    pub fn finish(mut self, names: &[&str]) -> Self {
+        for name in names {
+            if !self.checks.iter().any(|check| check.name == *name) {
+                self.checks.push(ValidationCheck {
+                    name: name.to_string(),
+                    status: CheckStatus::Skipped,
+                    error: None,
+                });
+            }
+        }
+        self.valid = self
+            .checks
+            .iter()
+            .all(|check| check.status == CheckStatus::Passed);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_failed_check_when_finish_then_remaining_checks_are_skipped_and_invalid() {
+        let mut validation = ProjectValidation::default();
+
+        validation.check("params", Ok(()));
+        validation.check::<()>("remote", Err("unreachable".to_string()));
+        let actual = validation.finish(&["params", "remote", "compose_file"]);
+
+        let statuses = actual
+            .checks
+            .iter()
+            .map(|check| (check.name.as_str(), check.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                ("params", CheckStatus::Passed),
+                ("remote", CheckStatus::Failed),
+                ("compose_file", CheckStatus::Skipped),
+            ]
+        );
+        assert!(!actual.valid);
+    }
+
+    #[test]
+    fn given_all_checks_passed_when_finish_then_valid() {
+        let mut validation = ProjectValidation::default();
+
+        validation.check("params", Ok(()));
+        let actual = validation.finish(&["params"]);
+
+        assert!(actual.valid);
+    }
+}
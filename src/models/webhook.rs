@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+
+/// A push from any forge, reduced to what is needed to find the projects it affects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushEvent {
+    pub repository_urls: Vec<String>,
+    /// Branch or tag name the push updated.
+    pub ref_name: Option<String>,
+    /// The push deleted the branch or tag, leaving nothing to deploy.
+    pub deleted: bool,
+    /// The commits the ref moved between. `None` when the push created it, or the forge
+    /// didn't say.
+    pub commits: Option<CommitRange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitRange {
+    pub before: String,
+    pub after: String,
+}
+
+impl CommitRange {
+    /// The range between `before` and `after` as a push payload gives them, where all
+    /// zeros stands for a ref that doesn't exist.
+    pub fn of_push(before: &str, after: &str) -> Option<Self> {
+        let is_commit = |sha: &str| !sha.is_empty() && !sha.bytes().all(|b| b == b'0');
+        (is_commit(before) && is_commit(after)).then(|| CommitRange {
+            before: before.to_string(),
+            after: after.to_string(),
+        })
+    }
+}
+
+/// A pull or merge request being opened, updated or closed, reduced to what is needed to
+/// preview it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequestEvent {
+    /// URLs of the repository the pull request targets.
+    pub repository_urls: Vec<String>,
+    /// The branch it would merge into.
+    pub base_branch: String,
+    pub number: u64,
+    /// Where its branch lives, which is a fork for pull requests from one. `None` once
+    /// the fork is gone.
+    pub head_url: Option<String>,
+    pub head_branch: String,
+    pub action: PullRequestAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullRequestAction {
+    /// Opened or reopened.
+    Opened,
+    /// New commits were pushed to its branch.
+    Updated,
+    /// Closed, merged or not.
+    Closed,
+    /// Anything else, e.g. a label or reviewer changed, which leaves previews as they are.
+    Other,
+}
+
+/// A project a push would sync, as reported by `POST /webhooks/dry-run`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct WebhookMatch {
+    pub project: String,
+    /// `source` when the project's own URL and branch matched, or the `repository`
+    /// pattern of the rule that did.
+    pub matched_by: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DryRunQuery {
+    /// The forge whose payload format the body is in: `github`, `gitlab` or `gitea`.
+    pub forge: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubPushEvent {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    #[serde(default)]
+    pub before: String,
+    #[serde(default)]
+    pub after: String,
+    #[serde(default)]
+    pub deleted: bool,
+    pub repository: GithubRepository,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubRepository {
+    pub clone_url: String,
+    pub ssh_url: String,
+    pub html_url: String,
+}
+
+impl From<GithubPushEvent> for PushEvent {
+    fn from(value: GithubPushEvent) -> Self {
+        PushEvent {
+            repository_urls: vec![
+                value.repository.clone_url,
+                value.repository.ssh_url,
+                value.repository.html_url,
+            ],
+            ref_name: ref_name(&value.git_ref),
+            deleted: value.deleted,
+            commits: CommitRange::of_push(&value.before, &value.after),
+        }
+    }
+}
+
+/// Gitea and Forgejo send GitHub-compatible push payloads.
+pub type GiteaPushEvent = GithubPushEvent;
+
+#[derive(Debug, Deserialize)]
+pub struct GithubPullRequestEvent {
+    pub action: String,
+    pub number: u64,
+    pub pull_request: GithubPullRequest,
+    pub repository: GithubRepository,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubPullRequest {
+    pub head: GithubBranch,
+    pub base: GithubBranch,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubBranch {
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// Null once the fork a pull request came from is deleted.
+    pub repo: Option<GithubRepository>,
+}
+
+impl From<GithubPullRequestEvent> for PullRequestEvent {
+    /// Gitea and Forgejo call new commits `synchronized` rather than GitHub's
+    /// `synchronize`.
+    fn from(value: GithubPullRequestEvent) -> Self {
+        let action = match value.action.as_str() {
+            "opened" | "reopened" => PullRequestAction::Opened,
+            "synchronize" | "synchronized" => PullRequestAction::Updated,
+            "closed" => PullRequestAction::Closed,
+            _ => PullRequestAction::Other,
+        };
+        PullRequestEvent {
+            repository_urls: vec![
+                value.repository.clone_url,
+                value.repository.ssh_url,
+                value.repository.html_url,
+            ],
+            base_branch: value.pull_request.base.git_ref,
+            number: value.number,
+            head_url: value.pull_request.head.repo.map(|repo| repo.clone_url),
+            head_branch: value.pull_request.head.git_ref,
+            action,
+        }
+    }
+}
+
+/// Gitea and Forgejo send GitHub-compatible pull request payloads.
+pub type GiteaPullRequestEvent = GithubPullRequestEvent;
+
+#[derive(Debug, Deserialize)]
+pub struct GitlabPushEvent {
+    pub object_kind: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// The commit the ref pointed at; all zeros when the push created it.
+    #[serde(default)]
+    pub before: String,
+    /// The commit the ref points at now; all zeros once it was deleted.
+    #[serde(default)]
+    pub after: String,
+    pub project: GitlabProject,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitlabProject {
+    pub git_http_url: String,
+    pub git_ssh_url: String,
+    pub web_url: String,
+}
+
+impl From<GitlabPushEvent> for PushEvent {
+    fn from(value: GitlabPushEvent) -> Self {
+        PushEvent {
+            repository_urls: vec![
+                value.project.git_http_url,
+                value.project.git_ssh_url,
+                value.project.web_url,
+            ],
+            ref_name: ref_name(&value.git_ref),
+            deleted: !value.after.is_empty() && value.after.bytes().all(|b| b == b'0'),
+            commits: CommitRange::of_push(&value.before, &value.after),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitlabMergeRequestEvent {
+    pub object_kind: String,
+    pub project: GitlabProject,
+    pub object_attributes: GitlabMergeRequest,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitlabMergeRequest {
+    pub iid: u64,
+    /// Missing from events GitLab sends for merge requests opened before the webhook was
+    /// added.
+    #[serde(default)]
+    pub action: Option<String>,
+    pub source_branch: String,
+    pub target_branch: String,
+    pub source: Option<GitlabMergeRequestSource>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitlabMergeRequestSource {
+    pub git_http_url: String,
+}
+
+impl From<GitlabMergeRequestEvent> for PullRequestEvent {
+    fn from(value: GitlabMergeRequestEvent) -> Self {
+        let merge_request = value.object_attributes;
+        let action = match merge_request.action.as_deref() {
+            Some("open" | "reopen") => PullRequestAction::Opened,
+            Some("update") => PullRequestAction::Updated,
+            Some("close" | "merge") => PullRequestAction::Closed,
+            _ => PullRequestAction::Other,
+        };
+        PullRequestEvent {
+            repository_urls: vec![
+                value.project.git_http_url,
+                value.project.git_ssh_url,
+                value.project.web_url,
+            ],
+            base_branch: merge_request.target_branch,
+            number: merge_request.iid,
+            head_url: merge_request.source.map(|source| source.git_http_url),
+            head_branch: merge_request.source_branch,
+            action,
+        }
+    }
+}
+
+/// `refs/heads/main` -> `main`, `refs/tags/v1.0.0` -> `v1.0.0`. Other refs name nothing
+/// a project can track.
+pub fn ref_name(git_ref: &str) -> Option<String> {
+    git_ref
+        .strip_prefix("refs/heads/")
+        .or_else(|| git_ref.strip_prefix("refs/tags/"))
+        .map(str::to_string)
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use chrono::Utc;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::models::activity::{ActivityEntry, ActivityKind};
+
+const ACTIVITY_FILE: &str = "activity.jsonl";
+
+/// Append-only activity log, one JSON line per entry, stored next to each project file.
+#[derive(Debug, Clone)]
+pub struct ActivityLog {
+    projects_dir: PathBuf,
+}
+
+impl ActivityLog {
+    pub fn new<P: AsRef<Path>>(projects_dir: P) -> Self {
+        Self {
+            projects_dir: projects_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn record(&self, project_name: &str, kind: ActivityKind, message: &str) -> Result<()> {
+        let entry = ActivityEntry {
+            timestamp: Utc::now(),
+            kind,
+            message: message.to_string(),
+            ago: None,
+        };
+
+        let project_dir = self.projects_dir.join(project_name);
+        fs::create_dir_all(&project_dir)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(project_dir.join(ACTIVITY_FILE))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+
+    /// Entries in the order they were recorded. Lines that fail to parse are skipped so a
+    /// partially written line does not hide the rest of the history.
+    pub fn list(&self, project_name: &str) -> Result<Vec<ActivityEntry>> {
+        let path = self.projects_dir.join(project_name).join(ACTIVITY_FILE);
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+
+        let entries = fs::read_to_string(path)?
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        Ok(entries)
+    }
+}
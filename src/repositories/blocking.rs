@@ -0,0 +1,82 @@
+//! Keeps blocking work, such as running `docker` and `git` or reading project files, off
+//! the async runtime's worker threads, where it would stall every other request served
+//! by the same thread.
+
+use std::cell::Cell;
+use tokio::task::{JoinError, JoinHandle};
+
+thread_local! {
+    static MAY_BLOCK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `work` on the blocking pool.
+pub async fn run<F, T>(work: F) -> Result<T, JoinError>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    spawn(work).await
+}
+
+/// Start `work` on the blocking pool, for callers that don't wait for it.
+pub fn spawn<F, T>(work: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || allow(work))
+}
+
+/// Run `work` with blocking allowed on this thread, for threads of our own that enter
+/// the runtime, such as the reconciler's.
+pub fn allow<T>(work: impl FnOnce() -> T) -> T {
+    let previous = MAY_BLOCK.replace(true);
+    let result = work();
+    MAY_BLOCK.set(previous);
+    result
+}
+
+/// Whether blocking work on this thread would stall the runtime: it is in a runtime's
+/// context without having been handed to the blocking pool or allowed to block.
+pub fn would_stall_runtime() -> bool {
+    tokio::runtime::Handle::try_current().is_ok() && !MAY_BLOCK.get()
+}
+
+/// Fails debug builds, and so tests, when `what` is about to block a runtime thread.
+#[track_caller]
+pub fn debug_assert_may_block(what: &str) {
+    debug_assert!(
+        !would_stall_runtime(),
+        "{} would block an async runtime thread; run it with blocking::run",
+        what
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_no_runtime_when_checked_then_blocking_does_not_stall() {
+        assert!(!would_stall_runtime());
+    }
+
+    #[tokio::test]
+    async fn given_async_task_when_checked_then_blocking_stalls_runtime() {
+        assert!(would_stall_runtime());
+    }
+
+    #[tokio::test]
+    async fn given_work_run_on_blocking_pool_when_checked_then_blocking_does_not_stall() {
+        let actual = run(would_stall_runtime).await.unwrap();
+
+        assert!(!actual);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "would block an async runtime thread")]
+    #[cfg(debug_assertions)]
+    async fn given_async_task_when_asserted_then_panics() {
+        debug_assert_may_block("git fetch");
+    }
+}
@@ -1,11 +1,85 @@
 use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::models::docker_compose::Container;
+use crate::models::docker_compose::{
+    ComposeNetwork, ComposeStack, Container, ContainerState, ExecOutput, LocalImage, ProjectEvent,
+    VolumeUsage,
+};
 
 pub trait ComposeClient {
     type Error: std::error::Error;
 
     fn list_containers(&self, path: &str) -> Result<Vec<Container>, Self::Error>;
+    /// Only the state of each container, for callers that need no more than the overall
+    /// status. Cheaper than `list_containers` on hosts with many containers.
+    fn container_states(&self, path: &str) -> Result<Vec<ContainerState>, Self::Error>;
+    /// Like `list_containers`, with details that need an extra lookup such as restart counts.
+    fn inspect_containers(&self, path: &str) -> Result<Vec<Container>, Self::Error>;
     fn up(&self, path: &str) -> Result<(), Self::Error>;
+    /// Like `up`, with override files applied on top of the project's compose file.
+    fn up_with_overrides(&self, path: &str, overrides: &[PathBuf]) -> Result<(), Self::Error>;
     fn down(&self, path: &str) -> Result<(), Self::Error>;
+    /// Like `down`, also removing the project's volumes and orphaned containers.
+    fn remove(&self, path: &str) -> Result<(), Self::Error>;
+    /// Like `remove`, for a compose project known only by name, e.g. once its compose file
+    /// is gone.
+    fn remove_stack(&self, project_name: &str) -> Result<(), Self::Error>;
+    /// Stop the containers without removing them.
+    fn stop(&self, path: &str) -> Result<(), Self::Error>;
+    /// Restart the containers without recreating them.
+    fn restart(&self, path: &str) -> Result<(), Self::Error>;
+    fn pause(&self, path: &str) -> Result<(), Self::Error>;
+    fn unpause(&self, path: &str) -> Result<(), Self::Error>;
+    fn pull_image(&self, image: &str) -> Result<(), Self::Error>;
+    /// The images the services of the compose file at `path` run, with `overrides` applied.
+    fn images(&self, path: &str, overrides: &[PathBuf]) -> Result<Vec<String>, Self::Error>;
+    /// The registry digests, as `name@sha256:...`, of the local copy of `image`; none when
+    /// it was never pulled.
+    fn local_image_digests(&self, image: &str) -> Result<Vec<String>, Self::Error>;
+    /// The digest `image` has on its registry now, without pulling it.
+    fn remote_image_digest(&self, image: &str) -> Result<String, Self::Error>;
+    /// The config hash of each service in the compose file at `path`, with `overrides`
+    /// applied, as compose would label a container created from it now.
+    fn config_hashes(
+        &self,
+        path: &str,
+        overrides: &[PathBuf],
+    ) -> Result<HashMap<String, String>, Self::Error>;
+    /// The compose file at `path`, with `overrides` applied, as `docker compose config`
+    /// renders it. Variables are left unsubstituted and env files unread, so none of their
+    /// values show up.
+    fn rendered_config(&self, path: &str, overrides: &[PathBuf]) -> Result<String, Self::Error>;
+    /// Have compose parse and validate a compose file, as `docker compose config` does.
+    fn check_config(&self, compose_path: &Path) -> Result<(), Self::Error>;
+    fn engine_version(&self) -> Result<String, Self::Error>;
+    fn compose_version(&self) -> Result<String, Self::Error>;
+    /// Every compose project on the engine, stopped ones included, whoever started them.
+    fn list_stacks(&self) -> Result<Vec<ComposeStack>, Self::Error>;
+    /// Every network labeled with a compose project, including those of removed stacks.
+    fn list_networks(&self) -> Result<Vec<ComposeNetwork>, Self::Error>;
+    fn remove_network(&self, name: &str) -> Result<(), Self::Error>;
+    /// Create a network outside of any compose project, unless it exists already.
+    fn create_network(&self, name: &str) -> Result<(), Self::Error>;
+    /// Every image in the engine's local store, dangling ones included.
+    fn list_images(&self) -> Result<Vec<LocalImage>, Self::Error>;
+    /// Remove an image by `LocalImage::reference`. Fails for images a container uses.
+    fn remove_image(&self, reference: &str) -> Result<(), Self::Error>;
+    /// Named volumes of the compose project, with the space each takes up.
+    fn volume_usage(&self, project_name: &str) -> Result<Vec<VolumeUsage>, Self::Error>;
+    /// Lifecycle events of the compose project's containers from now on. The iterator
+    /// blocks until the next event and stops watching once dropped.
+    fn events(
+        &self,
+        project_name: &str,
+    ) -> Result<Box<dyn Iterator<Item = ProjectEvent> + Send>, Self::Error>;
+    /// Run a command in a service's running container, killing it once `timeout` passes.
+    fn exec(
+        &self,
+        path: &str,
+        service: &str,
+        command: &[String],
+        timeout: Duration,
+    ) -> Result<ExecOutput, Self::Error>;
 }
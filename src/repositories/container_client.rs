@@ -1,14 +1,25 @@
-use anyhow::Result;
 use async_trait::async_trait;
+use futures_util::stream::BoxStream;
 
-use crate::models::container_client::{ContainerCreateResponse, ContainerInfo};
+use crate::models::container_client::{
+    ContainerCreateResponse, ContainerEvent, ContainerInfo, CreateContainerConfig,
+};
+use crate::models::docker_compose::Container;
 
 #[async_trait]
 pub trait ContainerClient {
-    async fn create_container(&self, name: &str, image: &str) -> Result<ContainerCreateResponse>;
-    async fn create_image(&self, image: &str) -> Result<()>;
-    async fn list_containers(&self) -> Result<Vec<ContainerInfo>>;
-    async fn remove_container(&self, name: &str) -> Result<()>;
-    async fn start_container(&self, name: &str) -> Result<()>;
-    async fn stop_container(&self, name: &str) -> Result<()>;
+    type Error: std::error::Error;
+
+    async fn create_container(
+        &self,
+        config: &CreateContainerConfig,
+    ) -> Result<ContainerCreateResponse, Self::Error>;
+    async fn create_image(&self, image: &str) -> Result<(), Self::Error>;
+    async fn inspect_container(&self, name: &str) -> Result<Container, Self::Error>;
+    async fn list_containers(&self) -> Result<Vec<ContainerInfo>, Self::Error>;
+    async fn remove_container(&self, name: &str) -> Result<(), Self::Error>;
+    async fn start_container(&self, name: &str) -> Result<(), Self::Error>;
+    async fn stop_container(&self, name: &str) -> Result<(), Self::Error>;
+    /// Container events from now on, until the stream is dropped.
+    fn events(&self) -> BoxStream<'static, Result<ContainerEvent, Self::Error>>;
 }
@@ -0,0 +1,402 @@
+#[cfg(feature = "sqlite-store")]
+use anyhow::anyhow;
+use anyhow::Result;
+#[cfg(feature = "sqlite-store")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "sqlite-store")]
+use rusqlite::{params, Connection, Row};
+#[cfg(feature = "sqlite-store")]
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "sqlite-store")]
+use std::path::PathBuf;
+#[cfg(not(feature = "sqlite-store"))]
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "sqlite-store")]
+use std::time::Duration;
+
+#[cfg(feature = "sqlite-store")]
+use crate::models::deployment::DeploymentTrigger;
+use crate::models::deployment::{Deployment, DeploymentOutcome};
+
+/// How long a write waits for another one, e.g. of a deployment finishing at the same
+/// time, before giving up.
+#[cfg(feature = "sqlite-store")]
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[cfg(feature = "sqlite-store")]
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS deployments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    project TEXT NOT NULL,
+    revision TEXT,
+    trigger TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    finished_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    error TEXT,
+    rollback_of INTEGER
+);
+CREATE INDEX IF NOT EXISTS deployments_by_project ON deployments (project, id);
+";
+
+/// Every finished deployment, kept in an SQLite database so it survives restarts.
+/// Each call opens its own connection, so the history can be shared between jobs.
+#[cfg(feature = "sqlite-store")]
+#[derive(Debug, Clone)]
+pub struct DeploymentHistory {
+    path: PathBuf,
+}
+
+#[cfg(feature = "sqlite-store")]
+impl DeploymentHistory {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Store `deployment` and return the ID it was given; its own `id` is ignored.
+    pub fn record(&self, deployment: &Deployment) -> Result<i64> {
+        let connection = self.connect()?;
+        connection.execute(
+            "INSERT INTO deployments
+                (project, revision, trigger, started_at, finished_at, duration_ms, outcome, error,
+                 rollback_of)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                deployment.project,
+                deployment.revision,
+                deployment.trigger.as_str(),
+                deployment.started_at.to_rfc3339(),
+                deployment.finished_at.to_rfc3339(),
+                deployment.duration_ms,
+                deployment.outcome.as_str(),
+                deployment.error,
+                deployment.rollback_of,
+            ],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    /// The project's latest deployments, newest first.
+    pub fn list(&self, project: &str, limit: usize) -> Result<Vec<Deployment>> {
+        let connection = self.connect()?;
+        let mut statement = connection.prepare(
+            "SELECT id, project, revision, trigger, started_at, finished_at, duration_ms,
+                    outcome, error, rollback_of
+             FROM deployments WHERE project = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = statement.query_map(params![project, limit], |row| Ok(read_row(row)))?;
+        rows.map(|row| row?).collect()
+    }
+
+    /// The project's deployment with `id`, if it has one.
+    pub fn get(&self, project: &str, id: i64) -> Result<Option<Deployment>> {
+        let connection = self.connect()?;
+        let mut statement = connection.prepare(
+            "SELECT id, project, revision, trigger, started_at, finished_at, duration_ms,
+                    outcome, error, rollback_of
+             FROM deployments WHERE project = ?1 AND id = ?2",
+        )?;
+        let mut rows = statement.query_map(params![project, id], |row| Ok(read_row(row)))?;
+        rows.next().transpose()?.transpose()
+    }
+
+    /// The project's newest deployment with `outcome`, if it has one.
+    pub fn latest(&self, project: &str, outcome: DeploymentOutcome) -> Result<Option<Deployment>> {
+        let connection = self.connect()?;
+        let mut statement = connection.prepare(
+            "SELECT id, project, revision, trigger, started_at, finished_at, duration_ms,
+                    outcome, error, rollback_of
+             FROM deployments WHERE project = ?1 AND outcome = ?2 ORDER BY id DESC LIMIT 1",
+        )?;
+        let mut rows =
+            statement.query_map(params![project, outcome.as_str()], |row| Ok(read_row(row)))?;
+        rows.next().transpose()?.transpose()
+    }
+
+    /// Overwrite the deployment with `deployment.id`, e.g. a pending one once it has been
+    /// approved and deployed.
+    pub fn update(&self, deployment: &Deployment) -> Result<()> {
+        self.connect()?.execute(
+            "UPDATE deployments SET
+                revision = ?2, trigger = ?3, started_at = ?4, finished_at = ?5,
+                duration_ms = ?6, outcome = ?7, error = ?8, rollback_of = ?9
+             WHERE id = ?1",
+            params![
+                deployment.id,
+                deployment.revision,
+                deployment.trigger.as_str(),
+                deployment.started_at.to_rfc3339(),
+                deployment.finished_at.to_rfc3339(),
+                deployment.duration_ms,
+                deployment.outcome.as_str(),
+                deployment.error,
+                deployment.rollback_of,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the project's deployments, once it has been deleted.
+    pub fn remove(&self, project: &str) -> Result<()> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        self.connect()?.execute(
+            "DELETE FROM deployments WHERE project = ?1",
+            params![project],
+        )?;
+        Ok(())
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(&self.path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+        // Histories written before rollbacks were recorded lack their column.
+        let has_rollback_of: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('deployments') WHERE name = 'rollback_of'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_rollback_of {
+            connection.execute_batch("ALTER TABLE deployments ADD COLUMN rollback_of INTEGER")?;
+        }
+        Ok(connection)
+    }
+}
+
+#[cfg(feature = "sqlite-store")]
+fn read_row(row: &Row) -> Result<Deployment> {
+    let trigger: String = row.get(3)?;
+    let outcome: String = row.get(7)?;
+    Ok(Deployment {
+        id: row.get(0)?,
+        project: row.get(1)?,
+        revision: row.get(2)?,
+        trigger: DeploymentTrigger::ALL
+            .into_iter()
+            .find(|known| known.as_str() == trigger)
+            .ok_or_else(|| anyhow!("Unknown deployment trigger: {}", trigger))?,
+        started_at: parse_time(&row.get::<_, String>(4)?)?,
+        finished_at: parse_time(&row.get::<_, String>(5)?)?,
+        duration_ms: row.get(6)?,
+        outcome: DeploymentOutcome::ALL
+            .into_iter()
+            .find(|known| known.as_str() == outcome)
+            .ok_or_else(|| anyhow!("Unknown deployment outcome: {}", outcome))?,
+        error: row.get(8)?,
+        rollback_of: row.get(9)?,
+    })
+}
+
+#[cfg(feature = "sqlite-store")]
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(value)?.to_utc())
+}
+
+/// Every finished deployment since the process started, for builds without the
+/// `sqlite-store` feature. Rollbacks and approvals work the same, but only until a restart.
+#[cfg(not(feature = "sqlite-store"))]
+#[derive(Debug, Clone, Default)]
+pub struct DeploymentHistory {
+    /// In the order they were recorded, which is also the order of their IDs.
+    deployments: Arc<Mutex<Vec<Deployment>>>,
+}
+
+#[cfg(not(feature = "sqlite-store"))]
+impl DeploymentHistory {
+    /// `path` is where the SQLite store would keep the history; nothing is written there.
+    pub fn new<P: AsRef<Path>>(_path: P) -> Self {
+        Self::default()
+    }
+
+    /// Store `deployment` and return the ID it was given; its own `id` is ignored.
+    pub fn record(&self, deployment: &Deployment) -> Result<i64> {
+        let mut deployments = self.lock();
+        let id = deployments.last().map_or(1, |last| last.id + 1);
+        deployments.push(Deployment {
+            id,
+            ..deployment.clone()
+        });
+        Ok(id)
+    }
+
+    /// The project's latest deployments, newest first.
+    pub fn list(&self, project: &str, limit: usize) -> Result<Vec<Deployment>> {
+        Ok(self
+            .lock()
+            .iter()
+            .rev()
+            .filter(|deployment| deployment.project == project)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    /// The project's deployment with `id`, if it has one.
+    pub fn get(&self, project: &str, id: i64) -> Result<Option<Deployment>> {
+        Ok(self
+            .lock()
+            .iter()
+            .find(|deployment| deployment.project == project && deployment.id == id)
+            .cloned())
+    }
+
+    /// The project's newest deployment with `outcome`, if it has one.
+    pub fn latest(&self, project: &str, outcome: DeploymentOutcome) -> Result<Option<Deployment>> {
+        Ok(self
+            .lock()
+            .iter()
+            .rev()
+            .find(|deployment| deployment.project == project && deployment.outcome == outcome)
+            .cloned())
+    }
+
+    /// Overwrite the deployment with `deployment.id`, e.g. a pending one once it has been
+    /// approved and deployed.
+    pub fn update(&self, deployment: &Deployment) -> Result<()> {
+        if let Some(stored) = self
+            .lock()
+            .iter_mut()
+            .find(|stored| stored.id == deployment.id)
+        {
+            *stored = Deployment {
+                project: stored.project.clone(),
+                ..deployment.clone()
+            };
+        }
+        Ok(())
+    }
+
+    /// Forget the project's deployments, once it has been deleted.
+    pub fn remove(&self, project: &str) -> Result<()> {
+        self.lock()
+            .retain(|deployment| deployment.project != project);
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Deployment>> {
+        self.deployments.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::deployment::DeploymentTrigger;
+    use chrono::{TimeZone, Utc};
+
+    fn deployment(project: &str, outcome: DeploymentOutcome) -> Deployment {
+        let started_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        Deployment {
+            id: 0,
+            project: project.to_string(),
+            revision: Some("0123abc".to_string()),
+            trigger: DeploymentTrigger::Webhook,
+            started_at,
+            finished_at: started_at + chrono::Duration::seconds(42),
+            duration_ms: 42_000,
+            outcome,
+            error: None,
+            rollback_of: None,
+        }
+    }
+
+    #[cfg(feature = "sqlite-store")]
+    #[test]
+    fn given_recorded_deployments_when_reopened_then_list_them_newest_first() {
+        let root = tempfile::TempDir::new().unwrap();
+        let path = root.path().join("history.db");
+        let history = DeploymentHistory::new(&path);
+        history
+            .record(&deployment("app", DeploymentOutcome::Failed))
+            .unwrap();
+        let id = history
+            .record(&deployment("app", DeploymentOutcome::Succeeded))
+            .unwrap();
+        history
+            .record(&deployment("db", DeploymentOutcome::Succeeded))
+            .unwrap();
+
+        let actual = DeploymentHistory::new(&path).list("app", 10).unwrap();
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(
+            actual[0],
+            Deployment {
+                id,
+                ..deployment("app", DeploymentOutcome::Succeeded)
+            }
+        );
+        assert_eq!(actual[1].outcome, DeploymentOutcome::Failed);
+    }
+
+    #[test]
+    fn given_deployment_of_other_project_when_get_then_return_none() {
+        let root = tempfile::TempDir::new().unwrap();
+        let history = DeploymentHistory::new(root.path().join("history.db"));
+        let id = history
+            .record(&deployment("app", DeploymentOutcome::Succeeded))
+            .unwrap();
+
+        let actual = history.get("db", id).unwrap();
+
+        assert_eq!(actual, None);
+        assert_eq!(history.get("app", id).unwrap().map(|d| d.id), Some(id));
+    }
+
+    #[test]
+    fn given_pending_deployment_when_updated_then_latest_pending_is_gone() {
+        let root = tempfile::TempDir::new().unwrap();
+        let history = DeploymentHistory::new(root.path().join("history.db"));
+        let id = history
+            .record(&deployment("app", DeploymentOutcome::Pending))
+            .unwrap();
+        history
+            .record(&deployment("app", DeploymentOutcome::Succeeded))
+            .unwrap();
+        assert_eq!(
+            history
+                .latest("app", DeploymentOutcome::Pending)
+                .unwrap()
+                .map(|d| d.id),
+            Some(id)
+        );
+
+        history
+            .update(&Deployment {
+                id,
+                ..deployment("app", DeploymentOutcome::Succeeded)
+            })
+            .unwrap();
+
+        assert_eq!(
+            history.latest("app", DeploymentOutcome::Pending).unwrap(),
+            None
+        );
+        assert_eq!(
+            history.get("app", id).unwrap().map(|d| d.outcome),
+            Some(DeploymentOutcome::Succeeded)
+        );
+    }
+
+    #[test]
+    fn given_removed_project_when_list_then_return_nothing() {
+        let root = tempfile::TempDir::new().unwrap();
+        let history = DeploymentHistory::new(root.path().join("history.db"));
+        history
+            .record(&deployment("app", DeploymentOutcome::Succeeded))
+            .unwrap();
+
+        history.remove("app").unwrap();
+
+        assert!(history.list("app", 10).unwrap().is_empty());
+    }
+}
@@ -1,23 +1,81 @@
-use anyhow::Result;
 use async_trait::async_trait;
 use bollard::container::{
-    Config, CreateContainerOptions, ListContainersOptions, StartContainerOptions,
-    StopContainerOptions,
+    Config, CreateContainerOptions, InspectContainerOptions, ListContainersOptions,
+    StartContainerOptions, StopContainerOptions,
 };
 use bollard::image::CreateImageOptions;
+use bollard::system::EventsOptions;
 use bollard::Docker;
-use futures_util::stream::TryStreamExt;
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
+use std::collections::HashMap;
+use thiserror::Error;
 
-use crate::models::container_client::{ContainerCreateResponse, ContainerInfo};
+use crate::models::container_client::{
+    ContainerCreateResponse, ContainerEvent, ContainerInfo, CreateContainerConfig,
+    UnknownStateError,
+};
+use crate::models::docker_compose::Container;
 use crate::repositories::container_client::ContainerClient;
 
+#[derive(Debug, Error)]
+pub enum DockerClientError {
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Docker daemon is unreachable: {0}")]
+    DaemonUnreachable(bollard::errors::Error),
+    #[error("Failed to pull image {image}: {source}")]
+    ImagePullFailed {
+        image: String,
+        source: bollard::errors::Error,
+    },
+    #[error("Docker API request failed: {0}")]
+    Api(bollard::errors::Error),
+    #[error(transparent)]
+    UnknownState(#[from] UnknownStateError),
+}
+
+impl From<bollard::errors::Error> for DockerClientError {
+    fn from(error: bollard::errors::Error) -> Self {
+        use bollard::errors::Error;
+
+        match error {
+            Error::DockerResponseServerError {
+                status_code: 404,
+                message,
+            } => DockerClientError::NotFound(message),
+            Error::DockerResponseServerError {
+                status_code: 409,
+                message,
+            } => DockerClientError::Conflict(message),
+            error if is_unreachable(&error) => DockerClientError::DaemonUnreachable(error),
+            error => DockerClientError::Api(error),
+        }
+    }
+}
+
+/// Errors raised before the daemon answered, as opposed to the daemon rejecting a request.
+fn is_unreachable(error: &bollard::errors::Error) -> bool {
+    use bollard::errors::Error;
+
+    matches!(
+        error,
+        Error::SocketNotFoundError(_)
+            | Error::RequestTimeoutError
+            | Error::IOError { .. }
+            | Error::HyperLegacyError { .. }
+            | Error::HyperResponseError { .. }
+    )
+}
+
 #[derive(Debug, Clone)]
 pub struct DockerClient {
     docker: Docker,
 }
 
 impl DockerClient {
-    pub fn new() -> Result<DockerClient> {
+    pub fn new() -> Result<DockerClient, DockerClientError> {
         println!("Creating Docker client");
         let docker = Docker::connect_with_local_defaults()?;
         Ok(Self { docker })
@@ -26,23 +84,28 @@ impl DockerClient {
 
 #[async_trait]
 impl ContainerClient for DockerClient {
-    async fn create_container(&self, name: &str, image: &str) -> Result<ContainerCreateResponse> {
-        println!("Creating container: {}", name);
+    type Error = DockerClientError;
+
+    async fn create_container(
+        &self,
+        config: &CreateContainerConfig,
+    ) -> Result<ContainerCreateResponse, Self::Error> {
+        println!("Creating container: {}", config.name);
         let options = Some(CreateContainerOptions {
-            name,
+            name: config.name.as_str(),
             platform: None,
         });
 
-        let config = Config {
-            image: Some(image),
-            ..Default::default()
-        };
-        let created_container = self.docker.create_container(options, config).await?.into();
+        let created_container = self
+            .docker
+            .create_container(options, Config::from(config))
+            .await?
+            .into();
 
         Ok(created_container)
     }
 
-    async fn create_image(&self, image: &str) -> Result<()> {
+    async fn create_image(&self, image: &str) -> Result<(), Self::Error> {
         println!("Creating image: {}", image);
         let options = Some(CreateImageOptions {
             from_image: image,
@@ -53,12 +116,28 @@ impl ContainerClient for DockerClient {
             .docker
             .create_image(options, None, None)
             .try_collect::<Vec<_>>()
-            .await?;
+            .await
+            .map_err(|source| match is_unreachable(&source) {
+                true => DockerClientError::DaemonUnreachable(source),
+                false => DockerClientError::ImagePullFailed {
+                    image: image.to_string(),
+                    source,
+                },
+            })?;
 
         Ok(())
     }
 
-    async fn list_containers(&self) -> Result<Vec<ContainerInfo>> {
+    async fn inspect_container(&self, name: &str) -> Result<Container, Self::Error> {
+        println!("Inspecting container: {}", name);
+        self.docker
+            .inspect_container(name, None::<InspectContainerOptions>)
+            .await?
+            .try_into()
+            .map_err(Into::into)
+    }
+
+    async fn list_containers(&self) -> Result<Vec<ContainerInfo>, Self::Error> {
         println!("Listing containers");
         let options = Some(ListContainersOptions::<String> {
             all: true,
@@ -76,12 +155,12 @@ impl ContainerClient for DockerClient {
         Ok(containers)
     }
 
-    async fn remove_container(&self, name: &str) -> Result<()> {
+    async fn remove_container(&self, name: &str) -> Result<(), Self::Error> {
         println!("Removing container: {}", name);
         Ok(self.docker.remove_container(name, None).await?)
     }
 
-    async fn start_container(&self, name: &str) -> Result<()> {
+    async fn start_container(&self, name: &str) -> Result<(), Self::Error> {
         println!("Starting container: {}", name);
         Ok(self
             .docker
@@ -89,11 +168,55 @@ impl ContainerClient for DockerClient {
             .await?)
     }
 
-    async fn stop_container(&self, name: &str) -> Result<()> {
+    async fn stop_container(&self, name: &str) -> Result<(), Self::Error> {
         println!("Stopping container: {}", name);
         let timeout = 30;
         let options = Some(StopContainerOptions { t: timeout });
 
         Ok(self.docker.stop_container(name, options).await?)
     }
+
+    fn events(&self) -> BoxStream<'static, Result<ContainerEvent, Self::Error>> {
+        println!("Streaming container events");
+        let options = Some(EventsOptions::<String> {
+            filters: HashMap::from([("type".to_string(), vec!["container".to_string()])]),
+            ..Default::default()
+        });
+
+        self.docker
+            .events(options)
+            .map_ok(ContainerEvent::from)
+            .map_err(DockerClientError::from)
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_daemon_responses_when_converted_then_return_matching_variant() {
+        let response = |status_code| bollard::errors::Error::DockerResponseServerError {
+            status_code,
+            message: "No such container: web".to_string(),
+        };
+
+        assert!(matches!(
+            DockerClientError::from(response(404)),
+            DockerClientError::NotFound(message) if message == "No such container: web"
+        ));
+        assert!(matches!(
+            DockerClientError::from(response(409)),
+            DockerClientError::Conflict(_)
+        ));
+        assert!(matches!(
+            DockerClientError::from(response(500)),
+            DockerClientError::Api(_)
+        ));
+        assert!(matches!(
+            DockerClientError::from(bollard::errors::Error::RequestTimeoutError),
+            DockerClientError::DaemonUnreachable(_)
+        ));
+    }
 }
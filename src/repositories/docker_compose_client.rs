@@ -1,12 +1,20 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use mockall::automock;
 use mockall::predicate::*;
-use std::path::Path;
-use std::process::Command;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::Duration;
 use thiserror::Error;
 
-use crate::models::docker_compose::{Container, ContainerState};
+use crate::models::docker_compose::{
+    ComposeNetwork, ComposeStack, Container, ContainerState, ExecOutput, HealthStatus, LocalImage,
+    ProjectEvent, VolumeUsage, CONFIG_HASH_LABEL,
+};
 use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::process::{CommandTimeout, ProcessError};
 
 const SUPPORTED_COMPOSE_FILES: &[&str] = &[
     "docker-compose.yml",
@@ -15,6 +23,13 @@ const SUPPORTED_COMPOSE_FILES: &[&str] = &[
     "compose.yaml",
 ];
 
+const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+/// Container events worth showing on a project's timeline. The daemon matches
+/// `health_status` against every `health_status: <status>` event.
+const PROJECT_EVENTS: [&str; 5] = ["start", "stop", "die", "oom", "health_status"];
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1800);
+
 #[derive(Debug, Error)]
 pub enum DockerComposeError {
     #[error("Directory not found")]
@@ -29,21 +44,69 @@ pub enum DockerComposeError {
     MissingField(String),
     #[error("Unknown state: {0}")]
     UnknownState(String),
+    #[error("Invalid compose configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Failed to remove network: {0}")]
+    NetworkRemovalFailed(String),
+    #[error("Failed to create network: {0}")]
+    NetworkCreationFailed(String),
+    #[error("Failed to remove image: {0}")]
+    ImageRemovalFailed(String),
+    #[error("Invalid timestamp: {0}")]
+    InvalidTimestamp(String),
+    #[error("Failed to remove project: {0}")]
+    RemovalFailed(String),
+    #[error("Docker timed out: {0}")]
+    TimedOut(String),
+    #[error("Docker was cancelled: {0}")]
+    Cancelled(String),
+    #[error("Invalid image reference: {0}")]
+    InvalidImageReference(String),
+}
+
+impl From<ProcessError> for DockerComposeError {
+    fn from(value: ProcessError) -> Self {
+        match value {
+            ProcessError::Io(e) => DockerComposeError::DockerComposeExecutionFailed(e),
+            e @ ProcessError::TimedOut { .. } => DockerComposeError::TimedOut(e.to_string()),
+            e @ ProcessError::Cancelled { .. } => DockerComposeError::Cancelled(e.to_string()),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct DockerComposeClient;
+pub struct DockerComposeClient {
+    /// Cap on the stdout and stderr kept from a single `exec`.
+    max_output_bytes: usize,
+    /// Any single docker command taking longer is killed. `exec` has its own timeout,
+    /// and `events` runs until dropped.
+    timeout: Duration,
+}
 
 impl DockerComposeClient {
     pub fn new() -> Result<DockerComposeClient> {
-        Ok(Self {})
+        Ok(Self {
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            timeout: DEFAULT_TIMEOUT,
+        })
     }
 
-    fn run_cmd(args: &[&str], path: &str) -> Result<String, DockerComposeError> {
+    pub fn with_output_limit(self, max_output_bytes: usize) -> Self {
+        Self {
+            max_output_bytes,
+            ..self
+        }
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    fn run_cmd(&self, args: &[&str], path: &str) -> Result<String, DockerComposeError> {
         let output = Command::new("docker")
             .args(args)
             .current_dir(path)
-            .output()?;
+            .output_within(self.timeout)?;
 
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
@@ -54,21 +117,320 @@ impl ComposeClient for DockerComposeClient {
     type Error = DockerComposeError;
 
     fn up(&self, path: &str) -> Result<(), Self::Error> {
+        self.up_with_overrides(path, &[])
+    }
+
+    fn up_with_overrides(&self, path: &str, overrides: &[PathBuf]) -> Result<(), Self::Error> {
         println!("Running docker compose up");
         let compose_file_name = find_compose_file_name(Path::new(path))?;
-        Self::run_cmd(&["compose", "-f", &compose_file_name, "up", "-d"], path).map(|_| ())
+        let mut args = vec!["compose", "-f", compose_file_name.as_str()];
+        for file in overrides {
+            args.extend(["-f", file.to_str().unwrap_or_default()]);
+        }
+        args.extend(["up", "-d"]);
+        self.run_cmd(&args, path).map(|_| ())
     }
 
     fn down(&self, path: &str) -> Result<(), Self::Error> {
         println!("Running docker compose down");
         let compose_file_name = find_compose_file_name(Path::new(path))?;
-        Self::run_cmd(&["compose", "-f", &compose_file_name, "down"], path).map(|_| ())
+        self.run_cmd(&["compose", "-f", &compose_file_name, "down"], path)
+            .map(|_| ())
+    }
+
+    fn remove(&self, path: &str) -> Result<(), Self::Error> {
+        println!("Running docker compose down --volumes");
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        let output = Command::new("docker")
+            .args(["compose", "-f", &compose_file_name, "down"])
+            .args(["--volumes", "--remove-orphans"])
+            .current_dir(path)
+            .output_within(self.timeout)?;
+
+        output.status.success().then_some(()).ok_or_else(|| {
+            DockerComposeError::RemovalFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+        })
+    }
+
+    fn remove_stack(&self, project_name: &str) -> Result<(), Self::Error> {
+        println!("Running docker compose down --volumes for {}", project_name);
+        let output = Command::new("docker")
+            .args(["compose", "--project-name", project_name, "down"])
+            .args(["--volumes", "--remove-orphans"])
+            .output_within(self.timeout)?;
+
+        output.status.success().then_some(()).ok_or_else(|| {
+            DockerComposeError::RemovalFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+        })
+    }
+
+    fn stop(&self, path: &str) -> Result<(), Self::Error> {
+        println!("Running docker compose stop");
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        self.run_cmd(&["compose", "-f", &compose_file_name, "stop"], path)
+            .map(|_| ())
+    }
+
+    fn restart(&self, path: &str) -> Result<(), Self::Error> {
+        println!("Running docker compose restart");
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        self.run_cmd(&["compose", "-f", &compose_file_name, "restart"], path)
+            .map(|_| ())
+    }
+
+    fn pause(&self, path: &str) -> Result<(), Self::Error> {
+        println!("Running docker compose pause");
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        self.run_cmd(&["compose", "-f", &compose_file_name, "pause"], path)
+            .map(|_| ())
+    }
+
+    fn unpause(&self, path: &str) -> Result<(), Self::Error> {
+        println!("Running docker compose unpause");
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        self.run_cmd(&["compose", "-f", &compose_file_name, "unpause"], path)
+            .map(|_| ())
+    }
+
+    fn pull_image(&self, image: &str) -> Result<(), Self::Error> {
+        validate_image_reference(image)?;
+        println!("Running docker pull {}", image);
+        self.run_cmd(&["pull", "--", image], ".").map(|_| ())
+    }
+
+    fn images(&self, path: &str, overrides: &[PathBuf]) -> Result<Vec<String>, Self::Error> {
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        let mut args = vec!["compose", "-f", compose_file_name.as_str()];
+        for file in overrides {
+            args.extend(["-f", file.to_str().unwrap_or_default()]);
+        }
+        args.extend(["config", "--images"]);
+        let output = self.run_cmd(&args, path)?;
+
+        let mut images = output
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        images.sort();
+        images.dedup();
+        Ok(images)
+    }
+
+    fn local_image_digests(&self, image: &str) -> Result<Vec<String>, Self::Error> {
+        let output = self.run_cmd(
+            &[
+                "image",
+                "inspect",
+                "--format",
+                "{{json .RepoDigests}}",
+                image,
+            ],
+            ".",
+        )?;
+        match output.trim() {
+            "" => Ok(vec![]),
+            output => Ok(serde_json::from_str(output)?),
+        }
+    }
+
+    /// Needs the buildx plugin, which ships with Docker Desktop and docker-ce.
+    fn remote_image_digest(&self, image: &str) -> Result<String, Self::Error> {
+        let output = self.run_cmd(
+            &[
+                "buildx",
+                "imagetools",
+                "inspect",
+                "--format",
+                "{{.Manifest.Digest}}",
+                image,
+            ],
+            ".",
+        )?;
+        non_empty(output, "Manifest.Digest")
+    }
+
+    /// On timeout only the `docker compose exec` client is killed; a command that ignores
+    /// the hangup can keep running inside the container.
+    fn exec(
+        &self,
+        path: &str,
+        service: &str,
+        command: &[String],
+        timeout: Duration,
+    ) -> Result<ExecOutput, Self::Error> {
+        println!("Running docker compose exec {}", service);
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        let output = Command::new("docker")
+            .args(["compose", "-f", &compose_file_name, "exec", "-T", service])
+            .args(command)
+            .current_dir(path)
+            .capped_output_within(timeout, self.max_output_bytes)?;
+
+        Ok(ExecOutput {
+            exit_code: output
+                .status
+                .and_then(|status| status.code())
+                .map(i64::from),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            timed_out: output.status.is_none(),
+            truncated: output.truncated,
+        })
+    }
+
+    fn config_hashes(
+        &self,
+        path: &str,
+        overrides: &[PathBuf],
+    ) -> Result<HashMap<String, String>, Self::Error> {
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        let mut args = vec!["compose", "-f", compose_file_name.as_str()];
+        for file in overrides {
+            args.extend(["-f", file.to_str().unwrap_or_default()]);
+        }
+        args.extend(["config", "--hash", "*"]);
+        let output = non_empty(self.run_cmd(&args, path)?, "config hash")?;
+
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(service, hash)| (service.to_string(), hash.trim().to_string()))
+            .collect())
+    }
+
+    fn rendered_config(&self, path: &str, overrides: &[PathBuf]) -> Result<String, Self::Error> {
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        let mut args = vec!["compose", "-f", compose_file_name.as_str()];
+        for file in overrides {
+            args.extend(["-f", file.to_str().unwrap_or_default()]);
+        }
+        args.extend(["config", "--no-interpolate", "--no-env-resolution"]);
+        non_empty(self.run_cmd(&args, path)?, "config")
+    }
+
+    fn check_config(&self, compose_path: &Path) -> Result<(), Self::Error> {
+        println!("Running docker compose config");
+        let directory = compose_path
+            .parent()
+            .ok_or(DockerComposeError::DirectoryNotFound)?;
+        let output = Command::new("docker")
+            .args(["compose", "-f"])
+            .arg(compose_path)
+            .args(["config", "--quiet"])
+            .current_dir(directory)
+            .output_within(self.timeout)?;
+
+        output.status.success().then_some(()).ok_or_else(|| {
+            DockerComposeError::InvalidConfig(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+        })
+    }
+
+    fn engine_version(&self) -> Result<String, Self::Error> {
+        let version = self.run_cmd(&["version", "--format", "{{.Server.Version}}"], ".")?;
+        non_empty(version, "Server.Version")
+    }
+
+    fn compose_version(&self) -> Result<String, Self::Error> {
+        let version = self.run_cmd(&["compose", "version", "--short"], ".")?;
+        non_empty(version, "compose version")
+    }
+
+    fn list_stacks(&self) -> Result<Vec<ComposeStack>, Self::Error> {
+        let output = self.run_cmd(&["compose", "ls", "--all", "--format", "json"], ".")?;
+        parse_compose_ls(&output)
+    }
+
+    fn list_networks(&self) -> Result<Vec<ComposeNetwork>, Self::Error> {
+        let filter = format!("label={}", COMPOSE_PROJECT_LABEL);
+        let ids = self.run_cmd(&["network", "ls", "--quiet", "--filter", &filter], ".")?;
+        let ids = ids.split_whitespace().collect::<Vec<_>>();
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let output = self.run_cmd(&[&["network", "inspect"][..], &ids[..]].concat(), ".")?;
+        parse_network_inspect(&output)
+    }
+
+    fn remove_network(&self, name: &str) -> Result<(), Self::Error> {
+        println!("Running docker network rm {}", name);
+        let output = Command::new("docker")
+            .args(["network", "rm", name])
+            .output_within(self.timeout)?;
+
+        output.status.success().then_some(()).ok_or_else(|| {
+            DockerComposeError::NetworkRemovalFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+        })
+    }
+
+    fn list_images(&self) -> Result<Vec<LocalImage>, Self::Error> {
+        let output = self.run_cmd(
+            &["image", "ls", "--no-trunc", "--format", "{{json .}}"],
+            ".",
+        )?;
+        parse_image_ls(&output)
+    }
+
+    fn remove_image(&self, reference: &str) -> Result<(), Self::Error> {
+        println!("Running docker image rm {}", reference);
+        let output = Command::new("docker")
+            .args(["image", "rm", reference])
+            .output_within(self.timeout)?;
+
+        output.status.success().then_some(()).ok_or_else(|| {
+            DockerComposeError::ImageRemovalFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+        })
+    }
+
+    fn volume_usage(&self, project_name: &str) -> Result<Vec<VolumeUsage>, Self::Error> {
+        let output = self.run_cmd(
+            &["system", "df", "--verbose", "--format", "{{json .}}"],
+            ".",
+        )?;
+        parse_volume_usage(&output, project_name)
+    }
+
+    fn create_network(&self, name: &str) -> Result<(), Self::Error> {
+        let exists = || -> Result<bool, DockerComposeError> {
+            Ok(Command::new("docker")
+                .args(["network", "inspect", name])
+                .output_within(self.timeout)?
+                .status
+                .success())
+        };
+        if exists()? {
+            return Ok(());
+        }
+
+        println!("Running docker network create {}", name);
+        let output = Command::new("docker")
+            .args(["network", "create", name])
+            .output_within(self.timeout)?;
+        // Another deployment may have created it in the meantime.
+        match output.status.success() || exists()? {
+            true => Ok(()),
+            false => Err(DockerComposeError::NetworkCreationFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )),
+        }
     }
 
     fn list_containers(&self, path: &str) -> Result<Vec<Container>, Self::Error> {
         println!("Running docker compose ps");
         let compose_file_name = find_compose_file_name(Path::new(path))?;
-        Self::run_cmd(
+        self.run_cmd(
             &[
                 "compose",
                 "-f",
@@ -95,27 +457,407 @@ impl ComposeClient for DockerComposeClient {
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| DockerComposeError::MissingField("State".into()))?;
 
-            let state = match state_str {
-                "paused" => ContainerState::Paused,
-                "restarting" => ContainerState::Restarting,
-                "removing" => ContainerState::Removing,
-                "running" => ContainerState::Running,
-                "dead" => ContainerState::Dead,
-                "created" => ContainerState::Created,
-                "exited" => ContainerState::Exited,
-                other => return Err(DockerComposeError::UnknownState(other.into())),
+            let state = ContainerState::parse(state_str)
+                .ok_or_else(|| DockerComposeError::UnknownState(state_str.into()))?;
+
+            let health = value
+                .get("Health")
+                .and_then(|v| v.as_str())
+                .and_then(HealthStatus::parse);
+
+            let exit_code = value.get("ExitCode").and_then(|v| v.as_i64());
+
+            let service = value
+                .get("Service")
+                .and_then(|v| v.as_str())
+                .map(str::to_string);
+
+            // `Labels` is a single comma-separated list of `key=value` pairs.
+            let config_hash = value
+                .get("Labels")
+                .and_then(|v| v.as_str())
+                .and_then(|labels| {
+                    labels
+                        .split(',')
+                        .find_map(|label| label.strip_prefix(CONFIG_HASH_LABEL)?.strip_prefix('='))
+                })
+                .map(str::to_string);
+
+            Ok(Container {
+                name,
+                state,
+                health,
+                exit_code,
+                restart_count: None,
+                oom_killed: None,
+                service,
+                config_hash,
+            })
+        })
+        .collect()
+    }
+
+    /// Has compose print one state per line instead of every field as JSON.
+    fn container_states(&self, path: &str) -> Result<Vec<ContainerState>, Self::Error> {
+        println!("Running docker compose ps for states");
+        let compose_file_name = find_compose_file_name(Path::new(path))?;
+        self.run_cmd(
+            &[
+                "compose",
+                "-f",
+                &compose_file_name,
+                "ps",
+                "--all",
+                "--format",
+                "{{.State}}",
+            ],
+            path,
+        )?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|state| {
+            ContainerState::parse(state)
+                .ok_or_else(|| DockerComposeError::UnknownState(state.into()))
+        })
+        .collect()
+    }
+
+    fn inspect_containers(&self, path: &str) -> Result<Vec<Container>, Self::Error> {
+        let containers = self.list_containers(path)?;
+        if containers.is_empty() {
+            return Ok(containers);
+        }
+
+        println!("Running docker inspect");
+        let names = containers
+            .iter()
+            .map(|container| container.name.as_str())
+            .collect::<Vec<_>>();
+        let output = self.run_cmd(&[&["inspect"][..], &names[..]].concat(), path)?;
+
+        let details = serde_json::from_str::<Vec<serde_json::Value>>(&output)?
+            .iter()
+            .filter_map(|value| {
+                let name = value.get("Name")?.as_str()?.trim_start_matches('/');
+                let restart_count = value.get("RestartCount").and_then(|v| v.as_u64());
+                let oom_killed = value.pointer("/State/OOMKilled").and_then(|v| v.as_bool());
+                Some((name.to_string(), (restart_count, oom_killed)))
+            })
+            .collect::<HashMap<_, _>>();
+
+        Ok(containers
+            .into_iter()
+            .map(|container| {
+                let (restart_count, oom_killed) =
+                    details.get(&container.name).copied().unwrap_or_default();
+                Container {
+                    restart_count,
+                    oom_killed,
+                    ..container
+                }
+            })
+            .collect())
+    }
+
+    fn events(
+        &self,
+        project_name: &str,
+    ) -> Result<Box<dyn Iterator<Item = ProjectEvent> + Send>, Self::Error> {
+        println!("Running docker events for {}", project_name);
+        let mut command = Command::new("docker");
+        command
+            .args([
+                "events",
+                "--format",
+                "{{json .}}",
+                "--filter",
+                "type=container",
+            ])
+            .arg("--filter")
+            .arg(format!("label={}={}", COMPOSE_PROJECT_LABEL, project_name));
+        for event in PROJECT_EVENTS {
+            command.arg("--filter").arg(format!("event={}", event));
+        }
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| DockerComposeError::MissingField("stdout".to_string()))?;
+
+        Ok(Box::new(EventStream {
+            child,
+            lines: BufReader::new(stdout).lines(),
+        }))
+    }
+}
+
+/// Events read from a running `docker events`, which is killed once dropped.
+struct EventStream {
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+impl Iterator for EventStream {
+    type Item = ProjectEvent;
+
+    fn next(&mut self) -> Option<ProjectEvent> {
+        loop {
+            let line = self.lines.next()?.ok()?;
+            if let Some(event) = parse_event(&line) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// One line of `docker events --format '{{json .}}'`.
+fn parse_event(line: &str) -> Option<ProjectEvent> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    let attribute = |name: &str| -> Option<String> {
+        value
+            .pointer(&format!("/Actor/Attributes/{}", name))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    let time = match value.get("timeNano").and_then(|v| v.as_i64()) {
+        Some(nanos) => DateTime::<Utc>::from_timestamp_nanos(nanos),
+        None => DateTime::<Utc>::from_timestamp(value.get("time")?.as_i64()?, 0)?,
+    };
+
+    Some(ProjectEvent {
+        container: attribute("name")?,
+        service: attribute("com.docker.compose.service"),
+        action: value.get("Action")?.as_str()?.to_string(),
+        exit_code: attribute("exitCode").and_then(|code| code.parse().ok()),
+        time,
+    })
+}
+
+/// `run_cmd` returns empty output when docker fails, e.g. when the daemon is down.
+fn non_empty(output: String, field: &str) -> Result<String, DockerComposeError> {
+    let output = output.trim();
+    match output.is_empty() {
+        true => Err(DockerComposeError::MissingField(field.to_string())),
+        false => Ok(output.to_string()),
+    }
+}
+
+/// `docker compose ls --format json` prints one array, with the config files of each
+/// project joined by commas.
+fn parse_compose_ls(output: &str) -> Result<Vec<ComposeStack>, DockerComposeError> {
+    let values: Vec<serde_json::Value> = match output.trim() {
+        "" => vec![],
+        output => serde_json::from_str(output)?,
+    };
+
+    values
+        .iter()
+        .map(|value| {
+            let field = |name: &str| {
+                value
+                    .get(name)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| DockerComposeError::MissingField(name.into()))
+            };
+
+            Ok(ComposeStack {
+                name: field("Name")?.to_string(),
+                status: field("Status")?.to_string(),
+                config_files: field("ConfigFiles")?
+                    .split(',')
+                    .filter(|path| !path.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// `docker image ls` prints one object per line, with `<none>` for the repository and tag
+/// of dangling images and `CreatedAt` like `2024-05-01 10:00:00 +0200 CEST`.
+fn parse_image_ls(output: &str) -> Result<Vec<LocalImage>, DockerComposeError> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let value: serde_json::Value = serde_json::from_str(line)?;
+            let field = |name: &str| {
+                value
+                    .get(name)
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| DockerComposeError::MissingField(name.into()))
             };
+            let named = |name: &str| field(name).map(|v| (v != "<none>").then(|| v.to_string()));
+            let created_at = field("CreatedAt")?;
+            let without_zone_name = created_at
+                .rsplit_once(' ')
+                .map_or(created_at, |(timestamp, _)| timestamp);
+
+            Ok(LocalImage {
+                id: field("ID")?.to_string(),
+                repository: named("Repository")?,
+                tag: named("Tag")?,
+                created_at: DateTime::parse_from_str(without_zone_name, "%Y-%m-%d %H:%M:%S %z")
+                    .map_err(|_| DockerComposeError::InvalidTimestamp(created_at.to_string()))?
+                    .with_timezone(&Utc),
+                size: field("Size")?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `docker system df --verbose` prints one object whose `Volumes` carry their labels as
+/// `key=value` pairs joined by commas, and their size as `docker` prints sizes.
+fn parse_volume_usage(
+    output: &str,
+    project_name: &str,
+) -> Result<Vec<VolumeUsage>, DockerComposeError> {
+    let value: serde_json::Value = serde_json::from_str(output.trim())?;
+    let project_label = format!("{}={}", COMPOSE_PROJECT_LABEL, project_name);
+    let volumes = value
+        .get("Volumes")
+        .and_then(|v| v.as_array())
+        .map_or(&[][..], |volumes| volumes.as_slice());
+
+    Ok(volumes
+        .iter()
+        .filter(|volume| {
+            volume
+                .get("Labels")
+                .and_then(|v| v.as_str())
+                .is_some_and(|labels| labels.split(',').any(|label| label == project_label))
+        })
+        .filter_map(|volume| {
+            Some(VolumeUsage {
+                name: volume.get("Name")?.as_str()?.to_string(),
+                bytes: volume
+                    .get("Size")
+                    .and_then(|v| v.as_str())
+                    .and_then(parse_size),
+            })
+        })
+        .collect())
+}
+
+/// A size as `docker` prints it, e.g. `1.5kB` or `23MB`, in decimal units.
+fn parse_size(size: &str) -> Option<u64> {
+    let unit_start = size.find(|c: char| c.is_ascii_alphabetic())?;
+    let value = size[..unit_start].parse::<f64>().ok()?;
+    let factor = match &size[unit_start..] {
+        "B" => 1e0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "PB" => 1e15,
+        _ => return None,
+    };
+    Some((value * factor).round() as u64)
+}
+
+/// `docker network inspect` prints one array; `Containers` maps container ids to their
+/// endpoints on the network.
+fn parse_network_inspect(output: &str) -> Result<Vec<ComposeNetwork>, DockerComposeError> {
+    let values: Vec<serde_json::Value> = match output.trim() {
+        "" => vec![],
+        output => serde_json::from_str(output)?,
+    };
+
+    values
+        .iter()
+        .map(|value| {
+            let name = value
+                .get("Name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| DockerComposeError::MissingField("Name".into()))?;
+            let project = value
+                .get("Labels")
+                .and_then(|labels| labels.get(COMPOSE_PROJECT_LABEL))
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| DockerComposeError::MissingField(COMPOSE_PROJECT_LABEL.into()))?;
+            let containers = value
+                .get("Containers")
+                .and_then(|v| v.as_object())
+                .map_or(0, |containers| containers.len());
 
-            Ok(Container { name, state })
+            Ok(ComposeNetwork {
+                name: name.to_string(),
+                project: project.to_string(),
+                containers,
+            })
         })
         .collect()
+}
+
+/// Refuse an image reference docker could read as an option, such as `--help`, or that
+/// no registry would hold, before it reaches the command line.
+fn validate_image_reference(image: &str) -> Result<(), DockerComposeError> {
+    let valid = !image.is_empty()
+        && !image.starts_with('-')
+        && image
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-/:@".contains(c));
+    match valid {
+        true => Ok(()),
+        false => Err(DockerComposeError::InvalidImageReference(image.to_string())),
     }
 }
 
-fn find_compose_file_name(dir: &Path) -> Result<String, DockerComposeError> {
+pub(crate) fn find_compose_file_name(dir: &Path) -> Result<String, DockerComposeError> {
     SUPPORTED_COMPOSE_FILES
         .iter()
         .find(|name| dir.join(name).exists())
         .map(|name| name.to_string())
         .ok_or(DockerComposeError::DockerComposeFileDoesNotExist)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_image_references_when_validated_then_accept_only_plain_references() {
+        for image in [
+            "nginx",
+            "nginx:1.27-alpine",
+            "ghcr.io/owner/app:v1.2.3",
+            "registry.local:5000/team/app@sha256:0123abcd",
+        ] {
+            assert!(validate_image_reference(image).is_ok(), "{}", image);
+        }
+        for image in [
+            "",
+            "-q",
+            "--help",
+            "nginx latest",
+            "nginx\n--all-tags",
+            "$(reboot)",
+        ] {
+            assert!(validate_image_reference(image).is_err(), "{:?}", image);
+        }
+    }
+
+    #[test]
+    fn given_option_as_image_when_pull_image_then_docker_is_not_run() {
+        let client = DockerComposeClient::new().unwrap();
+
+        let actual = client.pull_image("--all-tags");
+
+        assert!(matches!(
+            actual,
+            Err(DockerComposeError::InvalidImageReference(image)) if image == "--all-tags"
+        ));
+    }
+}
@@ -1,28 +1,181 @@
 use anyhow::{anyhow, Result};
 use chrono::prelude::*;
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use crate::models::git::GitSource;
+use crate::config::{GitCredential, SshConfig};
+use crate::models::git::{Commit, GitSource, SshKey};
+use crate::repositories::process::CommandTimeout;
 
+/// Sources with a `tag_pattern` are checked out at the newest matching tag of the remote,
+/// and their `branch` is ignored.
 pub trait GitClient {
+    /// Implementations may clone only the newest commits; see [`GitClientImpl::with_depth`].
     fn clone_repository(&self, source: &GitSource, working_dir: &Path) -> Result<()>;
     fn pull_repository(&self, source: &GitSource, working_dir: &Path) -> Result<()>;
     fn get_last_commit_timestamp(&self, working_dir: &Path) -> Result<DateTime<Utc>>;
+    fn get_current_revision(&self, working_dir: &Path) -> Result<String>;
+    /// Check out `revision` into `target` as a detached worktree of `working_dir`.
+    fn add_worktree(&self, working_dir: &Path, revision: &str, target: &Path) -> Result<()>;
+    fn remove_worktree(&self, working_dir: &Path, target: &Path) -> Result<()>;
+    /// Reset the checkout in `working_dir` to the revision its worktree `worktree` has
+    /// checked out. Worktrees share the checkout's objects, so nothing is fetched.
+    fn checkout_worktree(&self, working_dir: &Path, worktree: &Path) -> Result<()>;
+    /// Reset the checkout in `working_dir` to `revision`, fetching it from `source` when a
+    /// shallow clone lacks it. The branch stays checked out, so the next pull moves it
+    /// forward again.
+    fn checkout_revision(
+        &self,
+        source: &GitSource,
+        working_dir: &Path,
+        revision: &str,
+    ) -> Result<()>;
+    /// Fetch `revision` from `source` into the checkout in `working_dir`, along with the
+    /// commits leading up to it, without moving the checkout.
+    fn fetch_revision(&self, source: &GitSource, working_dir: &Path, revision: &str) -> Result<()>;
+    /// The commits reachable from `to` but not from `from`, newest first.
+    fn list_commits(&self, working_dir: &Path, from: &str, to: &str) -> Result<Vec<Commit>>;
+    /// The files changed between commits `from` and `to`, relative to the repository root.
+    fn changed_files(&self, working_dir: &Path, from: &str, to: &str) -> Result<Vec<String>>;
+    /// Check the remote is reachable and has `source.branch`, without cloning it.
+    fn check_remote(&self, source: &GitSource) -> Result<()>;
+    /// The commit `source.branch` points at on the remote.
+    fn get_remote_revision(&self, source: &GitSource) -> Result<String>;
+    /// The remote and branch an existing checkout tracks, with an empty `path`.
+    fn describe_checkout(&self, working_dir: &Path) -> Result<GitSource>;
+    /// Commit everything in `working_dir`, which must not be a repository yet, and push it
+    /// as `source.branch` of `source.url`. `working_dir` is left as a checkout tracking it.
+    fn push_directory(&self, source: &GitSource, working_dir: &Path, message: &str) -> Result<()>;
 }
 
+/// Answers git's credential requests from the environment of the git process, so tokens
+/// never show up in arguments, remote URLs or `.git/config`. The empty helper before it
+/// drops helpers configured on the host.
+const CREDENTIAL_HELPER: &str = "credential.helper=!f() { test \"$1\" = get && echo \"username=$GFC_GIT_USERNAME\" && echo \"password=$GFC_GIT_PASSWORD\"; }; f";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Clones only the newest commit by default. Later pulls still fetch every new commit, so
+/// revisions deployed since the clone stay available for rollback.
 #[derive(Debug, Clone)]
-pub struct GitClientImpl;
+pub struct GitClientImpl {
+    depth: Option<u32>,
+    /// Any single git command taking longer is killed.
+    timeout: Duration,
+    ssh: SshConfig,
+    credentials: Vec<GitCredential>,
+    shared_clones: Option<SharedClones>,
+}
+
+/// One bare clone per remote and branch, e.g. of a monorepo deployed as several projects,
+/// which the checkouts of those projects borrow their objects from and pull through.
+#[derive(Debug, Clone)]
+struct SharedClones {
+    dir: PathBuf,
+    /// Held while a shared clone is cloned or fetched, so projects syncing together fetch
+    /// it once.
+    updating: Arc<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>>,
+}
+
+impl GitClientImpl {
+    /// Clone `depth` commits of history, or all of it with `None`.
+    pub fn with_depth(depth: Option<u32>) -> Self {
+        Self {
+            depth,
+            timeout: DEFAULT_TIMEOUT,
+            ssh: SshConfig::default(),
+            credentials: vec![],
+            shared_clones: None,
+        }
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn with_ssh(self, ssh: SshConfig) -> Self {
+        Self { ssh, ..self }
+    }
+
+    pub fn with_credentials(self, credentials: Vec<GitCredential>) -> Self {
+        Self {
+            credentials,
+            ..self
+        }
+    }
+
+    /// Share one clone per remote and branch between the checkouts tracking them, kept in
+    /// `dir`. Shared clones hold the whole history, as checkouts can't borrow from shallow
+    /// ones. Sources tracking tags are still cloned on their own.
+    pub fn with_shared_clones<P: AsRef<Path>>(self, dir: P) -> Self {
+        let dir = std::path::absolute(dir.as_ref()).unwrap_or_else(|_| dir.as_ref().to_path_buf());
+        Self {
+            shared_clones: Some(SharedClones {
+                dir,
+                updating: Arc::default(),
+            }),
+            ..self
+        }
+    }
+
+    /// `git`, set up to reach `source` with its deploy key and the host key policy, or
+    /// with the HTTPS credentials configured for its URL. A key stored as a secret must
+    /// have been resolved to its path beforehand.
+    fn git(&self, source: &GitSource) -> Result<Command> {
+        let key = match &source.ssh_key {
+            Some(SshKey::Path(path)) => Some(path.as_str()),
+            Some(SshKey::Secret(name)) => {
+                return Err(anyhow!("Deploy key secret {} was not resolved", name))
+            }
+            None => None,
+        };
+
+        let mut command = Command::new("git");
+        if key.is_some() || self.ssh != SshConfig::default() {
+            command.env("GIT_SSH_COMMAND", ssh_command(&self.ssh, key));
+        }
+        if let Some(credential) = credential_for(&self.credentials, &source.url) {
+            command
+                .args(["-c", "credential.helper=", "-c", CREDENTIAL_HELPER])
+                .env("GFC_GIT_USERNAME", &credential.username)
+                .env("GFC_GIT_PASSWORD", credential.token()?);
+        }
+        Ok(command)
+    }
+}
+
+impl Default for GitClientImpl {
+    fn default() -> Self {
+        Self::with_depth(Some(1))
+    }
+}
 
 impl GitClient for GitClientImpl {
     fn clone_repository(&self, source: &GitSource, working_dir: &Path) -> Result<()> {
-        Command::new("git")
-            .arg("clone")
-            .arg("--branch")
-            .arg(&source.branch)
+        if let Some(shared) = self.shared_clone(source) {
+            self.update_shared_clone(source, &shared, None)?;
+            return self.clone_from_shared(source, &shared, working_dir);
+        }
+
+        let reference = match self.newest_remote_tag(source)? {
+            Some((tag, _)) => tag,
+            None => source.branch.clone(),
+        };
+
+        let mut command = self.git(source)?;
+        command.arg("clone").arg("--branch").arg(&reference);
+        if let Some(depth) = self.depth {
+            command.arg("--depth").arg(depth.to_string());
+        }
+        command
             .arg(&source.url)
             .arg(working_dir)
-            .status()?
+            .status_within(self.timeout)?
             .success()
             .then_some(())
             .ok_or_else(|| anyhow!("Failed to clone {}", source.url))
@@ -32,11 +185,34 @@ impl GitClient for GitClientImpl {
         if !working_dir.exists() {
             return self.clone_repository(source, working_dir);
         }
+        if let Some(problem) = self.checkout_problem(source, working_dir) {
+            println!("Cloning {} again: {}", working_dir.display(), problem);
+            fs::remove_dir_all(working_dir)?;
+            return self.clone_repository(source, working_dir);
+        }
+        if let Some((tag, _)) = self.newest_remote_tag(source)? {
+            return self.checkout_tag(source, working_dir, &tag);
+        }
+        if let Some(shared) = self.shared_clone(source) {
+            self.update_shared_clone(source, &shared, None)?;
+            return Command::new("git")
+                .current_dir(working_dir)
+                .args(["pull", "--quiet", "--ff-only"])
+                .arg(&shared)
+                .arg(format!(
+                    "+refs/heads/{0}:refs/remotes/origin/{0}",
+                    source.branch
+                ))
+                .status_within(self.timeout)?
+                .success()
+                .then_some(())
+                .ok_or_else(|| anyhow!("Failed to pull {}", working_dir.display()));
+        }
 
-        Command::new("git")
+        self.git(source)?
             .arg("pull")
             .current_dir(working_dir)
-            .status()?
+            .status_within(self.timeout)?
             .success()
             .then_some(())
             .ok_or_else(|| anyhow!("Failed to pull {}", working_dir.display()))
@@ -46,7 +222,7 @@ impl GitClient for GitClientImpl {
         let output = Command::new("git")
             .current_dir(working_dir)
             .args(["log", "-1", "--format=%ct"])
-            .output()
+            .output_within(self.timeout)
             .map_err(|e| {
                 anyhow!(
                     "Failed to get last commit timestamp from {:?}: {}",
@@ -69,4 +245,802 @@ impl GitClient for GitClientImpl {
 
         Ok(timestamp)
     }
+
+    fn get_current_revision(&self, working_dir: &Path) -> Result<String> {
+        let output = Command::new("git")
+            .current_dir(working_dir)
+            .args(["rev-parse", "HEAD"])
+            .output_within(self.timeout)?;
+
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .ok_or_else(|| anyhow!("Failed to read revision of {}", working_dir.display()))
+    }
+
+    fn add_worktree(&self, working_dir: &Path, revision: &str, target: &Path) -> Result<()> {
+        Command::new("git")
+            .current_dir(working_dir)
+            .args(["worktree", "add", "--detach"])
+            .arg(target)
+            .arg(revision)
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| anyhow!("Failed to check out {} into {}", revision, target.display()))
+    }
+
+    fn remove_worktree(&self, working_dir: &Path, target: &Path) -> Result<()> {
+        Command::new("git")
+            .current_dir(working_dir)
+            .args(["worktree", "remove", "--force"])
+            .arg(target)
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| anyhow!("Failed to remove worktree {}", target.display()))
+    }
+
+    fn checkout_worktree(&self, working_dir: &Path, worktree: &Path) -> Result<()> {
+        let revision = self.get_current_revision(worktree)?;
+        self.reset_to(working_dir, &revision)
+    }
+
+    fn checkout_revision(
+        &self,
+        source: &GitSource,
+        working_dir: &Path,
+        revision: &str,
+    ) -> Result<()> {
+        if let Some(shared) = self.shared_clone(source) {
+            self.update_shared_clone(source, &shared, Some(revision))?;
+        }
+        // Checkouts cloned before shared clones were turned on don't borrow from them.
+        if !self.has_commit(working_dir, revision)? {
+            let mut fetch = self.git(source)?;
+            fetch.current_dir(working_dir).arg("fetch");
+            if let Some(depth) = self.depth {
+                fetch.args(["--depth", &depth.to_string()]);
+            }
+            fetch
+                .args(["origin", revision])
+                .status_within(self.timeout)?
+                .success()
+                .then_some(())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Failed to fetch {} into {}",
+                        revision,
+                        working_dir.display()
+                    )
+                })?;
+        }
+
+        self.reset_to(working_dir, revision)
+    }
+
+    fn fetch_revision(&self, source: &GitSource, working_dir: &Path, revision: &str) -> Result<()> {
+        if self.has_commit(working_dir, revision)? {
+            return Ok(());
+        }
+        if let Some(shared) = self.shared_clone(source) {
+            self.update_shared_clone(source, &shared, Some(revision))?;
+            if self.has_commit(working_dir, revision)? {
+                return Ok(());
+            }
+        }
+        // Without `--depth`, a shallow clone is deepened up to the commits it has, so
+        // the new ones can be listed.
+        self.git(source)?
+            .current_dir(working_dir)
+            .args(["fetch", "--quiet", "origin", revision])
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Failed to fetch {} into {}",
+                    revision,
+                    working_dir.display()
+                )
+            })
+    }
+
+    fn list_commits(&self, working_dir: &Path, from: &str, to: &str) -> Result<Vec<Commit>> {
+        let output = Command::new("git")
+            .current_dir(working_dir)
+            .args(["log", "--format=%H%x1f%an%x1f%cI%x1f%s"])
+            .arg(format!("{}..{}", from, to))
+            .output_within(self.timeout)?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Failed to list commits {}..{} in {}: {}",
+                from,
+                to,
+                working_dir.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(parse_commit)
+            .collect()
+    }
+
+    fn changed_files(&self, working_dir: &Path, from: &str, to: &str) -> Result<Vec<String>> {
+        let output = Command::new("git")
+            .current_dir(working_dir)
+            .args(["diff", "--name-only", "--no-renames", from, to])
+            .output_within(self.timeout)?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Failed to diff {}..{} in {}: {}",
+                from,
+                to,
+                working_dir.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn check_remote(&self, source: &GitSource) -> Result<()> {
+        if source.tag_pattern.is_some() {
+            return self.newest_remote_tag(source).map(|_| ());
+        }
+
+        let output = self
+            .git(source)?
+            .args(["ls-remote", "--exit-code"])
+            .arg(&source.url)
+            .arg(&source.branch)
+            .output_within(self.timeout)?;
+
+        match output.status.code() {
+            Some(0) => Ok(()),
+            Some(2) => Err(anyhow!(
+                "Branch {} not found in {}",
+                source.branch,
+                source.url
+            )),
+            _ => Err(anyhow!(
+                "Failed to reach {}: {}",
+                source.url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    fn get_remote_revision(&self, source: &GitSource) -> Result<String> {
+        if let Some((_, revision)) = self.newest_remote_tag(source)? {
+            return Ok(revision);
+        }
+
+        let output = self
+            .git(source)?
+            .args(["ls-remote", "--exit-code"])
+            .arg(&source.url)
+            .arg(&source.branch)
+            .output_within(self.timeout)?;
+
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+            .and_then(|refs| refs.split_whitespace().next().map(str::to_string))
+            .ok_or_else(|| anyhow!("Failed to read {} of {}", source.branch, source.url))
+    }
+
+    fn describe_checkout(&self, working_dir: &Path) -> Result<GitSource> {
+        let git = |args: &[&str]| -> Result<String> {
+            let output = Command::new("git")
+                .current_dir(working_dir)
+                .args(args)
+                .output_within(self.timeout)?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                .ok_or_else(|| anyhow!("{} is not a git checkout", working_dir.display()))
+        };
+
+        Ok(GitSource {
+            url: git(&["remote", "get-url", "origin"])?,
+            branch: git(&["rev-parse", "--abbrev-ref", "HEAD"])?,
+            path: String::new(),
+            extra_paths: vec![],
+            tag_pattern: None,
+            ssh_key: None,
+        })
+    }
+
+    fn push_directory(&self, source: &GitSource, working_dir: &Path, message: &str) -> Result<()> {
+        let git = |args: &[&str]| -> Result<()> {
+            Command::new("git")
+                .current_dir(working_dir)
+                .args(args)
+                .status_within(self.timeout)?
+                .success()
+                .then_some(())
+                .ok_or_else(|| {
+                    anyhow!("Failed to run git {} in {}", args[0], working_dir.display())
+                })
+        };
+
+        git(&["init", "--initial-branch", &source.branch])?;
+        git(&["add", "--all"])?;
+        // The host may have no git identity configured.
+        git(&[
+            "-c",
+            "user.name=gfc",
+            "-c",
+            "user.email=gfc@localhost",
+            "commit",
+            "--message",
+            message,
+        ])?;
+        git(&["remote", "add", "origin", &source.url])?;
+        self.git(source)?
+            .current_dir(working_dir)
+            .args(["push", "--set-upstream", "origin", &source.branch])
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| anyhow!("Failed to push {} to {}", working_dir.display(), source.url))
+    }
+}
+
+impl SharedClones {
+    fn lock(&self, path: &Path) -> Arc<Mutex<()>> {
+        let mut updating = self.updating.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(updating.entry(path.to_path_buf()).or_default())
+    }
+}
+
+impl GitClientImpl {
+    /// Where the clone `source` shares with other checkouts of its remote and branch is
+    /// kept, while shared clones are on and `source` tracks a branch.
+    fn shared_clone(&self, source: &GitSource) -> Option<PathBuf> {
+        let shared_clones = self.shared_clones.as_ref()?;
+        if source.tag_pattern.is_some() {
+            return None;
+        }
+        let key = Sha256::digest(format!("{}#{}", source.url, source.branch));
+        Some(shared_clones.dir.join(&hex::encode(key)[..16]))
+    }
+
+    /// Clone the shared clone at `path` if it is missing, or fetch `revision` into it, the
+    /// newest commit of the branch when `None`, unless it already has it.
+    fn update_shared_clone(
+        &self,
+        source: &GitSource,
+        path: &Path,
+        revision: Option<&str>,
+    ) -> Result<()> {
+        let lock = self
+            .shared_clones
+            .as_ref()
+            .map(|shared_clones| shared_clones.lock(path));
+        let _updating = lock
+            .as_ref()
+            .map(|lock| lock.lock().unwrap_or_else(|e| e.into_inner()));
+
+        if !path.exists() {
+            self.git(source)?
+                .args(["clone", "--quiet", "--bare", "--single-branch", "--branch"])
+                .arg(&source.branch)
+                .arg(&source.url)
+                .arg(path)
+                .status_within(self.timeout)?
+                .success()
+                .then_some(())
+                .ok_or_else(|| anyhow!("Failed to clone {}", source.url))?;
+            // Checkouts may still need commits the branch no longer reaches, e.g. after a
+            // force push, so they are never pruned.
+            return Command::new("git")
+                .current_dir(path)
+                .args(["config", "gc.pruneExpire", "never"])
+                .status_within(self.timeout)?
+                .success()
+                .then_some(())
+                .ok_or_else(|| anyhow!("Failed to configure {}", path.display()));
+        }
+
+        let revision = match revision {
+            Some(revision) => revision.to_string(),
+            None => self.get_remote_revision(source)?,
+        };
+        if self.has_commit(path, &revision)? {
+            return Ok(());
+        }
+        self.git(source)?
+            .current_dir(path)
+            .args(["fetch", "--quiet", "origin"])
+            .arg(format!("+refs/heads/{0}:refs/heads/{0}", source.branch))
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| anyhow!("Failed to fetch {} into {}", source.url, path.display()))?;
+        if self.has_commit(path, &revision)? {
+            return Ok(());
+        }
+        self.git(source)?
+            .current_dir(path)
+            .args(["fetch", "--quiet", "origin", &revision])
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| anyhow!("Failed to fetch {} into {}", revision, path.display()))
+    }
+
+    /// Check out the branch of the shared clone at `shared` into `working_dir`, borrowing
+    /// its objects, and point the checkout's `origin` back at the remote.
+    fn clone_from_shared(
+        &self,
+        source: &GitSource,
+        shared: &Path,
+        working_dir: &Path,
+    ) -> Result<()> {
+        Command::new("git")
+            .args(["clone", "--quiet", "--shared", "--branch"])
+            .arg(&source.branch)
+            .arg(shared)
+            .arg(working_dir)
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| anyhow!("Failed to clone {} from {}", source.url, shared.display()))?;
+        Command::new("git")
+            .current_dir(working_dir)
+            .args(["remote", "set-url", "origin"])
+            .arg(&source.url)
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| anyhow!("Failed to configure {}", working_dir.display()))
+    }
+
+    /// Move the branch checked out in `working_dir` to `revision`, which it already has.
+    fn reset_to(&self, working_dir: &Path, revision: &str) -> Result<()> {
+        Command::new("git")
+            .current_dir(working_dir)
+            .args(["reset", "--quiet", "--hard", revision])
+            .status_within(self.timeout)?
+            .success()
+            .then_some(())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Failed to check out {} in {}",
+                    revision,
+                    working_dir.display()
+                )
+            })
+    }
+
+    fn has_commit(&self, working_dir: &Path, revision: &str) -> Result<bool> {
+        Ok(Command::new("git")
+            .current_dir(working_dir)
+            .args(["cat-file", "-e", &format!("{}^{{commit}}", revision)])
+            .status_within(self.timeout)?
+            .success())
+    }
+
+    /// The newest remote tag `source.tag_pattern` selects and the commit it points at, or
+    /// `None` when the source tracks a branch.
+    fn newest_remote_tag(&self, source: &GitSource) -> Result<Option<(String, String)>> {
+        let Some(pattern) = &source.tag_pattern else {
+            return Ok(None);
+        };
+
+        let output = self
+            .git(source)?
+            .args(["ls-remote", "--tags"])
+            .arg(&source.url)
+            .output_within(self.timeout)?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Failed to reach {}: {}",
+                source.url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        // Annotated tags are listed twice; the `^{}` entry holds the commit they point at.
+        let mut tags = HashMap::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((revision, name)) = line.split_once('\t') else {
+                continue;
+            };
+            let Some(name) = name.strip_prefix("refs/tags/") else {
+                continue;
+            };
+            match name.strip_suffix("^{}") {
+                Some(name) => {
+                    tags.insert(name.to_string(), revision.to_string());
+                }
+                None => {
+                    tags.entry(name.to_string())
+                        .or_insert_with(|| revision.to_string());
+                }
+            }
+        }
+
+        let tag = source
+            .newest_matching_tag(tags.keys().map(String::as_str))
+            .ok_or_else(|| anyhow!("No tag of {} matches {}", source.url, pattern))?
+            .to_string();
+        let revision = tags[&tag].clone();
+        Ok(Some((tag, revision)))
+    }
+
+    /// Why pulling into the checkout in `working_dir` won't get `source`, if it won't: it
+    /// is broken or no repository at all, tracks another remote, e.g. after the project's
+    /// URL changed, or is not on the branch.
+    fn checkout_problem(&self, source: &GitSource, working_dir: &Path) -> Option<String> {
+        if !working_dir.join(".git").exists() {
+            return Some("no .git directory".to_string());
+        }
+        let checkout = match self.describe_checkout(working_dir) {
+            Ok(checkout) => checkout,
+            Err(e) => return Some(e.to_string()),
+        };
+        if !is_same_remote(&checkout.url, &source.url) {
+            return Some(format!("tracks {} instead", checkout.url));
+        }
+        // Checkouts of tags are detached on purpose.
+        if source.tag_pattern.is_none() && checkout.branch != source.branch {
+            return Some(format!(
+                "{} is checked out instead of {}",
+                checkout.branch, source.branch
+            ));
+        }
+        None
+    }
+
+    fn checkout_tag(&self, source: &GitSource, working_dir: &Path, tag: &str) -> Result<()> {
+        let git = |args: &[&str]| -> Result<()> {
+            self.git(source)?
+                .current_dir(working_dir)
+                .args(args)
+                .status_within(self.timeout)?
+                .success()
+                .then_some(())
+                .ok_or_else(|| anyhow!("Failed to check out {} in {}", tag, working_dir.display()))
+        };
+
+        match self.depth {
+            Some(depth) => git(&[
+                "fetch",
+                "--depth",
+                &depth.to_string(),
+                "--force",
+                "--tags",
+                "origin",
+            ])?,
+            None => git(&["fetch", "--force", "--tags", "origin"])?,
+        }
+        git(&["checkout", "--detach", &format!("refs/tags/{}", tag)])
+    }
+}
+
+/// The `GIT_SSH_COMMAND` for `ssh`, with the deploy key at `key` if any. Batch mode
+/// makes ssh fail instead of prompting for a passphrase or an unknown host.
+/// A line of `git log --format=%H%x1f%an%x1f%cI%x1f%s`.
+fn parse_commit(line: &str) -> Result<Commit> {
+    let mut fields = line.splitn(4, '\x1f');
+    let mut field = || {
+        fields
+            .next()
+            .ok_or_else(|| anyhow!("Unexpected git log line: {}", line))
+    };
+    Ok(Commit {
+        revision: field()?.to_string(),
+        author: field()?.to_string(),
+        committed_at: DateTime::parse_from_rfc3339(field()?)?.to_utc(),
+        subject: field()?.to_string(),
+    })
+}
+
+fn ssh_command(ssh: &SshConfig, key: Option<&str>) -> String {
+    let mut command = format!(
+        "ssh -o BatchMode=yes -o StrictHostKeyChecking={}",
+        ssh.host_key_checking.as_ssh_option()
+    );
+    if let Some(known_hosts_file) = &ssh.known_hosts_file {
+        command.push_str(&format!(
+            " -o UserKnownHostsFile={}",
+            shell_quote(known_hosts_file)
+        ));
+    }
+    if let Some(key) = key {
+        command.push_str(&format!(" -o IdentitiesOnly=yes -i {}", shell_quote(key)));
+    }
+    command
+}
+
+/// The credentials with the longest `url` that `url` starts with. Only HTTPS remotes get
+/// any, so a token can't be sent in the clear.
+fn credential_for<'a>(credentials: &'a [GitCredential], url: &str) -> Option<&'a GitCredential> {
+    credentials
+        .iter()
+        .filter(|credential| url.starts_with("https://") && url.starts_with(&credential.url))
+        .max_by_key(|credential| credential.url.len())
+}
+
+/// Whether two remote URLs name the same repository, give or take a trailing slash or
+/// `.git`.
+fn is_same_remote(a: &str, b: &str) -> bool {
+    let normalize = |url: &str| {
+        let url = url.trim_end_matches('/');
+        url.strip_suffix(".git").unwrap_or(url).to_string()
+    };
+    normalize(a) == normalize(b)
+}
+
+/// git runs `GIT_SSH_COMMAND` through the shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HostKeyChecking;
+
+    #[test]
+    fn given_deploy_key_and_host_key_policy_when_ssh_command_then_pass_both_to_ssh() {
+        let ssh = SshConfig {
+            host_key_checking: HostKeyChecking::AcceptNew,
+            known_hosts_file: Some("/etc/gfc/known_hosts".to_string()),
+        };
+
+        let actual = ssh_command(&ssh, Some("/srv/gfc/secrets/web/it's a key"));
+
+        assert_eq!(
+            actual,
+            "ssh -o BatchMode=yes -o StrictHostKeyChecking=accept-new \
+             -o UserKnownHostsFile='/etc/gfc/known_hosts' \
+             -o IdentitiesOnly=yes -i '/srv/gfc/secrets/web/it'\\''s a key'"
+        );
+    }
+
+    #[test]
+    fn given_host_and_repository_credentials_when_credential_for_then_longest_match_wins() {
+        let credential = |url: &str, token: &str| GitCredential {
+            url: url.to_string(),
+            username: "gfc".to_string(),
+            token: Some(token.to_string()),
+            token_env: None,
+        };
+        let credentials = [
+            credential("https://github.com/", "host"),
+            credential("https://github.com/acme/web", "repository"),
+        ];
+
+        let token = |url: &str| {
+            credential_for(&credentials, url).and_then(|credential| credential.token.clone())
+        };
+
+        assert_eq!(
+            token("https://github.com/acme/web.git").as_deref(),
+            Some("repository")
+        );
+        assert_eq!(
+            token("https://github.com/acme/api.git").as_deref(),
+            Some("host")
+        );
+        assert_eq!(token("http://github.com/acme/web.git"), None);
+    }
+
+    #[test]
+    fn given_checkout_of_other_remote_or_branch_when_checkout_problem_then_report_it() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .current_dir(dir.path())
+                .args(["-c", "user.name=gfc", "-c", "user.email=gfc@example.com"])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "--quiet", "--initial-branch", "main"]);
+        git(&["remote", "add", "origin", "https://example.com/web.git"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "init"]);
+        let source = |url: &str, branch: &str| GitSource {
+            url: url.to_string(),
+            branch: branch.to_string(),
+            ..Default::default()
+        };
+        let client = GitClientImpl::default();
+
+        let problem = |url, branch| client.checkout_problem(&source(url, branch), dir.path());
+
+        assert_eq!(problem("https://example.com/web/", "main"), None);
+        assert!(problem("https://example.com/api.git", "main").is_some());
+        assert!(problem("https://example.com/web.git", "release").is_some());
+    }
+
+    #[test]
+    fn given_depth_when_clone_repository_then_clone_only_that_much_history() {
+        let root = tempfile::TempDir::new().unwrap();
+        let remote = root.path().join("remote");
+        std::fs::create_dir_all(&remote).unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(dir)
+                .args(["-c", "user.name=gfc", "-c", "user.email=gfc@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        git(&remote, &["init", "--quiet", "--initial-branch", "main"]);
+        for message in ["first", "second", "third"] {
+            git(
+                &remote,
+                &["commit", "--quiet", "--allow-empty", "--message", message],
+            );
+        }
+        // Local paths are cloned by copying objects, which ignores `--depth`.
+        let source = GitSource {
+            url: format!("file://{}", remote.display()),
+            branch: "main".to_string(),
+            ..Default::default()
+        };
+
+        let history = |client: GitClientImpl, checkout: &str| {
+            let checkout = root.path().join(checkout);
+            client.clone_repository(&source, &checkout).unwrap();
+            git(&checkout, &["rev-list", "--count", "HEAD"])
+        };
+
+        assert_eq!(history(GitClientImpl::default(), "shallow"), "1");
+        assert_eq!(history(GitClientImpl::with_depth(Some(2)), "deeper"), "2");
+        assert_eq!(history(GitClientImpl::with_depth(None), "full"), "3");
+    }
+
+    #[test]
+    fn given_older_revision_when_checkout_revision_then_reset_the_branch_to_it() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(dir.path())
+                .args(["-c", "user.name=gfc", "-c", "user.email=gfc@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        git(&["init", "--quiet", "--initial-branch", "main"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "first"]);
+        let first = git(&["rev-parse", "HEAD"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "second"]);
+        let client = GitClientImpl::default();
+
+        client
+            .checkout_revision(&GitSource::default(), dir.path(), &first)
+            .unwrap();
+
+        assert_eq!(client.get_current_revision(dir.path()).unwrap(), first);
+        assert_eq!(git(&["branch", "--show-current"]), "main");
+    }
+
+    #[test]
+    fn given_worktree_of_older_revision_when_checkout_worktree_then_reset_to_it_without_a_remote() {
+        let root = tempfile::TempDir::new().unwrap();
+        let checkout = root.path().join("checkout");
+        let worktree = root.path().join("standby");
+        std::fs::create_dir_all(&checkout).unwrap();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(&checkout)
+                .args(["-c", "user.name=gfc", "-c", "user.email=gfc@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        git(&["init", "--quiet", "--initial-branch", "main"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "first"]);
+        let first = git(&["rev-parse", "HEAD"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "second"]);
+        let client = GitClientImpl::default();
+        client.add_worktree(&checkout, &first, &worktree).unwrap();
+
+        client.checkout_worktree(&checkout, &worktree).unwrap();
+
+        assert_eq!(client.get_current_revision(&checkout).unwrap(), first);
+        assert_eq!(git(&["branch", "--show-current"]), "main");
+    }
+
+    #[test]
+    fn given_newer_commits_when_list_commits_then_return_only_them_newest_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(dir.path())
+                .args(["-c", "user.name=gfc", "-c", "user.email=gfc@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        git(&["init", "--quiet", "--initial-branch", "main"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "first"]);
+        let first = git(&["rev-parse", "HEAD"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "second"]);
+        git(&[
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "--message",
+            "third\n\nbody",
+        ]);
+        let third = git(&["rev-parse", "HEAD"]);
+
+        let commits = GitClientImpl::default()
+            .list_commits(dir.path(), &first, &third)
+            .unwrap();
+
+        let subjects: Vec<_> = commits.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, ["third", "second"]);
+        assert_eq!(commits[0].revision, third);
+        assert_eq!(commits[0].author, "gfc");
+    }
+
+    #[test]
+    fn given_shared_clones_when_two_checkouts_track_one_branch_then_clone_and_fetch_it_once() {
+        let root = tempfile::TempDir::new().unwrap();
+        let remote = root.path().join("remote");
+        std::fs::create_dir_all(&remote).unwrap();
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(&remote)
+                .args(["-c", "user.name=gfc", "-c", "user.email=gfc@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        git(&["init", "--quiet", "--initial-branch", "main"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "first"]);
+        let source = GitSource {
+            url: remote.display().to_string(),
+            branch: "main".to_string(),
+            ..Default::default()
+        };
+        let client = GitClientImpl::default().with_shared_clones(root.path().join("shared"));
+        let (api, web) = (root.path().join("api"), root.path().join("web"));
+        client.clone_repository(&source, &api).unwrap();
+        client.clone_repository(&source, &web).unwrap();
+        git(&["commit", "--quiet", "--allow-empty", "--message", "second"]);
+        let second = git(&["rev-parse", "HEAD"]);
+
+        client.pull_repository(&source, &api).unwrap();
+        client.pull_repository(&source, &web).unwrap();
+
+        assert_eq!(
+            std::fs::read_dir(root.path().join("shared"))
+                .unwrap()
+                .count(),
+            1
+        );
+        for checkout in [&api, &web] {
+            assert!(checkout.join(".git/objects/info/alternates").exists());
+            assert_eq!(client.get_current_revision(checkout).unwrap(), second);
+            assert_eq!(client.checkout_problem(&source, checkout), None);
+        }
+    }
 }
@@ -0,0 +1,29 @@
+use anyhow::{anyhow, Result};
+use std::process::Command;
+
+use crate::models::device::Gpu;
+use crate::repositories::blocking;
+
+/// The NVIDIA GPUs on this host. Fails when `nvidia-smi` is missing or cannot reach the
+/// driver.
+pub fn list_gpus() -> Result<Vec<Gpu>> {
+    blocking::debug_assert_may_block("nvidia-smi");
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=index,uuid", "--format=csv,noheader"])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "nvidia-smi failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(','))
+        .map(|(index, uuid)| Gpu {
+            index: index.trim().to_string(),
+            uuid: uuid.trim().to_string(),
+        })
+        .collect())
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::models::job::Job;
+
+/// How long a write waits for another one before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS unfinished_jobs (
+    id TEXT PRIMARY KEY,
+    job TEXT NOT NULL
+);
+";
+
+/// Jobs that have not finished yet, kept in an SQLite database so that those a crash
+/// interrupts are still known on the next start. Jobs are forgotten once they finish.
+#[derive(Debug, Clone)]
+pub struct JobStore {
+    path: PathBuf,
+}
+
+impl JobStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Store `job`, or forget it once it has finished.
+    pub fn save(&self, job: &Job) -> Result<()> {
+        let connection = self.connect()?;
+        match job.status.is_finished() {
+            true => {
+                connection.execute("DELETE FROM unfinished_jobs WHERE id = ?1", params![job.id])?
+            }
+            false => connection.execute(
+                "INSERT OR REPLACE INTO unfinished_jobs (id, job) VALUES (?1, ?2)",
+                params![job.id, serde_json::to_string(job)?],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Every stored job, oldest first.
+    pub fn list(&self) -> Result<Vec<Job>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        let connection = self.connect()?;
+        let mut statement = connection.prepare("SELECT job FROM unfinished_jobs ORDER BY id")?;
+        let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+        rows.map(|row| Ok(serde_json::from_str(&row?)?)).collect()
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(&self.path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(connection)
+    }
+}
@@ -1,5 +1,18 @@
+pub mod activity_log;
+pub mod blocking;
 pub mod compose_client;
+#[cfg(feature = "docker-api")]
 pub mod container_client;
+pub mod credentials;
+pub mod deployment_history;
+#[cfg(feature = "docker-api")]
 pub mod docker_client;
 pub mod docker_compose_client;
 pub mod git;
+pub mod gpu;
+#[cfg(feature = "sqlite-store")]
+pub mod job_store;
+pub mod process;
+pub mod secret_store;
+pub mod sops;
+pub mod usage_reporter;
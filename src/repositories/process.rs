@@ -0,0 +1,349 @@
+use std::cell::{Cell, RefCell};
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::repositories::blocking;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Error)]
+pub enum ProcessError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("{program} did not finish within {timeout:?} and was killed")]
+    TimedOut { program: String, timeout: Duration },
+    #[error("{program} was killed because its job was cancelled")]
+    Cancelled { program: String },
+}
+
+/// Asks commands running on behalf of some work, e.g. a job, to be killed.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static CANCELLATION: RefCell<Option<Cancellation>> = const { RefCell::new(None) };
+    static TIME_LIMIT: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Run `work` with the commands it starts on this thread killed once `cancellation` is
+/// cancelled, so the clients running them need not know about it.
+pub fn with_cancellation<T>(cancellation: &Cancellation, work: impl FnOnce() -> T) -> T {
+    let previous = CANCELLATION.replace(Some(cancellation.clone()));
+    let result = work();
+    CANCELLATION.set(previous);
+    result
+}
+
+/// Run `work` with the commands it starts on this thread killed once `limit` has passed,
+/// however long their own timeouts, e.g. to answer a request by its deadline. `None`
+/// leaves their timeouts alone.
+pub fn with_time_limit<T>(limit: Option<Duration>, work: impl FnOnce() -> T) -> T {
+    let previous = TIME_LIMIT.get();
+    let until = limit.map(|limit| Instant::now() + limit);
+    TIME_LIMIT.set(match (previous, until) {
+        (Some(previous), Some(until)) => Some(previous.min(until)),
+        (previous, until) => until.or(previous),
+    });
+    let result = work();
+    TIME_LIMIT.set(previous);
+    result
+}
+
+/// Whether the work running on this thread was cancelled.
+pub fn is_cancelled() -> bool {
+    CANCELLATION.with_borrow(|cancellation| {
+        cancellation
+            .as_ref()
+            .is_some_and(Cancellation::is_cancelled)
+    })
+}
+
+/// What `capped_output_within` kept of the output of a command.
+#[derive(Debug)]
+pub struct CappedOutput {
+    /// `None` when the command was killed for running past its timeout.
+    pub status: Option<ExitStatus>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// Whether stdout or stderr went over the cap and was cut.
+    pub truncated: bool,
+}
+
+/// `Command::status` and `Command::output` that give up after `timeout`, or once the work
+/// they run for is cancelled. The command runs in a process group of its own, which is
+/// killed as a whole, so helpers it started, such as the `ssh` behind `git clone`, don't
+/// outlive it.
+pub trait CommandTimeout {
+    fn status_within(&mut self, timeout: Duration) -> Result<ExitStatus, ProcessError>;
+    fn output_within(&mut self, timeout: Duration) -> Result<Output, ProcessError>;
+    /// Like `output_within`, keeping at most `limit` bytes of each stream. Running past
+    /// `timeout` is not an error: what the command printed until then is kept.
+    fn capped_output_within(
+        &mut self,
+        timeout: Duration,
+        limit: usize,
+    ) -> Result<CappedOutput, ProcessError>;
+}
+
+impl CommandTimeout for Command {
+    fn status_within(&mut self, timeout: Duration) -> Result<ExitStatus, ProcessError> {
+        let timeout = time_left(self, timeout)?;
+        let mut child = self.process_group(0).spawn()?;
+        wait_within(self, &mut child, timeout)
+    }
+
+    fn output_within(&mut self, timeout: Duration) -> Result<Output, ProcessError> {
+        let timeout = time_left(self, timeout)?;
+        let (mut child, stdout, stderr) = spawn_piped(self, usize::MAX)?;
+
+        let status = wait_within(self, &mut child, timeout)?;
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default().0,
+            stderr: stderr.join().unwrap_or_default().0,
+        })
+    }
+
+    fn capped_output_within(
+        &mut self,
+        timeout: Duration,
+        limit: usize,
+    ) -> Result<CappedOutput, ProcessError> {
+        let timeout = time_left(self, timeout)?;
+        let (mut child, stdout, stderr) = spawn_piped(self, limit)?;
+
+        let status = match wait_within(self, &mut child, timeout) {
+            Ok(status) => Some(status),
+            Err(ProcessError::TimedOut { .. }) => None,
+            Err(e) => return Err(e),
+        };
+        let (stdout, stdout_truncated) = stdout.join().unwrap_or_default();
+        let (stderr, stderr_truncated) = stderr.join().unwrap_or_default();
+        Ok(CappedOutput {
+            status,
+            stdout,
+            stderr,
+            truncated: stdout_truncated || stderr_truncated,
+        })
+    }
+}
+
+type CappedReader = thread::JoinHandle<(Vec<u8>, bool)>;
+
+/// Start `command` in a group of its own, its stdout and stderr read in the background
+/// up to `limit` bytes each.
+fn spawn_piped(
+    command: &mut Command,
+    limit: usize,
+) -> Result<(Child, CappedReader, CappedReader), ProcessError> {
+    let mut child = command
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = read_in_background(child.stdout.take(), limit);
+    let stderr = read_in_background(child.stderr.take(), limit);
+    Ok((child, stdout, stderr))
+}
+
+fn wait_within(
+    command: &Command,
+    child: &mut Child,
+    timeout: Duration,
+) -> Result<ExitStatus, ProcessError> {
+    let started_at = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if is_cancelled() || started_at.elapsed() >= timeout {
+            kill_group(child);
+            child.wait()?;
+            check_cancelled(command)?;
+            return Err(ProcessError::TimedOut {
+                program: program(command),
+                timeout,
+            });
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// `timeout`, cut short to what is left of the time limit of the work running on this
+/// thread. Fails without starting the command if the work was cancelled or is out of time.
+fn time_left(command: &Command, timeout: Duration) -> Result<Duration, ProcessError> {
+    check_cancelled(command)?;
+    let timeout = TIME_LIMIT.get().map_or(timeout, |until| {
+        timeout.min(until.saturating_duration_since(Instant::now()))
+    });
+    match timeout.is_zero() {
+        true => Err(ProcessError::TimedOut {
+            program: program(command),
+            timeout,
+        }),
+        false => Ok(timeout),
+    }
+}
+
+fn check_cancelled(command: &Command) -> Result<(), ProcessError> {
+    blocking::debug_assert_may_block(&program(command));
+    match is_cancelled() {
+        true => Err(ProcessError::Cancelled {
+            program: program(command),
+        }),
+        false => Ok(()),
+    }
+}
+
+fn program(command: &Command) -> String {
+    command.get_program().to_string_lossy().to_string()
+}
+
+fn kill_group(child: &mut Child) {
+    // SAFETY: `kill` takes no pointers. The child has not been waited for yet, so its ID,
+    // which is also the ID of the group it leads, can't have been reused.
+    let killed = unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+    if killed != 0 {
+        let _ = child.kill();
+    }
+}
+
+/// Keep the first `limit` bytes read from `pipe`, and whether there was more. The rest is
+/// drained so the command doesn't block on a full pipe.
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>, limit: usize) -> CappedReader {
+    thread::spawn(move || {
+        let Some(pipe) = pipe else {
+            return (Vec::new(), false);
+        };
+
+        let mut output = Vec::new();
+        let mut kept = pipe.take(limit as u64);
+        let _ = kept.read_to_end(&mut output);
+        let discarded = io::copy(&mut kept.into_inner(), &mut io::sink()).unwrap_or(0);
+        (output, discarded > 0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_quick_command_when_output_within_then_return_its_output() {
+        let output = Command::new("sh")
+            .args(["-c", "echo out; echo err >&2; exit 3"])
+            .output_within(Duration::from_secs(5))
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn given_hung_command_with_children_when_output_within_then_kill_them_all() {
+        let started_at = Instant::now();
+
+        // The backgrounded sleep holds stdout open, so reading it would hang if only
+        // the shell were killed.
+        let actual = Command::new("sh")
+            .args(["-c", "sleep 30 & sleep 30"])
+            .output_within(Duration::from_millis(200));
+
+        assert!(matches!(actual, Err(ProcessError::TimedOut { .. })));
+        assert!(started_at.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn given_chatty_command_when_capped_output_within_then_keep_up_to_the_cap() {
+        let output = Command::new("sh")
+            .args(["-c", "printf 0123456789; printf err >&2"])
+            .capped_output_within(Duration::from_secs(5), 4)
+            .unwrap();
+
+        assert!(output.status.unwrap().success());
+        assert_eq!(output.stdout, b"0123");
+        assert_eq!(output.stderr, b"err");
+        assert!(output.truncated);
+    }
+
+    #[test]
+    fn given_hung_command_with_children_when_capped_output_within_then_keep_output_so_far() {
+        let started_at = Instant::now();
+
+        let output = Command::new("sh")
+            .args(["-c", "echo started; sleep 30 & sleep 30"])
+            .capped_output_within(Duration::from_millis(200), 1024)
+            .unwrap();
+
+        assert!(output.status.is_none());
+        assert_eq!(output.stdout, b"started\n");
+        assert!(!output.truncated);
+        assert!(started_at.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn given_cancelled_work_when_output_within_then_kill_its_command() {
+        let cancellation = Cancellation::default();
+        let started_at = Instant::now();
+
+        let canceller = {
+            let cancellation = cancellation.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(200));
+                cancellation.cancel();
+            })
+        };
+        let actual = with_cancellation(&cancellation, || {
+            Command::new("sh")
+                .args(["-c", "sleep 30 & sleep 30"])
+                .output_within(Duration::from_secs(60))
+        });
+        canceller.join().unwrap();
+        let next = with_cancellation(&cancellation, || {
+            Command::new("true").status_within(Duration::from_secs(5))
+        });
+
+        assert!(matches!(actual, Err(ProcessError::Cancelled { .. })));
+        assert!(matches!(next, Err(ProcessError::Cancelled { .. })));
+        assert!(started_at.elapsed() < Duration::from_secs(10));
+        assert!(!is_cancelled());
+    }
+
+    #[test]
+    fn given_time_limit_shorter_than_timeout_when_output_within_then_kill_at_time_limit() {
+        let started_at = Instant::now();
+
+        let actual = with_time_limit(Some(Duration::from_millis(200)), || {
+            Command::new("sleep")
+                .arg("30")
+                .output_within(Duration::from_secs(60))
+        });
+        let spent = with_time_limit(Some(Duration::ZERO), || {
+            Command::new("true").status_within(Duration::from_secs(5))
+        });
+        let unlimited = Command::new("true").status_within(Duration::from_secs(5));
+
+        assert!(matches!(actual, Err(ProcessError::TimedOut { .. })));
+        assert!(started_at.elapsed() < Duration::from_secs(10));
+        assert!(matches!(spent, Err(ProcessError::TimedOut { .. })));
+        assert!(unlimited.unwrap().success());
+    }
+}
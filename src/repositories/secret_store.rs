@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Result};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::repositories::credentials::CredentialCipher;
+
+/// Secret values, one file per secret under a directory per project. Files are only
+/// readable by the gfc user, and sealed with the master key when one is set.
+#[derive(Debug, Clone)]
+pub struct SecretStore {
+    root: PathBuf,
+    cipher: Option<CredentialCipher>,
+}
+
+impl SecretStore {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            cipher: None,
+        }
+    }
+
+    /// Seal values written from now on. Values already stored stay readable.
+    pub fn with_cipher(self, cipher: Option<CredentialCipher>) -> Self {
+        Self { cipher, ..self }
+    }
+
+    /// Whether stored files hold sealed values rather than the secrets themselves.
+    pub fn is_sealed(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    pub fn put(&self, project_name: &str, name: &str, value: &[u8]) -> Result<()> {
+        let path = self.path_for(project_name, name)?;
+        create_private_dir(path.parent().unwrap())?;
+        match &self.cipher {
+            Some(cipher) => write_private_file(&path, &cipher.seal(value)?),
+            None => write_private_file(&path, value),
+        }
+    }
+
+    pub fn get(&self, project_name: &str, name: &str) -> Result<Vec<u8>> {
+        let path = self.path_for(project_name, name)?;
+        let value = fs::read(&path)
+            .map_err(|e| anyhow!("Secret {} of {} not found: {}", name, project_name, e))?;
+        match &self.cipher {
+            Some(cipher) => cipher.unseal(&value),
+            None => Ok(value),
+        }
+    }
+
+    pub fn delete(&self, project_name: &str, name: &str) -> Result<()> {
+        Ok(fs::remove_file(self.path_for(project_name, name)?)?)
+    }
+
+    pub fn remove_all(&self, project_name: &str) -> Result<()> {
+        let dir = self.root.join(project_name);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Names of the project's secrets, sorted. Values are never listed.
+    pub fn names(&self, project_name: &str) -> Result<Vec<String>> {
+        let dir = self.root.join(project_name);
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut names = fs::read_dir(dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<Result<Vec<_>>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    /// Where the secret is stored, whether or not it has been put yet.
+    pub fn path_for(&self, project_name: &str, name: &str) -> Result<PathBuf> {
+        validate_secret_name(name)?;
+        Ok(self.root.join(project_name).join(name))
+    }
+}
+
+/// Secret names become file names, so they may not contain path separators or start
+/// with a dot.
+pub fn validate_secret_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    match valid {
+        true => Ok(()),
+        false => Err(anyhow!("Invalid secret name: {}", name)),
+    }
+}
+
+pub fn create_private_dir(path: &Path) -> Result<()> {
+    Ok(fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)?)
+}
+
+pub fn write_private_file(path: &Path, value: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(value)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn given_stored_secret_when_get_then_return_value_from_private_file() {
+        let root = TempDir::new().unwrap();
+        let store = SecretStore::new(root.path());
+
+        store.put("demo", "db_password", b"hunter2").unwrap();
+
+        assert_eq!(store.get("demo", "db_password").unwrap(), b"hunter2");
+        assert_eq!(store.names("demo").unwrap(), vec!["db_password"]);
+        let mode = fs::metadata(root.path().join("demo/db_password"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn given_traversing_name_when_put_then_return_error() {
+        let root = TempDir::new().unwrap();
+        let store = SecretStore::new(root.path());
+
+        assert!(store.put("demo", "../escape", b"value").is_err());
+        assert!(store.put("demo", ".hidden", b"value").is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::Path;
+use std::process::Command;
+use thiserror::Error;
+
+use crate::config::SopsConfig;
+use crate::repositories::blocking;
+
+/// Marks a file name as encrypted, e.g. `prod.enc.env` or `secrets.yml.enc`.
+const ENCRYPTED_MARKER: &str = "enc";
+
+#[derive(Debug, Error)]
+pub enum SopsError {
+    #[error("Failed to run sops: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to decrypt {path}: {message}")]
+    DecryptFailed { path: String, message: String },
+}
+
+/// Decrypts files committed to a repository with sops. Keys are looked up the way sops
+/// looks them up, e.g. from `SOPS_AGE_KEY_FILE` or a cloud KMS, unless an age key file is
+/// configured.
+#[derive(Debug, Clone)]
+pub struct Sops {
+    config: SopsConfig,
+}
+
+impl Sops {
+    pub fn new(config: SopsConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn decrypt(&self, path: &Path) -> Result<Vec<u8>, SopsError> {
+        let mut command = Command::new(&self.config.binary);
+        command.arg("--decrypt").arg(path);
+        if let Some(key_file) = &self.config.age_key_file {
+            command.env("SOPS_AGE_KEY_FILE", key_file);
+        }
+        blocking::debug_assert_may_block("sops");
+        let output = command.output()?;
+
+        match output.status.success() {
+            true => Ok(output.stdout),
+            false => Err(SopsError::DecryptFailed {
+                path: path.display().to_string(),
+                message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            }),
+        }
+    }
+}
+
+impl Default for Sops {
+    fn default() -> Self {
+        Self::new(SopsConfig::default())
+    }
+}
+
+/// Where the plaintext of the encrypted file at `path` goes: next to it, with `enc` taken
+/// out of its name. `None` when the name has no `enc` part.
+pub fn decrypted_path(path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let name = path.file_name()?.to_str()?;
+    let mut parts = name.split('.').collect::<Vec<_>>();
+    let position = parts
+        .iter()
+        .skip(1)
+        .position(|part| *part == ENCRYPTED_MARKER)?;
+    if parts[0].is_empty() {
+        return None;
+    }
+
+    parts.remove(position + 1);
+    Some(path.with_file_name(parts.join(".")).display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn given_encrypted_file_names_when_decrypted_path_then_drop_enc() {
+        assert_eq!(
+            decrypted_path("env/prod.enc.env"),
+            Some("env/prod.env".to_string())
+        );
+        assert_eq!(
+            decrypted_path("secrets.yml.enc"),
+            Some("secrets.yml".to_string())
+        );
+        assert_eq!(decrypted_path("prod.env"), None);
+        assert_eq!(decrypted_path(".enc"), None);
+    }
+
+    #[test]
+    fn given_sops_binary_when_decrypt_then_return_its_output() {
+        let dir = TempDir::new().unwrap();
+        let binary = dir.path().join("sops");
+        fs::write(
+            &binary,
+            "#!/bin/sh\nprintf 'TOKEN=%s' \"$SOPS_AGE_KEY_FILE\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+        let sops = Sops::new(SopsConfig {
+            binary: binary.display().to_string(),
+            age_key_file: Some("/etc/gfc/age.key".to_string()),
+        });
+
+        let actual = sops.decrypt(Path::new("prod.enc.env")).unwrap();
+
+        assert_eq!(actual, b"TOKEN=/etc/gfc/age.key");
+    }
+}
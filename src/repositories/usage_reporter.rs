@@ -0,0 +1,49 @@
+use anyhow::Result;
+
+use crate::models::usage::UsageStats;
+
+/// Where anonymous usage reports go. Implement it to send them to a collector of your own.
+pub trait UsageReporter: Send + Sync {
+    fn report(&self, stats: &UsageStats) -> Result<()>;
+}
+
+/// Prints each report instead of sending it, to see what would be sent.
+#[derive(Debug, Clone, Default)]
+pub struct LogUsageReporter;
+
+impl UsageReporter for LogUsageReporter {
+    fn report(&self, stats: &UsageStats) -> Result<()> {
+        println!("Usage report: {}", serde_json::to_string(stats)?);
+        Ok(())
+    }
+}
+
+/// POSTs each report as JSON to `endpoint`.
+#[cfg(feature = "usage-stats")]
+#[derive(Debug, Clone)]
+pub struct HttpUsageReporter {
+    endpoint: String,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "usage-stats")]
+impl HttpUsageReporter {
+    pub fn new(endpoint: &str) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(std::time::Duration::from_secs(30)))
+            .build()
+            .into();
+        Self {
+            endpoint: endpoint.to_string(),
+            agent,
+        }
+    }
+}
+
+#[cfg(feature = "usage-stats")]
+impl UsageReporter for HttpUsageReporter {
+    fn report(&self, stats: &UsageStats) -> Result<()> {
+        self.agent.post(&self.endpoint).send_json(stats)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::models::docker_compose::ComposeProject;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::docker_compose_client::find_compose_file_name;
+use crate::usecases::project::project_status_from_states;
+
+#[derive(Debug, Error)]
+pub enum ComposeUsecaseError {
+    #[error("resources.compose_projects_dir is not set")]
+    NotConfigured,
+    #[error("Failed to list compose projects: {0}")]
+    ListFailed(String),
+}
+
+/// Read-only view of compose projects kept outside gfc, e.g. ones started by hand, so
+/// they show up next to managed projects before being imported.
+#[derive(Debug)]
+pub struct ComposeUsecase<C> {
+    compose_client: Arc<C>,
+    root: Option<PathBuf>,
+}
+
+/// Only the `Arc` is cloned, so the compose client needn't be `Clone` itself.
+impl<C> Clone for ComposeUsecase<C> {
+    fn clone(&self) -> Self {
+        Self {
+            compose_client: Arc::clone(&self.compose_client),
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<C> ComposeUsecase<C>
+where
+    C: ComposeClient + Send + Sync,
+{
+    pub fn new<P: AsRef<Path>>(compose_client: Arc<C>, root: Option<P>) -> Self {
+        Self {
+            compose_client,
+            root: root.map(|root| root.as_ref().to_path_buf()),
+        }
+    }
+
+    /// Directories directly under the root that hold a compose file, sorted by name.
+    pub fn list_compose_projects(&self) -> Result<Vec<ComposeProject>, ComposeUsecaseError> {
+        let root = self
+            .root
+            .as_ref()
+            .ok_or(ComposeUsecaseError::NotConfigured)?;
+        let entries =
+            fs::read_dir(root).map_err(|e| ComposeUsecaseError::ListFailed(e.to_string()))?;
+
+        let mut projects = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| ComposeUsecaseError::ListFailed(e.to_string()))?
+                .path();
+            if !path.is_dir() || find_compose_file_name(&path).is_err() {
+                continue;
+            }
+
+            let states = self
+                .compose_client
+                .container_states(&path.display().to_string())
+                .map_err(|e| ComposeUsecaseError::ListFailed(e.to_string()))?;
+            projects.push(ComposeProject {
+                name: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: path.display().to_string(),
+                status: project_status_from_states(&states),
+            });
+        }
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(projects)
+    }
+}
@@ -0,0 +1,65 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::models::compose_file::{ComposeFile, ComposeFileError};
+
+/// Parsed compose files of the projects' checkouts, so status, preflight and rendering
+/// don't each parse the same YAML again. A file is still read on every lookup, and only
+/// parsed again once its SHA-256 changes, e.g. after a sync.
+#[derive(Debug, Clone, Default)]
+pub struct ComposeFileCache {
+    entries: Arc<Mutex<HashMap<PathBuf, CachedComposeFile>>>,
+}
+
+#[derive(Debug)]
+struct CachedComposeFile {
+    digest: Vec<u8>,
+    compose_file: ComposeFile,
+}
+
+impl ComposeFileCache {
+    pub fn load(&self, path: &Path) -> Result<ComposeFile, ComposeFileError> {
+        let source = fs::read_to_string(path)?;
+        let digest = Sha256::digest(source.as_bytes()).to_vec();
+
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(cached) = entries.get(path).filter(|cached| cached.digest == digest) {
+            return Ok(cached.compose_file.clone());
+        }
+
+        let compose_file = ComposeFile::parse(&source)?;
+        entries.insert(
+            path.to_path_buf(),
+            CachedComposeFile {
+                digest,
+                compose_file: compose_file.clone(),
+            },
+        );
+        Ok(compose_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn given_changed_file_when_load_then_return_new_contents() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("docker-compose.yml");
+        let cache = ComposeFileCache::default();
+        fs::write(&path, "services:\n  web:\n    image: nginx\n").unwrap();
+
+        let first = cache.load(&path).unwrap();
+        let cached = cache.load(&path).unwrap();
+        fs::write(&path, "services:\n  api:\n    image: nginx\n").unwrap();
+        let changed = cache.load(&path).unwrap();
+
+        assert_eq!(first, cached);
+        assert_eq!(changed.service_names(), vec!["api"]);
+    }
+}
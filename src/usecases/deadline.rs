@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+/// Point in time by which a request has to be answered. The git and docker commands the
+/// usecase runs for the request are killed once it passes, and no more are started, so a
+/// slow request stops doing work once the caller has given up on it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Deadline {
+    at: Option<Instant>,
+}
+
+impl Deadline {
+    /// No deadline; operations run to completion.
+    pub fn none() -> Self {
+        Self { at: None }
+    }
+
+    pub fn after(timeout: Duration) -> Self {
+        Self {
+            at: Some(Instant::now() + timeout),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// Parse a timeout such as `10`, `10s`, or `500ms`. Bare numbers are seconds.
+pub fn parse_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Some(millis) = value.strip_suffix("ms") {
+        return millis.trim().parse().ok().map(Duration::from_millis);
+    }
+
+    value
+        .strip_suffix('s')
+        .unwrap_or(value)
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn given_supported_units_when_parse_timeout_then_return_duration() {
+        assert_eq!(parse_timeout("10"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_timeout("10s"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_timeout("500ms"), Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn given_invalid_value_when_parse_timeout_then_return_none() {
+        assert_eq!(parse_timeout("soon"), None);
+        assert_eq!(parse_timeout("-1"), None);
+    }
+
+    #[test]
+    fn given_elapsed_deadline_when_is_expired_then_return_true() {
+        let deadline = Deadline::after(Duration::ZERO);
+
+        assert!(deadline.is_expired());
+        assert!(!Deadline::none().is_expired());
+    }
+}
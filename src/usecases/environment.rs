@@ -0,0 +1,103 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+use crate::models::project::ProjectFile;
+
+/// Compose reads variables to substitute into the compose file from this file in the
+/// project directory.
+pub const DOTENV_FILE: &str = ".env";
+
+/// Write the `.env` compose reads next to the compose file, from the project's
+/// `env_file` followed by its `environment`, so the latter win. A `.env` committed to the
+/// repository is replaced. Nothing is written for projects that set neither.
+pub fn write_env_file(repository_dir: &Path, project_file: &ProjectFile) -> Result<()> {
+    if project_file.environment.is_empty() && project_file.env_file.is_none() {
+        return Ok(());
+    }
+
+    let mut contents = match &project_file.env_file {
+        Some(path) => fs::read_to_string(repository_dir.join(path))?,
+        None => String::new(),
+    };
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    for (name, value) in &project_file.environment {
+        contents.push_str(&format!("{}={}\n", name, quote(value)));
+    }
+    fs::write(repository_dir.join(DOTENV_FILE), contents)?;
+    Ok(())
+}
+
+/// Compose takes single-quoted values literally. The rare value with a single quote is
+/// double-quoted instead, where compose still expands `${...}`.
+fn quote(value: &str) -> String {
+    match value.contains('\'') {
+        false => format!("'{}'", value),
+        true => format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+/// Whether `name` can be set from a `.env` file: letters, digits and `_`, not starting
+/// with a digit.
+pub fn is_valid_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    #[test]
+    fn given_env_file_and_environment_when_write_env_file_then_environment_comes_last() {
+        let repository_dir = TempDir::new().unwrap();
+        fs::write(
+            repository_dir.path().join("prod.env"),
+            "TAG=1.0\nREPLICAS=2",
+        )
+        .unwrap();
+        let project_file = ProjectFile {
+            environment: BTreeMap::from([
+                ("TAG".to_string(), "1.1".to_string()),
+                ("GREETING".to_string(), "it's me".to_string()),
+            ]),
+            env_file: Some("prod.env".to_string()),
+            ..Default::default()
+        };
+
+        write_env_file(repository_dir.path(), &project_file).unwrap();
+
+        let actual = fs::read_to_string(repository_dir.path().join(DOTENV_FILE)).unwrap();
+        assert_eq!(
+            actual,
+            "TAG=1.0\nREPLICAS=2\nGREETING=\"it's me\"\nTAG='1.1'\n"
+        );
+    }
+
+    #[test]
+    fn given_no_environment_when_write_env_file_then_leave_dotenv_alone() {
+        let repository_dir = TempDir::new().unwrap();
+        fs::write(repository_dir.path().join(DOTENV_FILE), "TAG=1.0\n").unwrap();
+
+        write_env_file(repository_dir.path(), &ProjectFile::default()).unwrap();
+
+        let actual = fs::read_to_string(repository_dir.path().join(DOTENV_FILE)).unwrap();
+        assert_eq!(actual, "TAG=1.0\n");
+    }
+
+    #[test]
+    fn given_variable_names_when_is_valid_variable_name_then_only_accept_identifiers() {
+        assert!(is_valid_variable_name("DB_HOST"));
+        assert!(is_valid_variable_name("_private"));
+        assert!(!is_valid_variable_name("1ST"));
+        assert!(!is_valid_variable_name("DB-HOST"));
+        assert!(!is_valid_variable_name(""));
+    }
+}
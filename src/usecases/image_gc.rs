@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+
+use crate::models::docker_compose::LocalImage;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::ProjectUsecase;
+
+/// Removes images no managed project uses any more, on every tick; see
+/// [`ProjectUsecase::collect_unused_images`].
+pub struct ImageCollector<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    project_usecase: ProjectUsecase<C, G>,
+    interval: Duration,
+    retention: Duration,
+}
+
+impl<C, G> ImageCollector<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    pub fn new(
+        project_usecase: ProjectUsecase<C, G>,
+        interval: Duration,
+        retention: Duration,
+    ) -> Self {
+        Self {
+            project_usecase,
+            interval,
+            retention,
+        }
+    }
+
+    /// Collect on a dedicated thread.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || loop {
+            self.tick();
+            thread::sleep(self.interval);
+        })
+    }
+
+    pub fn tick(&self) {
+        match self.project_usecase.collect_unused_images(self.retention) {
+            Ok(report) => {
+                for image in &report.removed {
+                    println!("Removed unused image {}", image.reference());
+                }
+                for failure in &report.failed {
+                    println!("Failed to remove unused image {}", failure);
+                }
+            }
+            Err(e) => println!("Failed to collect unused images: {}", e),
+        }
+    }
+}
+
+/// Images created before `cutoff` that are dangling, or that are tags of a repository
+/// some image in `referenced` comes from while no image there is that tag. Repositories
+/// referenced by digest or through a variable keep every tag, since which one is in use
+/// cannot be told; repositories nothing references are not gfc's to remove.
+pub fn unused_images(
+    images: &[LocalImage],
+    referenced: &[String],
+    cutoff: DateTime<Utc>,
+) -> Vec<LocalImage> {
+    let mut tags = HashSet::new();
+    let mut repositories = HashSet::new();
+    let mut pinned = HashSet::new();
+    for reference in referenced {
+        let (repository, tag) = split_reference(reference);
+        match tag.contains('$') || reference.contains('@') {
+            true => pinned.insert(repository.clone()),
+            false => tags.insert((repository.clone(), tag)),
+        };
+        repositories.insert(repository);
+    }
+
+    images
+        .iter()
+        .filter(|image| image.created_at < cutoff)
+        .filter(|image| match (&image.repository, &image.tag) {
+            (Some(repository), Some(tag)) => {
+                let repository = normalize_repository(repository);
+                repositories.contains(&repository)
+                    && !pinned.contains(&repository)
+                    && !tags.contains(&(repository, tag.clone()))
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// `reference` as repository and tag, so that `nginx`, `nginx:latest` and
+/// `docker.io/library/nginx:latest` compare equal.
+fn split_reference(reference: &str) -> (String, String) {
+    let reference = reference.split('@').next().unwrap_or(reference);
+    let name_start = reference.rfind('/').map_or(0, |slash| slash + 1);
+    let (repository, tag) = match reference[name_start..].rfind(':') {
+        Some(colon) => (
+            &reference[..name_start + colon],
+            &reference[name_start + colon + 1..],
+        ),
+        None => (reference, "latest"),
+    };
+    (normalize_repository(repository), tag.to_string())
+}
+
+fn normalize_repository(repository: &str) -> String {
+    repository
+        .strip_prefix("docker.io/library/")
+        .or_else(|| repository.strip_prefix("docker.io/"))
+        .unwrap_or(repository)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn image(id: &str, repository: Option<&str>, tag: Option<&str>, day: u32) -> LocalImage {
+        LocalImage {
+            id: id.to_string(),
+            repository: repository.map(str::to_string),
+            tag: tag.map(str::to_string),
+            created_at: Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+            size: "10MB".to_string(),
+        }
+    }
+
+    fn ids(images: Vec<LocalImage>) -> Vec<String> {
+        images.into_iter().map(|image| image.id).collect()
+    }
+
+    #[test]
+    fn given_old_tags_of_referenced_repository_when_selected_then_only_unreferenced_tags_are_unused(
+    ) {
+        let images = vec![
+            image("current", Some("nginx"), Some("1.27"), 1),
+            image("previous", Some("nginx"), Some("1.25"), 1),
+            image("dangling", None, None, 1),
+            image("unrelated", Some("postgres"), Some("16"), 1),
+        ];
+        let referenced = vec!["docker.io/library/nginx:1.27".to_string()];
+        let cutoff = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            ids(unused_images(&images, &referenced, cutoff)),
+            vec!["previous", "dangling"]
+        );
+    }
+
+    #[test]
+    fn given_images_newer_than_cutoff_when_selected_then_they_are_kept() {
+        let images = vec![
+            image("previous", Some("nginx"), Some("1.25"), 20),
+            image("dangling", None, None, 20),
+        ];
+        let referenced = vec!["nginx".to_string()];
+        let cutoff = Utc.with_ymd_and_hms(2024, 1, 10, 0, 0, 0).unwrap();
+
+        assert!(unused_images(&images, &referenced, cutoff).is_empty());
+    }
+
+    #[test]
+    fn given_repository_referenced_through_variable_when_selected_then_every_tag_is_kept() {
+        let images = vec![
+            image("a", Some("registry.local:5000/app"), Some("v1"), 1),
+            image("b", Some("registry.local:5000/app"), Some("v2"), 1),
+        ];
+        let referenced = vec!["registry.local:5000/app:${TAG}".to_string()];
+        let cutoff = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+
+        assert!(unused_images(&images, &referenced, cutoff).is_empty());
+    }
+
+    #[test]
+    fn given_registry_with_port_when_split_then_port_is_not_taken_for_tag() {
+        assert_eq!(
+            split_reference("registry.local:5000/app"),
+            ("registry.local:5000/app".to_string(), "latest".to_string())
+        );
+    }
+}
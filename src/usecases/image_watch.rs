@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use crate::repositories::blocking;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::ProjectUsecase;
+use crate::usecases::reconciler::wants_deployment;
+
+/// Keeps projects with `watch_images` on the newest push of their images' tags: on every
+/// tick, images whose registry digest was not pulled yet are pulled and the project is
+/// redeployed, as watchtower does for mutable tags such as `:latest`.
+///
+/// Projects the reconciler would leave alone are left alone here too, as is a project
+/// whose previous update is still running.
+pub struct ImageWatcher<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    project_usecase: ProjectUsecase<C, G>,
+    interval: Duration,
+    /// The last update job queued for each project.
+    updates: HashMap<String, String>,
+}
+
+impl<C, G> ImageWatcher<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    pub fn new(project_usecase: ProjectUsecase<C, G>, interval: Duration) -> Self {
+        Self {
+            project_usecase,
+            interval,
+            updates: HashMap::new(),
+        }
+    }
+
+    /// Watch on a dedicated thread. Must be called from within a Tokio runtime, which the
+    /// update jobs are submitted to.
+    pub fn spawn(mut self) -> thread::JoinHandle<()> {
+        let runtime = tokio::runtime::Handle::current();
+        thread::spawn(move || {
+            let _runtime = runtime.enter();
+            blocking::allow(|| loop {
+                self.tick();
+                thread::sleep(self.interval);
+            })
+        })
+    }
+
+    pub fn tick(&mut self) {
+        let project_files = match self.project_usecase.project_files() {
+            Ok(project_files) => project_files,
+            Err(e) => {
+                println!("Failed to list projects to watch: {}", e);
+                return;
+            }
+        };
+
+        for project_file in project_files {
+            if !project_file.watch_images
+                || self.update_in_progress(&project_file.name)
+                || !wants_deployment(&self.project_usecase, &project_file)
+            {
+                continue;
+            }
+
+            let images = match self.project_usecase.outdated_images(&project_file) {
+                Ok(images) if images.is_empty() => continue,
+                Ok(images) => images,
+                Err(e) => {
+                    println!("Failed to check images of {}: {}", project_file.name, e);
+                    continue;
+                }
+            };
+
+            match self
+                .project_usecase
+                .update_images(&project_file.name, images)
+            {
+                Ok(job) => {
+                    self.updates.insert(project_file.name, job.id);
+                }
+                Err(e) => println!("Failed to update images of {}: {}", project_file.name, e),
+            }
+        }
+    }
+
+    fn update_in_progress(&self, name: &str) -> bool {
+        self.updates
+            .get(name)
+            .and_then(|id| self.project_usecase.job(id).ok())
+            .is_some_and(|job| !job.status.is_finished())
+    }
+}
@@ -0,0 +1,517 @@
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::models::job::{Job, JobKind, JobQueue, JobStatus, QueueStats};
+use crate::repositories::blocking;
+#[cfg(feature = "sqlite-store")]
+use crate::repositories::job_store::JobStore;
+use crate::repositories::process::{self, Cancellation};
+
+const DEFAULT_MAX_CONCURRENT_JOBS: usize = 8;
+const DEFAULT_MAX_FINISHED_JOBS: usize = 500;
+
+type Work = Box<dyn FnOnce() -> Result<()> + Send>;
+type CancelHook = Box<dyn FnOnce() + Send>;
+
+/// Tracks background work so callers can poll for its outcome instead of it being
+/// fire-and-forget. Jobs live in memory; with a store, unfinished ones are also kept
+/// there, so those a crash or restart interrupted are known on the next start.
+/// See [`Self::interrupted`].
+#[derive(Debug, Clone)]
+pub struct JobManager {
+    /// Keyed by ID, which sorts in submission order.
+    jobs: Arc<Mutex<BTreeMap<String, Job>>>,
+    next_sequence: Arc<AtomicU64>,
+    queues: Arc<Mutex<BTreeMap<JobQueue, QueueState>>>,
+    /// Running jobs by ID. Guarded by `queues`, so a job is always either pending or here
+    /// until it finishes.
+    running: Arc<Mutex<HashMap<String, Cancellation>>>,
+    /// Jobs of one queue running at once; the rest wait in the queue without holding a
+    /// thread.
+    max_concurrent: usize,
+    /// Finished jobs beyond this many are forgotten, oldest first.
+    max_finished: usize,
+    #[cfg(feature = "sqlite-store")]
+    store: Option<JobStore>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Work not started yet, by job ID, oldest first, with what to do instead should it
+    /// be cancelled before it starts.
+    pending: VecDeque<(String, Work, Option<CancelHook>)>,
+    running: usize,
+    succeeded: u64,
+    failed: u64,
+    cancelled: u64,
+}
+
+/// How a job that ran turned out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl fmt::Debug for QueueState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueState")
+            .field("pending", &self.pending.len())
+            .field("running", &self.running)
+            .field("succeeded", &self.succeeded)
+            .field("failed", &self.failed)
+            .field("cancelled", &self.cancelled)
+            .finish()
+    }
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_JOBS, DEFAULT_MAX_FINISHED_JOBS)
+    }
+}
+
+impl JobManager {
+    pub fn new(max_concurrent: usize, max_finished: usize) -> Self {
+        Self {
+            jobs: Arc::default(),
+            next_sequence: Arc::default(),
+            queues: Arc::default(),
+            running: Arc::default(),
+            max_concurrent: max_concurrent.max(1),
+            max_finished,
+            #[cfg(feature = "sqlite-store")]
+            store: None,
+        }
+    }
+
+    #[cfg(feature = "sqlite-store")]
+    pub fn with_store(self, store: JobStore) -> Self {
+        Self {
+            store: Some(store),
+            ..self
+        }
+    }
+
+    /// Queue `work` and return the job tracking it. It runs on the blocking pool once its
+    /// queue has a free slot.
+    pub fn submit<F>(&self, kind: JobKind, project: &str, work: F) -> Job
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+    {
+        self.push(kind, project, Box::new(work), None)
+    }
+
+    /// Like [`Self::submit`], but `on_cancel` runs in place of `work` if the job is
+    /// cancelled while still queued, so whatever `work` would have recorded about how it
+    /// went isn't left unwritten.
+    pub fn submit_with_cancel_hook<F, C>(
+        &self,
+        kind: JobKind,
+        project: &str,
+        work: F,
+        on_cancel: C,
+    ) -> Job
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+        C: FnOnce() + Send + 'static,
+    {
+        self.push(kind, project, Box::new(work), Some(Box::new(on_cancel)))
+    }
+
+    pub fn get(&self, id: &str) -> Option<Job> {
+        self.lock().get(id).cloned()
+    }
+
+    /// Whether the project has a job that hasn't finished.
+    pub fn in_progress(&self, project: &str) -> bool {
+        self.lock()
+            .values()
+            .any(|job| job.project == project && !job.status.is_finished())
+    }
+
+    /// Jobs the store still holds as queued or running that this manager never ran: a
+    /// previous process stopped before they finished.
+    #[cfg(feature = "sqlite-store")]
+    pub fn interrupted(&self) -> Vec<Job> {
+        let Some(store) = &self.store else {
+            return vec![];
+        };
+        let stored = match store.list() {
+            Ok(stored) => stored,
+            Err(e) => {
+                println!("Failed to read unfinished jobs: {}", e);
+                return vec![];
+            }
+        };
+        let jobs = self.lock();
+        stored
+            .into_iter()
+            .filter(|job| !jobs.contains_key(&job.id))
+            .collect()
+    }
+
+    /// Without a store, no job outlives the process that ran it.
+    #[cfg(not(feature = "sqlite-store"))]
+    pub fn interrupted(&self) -> Vec<Job> {
+        vec![]
+    }
+
+    /// Mark an interrupted job failed for `reason`, and keep it with the others so it can
+    /// still be looked up by its ID.
+    pub fn fail_interrupted(&self, job: Job, reason: String) {
+        let job = Job {
+            status: JobStatus::Failed,
+            error: Some(reason),
+            updated_at: Utc::now(),
+            ..job
+        };
+        self.persist(&job);
+        let mut jobs = self.lock();
+        jobs.insert(job.id.clone(), job);
+        prune_finished(&mut jobs, self.max_finished);
+    }
+
+    /// Cancel a job that hasn't finished and return it. A queued job is dropped from its
+    /// queue and its cancel hook, if any, runs. A running job has its git or docker command
+    /// killed, after which it fails and is marked cancelled, so it may still show as running
+    /// for a moment.
+    pub fn cancel(&self, id: &str) -> Option<Job> {
+        let job = self.get(id)?;
+        if job.status.is_finished() {
+            return Some(job);
+        }
+        let on_cancel = {
+            let mut queues = self.lock_queues();
+            let state = queues.entry(job.kind.queue()).or_default();
+            match state
+                .pending
+                .iter()
+                .position(|(pending, _, _)| pending == id)
+            {
+                Some(position) => {
+                    let on_cancel = state.pending.remove(position).and_then(|(_, _, hook)| hook);
+                    state.cancelled += 1;
+                    self.update(id, JobStatus::Cancelled, None);
+                    on_cancel
+                }
+                None => {
+                    if let Some(cancellation) = self.lock_running().get(id) {
+                        cancellation.cancel();
+                    }
+                    None
+                }
+            }
+        };
+        if let Some(on_cancel) = on_cancel {
+            on_cancel();
+        }
+        self.get(id)
+    }
+
+    pub fn queue_stats(&self) -> Vec<QueueStats> {
+        let queues = self.lock_queues();
+        JobQueue::ALL
+            .iter()
+            .map(|queue| {
+                let state = queues.get(queue);
+                QueueStats {
+                    queue: *queue,
+                    max_parallel: self.max_concurrent,
+                    queued: state.map_or(0, |state| state.pending.len()),
+                    running: state.map_or(0, |state| state.running),
+                    succeeded: state.map_or(0, |state| state.succeeded),
+                    failed: state.map_or(0, |state| state.failed),
+                    cancelled: state.map_or(0, |state| state.cancelled),
+                }
+            })
+            .collect()
+    }
+
+    fn push(&self, kind: JobKind, project: &str, work: Work, on_cancel: Option<CancelHook>) -> Job {
+        let job = self.enqueue(kind, project);
+        self.lock_queues()
+            .entry(kind.queue())
+            .or_default()
+            .pending
+            .push_back((job.id.clone(), work, on_cancel));
+        self.dispatch(kind.queue());
+        job
+    }
+
+    fn enqueue(&self, kind: JobKind, project: &str) -> Job {
+        let now = Utc::now();
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        let job = Job {
+            id: format!("{:012x}{:06x}", now.timestamp_millis(), sequence),
+            kind,
+            project: project.to_string(),
+            status: JobStatus::Queued,
+            error: None,
+            created_at: now,
+            updated_at: now,
+            created_ago: None,
+            updated_ago: None,
+        };
+
+        self.persist(&job);
+        let mut jobs = self.lock();
+        jobs.insert(job.id.clone(), job.clone());
+        prune_finished(&mut jobs, self.max_finished);
+        job
+    }
+
+    /// Start waiting work of `queue` while it has free slots.
+    fn dispatch(&self, queue: JobQueue) {
+        let mut queues = self.lock_queues();
+        let state = queues.entry(queue).or_default();
+        while state.running < self.max_concurrent {
+            let Some((id, work, _)) = state.pending.pop_front() else {
+                break;
+            };
+            state.running += 1;
+            let cancellation = Cancellation::default();
+            self.lock_running().insert(id.clone(), cancellation.clone());
+            let manager = self.clone();
+            blocking::spawn(move || {
+                let outcome = manager.execute(&id, &cancellation, work);
+                manager.finish(queue, &id, outcome);
+            });
+        }
+    }
+
+    fn finish(&self, queue: JobQueue, id: &str, outcome: Outcome) {
+        {
+            let mut queues = self.lock_queues();
+            self.lock_running().remove(id);
+            let state = queues.entry(queue).or_default();
+            state.running -= 1;
+            match outcome {
+                Outcome::Succeeded => state.succeeded += 1,
+                Outcome::Failed => state.failed += 1,
+                Outcome::Cancelled => state.cancelled += 1,
+            }
+        }
+        self.dispatch(queue);
+    }
+
+    /// Run `work` and record how it went. Work that fails after being cancelled counts
+    /// as cancelled; work that got done anyway still succeeds.
+    fn execute<F>(&self, id: &str, cancellation: &Cancellation, work: F) -> Outcome
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        self.update(id, JobStatus::Running, None);
+        match process::with_cancellation(cancellation, work) {
+            Ok(()) => {
+                self.update(id, JobStatus::Succeeded, None);
+                Outcome::Succeeded
+            }
+            Err(e) if cancellation.is_cancelled() => {
+                self.update(id, JobStatus::Cancelled, Some(e.to_string()));
+                Outcome::Cancelled
+            }
+            Err(e) => {
+                self.update(id, JobStatus::Failed, Some(e.to_string()));
+                Outcome::Failed
+            }
+        }
+    }
+
+    fn update(&self, id: &str, status: JobStatus, error: Option<String>) {
+        let updated = self.lock().get_mut(id).map(|job| {
+            job.status = status;
+            job.error = error;
+            job.updated_at = Utc::now();
+            job.clone()
+        });
+        if let Some(job) = updated {
+            self.persist(&job);
+        }
+    }
+
+    /// Keep the store in step with `job`. A store that can't be written only costs the
+    /// job its recovery after a crash, so the job goes on regardless.
+    #[cfg(feature = "sqlite-store")]
+    fn persist(&self, job: &Job) {
+        if let Some(Err(e)) = self.store.as_ref().map(|store| store.save(job)) {
+            println!("Failed to store job {}: {}", job.id, e);
+        }
+    }
+
+    #[cfg(not(feature = "sqlite-store"))]
+    fn persist(&self, _job: &Job) {}
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Job>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_queues(&self) -> MutexGuard<'_, BTreeMap<JobQueue, QueueState>> {
+        self.queues.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_running(&self) -> MutexGuard<'_, HashMap<String, Cancellation>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn prune_finished(jobs: &mut BTreeMap<String, Job>, max_finished: usize) {
+    let finished = jobs
+        .values()
+        .filter(|job| job.status.is_finished())
+        .map(|job| job.id.clone())
+        .collect::<Vec<_>>();
+    let excess = finished.len().saturating_sub(max_finished);
+    for id in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::process::CommandTimeout;
+    use anyhow::anyhow;
+
+    #[test]
+    fn given_enqueued_job_when_work_succeeds_then_job_is_succeeded() {
+        let manager = JobManager::default();
+        let job = manager.enqueue(JobKind::CreateProject, "demo");
+
+        manager.execute(&job.id, &Cancellation::default(), || Ok(()));
+
+        let actual = manager.get(&job.id).unwrap();
+        assert_eq!(actual.status, JobStatus::Succeeded);
+        assert_eq!(actual.error, None);
+    }
+
+    #[test]
+    fn given_enqueued_job_when_work_fails_then_job_is_failed_with_error() {
+        let manager = JobManager::default();
+        let job = manager.enqueue(JobKind::SyncProject, "demo");
+
+        manager.execute(&job.id, &Cancellation::default(), || {
+            Err(anyhow!("clone failed"))
+        });
+
+        let actual = manager.get(&job.id).unwrap();
+        assert_eq!(actual.status, JobStatus::Failed);
+        assert_eq!(actual.error, Some("clone failed".to_string()));
+    }
+
+    #[test]
+    fn given_finished_jobs_over_limit_when_enqueued_then_oldest_are_forgotten() {
+        let manager = JobManager::new(1, 1);
+        let first = manager.enqueue(JobKind::SyncProject, "demo");
+        manager.execute(&first.id, &Cancellation::default(), || Ok(()));
+        let second = manager.enqueue(JobKind::SyncProject, "demo");
+        manager.execute(&second.id, &Cancellation::default(), || Ok(()));
+
+        manager.enqueue(JobKind::SyncProject, "demo");
+
+        assert_eq!(manager.get(&first.id), None);
+        assert!(manager.get(&second.id).is_some());
+    }
+
+    #[tokio::test]
+    async fn given_more_deployments_than_slots_when_submitted_then_the_rest_wait_queued() {
+        let manager = JobManager::new(1, 10);
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let blocking = manager.submit(JobKind::CreateProject, "first", move || {
+            released.recv().unwrap();
+            Ok(())
+        });
+        let waiting = manager.submit(JobKind::CreateProject, "second", || Ok(()));
+        manager.submit(JobKind::DeleteProject, "old", || Ok(()));
+        // A job shows as succeeded just before its queue counts it.
+        while manager.queue_stats()[1].succeeded != 1 {
+            tokio::task::yield_now().await;
+        }
+
+        let busy = manager.queue_stats();
+        release.send(()).unwrap();
+        while manager.get(&waiting.id).unwrap().status != JobStatus::Succeeded {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(busy[0].queue, JobQueue::Deployments);
+        assert_eq!((busy[0].running, busy[0].queued), (1, 1));
+        assert_eq!(busy[1].succeeded, 1);
+        assert_eq!(
+            manager.get(&blocking.id).unwrap().status,
+            JobStatus::Succeeded
+        );
+        assert_eq!(manager.queue_stats()[0].queued, 0);
+    }
+
+    #[tokio::test]
+    async fn given_queued_and_running_jobs_when_cancelled_then_both_end_cancelled() {
+        let manager = JobManager::new(1, 10);
+        let running = manager.submit(JobKind::CreateProject, "first", || {
+            std::process::Command::new("sleep")
+                .arg("30")
+                .status_within(std::time::Duration::from_secs(60))?;
+            Ok(())
+        });
+        let queued = manager.submit(JobKind::SyncProject, "second", || Ok(()));
+        while manager.get(&running.id).unwrap().status != JobStatus::Running {
+            tokio::task::yield_now().await;
+        }
+
+        let dropped = manager.cancel(&queued.id).unwrap();
+        manager.cancel(&running.id);
+        while manager.get(&running.id).unwrap().status == JobStatus::Running {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(dropped.status, JobStatus::Cancelled);
+        assert_eq!(
+            manager.get(&running.id).unwrap().status,
+            JobStatus::Cancelled
+        );
+        let stats = &manager.queue_stats()[0];
+        assert_eq!((stats.queued, stats.running, stats.cancelled), (0, 0, 2));
+    }
+
+    #[cfg(feature = "sqlite-store")]
+    #[test]
+    fn given_jobs_left_unfinished_in_store_when_reopened_then_they_are_interrupted() {
+        let root = tempfile::TempDir::new().unwrap();
+        let store = JobStore::new(root.path().join("history.db"));
+        let crashed = JobManager::default().with_store(store.clone());
+        let running = crashed.enqueue(JobKind::SyncProject, "demo");
+        crashed.update(&running.id, JobStatus::Running, None);
+        let finished = crashed.enqueue(JobKind::SyncProject, "demo");
+        crashed.execute(&finished.id, &Cancellation::default(), || Ok(()));
+
+        let restarted = JobManager::default().with_store(store.clone());
+        let interrupted = restarted.interrupted();
+        restarted.fail_interrupted(interrupted[0].clone(), "interrupted".to_string());
+
+        assert_eq!(interrupted.len(), 1);
+        assert_eq!(interrupted[0].id, running.id);
+        assert_eq!(interrupted[0].status, JobStatus::Running);
+        assert_eq!(
+            restarted.get(&running.id).unwrap().status,
+            JobStatus::Failed
+        );
+        assert!(restarted.interrupted().is_empty());
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn given_two_jobs_when_enqueued_then_ids_sort_in_submission_order() {
+        let manager = JobManager::default();
+
+        let first = manager.enqueue(JobKind::SyncProject, "demo");
+        let second = manager.enqueue(JobKind::SyncProject, "demo");
+
+        assert!(first.id < second.id);
+        assert_eq!(first.status, JobStatus::Queued);
+    }
+}
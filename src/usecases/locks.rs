@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// One lease per project, so operations that change a project run one after another
+/// while other projects carry on in parallel. A lease is given back when dropped.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectLocks {
+    /// The operation holding each leased project.
+    held: Arc<(Mutex<HashMap<String, &'static str>>, Condvar)>,
+}
+
+#[derive(Debug)]
+pub struct ProjectLease {
+    locks: ProjectLocks,
+    name: String,
+}
+
+impl ProjectLocks {
+    /// Wait for the project's lease, for background jobs that may take their turn.
+    pub fn lock(&self, name: &str, operation: &'static str) -> ProjectLease {
+        let (_, released) = &*self.held;
+        let mut held = self.lock_held();
+        while held.contains_key(name) {
+            held = released.wait(held).unwrap_or_else(|e| e.into_inner());
+        }
+        self.take(&mut held, name, operation)
+    }
+
+    /// Take the project's lease if it is free, or return the operation holding it. For
+    /// requests, which shouldn't wait out a deployment.
+    pub fn try_lock(
+        &self,
+        name: &str,
+        operation: &'static str,
+    ) -> Result<ProjectLease, &'static str> {
+        let mut held = self.lock_held();
+        match held.get(name) {
+            Some(holder) => Err(*holder),
+            None => Ok(self.take(&mut held, name, operation)),
+        }
+    }
+
+    fn take(
+        &self,
+        held: &mut HashMap<String, &'static str>,
+        name: &str,
+        operation: &'static str,
+    ) -> ProjectLease {
+        held.insert(name.to_string(), operation);
+        ProjectLease {
+            locks: self.clone(),
+            name: name.to_string(),
+        }
+    }
+
+    fn lock_held(&self) -> MutexGuard<'_, HashMap<String, &'static str>> {
+        self.held.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for ProjectLease {
+    fn drop(&mut self) {
+        self.locks.lock_held().remove(&self.name);
+        self.locks.held.1.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn given_leased_project_when_try_lock_then_return_holder_until_released() {
+        let locks = ProjectLocks::default();
+        let lease = locks.lock("app", "deployment");
+
+        let busy = locks.try_lock("app", "pause").map(|_| ());
+        let other = locks.try_lock("db", "pause").map(|_| ());
+        drop(lease);
+        let released = locks.try_lock("app", "pause").map(|_| ());
+
+        assert_eq!(busy, Err("deployment"));
+        assert_eq!(other, Ok(()));
+        assert_eq!(released, Ok(()));
+    }
+
+    #[test]
+    fn given_leased_project_when_lock_then_wait_for_release() {
+        let locks = ProjectLocks::default();
+        let lease = locks.lock("app", "deployment");
+        let (acquired, acquisitions) = mpsc::channel();
+
+        let waiter = {
+            let locks = locks.clone();
+            thread::spawn(move || {
+                let _lease = locks.lock("app", "deletion");
+                acquired.send(()).unwrap();
+            })
+        };
+
+        assert!(acquisitions
+            .recv_timeout(Duration::from_millis(50))
+            .is_err());
+        drop(lease);
+        acquisitions.recv_timeout(Duration::from_secs(5)).unwrap();
+        waiter.join().unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::models::job::QueueStats;
+
+const METRIC_NAME: &str = "gfc_http_request_duration_seconds";
+const QUEUE_JOBS_METRIC: &str = "gfc_job_queue_jobs";
+const QUEUE_SLOTS_METRIC: &str = "gfc_job_queue_max_parallel";
+const QUEUE_FINISHED_METRIC: &str = "gfc_job_queue_finished";
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RouteKey {
+    pub method: String,
+    /// The matched route template, e.g. `/projects/{name}`, never the raw path.
+    pub route: String,
+    pub status: u16,
+}
+
+/// The most recent traced request that landed in a bucket.
+#[derive(Debug, Clone, PartialEq)]
+struct Exemplar {
+    trace_id: String,
+    seconds: f64,
+    timestamp: f64,
+}
+
+#[derive(Debug, Clone)]
+struct LatencyHistogram {
+    /// One count per bucket plus a final `+Inf` bucket, not cumulative.
+    counts: Vec<u64>,
+    exemplars: Vec<Option<Exemplar>>,
+    sum: f64,
+    count: u64,
+}
+
+/// Per-route request latency histograms, rendered in the OpenMetrics text format so
+/// each bucket can carry a trace exemplar.
+#[derive(Debug, Clone, Default)]
+pub struct RequestMetrics {
+    routes: Arc<Mutex<BTreeMap<RouteKey, LatencyHistogram>>>,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; LATENCY_BUCKETS.len() + 1],
+            exemplars: vec![None; LATENCY_BUCKETS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, seconds: f64, trace_id: Option<&str>, timestamp: f64) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+
+        if let Some(trace_id) = trace_id {
+            self.exemplars[bucket] = Some(Exemplar {
+                trace_id: trace_id.to_string(),
+                seconds,
+                timestamp,
+            });
+        }
+    }
+
+    fn render(&self, labels: &str, output: &mut String) {
+        let bounds = LATENCY_BUCKETS
+            .iter()
+            .map(|bound| bound.to_string())
+            .chain(["+Inf".to_string()]);
+        let mut cumulative = 0;
+        for ((bound, count), exemplar) in bounds.zip(&self.counts).zip(&self.exemplars) {
+            cumulative += count;
+            let _ = write!(
+                output,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                METRIC_NAME, labels, bound, cumulative
+            );
+            if let Some(exemplar) = exemplar {
+                let _ = write!(
+                    output,
+                    " # {{trace_id=\"{}\"}} {} {}",
+                    exemplar.trace_id, exemplar.seconds, exemplar.timestamp
+                );
+            }
+            output.push('\n');
+        }
+        let _ = writeln!(output, "{}_sum{{{}}} {}", METRIC_NAME, labels, self.sum);
+        let _ = writeln!(output, "{}_count{{{}}} {}", METRIC_NAME, labels, self.count);
+    }
+}
+
+impl RequestMetrics {
+    pub fn observe(&self, key: RouteKey, latency: Duration, trace_id: Option<&str>) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        routes
+            .entry(key)
+            .or_default()
+            .observe(latency.as_secs_f64(), trace_id, timestamp);
+    }
+
+    /// Render the request histograms, followed by the state of each job queue.
+    pub fn render(&self, queues: &[QueueStats]) -> String {
+        let routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let mut output = String::new();
+        let _ = writeln!(output, "# TYPE {} histogram", METRIC_NAME);
+        let _ = writeln!(output, "# UNIT {} seconds", METRIC_NAME);
+        let _ = writeln!(
+            output,
+            "# HELP {} HTTP request latency by route.",
+            METRIC_NAME
+        );
+        for (key, histogram) in routes.iter() {
+            let labels = format!(
+                "method=\"{}\",route=\"{}\",status=\"{}\"",
+                escape_label(&key.method),
+                escape_label(&key.route),
+                key.status
+            );
+            histogram.render(&labels, &mut output);
+        }
+        render_queues(queues, &mut output);
+        output.push_str("# EOF\n");
+        output
+    }
+}
+
+fn render_queues(queues: &[QueueStats], output: &mut String) {
+    let _ = writeln!(output, "# TYPE {} gauge", QUEUE_JOBS_METRIC);
+    let _ = writeln!(
+        output,
+        "# HELP {} Jobs waiting for or holding a slot, by queue.",
+        QUEUE_JOBS_METRIC
+    );
+    for stats in queues {
+        let queue = stats.queue.as_str();
+        for (state, count) in [("queued", stats.queued), ("running", stats.running)] {
+            let _ = writeln!(
+                output,
+                "{}{{queue=\"{}\",state=\"{}\"}} {}",
+                QUEUE_JOBS_METRIC, queue, state, count
+            );
+        }
+    }
+
+    let _ = writeln!(output, "# TYPE {} gauge", QUEUE_SLOTS_METRIC);
+    let _ = writeln!(
+        output,
+        "# HELP {} Jobs each queue runs at once.",
+        QUEUE_SLOTS_METRIC
+    );
+    for stats in queues {
+        let _ = writeln!(
+            output,
+            "{}{{queue=\"{}\"}} {}",
+            QUEUE_SLOTS_METRIC,
+            stats.queue.as_str(),
+            stats.max_parallel
+        );
+    }
+
+    let _ = writeln!(output, "# TYPE {} counter", QUEUE_FINISHED_METRIC);
+    let _ = writeln!(
+        output,
+        "# HELP {} Jobs finished since startup, by queue and outcome.",
+        QUEUE_FINISHED_METRIC
+    );
+    for stats in queues {
+        let queue = stats.queue.as_str();
+        for (outcome, count) in [
+            ("succeeded", stats.succeeded),
+            ("failed", stats.failed),
+            ("cancelled", stats.cancelled),
+        ] {
+            let _ = writeln!(
+                output,
+                "{}_total{{queue=\"{}\",outcome=\"{}\"}} {}",
+                QUEUE_FINISHED_METRIC, queue, outcome, count
+            );
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::job::JobQueue;
+
+    fn route_key() -> RouteKey {
+        RouteKey {
+            method: "GET".to_string(),
+            route: "/projects/{name}/status".to_string(),
+            status: 200,
+        }
+    }
+
+    #[test]
+    fn given_traced_requests_when_render_then_buckets_are_cumulative_with_latest_exemplar() {
+        let metrics = RequestMetrics::default();
+
+        metrics.observe(route_key(), Duration::from_millis(3), None);
+        metrics.observe(route_key(), Duration::from_millis(4), Some("aaaa"));
+        metrics.observe(route_key(), Duration::from_secs(60), Some("bbbb"));
+        let actual = metrics.render(&[]);
+
+        let labels = r#"method="GET",route="/projects/{name}/status",status="200""#;
+        assert!(actual.contains(&format!(
+            "{}_bucket{{{},le=\"0.005\"}} 2 # {{trace_id=\"aaaa\"}} 0.004 ",
+            METRIC_NAME, labels
+        )));
+        assert!(actual.contains(&format!(
+            "{}_bucket{{{},le=\"30\"}} 2\n",
+            METRIC_NAME, labels
+        )));
+        assert!(actual.contains(&format!(
+            "{}_bucket{{{},le=\"+Inf\"}} 3 # {{trace_id=\"bbbb\"}} 60 ",
+            METRIC_NAME, labels
+        )));
+        assert!(actual.contains(&format!("{}_count{{{}}} 3\n", METRIC_NAME, labels)));
+        assert!(actual.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn given_busy_queue_when_render_then_report_its_jobs_and_outcomes() {
+        let metrics = RequestMetrics::default();
+        let queues = [QueueStats {
+            queue: JobQueue::Deployments,
+            max_parallel: 4,
+            queued: 3,
+            running: 4,
+            succeeded: 10,
+            failed: 2,
+            cancelled: 1,
+        }];
+
+        let actual = metrics.render(&queues);
+
+        assert!(actual.contains("gfc_job_queue_jobs{queue=\"deployments\",state=\"queued\"} 3\n"));
+        assert!(actual.contains("gfc_job_queue_max_parallel{queue=\"deployments\"} 4\n"));
+        assert!(actual.contains(
+            "gfc_job_queue_finished_total{queue=\"deployments\",outcome=\"failed\"} 2\n"
+        ));
+        assert!(actual.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn given_no_requests_when_render_then_return_only_metadata() {
+        let metrics = RequestMetrics::default();
+
+        let actual = metrics.render(&[]);
+
+        assert!(!actual.contains("_bucket"));
+        assert!(actual.ends_with("# EOF\n"));
+    }
+}
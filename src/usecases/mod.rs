@@ -1 +1,27 @@
+pub mod compose;
+pub mod compose_cache;
+pub mod deadline;
+pub mod environment;
+pub mod image_gc;
+pub mod image_watch;
+pub mod job;
+pub mod locks;
+#[cfg(feature = "telemetry")]
+pub mod metrics;
+pub mod portainer;
+pub mod preflight;
+pub mod preview;
 pub mod project;
+pub mod provenance;
+pub mod reconciler;
+pub mod retry;
+pub mod schedule;
+pub mod secrets;
+pub mod shared_network;
+pub mod standby;
+pub mod subnets;
+pub mod system;
+pub mod tenancy;
+pub mod usage;
+pub mod validation;
+pub mod webhook;
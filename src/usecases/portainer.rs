@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use crate::models::docker_compose::ComposeStack;
+
+/// Where Portainer keeps its data inside its own container. Stack files live under
+/// `compose/<stack id>/`, and compose records their paths as seen from in there.
+const PORTAINER_DATA_MOUNT: &str = "/data";
+
+/// A stack Portainer deployed, located on this host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortainerStack {
+    pub name: String,
+    /// The stack's directory, holding either the files from Portainer's web editor or a
+    /// git checkout.
+    pub dir: PathBuf,
+    /// The compose file, relative to `dir`.
+    pub compose_path: String,
+}
+
+/// The stacks among `stacks` that Portainer deployed, with their files looked up under
+/// `data_dir`, the host path of Portainer's data volume.
+pub fn portainer_stacks(stacks: &[ComposeStack], data_dir: &Path) -> Vec<PortainerStack> {
+    let compose_root = Path::new(PORTAINER_DATA_MOUNT).join("compose");
+
+    stacks
+        .iter()
+        .filter_map(|stack| {
+            let config_file = Path::new(stack.config_files.first()?);
+            let mut components = config_file.strip_prefix(&compose_root).ok()?.components();
+            let stack_id = components.next()?.as_os_str();
+            let compose_path = components.as_path().to_str()?.to_string();
+            if compose_path.is_empty() {
+                return None;
+            }
+
+            Some(PortainerStack {
+                name: stack.name.clone(),
+                dir: data_dir.join("compose").join(stack_id),
+                compose_path,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_stack(name: &str, config_file: &str) -> ComposeStack {
+        ComposeStack {
+            name: name.to_string(),
+            status: "running(1)".to_string(),
+            config_files: vec![config_file.to_string()],
+        }
+    }
+
+    #[test]
+    fn given_portainer_and_other_stacks_when_portainer_stacks_then_map_portainer_ones_to_data_dir()
+    {
+        let stacks = vec![
+            make_stack("homepage", "/data/compose/3/docker-compose.yml"),
+            make_stack("media", "/data/compose/7/deploy/compose.yaml"),
+            make_stack("manual", "/home/me/manual/docker-compose.yml"),
+        ];
+
+        let actual = portainer_stacks(&stacks, Path::new("/var/lib/portainer"));
+
+        assert_eq!(
+            actual,
+            vec![
+                PortainerStack {
+                    name: "homepage".to_string(),
+                    dir: PathBuf::from("/var/lib/portainer/compose/3"),
+                    compose_path: "docker-compose.yml".to_string(),
+                },
+                PortainerStack {
+                    name: "media".to_string(),
+                    dir: PathBuf::from("/var/lib/portainer/compose/7"),
+                    compose_path: "deploy/compose.yaml".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::models::compose_file::{ComposeFile, PublishedPort};
+use crate::models::device::{DeviceCount, Gpu};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightProblem {
+    ProjectNameTaken(String),
+    ContainerNameTaken {
+        container_name: String,
+        project: String,
+    },
+    PortTaken {
+        port: PublishedPort,
+        project: String,
+    },
+    GpuTaken {
+        device_id: String,
+        project: String,
+    },
+    GpusExhausted {
+        requested: usize,
+        available: usize,
+    },
+}
+
+impl fmt::Display for PreflightProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreflightProblem::ProjectNameTaken(name) => {
+                write!(f, "project name '{}' is already in use", name)
+            }
+            PreflightProblem::ContainerNameTaken {
+                container_name,
+                project,
+            } => write!(
+                f,
+                "container name '{}' is already used by project '{}'",
+                container_name, project
+            ),
+            PreflightProblem::PortTaken { port, project } => write!(
+                f,
+                "port {}/{} is already published by project '{}'",
+                port.port, port.protocol, project
+            ),
+            PreflightProblem::GpuTaken { device_id, project } => write!(
+                f,
+                "GPU '{}' is already reserved by project '{}'",
+                device_id, project
+            ),
+            PreflightProblem::GpusExhausted {
+                requested,
+                available,
+            } => write!(
+                f,
+                "{} GPUs requested, but only {} are not reserved by other projects",
+                requested, available
+            ),
+        }
+    }
+}
+
+/// Every problem found by [`preflight`], reported together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightReport(pub Vec<PreflightProblem>);
+
+impl fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problems = self.0.iter().map(ToString::to_string).collect::<Vec<_>>();
+        write!(f, "{}", problems.join("; "))
+    }
+}
+
+/// A managed project as seen by the preflight checks. The compose file is `None` when
+/// the project has not been checked out yet.
+pub struct ExistingProject {
+    pub name: String,
+    pub compose_file: Option<ComposeFile>,
+}
+
+/// Cheap collision checks for a project about to be created, against the projects already
+/// managed. Container names, ports and GPUs can only be compared when the candidate's
+/// compose file is already on disk. GPU counts are only checked against `gpus`, the host's
+/// inventory, when it is known; without it, only explicit device ids are compared.
+pub fn preflight(
+    name: &str,
+    compose_file: Option<&ComposeFile>,
+    existing: &[ExistingProject],
+    gpus: &[Gpu],
+) -> Vec<PreflightProblem> {
+    let mut problems = vec![];
+    if existing.iter().any(|project| project.name == name) {
+        problems.push(PreflightProblem::ProjectNameTaken(name.to_string()));
+    }
+
+    let Some(compose_file) = compose_file else {
+        return problems;
+    };
+
+    let mut container_owners = HashMap::new();
+    let mut port_owners = HashMap::new();
+    for project in existing.iter().filter(|project| project.name != name) {
+        let Some(existing_compose) = &project.compose_file else {
+            continue;
+        };
+        for container_name in existing_compose.container_names() {
+            container_owners.insert(container_name, project.name.clone());
+        }
+        for port in existing_compose.published_ports() {
+            port_owners.insert(port, project.name.clone());
+        }
+    }
+
+    for container_name in compose_file.container_names() {
+        if let Some(project) = container_owners.get(&container_name) {
+            problems.push(PreflightProblem::ContainerNameTaken {
+                container_name,
+                project: project.clone(),
+            });
+        }
+    }
+    for port in compose_file.published_ports() {
+        if let Some(project) = port_owners.get(&port) {
+            problems.push(PreflightProblem::PortTaken {
+                port,
+                project: project.clone(),
+            });
+        }
+    }
+    problems.extend(gpu_problems(name, compose_file, existing, gpus));
+
+    problems
+}
+
+/// GPUs a compose file reserves, device ids resolved to UUIDs where the inventory knows
+/// them, so `0` and `GPU-...` name the same device.
+#[derive(Debug, Default)]
+struct GpuDemand {
+    device_ids: BTreeSet<String>,
+    count: usize,
+    all: bool,
+}
+
+impl GpuDemand {
+    fn of(compose_file: &ComposeFile, gpus: &[Gpu]) -> Self {
+        let mut demand = GpuDemand::default();
+        for reservation in compose_file.device_reservations() {
+            if !reservation.is_gpu() {
+                continue;
+            }
+            match reservation.count {
+                Some(DeviceCount::All) => demand.all = true,
+                Some(DeviceCount::Exactly(count)) => demand.count += count as usize,
+                None => demand
+                    .device_ids
+                    .extend(reservation.device_ids.iter().map(|id| gpu_uuid(gpus, id))),
+            }
+        }
+        demand
+    }
+
+    fn is_empty(&self) -> bool {
+        self.device_ids.is_empty() && self.count == 0 && !self.all
+    }
+
+    fn total(&self, gpus: &[Gpu]) -> usize {
+        match self.all {
+            true => gpus.len(),
+            false => self.device_ids.len() + self.count,
+        }
+    }
+}
+
+fn gpu_uuid(gpus: &[Gpu], id: &str) -> String {
+    gpus.iter()
+        .find(|gpu| gpu.index == id || gpu.uuid == id)
+        .map_or(id.to_string(), |gpu| gpu.uuid.clone())
+}
+
+fn gpu_problems(
+    name: &str,
+    compose_file: &ComposeFile,
+    existing: &[ExistingProject],
+    gpus: &[Gpu],
+) -> Vec<PreflightProblem> {
+    let demand = GpuDemand::of(compose_file, gpus);
+    if demand.is_empty() {
+        return vec![];
+    }
+
+    let mut problems = vec![];
+    let mut reserved = 0;
+    for project in existing.iter().filter(|project| project.name != name) {
+        let Some(existing_compose) = &project.compose_file else {
+            continue;
+        };
+        let existing_demand = GpuDemand::of(existing_compose, gpus);
+        reserved += existing_demand.total(gpus);
+        for device_id in demand.device_ids.intersection(&existing_demand.device_ids) {
+            problems.push(PreflightProblem::GpuTaken {
+                device_id: device_id.clone(),
+                project: project.name.clone(),
+            });
+        }
+    }
+
+    let requested = demand.total(gpus);
+    let available = gpus.len().saturating_sub(reserved);
+    if !gpus.is_empty() && problems.is_empty() && requested > available {
+        problems.push(PreflightProblem::GpusExhausted {
+            requested,
+            available,
+        });
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_project(name: &str, compose: &str) -> ExistingProject {
+        ExistingProject {
+            name: name.to_string(),
+            compose_file: Some(ComposeFile::parse(compose).unwrap()),
+        }
+    }
+
+    #[test]
+    fn given_name_container_and_port_collisions_when_preflight_then_report_all_problems() {
+        let existing = vec![
+            make_project(
+                "app",
+                "services:\n  web:\n    image: nginx\n    container_name: web\n",
+            ),
+            make_project(
+                "proxy",
+                "services:\n  proxy:\n    image: traefik\n    ports:\n      - \"80:80\"\n",
+            ),
+        ];
+        let candidate = ComposeFile::parse(
+            "services:\n  web:\n    image: nginx\n    container_name: web\n    ports:\n      - \"80:8080\"\n",
+        )
+        .unwrap();
+
+        let actual = preflight("app", Some(&candidate), &existing, &[]);
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(
+            actual[0],
+            PreflightProblem::ProjectNameTaken("app".to_string())
+        );
+        assert!(matches!(
+            &actual[1],
+            PreflightProblem::PortTaken { port, project } if port.port == 80 && project == "proxy"
+        ));
+    }
+
+    #[test]
+    fn given_new_name_without_compose_file_when_preflight_then_return_no_problems() {
+        let existing = vec![make_project("app", "services:\n  web:\n    image: nginx\n")];
+
+        let actual = preflight("other", None, &existing, &[]);
+
+        assert!(actual.is_empty());
+    }
+
+    fn gpu_compose(device: &str) -> String {
+        format!(
+            "services:\n  train:\n    image: pytorch\n    deploy:\n      resources:\n        reservations:\n          devices:\n            - {{ {}, capabilities: [gpu] }}\n",
+            device
+        )
+    }
+
+    fn gpu(index: &str) -> Gpu {
+        Gpu {
+            index: index.to_string(),
+            uuid: format!("GPU-{}", index),
+        }
+    }
+
+    #[test]
+    fn given_gpu_reserved_by_index_and_uuid_when_preflight_then_report_gpu_taken() {
+        let existing = vec![make_project("train", &gpu_compose("device_ids: [\"0\"]"))];
+        let candidate = ComposeFile::parse(&gpu_compose("device_ids: [GPU-0]")).unwrap();
+
+        let actual = preflight("infer", Some(&candidate), &existing, &[gpu("0"), gpu("1")]);
+
+        assert_eq!(
+            actual,
+            vec![PreflightProblem::GpuTaken {
+                device_id: "GPU-0".to_string(),
+                project: "train".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn given_more_gpus_requested_than_free_when_preflight_then_report_exhausted() {
+        let existing = vec![make_project("train", &gpu_compose("count: 1"))];
+        let candidate = ComposeFile::parse(&gpu_compose("count: all")).unwrap();
+
+        let actual = preflight("infer", Some(&candidate), &existing, &[gpu("0"), gpu("1")]);
+
+        assert_eq!(
+            actual,
+            vec![PreflightProblem::GpusExhausted {
+                requested: 2,
+                available: 1,
+            }]
+        );
+    }
+}
@@ -0,0 +1,417 @@
+use anyhow::{anyhow, Result};
+use serde_yaml::value::{Tag, TaggedValue};
+use serde_yaml::{Mapping, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::config::PortRange;
+use crate::models::compose_file::ComposeFile;
+use crate::models::git::GitSource;
+use crate::models::preview::{preview_name, Preview};
+use crate::models::project::ProjectFile;
+
+/// Kept next to the project file, so allocations go away with the preview.
+const ALLOCATIONS_FILE: &str = "ports.lock";
+const OVERRIDE_FILE: &str = "preview.override.yml";
+
+/// Gives the published ports of pull request previews host ports from the configured
+/// range, so a preview runs next to the project it previews. A port keeps its host port
+/// across deployments of the preview, and no two previews share one. Like subnets, the
+/// override file assigning them lives in the project's runtime directory.
+#[derive(Debug, Clone)]
+pub struct PreviewPorts {
+    range: PortRange,
+    projects_dir: PathBuf,
+    runtime_dir: PathBuf,
+    /// Projects directories of other workspaces on the same Docker host, whose previews'
+    /// ports are taken as well.
+    neighbours: Vec<PathBuf>,
+    /// Held while allocating, so concurrent deployments don't pick the same port.
+    allocating: Arc<Mutex<()>>,
+}
+
+impl PreviewPorts {
+    pub fn new<P: AsRef<Path>>(range: PortRange, projects_dir: P, runtime_dir: P) -> Self {
+        Self {
+            range,
+            projects_dir: projects_dir.as_ref().to_path_buf(),
+            runtime_dir: runtime_dir.as_ref().to_path_buf(),
+            neighbours: vec![],
+            allocating: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn with_range(self, range: PortRange) -> Self {
+        Self { range, ..self }
+    }
+
+    /// Also keep clear of the ports of previews in `neighbours`, allocating under a lock
+    /// shared with them.
+    pub fn with_neighbours(self, neighbours: Vec<PathBuf>, allocating: Arc<Mutex<()>>) -> Self {
+        Self {
+            neighbours,
+            allocating,
+            ..self
+        }
+    }
+
+    /// Write the override file turning `compose_file` into the preview `preview`: host
+    /// ports from the range, the preview's labels, and no fixed container names, which
+    /// the project it previews holds already. Must run after secrets are materialized,
+    /// which clears the runtime directory.
+    pub fn materialize(
+        &self,
+        project_name: &str,
+        compose_file: &ComposeFile,
+        preview: &Preview,
+    ) -> Result<PathBuf> {
+        let mut published = Vec::new();
+        for service_name in compose_file.service_names() {
+            let ports = compose_file
+                .service(&service_name)
+                .and_then(|service| service.get("ports"))
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten();
+            published.extend(ports.filter_map(|port| port_key(&service_name, port)));
+        }
+        let host_ports = self.allocate(project_name, &published)?;
+
+        let labels = preview
+            .service_labels()
+            .into_iter()
+            .map(|(key, value)| (Value::from(key), Value::from(value)))
+            .collect::<Mapping>();
+        let mut services = Mapping::new();
+        for service_name in compose_file.service_names() {
+            let Some(service) = compose_file.service(&service_name) else {
+                continue;
+            };
+            let mut definition = Mapping::new();
+            definition.insert(Value::from("labels"), Value::Mapping(labels.clone()));
+            if let Some(ports) = service.get("ports").and_then(Value::as_sequence) {
+                let ports = ports
+                    .iter()
+                    .map(|port| remap_port(&service_name, port, &host_ports))
+                    .collect();
+                definition.insert(
+                    Value::from("ports"),
+                    tagged("override", Value::Sequence(ports)),
+                );
+            }
+            if service.get("container_name").is_some() {
+                definition.insert(Value::from("container_name"), tagged("reset", Value::Null));
+            }
+            services.insert(Value::from(service_name), Value::Mapping(definition));
+        }
+        let mut document = Mapping::new();
+        document.insert(Value::from("services"), Value::Mapping(services));
+
+        let dir = self.runtime_dir.join(project_name);
+        fs::create_dir_all(&dir)?;
+        let override_path = dir.join(OVERRIDE_FILE);
+        fs::write(&override_path, serde_yaml::to_string(&document)?)?;
+        Ok(fs::canonicalize(override_path)?)
+    }
+
+    /// The override file written by the last `materialize`, while the preview is up.
+    pub fn override_file(&self, project_name: &str) -> Option<PathBuf> {
+        let path = self.runtime_dir.join(project_name).join(OVERRIDE_FILE);
+        fs::canonicalize(path).ok()
+    }
+
+    fn allocate(&self, project_name: &str, published: &[String]) -> Result<BTreeMap<String, u16>> {
+        let _allocating = self.allocating.lock().unwrap_or_else(|e| e.into_inner());
+        let mut allocations = self.allocations(project_name)?;
+        let mut taken = self.taken_ports()?;
+
+        for key in published {
+            if allocations.contains_key(key) {
+                continue;
+            }
+            let port = (self.range.start..=self.range.end)
+                .find(|candidate| !taken.contains(candidate))
+                .ok_or_else(|| anyhow!("No free preview port left for {}", key))?;
+            taken.insert(port);
+            allocations.insert(key.clone(), port);
+        }
+
+        let path = self.projects_dir.join(project_name).join(ALLOCATIONS_FILE);
+        fs::create_dir_all(self.projects_dir.join(project_name))?;
+        fs::write(path, serde_yaml::to_string(&allocations)?)?;
+        Ok(allocations)
+    }
+
+    fn allocations(&self, project_name: &str) -> Result<BTreeMap<String, u16>> {
+        allocations_in(&self.projects_dir, project_name)
+    }
+
+    /// Host ports allocated to any preview, in this workspace or a neighbouring one.
+    fn taken_ports(&self) -> Result<BTreeSet<u16>> {
+        let mut taken = BTreeSet::new();
+        for projects_dir in std::iter::once(&self.projects_dir).chain(&self.neighbours) {
+            if !projects_dir.exists() {
+                continue;
+            }
+            for entry in fs::read_dir(projects_dir)? {
+                let project_name = entry?.file_name().to_string_lossy().to_string();
+                taken.extend(allocations_in(projects_dir, &project_name)?.into_values());
+            }
+        }
+        Ok(taken)
+    }
+}
+
+fn allocations_in(projects_dir: &Path, project_name: &str) -> Result<BTreeMap<String, u16>> {
+    let path = projects_dir.join(project_name).join(ALLOCATIONS_FILE);
+    match path.exists() {
+        true => Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?),
+        false => Ok(BTreeMap::new()),
+    }
+}
+
+/// The manifest of the preview of pull request `number` of the project, deploying
+/// `head_branch` of `head_url` in its place. Webhooks, schedules and shared services
+/// stay with the project, as previews must not answer for it.
+pub fn preview_project_file(
+    project_file: &ProjectFile,
+    number: u64,
+    head_url: &str,
+    head_branch: &str,
+) -> ProjectFile {
+    let name = preview_name(&project_file.name, number);
+    let render = |template: &str| {
+        template
+            .replace("{name}", &name)
+            .replace("{project}", &project_file.name)
+            .replace("{number}", &number.to_string())
+            .replace("{branch}", head_branch)
+    };
+    let labels = project_file
+        .previews
+        .iter()
+        .flat_map(|settings| &settings.labels)
+        .map(|(key, value)| (render(key), render(value)))
+        .collect();
+
+    ProjectFile {
+        source: GitSource {
+            url: head_url.to_string(),
+            branch: head_branch.to_string(),
+            tag_pattern: None,
+            ..project_file.source.clone()
+        },
+        webhook: None,
+        schedule: None,
+        shared_services: vec![],
+        previews: None,
+        preview: Some(Preview {
+            of: project_file.name.clone(),
+            number,
+            labels,
+        }),
+        deletion: None,
+        creation: None,
+        name,
+        ..project_file.clone()
+    }
+}
+
+/// `<service>:<container port>/<protocol>` for a port published on a single host port,
+/// which is what previews get a port of their own for. Ports without a host port, or
+/// published on a range, are left to Docker.
+fn port_key(service_name: &str, port: &Value) -> Option<String> {
+    let (target, protocol) = match port {
+        Value::String(short) => {
+            let (mapping, protocol) = short.split_once('/').unwrap_or((short.as_str(), "tcp"));
+            let (host, target) = mapping.rsplit_once(':')?;
+            let host = host.rsplit_once(':').map_or(host, |(_ip, port)| port);
+            host.parse::<u16>().ok()?;
+            (target.to_string(), protocol.to_string())
+        }
+        Value::Mapping(long) => {
+            let published = match long.get("published")? {
+                Value::Number(number) => number.to_string(),
+                Value::String(published) => published.clone(),
+                _ => return None,
+            };
+            published.parse::<u16>().ok()?;
+            let target = match long.get("target")? {
+                Value::Number(number) => number.to_string(),
+                Value::String(target) => target.clone(),
+                _ => return None,
+            };
+            let protocol = long
+                .get("protocol")
+                .and_then(Value::as_str)
+                .unwrap_or("tcp");
+            (target, protocol.to_string())
+        }
+        _ => return None,
+    };
+    Some(format!("{}:{}/{}", service_name, target, protocol))
+}
+
+/// `port` published on its allocated host port, or on one Docker picks when it was
+/// published on a range.
+fn remap_port(service_name: &str, port: &Value, host_ports: &BTreeMap<String, u16>) -> Value {
+    let host_port = port_key(service_name, port).and_then(|key| host_ports.get(&key).copied());
+    match port {
+        Value::String(short) => {
+            let (mapping, protocol) = match short.split_once('/') {
+                Some((mapping, protocol)) => (mapping, format!("/{}", protocol)),
+                None => (short.as_str(), String::new()),
+            };
+            let Some((host, target)) = mapping.rsplit_once(':') else {
+                return port.clone();
+            };
+            let ip = host.rsplit_once(':').map(|(ip, _port)| ip);
+            let host_port = host_port.map(|port| port.to_string()).unwrap_or_default();
+            let remapped = match ip {
+                Some(ip) => format!("{}:{}:{}{}", ip, host_port, target, protocol),
+                None if host_port.is_empty() => format!("{}{}", target, protocol),
+                None => format!("{}:{}{}", host_port, target, protocol),
+            };
+            Value::from(remapped)
+        }
+        Value::Mapping(long) => {
+            let mut long = long.clone();
+            match host_port {
+                Some(host_port) => long.insert(Value::from("published"), Value::from(host_port)),
+                None => long.remove("published"),
+            };
+            Value::Mapping(long)
+        }
+        _ => port.clone(),
+    }
+}
+
+/// A value with a compose merge tag, e.g. `!override` to replace a list in the compose
+/// file instead of extending it.
+fn tagged(tag: &str, value: Value) -> Value {
+    Value::Tagged(Box::new(TaggedValue {
+        tag: Tag::new(tag),
+        value,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::preview::PreviewSettings;
+
+    fn make_preview() -> Preview {
+        Preview {
+            of: "shop".to_string(),
+            number: 42,
+            labels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn given_published_ports_when_materialized_then_move_them_into_the_range() {
+        let root = tempfile::TempDir::new().unwrap();
+        let previews = PreviewPorts::new(
+            PortRange {
+                start: 20000,
+                end: 20010,
+            },
+            root.path().join("projects"),
+            root.path().join("runtime"),
+        );
+        let compose_file = ComposeFile::parse(
+            "services:\n  web:\n    image: nginx\n    container_name: shop-web\n    ports:\n      - \"8080:80\"\n      - \"127.0.0.1:8443:443/tcp\"\n      - \"9000-9001:9000-9001\"\n      - target: 53\n        published: 5353\n        protocol: udp\n",
+        )
+        .unwrap();
+
+        let path = previews
+            .materialize("shop-pr-42", &compose_file, &make_preview())
+            .unwrap();
+
+        let actual: Value = serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let web = &actual["services"]["web"];
+        let Value::Tagged(ports) = &web["ports"] else {
+            panic!("ports are not tagged: {:?}", web["ports"]);
+        };
+        assert_eq!(ports.tag, Tag::new("override"));
+        assert_eq!(ports.value[0], Value::from("20000:80"));
+        assert_eq!(ports.value[1], Value::from("127.0.0.1:20001:443/tcp"));
+        assert_eq!(ports.value[2], Value::from("9000-9001"));
+        assert_eq!(ports.value[3]["published"], Value::from(20002));
+        assert!(matches!(&web["container_name"], Value::Tagged(_)));
+        assert_eq!(web["labels"]["gfc.preview.of"], Value::from("shop"));
+    }
+
+    #[test]
+    fn given_ports_of_another_preview_when_materialized_then_keep_clear_of_them() {
+        let root = tempfile::TempDir::new().unwrap();
+        let previews = PreviewPorts::new(
+            PortRange {
+                start: 20000,
+                end: 20001,
+            },
+            root.path().join("projects"),
+            root.path().join("runtime"),
+        );
+        let compose_file =
+            ComposeFile::parse("services:\n  web:\n    ports: [\"8080:80\"]\n").unwrap();
+
+        previews
+            .materialize("shop-pr-1", &compose_file, &make_preview())
+            .unwrap();
+        previews
+            .materialize("shop-pr-1", &compose_file, &make_preview())
+            .unwrap();
+        let second = previews
+            .materialize("shop-pr-2", &compose_file, &make_preview())
+            .unwrap();
+        let third = previews.materialize("shop-pr-3", &compose_file, &make_preview());
+
+        let actual: Value = serde_yaml::from_str(&fs::read_to_string(second).unwrap()).unwrap();
+        let Value::Tagged(ports) = &actual["services"]["web"]["ports"] else {
+            panic!("ports are not tagged");
+        };
+        assert_eq!(ports.value[0], Value::from("20001:80"));
+        assert!(third.is_err());
+    }
+
+    #[test]
+    fn given_project_with_previews_when_previewing_then_track_the_pull_request_branch() {
+        let project_file = ProjectFile {
+            name: "shop".to_string(),
+            source: GitSource {
+                url: "https://github.com/acme/shop.git".to_string(),
+                branch: "main".to_string(),
+                path: "docker-compose.yml".to_string(),
+                ..Default::default()
+            },
+            previews: Some(PreviewSettings {
+                labels: BTreeMap::from([(
+                    "traefik.http.routers.{name}.rule".to_string(),
+                    "Host(`{name}.preview.example.com`)".to_string(),
+                )]),
+            }),
+            ..Default::default()
+        };
+
+        let actual = preview_project_file(
+            &project_file,
+            42,
+            "https://github.com/someone/shop.git",
+            "fix-cart",
+        );
+
+        assert_eq!(actual.name, "shop-pr-42");
+        assert_eq!(actual.source.url, "https://github.com/someone/shop.git");
+        assert_eq!(actual.source.branch, "fix-cart");
+        assert!(actual.previews.is_none());
+        let preview = actual.preview.unwrap();
+        assert_eq!(preview.of, "shop");
+        assert_eq!(
+            preview.labels.get("traefik.http.routers.shop-pr-42.rule"),
+            Some(&"Host(`shop-pr-42.preview.example.com`)".to_string())
+        );
+    }
+}
@@ -1,16 +1,88 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
 use glob::glob;
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
 use thiserror::Error;
 
-use crate::config::ResourcesConfig;
-use crate::models::docker_compose::{Container, ContainerState};
-use crate::models::project::{Project, ProjectFile};
-use crate::models::response::{GenericResponse, ResponseStatus};
+use crate::config::{
+    AddressPool, DiskQuotaConfig, PortRange, PreviewsConfig, Profile, ProfileLimits,
+    QuotaEnforcement, ResourcesConfig, SopsConfig,
+};
+use crate::models::activity::{ActivityKind, ActivityPage, ActivityQuery};
+use crate::models::bootstrap::{Bootstrap, SecretReference};
+use crate::models::compose_file::ComposeFile;
+use crate::models::deployment::{
+    Deployment, DeploymentOutcome, DeploymentTrigger, DeploymentsQuery,
+};
+use crate::models::device::{DeviceReservation, Gpu};
+use crate::models::docker_compose::{
+    ComposeNetwork, ComposeStack, Container, ContainerFailure, ContainerState, ExecOutput,
+    ExecRequest, LocalImage, ProjectEvent, ProjectStatusDetail, VolumeUsage,
+};
+use crate::models::export::{
+    ImportStatus, ImportedProject, PortainerImportRequest, WorkspaceExport, EXPORT_VERSION,
+};
+use crate::models::git::{GitSource, ProjectDiff};
+use crate::models::job::{Job, JobKind, QueueStats};
+use crate::models::preview::preview_name;
+use crate::models::project::{
+    Creation, Deletion, ManifestFormat, MigrateToGitRequest, Project, ProjectFile, ProjectStatus,
+    QueuedJob, INLINE_COMPOSE_FILE, MANIFEST_EXTENSIONS,
+};
+use crate::models::response::GenericResponse;
+use crate::models::retry::RetryPolicy;
+use crate::models::schedule::{ScheduledActionKind, ScheduledActionStatus};
+use crate::models::selector::{LabelSelector, SelectorError};
+use crate::models::system::{
+    DirectoryUsage, ImageGcReport, ProjectDiskUsage, PruneReport, SystemInfo,
+};
+use crate::models::validation::{CheckStatus, ProjectValidation};
+use crate::repositories::activity_log::ActivityLog;
+use crate::repositories::blocking;
 use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::credentials::CredentialCipher;
+use crate::repositories::deployment_history::DeploymentHistory;
+use crate::repositories::docker_compose_client::find_compose_file_name;
 use crate::repositories::git::GitClient;
+use crate::repositories::gpu::list_gpus;
+#[cfg(feature = "sqlite-store")]
+use crate::repositories::job_store::JobStore;
+use crate::repositories::process;
+use crate::repositories::secret_store::{write_private_file, SecretStore};
+use crate::repositories::sops::{decrypted_path, Sops};
+use crate::usecases::compose_cache::ComposeFileCache;
+use crate::usecases::deadline::Deadline;
+use crate::usecases::environment::{write_env_file, DOTENV_FILE};
+use crate::usecases::image_gc::unused_images;
+use crate::usecases::job::JobManager;
+use crate::usecases::locks::{ProjectLease, ProjectLocks};
+use crate::usecases::portainer::{portainer_stacks, PortainerStack};
+use crate::usecases::preflight::{preflight, ExistingProject, PreflightReport};
+use crate::usecases::preview::{preview_project_file, PreviewPorts};
+use crate::usecases::provenance;
+use crate::usecases::retry::retry;
+use crate::usecases::secrets::ProjectSecrets;
+use crate::usecases::shared_network::{self, SHARED_NETWORK};
+use crate::usecases::standby::StandbyCheckouts;
+use crate::usecases::subnets::ProjectSubnets;
+use crate::usecases::system::directory_size;
+use crate::usecases::validation::{
+    resolve_compose_file, validate_compose_file, validate_create_project_params,
+    validate_source_path, ValidationError,
+};
+
+/// Largest file `repository_file` serves.
+const MAX_REPOSITORY_FILE_BYTES: u64 = 2 * 1024 * 1024;
+/// Restarts after which a container counts as failing rather than recovering.
+const RESTART_LOOP_THRESHOLD: u64 = 3;
 
 #[derive(Debug, Error)]
 pub enum ProjectUsecaseError {
@@ -18,6 +90,86 @@ pub enum ProjectUsecaseError {
     CreateProjectFailed(String),
     #[error("Failed to list projects: {0}")]
     ListProjectsFailed(String),
+    #[error("Failed to read project activity: {0}")]
+    ReadActivityFailed(String),
+    #[error("Failed to read project status: {0}")]
+    ReadStatusFailed(String),
+    #[error("Failed to pause or unpause project: {0}")]
+    PauseFailed(String),
+    #[error("Failed to import Portainer stacks: {0}")]
+    PortainerImportFailed(String),
+    #[error("Failed to migrate project to git: {0}")]
+    MigrationFailed(String),
+    #[error("File not found: {0}")]
+    FileNotFound(String),
+    #[error("File is larger than {limit} bytes: {path}")]
+    FileTooLarge { path: String, limit: u64 },
+    #[error("Invalid file path: {0}")]
+    InvalidFilePath(String),
+    #[error("File holds secrets gfc wrote for the deployment: {0}")]
+    FileNotServed(String),
+    #[error("Failed to access secret: {0}")]
+    SecretFailed(String),
+    #[error("Failed to prune networks: {0}")]
+    PruneNetworksFailed(String),
+    #[error("Failed to apply schedule: {0}")]
+    ScheduleFailed(String),
+    #[error("Failed to read compose file: {0}")]
+    ReadComposeFileFailed(String),
+    #[error("Deadline exceeded: {0}")]
+    DeadlineExceeded(String),
+    #[error("Project not found: {0}")]
+    ProjectNotFound(String),
+    #[error("Invalid project: {0}")]
+    InvalidProject(#[from] ValidationError),
+    #[error("Project conflicts with existing projects: {0}")]
+    PreflightFailed(PreflightReport),
+    #[error("Service not found: {0}")]
+    ServiceNotFound(String),
+    #[error("Failed to exec in service: {0}")]
+    ExecFailed(String),
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+    #[error("Failed to read deployment history: {0}")]
+    ReadHistoryFailed(String),
+    #[error("Deployment not found: {0}")]
+    DeploymentNotFound(String),
+    #[error("Cannot roll back: {0}")]
+    RollbackUnavailable(String),
+    #[error("Deployment is not awaiting approval: {0}")]
+    DeploymentNotPending(String),
+    #[error("Failed to request approval: {0}")]
+    ApprovalFailed(String),
+    #[error("Cannot diff: {0}")]
+    DiffUnavailable(String),
+    #[error("Failed to diff project: {0}")]
+    DiffFailed(String),
+    #[error("Job has already finished: {0}")]
+    JobFinished(String),
+    #[error("Unsupported export version: {0}")]
+    UnsupportedExportVersion(u32),
+    #[error("Failed to delete project: {0}")]
+    DeleteProjectFailed(String),
+    #[error("Project is being deleted: {0}")]
+    ProjectDeleting(String),
+    #[error("Project {name} is busy with a {operation}, try again once it finishes")]
+    ProjectBusy { name: String, operation: String },
+    #[error("Failed to prune orphaned resources: {0}")]
+    PruneFailed(String),
+    #[error("Failed to measure disk usage: {0}")]
+    DiskUsageFailed(String),
+    #[error("Disk quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error(transparent)]
+    InvalidSelector(#[from] SelectorError),
+}
+
+/// Why `deploy_revision` deploys a revision, which decides how the deployment is recorded.
+enum RevisionDeployment {
+    /// A rollback to the deployment with this ID.
+    Rollback(i64),
+    /// The pending deployment approved, completed in place once deployed.
+    Approval(Deployment),
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +181,21 @@ where
     pub compose_client: Arc<C>,
     pub git_client: Arc<G>,
     pub resources_config: ResourcesConfig,
+    pub activity_log: ActivityLog,
+    pub deployments: DeploymentHistory,
+    pub jobs: JobManager,
+    pub limits: ProfileLimits,
+    pub secrets: ProjectSecrets,
+    pub subnets: ProjectSubnets,
+    pub previews: PreviewPorts,
+    pub compose_files: ComposeFileCache,
+    pub sops: Sops,
+    pub retry_policy: RetryPolicy,
+    pub disk_quota: DiskQuotaConfig,
+    /// Serializes the operations that change a project.
+    pub locks: ProjectLocks,
+    /// Other workspaces deploying to the same Docker host.
+    pub neighbours: Vec<ResourcesConfig>,
 }
 
 impl<C, G> ProjectUsecase<C, G>
@@ -36,26 +203,602 @@ where
     C: ComposeClient + Send + Sync,
     G: GitClient + Send + Sync,
 {
+    /// Deployments run as jobs on Tokio's blocking pool, so `create_project` and
+    /// `sync_project` must be called from within a Tokio runtime.
     pub fn new(
         compose_client: Arc<C>,
         git_client: Arc<G>,
         resources_config: ResourcesConfig,
     ) -> Self {
+        let activity_log = ActivityLog::new(&resources_config.projects_dir);
+        let deployments = DeploymentHistory::new(resources_config.history_db_path());
+        let jobs = stored_jobs(JobManager::default(), &resources_config);
+        let secrets = ProjectSecrets::new(
+            SecretStore::new(&resources_config.secrets_dir),
+            &resources_config.runtime_dir,
+        );
+        let subnets = ProjectSubnets::new(
+            vec![],
+            &resources_config.projects_dir,
+            &resources_config.runtime_dir,
+        );
+        let previews = PreviewPorts::new(
+            PreviewsConfig::default().ports,
+            &resources_config.projects_dir,
+            &resources_config.runtime_dir,
+        );
         Self {
             compose_client,
             git_client,
             resources_config,
+            activity_log,
+            deployments,
+            jobs,
+            limits: Profile::Standard.limits(),
+            secrets,
+            subnets,
+            previews,
+            compose_files: ComposeFileCache::default(),
+            sops: Sops::default(),
+            retry_policy: RetryPolicy::default(),
+            disk_quota: DiskQuotaConfig::default(),
+            locks: ProjectLocks::default(),
+            neighbours: vec![],
+        }
+    }
+
+    /// Apply a resource profile's job limits. Output limits belong to the clients.
+    pub fn with_limits(self, limits: ProfileLimits) -> Self {
+        Self {
+            jobs: stored_jobs(
+                JobManager::new(limits.max_concurrent_jobs, limits.max_finished_jobs),
+                &self.resources_config,
+            ),
+            limits,
+            ..self
+        }
+    }
+
+    /// Give project networks subnets from these pools instead of leaving it to Docker.
+    pub fn with_address_pools(self, pools: Vec<AddressPool>) -> Self {
+        Self {
+            subnets: self.subnets.with_pools(pools),
+            ..self
+        }
+    }
+
+    /// Publish the services of pull request previews on host ports from this range.
+    pub fn with_preview_ports(self, ports: PortRange) -> Self {
+        Self {
+            previews: self.previews.with_range(ports),
+            ..self
+        }
+    }
+
+    /// Share the Docker host with the workspaces of `neighbours`: new projects must not
+    /// take their project names, container names or ports, and subnets and preview ports
+    /// are allocated clear of theirs, under `allocating`, a lock shared with them.
+    pub fn with_neighbours(
+        self,
+        neighbours: Vec<ResourcesConfig>,
+        allocating: Arc<Mutex<()>>,
+    ) -> Self {
+        let projects_dirs = neighbours
+            .iter()
+            .map(|neighbour| PathBuf::from(&neighbour.projects_dir))
+            .collect::<Vec<_>>();
+        Self {
+            subnets: self
+                .subnets
+                .with_neighbours(projects_dirs.clone(), Arc::clone(&allocating)),
+            previews: self.previews.with_neighbours(projects_dirs, allocating),
+            neighbours,
+            ..self
+        }
+    }
+
+    /// Seal stored secrets, deploy keys among them, with this cipher.
+    pub fn with_secrets_cipher(self, cipher: Option<CredentialCipher>) -> Self {
+        Self {
+            secrets: ProjectSecrets::new(
+                SecretStore::new(&self.resources_config.secrets_dir).with_cipher(cipher),
+                &self.resources_config.runtime_dir,
+            ),
+            ..self
+        }
+    }
+
+    /// Decrypt projects' sops files with this configuration.
+    pub fn with_sops(self, config: SopsConfig) -> Self {
+        Self {
+            sops: Sops::new(config),
+            ..self
+        }
+    }
+
+    /// Retry failed clones, pulls and deployments of projects without a policy of their
+    /// own with this one.
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    pub fn with_disk_quota(self, disk_quota: DiskQuotaConfig) -> Self {
+        Self { disk_quota, ..self }
+    }
+
+    /// The project's own retry policy, or the global one.
+    fn retry_policy(&self, project_file: &ProjectFile) -> RetryPolicy {
+        project_file
+            .retry
+            .clone()
+            .unwrap_or_else(|| self.retry_policy.clone())
+    }
+
+    /// Set up the project and queue its first deployment, returning the job to poll.
+    pub fn create_project(&self, project_file: ProjectFile) -> Result<Job, ProjectUsecaseError> {
+        self.start_project(project_file, true)
+    }
+
+    /// Create a project from an uploaded compose file, for stacks that are not in git yet.
+    /// The file is stored in the project's workspace and brought up in the background.
+    pub fn create_inline_project(
+        &self,
+        name: &str,
+        compose: &str,
+    ) -> Result<Job, ProjectUsecaseError> {
+        println!("Creating inline project: {}", name);
+        let compose_file = ComposeFile::parse(compose).map_err(ValidationError::from)?;
+        self.start_inline_project(
+            ProjectFile::inline(name),
+            &compose_file,
+            "Project created from an uploaded compose file",
+            |workspace| Ok(fs::write(workspace.join(INLINE_COMPOSE_FILE), compose)?),
+            true,
+        )
+    }
+
+    /// Write the manifest, fill the workspace with `populate` and queue `compose up` when
+    /// `deploy`. `origin` says where the files came from, for the activity log.
+    fn start_inline_project(
+        &self,
+        project_file: ProjectFile,
+        compose_file: &ComposeFile,
+        origin: &str,
+        populate: impl FnOnce(&Path) -> Result<()>,
+        deploy: bool,
+    ) -> Result<Job, ProjectUsecaseError> {
+        validate_create_project_params(&project_file)?;
+        validate_compose_file(compose_file)?;
+        let project_file = ProjectFile {
+            creation: Some(Creation::default()),
+            ..project_file
+        };
+
+        let problems = preflight(
+            &project_file.name,
+            Some(compose_file),
+            &self.existing_projects()?,
+            &host_gpus(Some(compose_file)),
+        );
+        if !problems.is_empty() {
+            return Err(ProjectUsecaseError::PreflightFailed(PreflightReport(
+                problems,
+            )));
+        }
+
+        let (project_path, project_file_path, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        setup_project_workspace(
+            &project_file,
+            &project_path,
+            &project_file_path,
+            &repository_dir,
+        )
+        .and_then(|_| populate(&repository_dir))
+        .map_err(|e| ProjectUsecaseError::CreateProjectFailed(e.to_string()))?;
+
+        let compose_client = Arc::clone(&self.compose_client);
+        let secrets = self.secrets.clone();
+        let subnets = self.subnets.clone();
+        let previews = self.previews.clone();
+        let compose_files = self.compose_files.clone();
+        let sops = self.sops.clone();
+        let manifest = project_file.clone();
+        let retry_policy = self.retry_policy(&project_file);
+        let locks = self.locks.clone();
+        let activity_log = self.activity_log.clone();
+        let deployments = self.deployments.clone();
+        let name = project_file.name.clone();
+        record_activity(&activity_log, &name, ActivityKind::Deployment, origin);
+        let cancelled_path = project_file_path.clone();
+
+        Ok(self.jobs.submit_with_cancel_hook(
+            JobKind::CreateProject,
+            &project_file.name,
+            move || {
+                let _lease = locks.lock(&name, "deployment");
+                let started_at = Utc::now();
+                let result = match deploy {
+                    true => retry(&retry_policy, &format!("Deploying {}", name), || {
+                        compose_up(
+                            compose_client.as_ref(),
+                            &secrets,
+                            &subnets,
+                            &previews,
+                            &compose_files,
+                            &sops,
+                            &repository_dir,
+                            &manifest,
+                            None,
+                        )
+                    }),
+                    false => Ok(()),
+                };
+                record_creation_outcome(&project_file_path, &result);
+                record_deployment_outcome(&activity_log, &name, &result);
+                if deploy {
+                    let deployment = finished_deployment(
+                        &name,
+                        DeploymentTrigger::Create,
+                        started_at,
+                        None,
+                        &result,
+                    );
+                    record_deployment(&deployments, &deployment);
+                }
+                result
+            },
+            move || record_creation_cancelled(&cancelled_path),
+        ))
+    }
+
+    /// Turn an inline project into a git-backed one. The running containers are left
+    /// alone: the workspace is either turned into a checkout by pushing it, or swapped for
+    /// a clone of the remote once that has been checked to hold a valid compose file.
+    /// Compose keys the stack on the workspace directory name, which does not change.
+    pub fn migrate_to_git(
+        &self,
+        name: &str,
+        request: &MigrateToGitRequest,
+    ) -> Result<ProjectFile, ProjectUsecaseError> {
+        let _lease = self.try_lock(name, "migration")?;
+        let project_file = self.find_project_file(name)?;
+        if !project_file.inline {
+            return Err(ProjectUsecaseError::MigrationFailed(format!(
+                "{} is already backed by git",
+                name
+            )));
+        }
+
+        let migrated = ProjectFile {
+            source: request.source(),
+            inline: false,
+            ..project_file
+        };
+        validate_create_project_params(&migrated)?;
+
+        let (_, project_file_path, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, name);
+        let result = match request.push {
+            true => self.push_workspace(&migrated, &repository_dir),
+            false => self.replace_workspace(&migrated, &repository_dir),
+        };
+        result
+            .and_then(|_| write_manifest(&project_file_path, &migrated))
+            .map_err(|e| ProjectUsecaseError::MigrationFailed(e.to_string()))?;
+
+        record_activity(
+            &self.activity_log,
+            name,
+            ActivityKind::ManualAction,
+            &format!(
+                "Migrated to {}@{}",
+                migrated.source.url, migrated.source.branch
+            ),
+        );
+        Ok(migrated.redacted())
+    }
+
+    /// Push the uploaded files as they are. A failed push leaves no `.git` behind, so the
+    /// migration can be retried.
+    fn push_workspace(&self, migrated: &ProjectFile, repository_dir: &Path) -> Result<()> {
+        resolve_compose_file(repository_dir, &migrated.source.path)?;
+        let pushed = self.git_client.push_directory(
+            &migrated.source,
+            repository_dir,
+            &format!("Import {} from gfc", migrated.name),
+        );
+        if pushed.is_err() {
+            let _ = fs::remove_dir_all(repository_dir.join(".git"));
+        }
+        pushed
+    }
+
+    /// Clone the remote next to the workspace and swap the two directories.
+    fn replace_workspace(&self, migrated: &ProjectFile, repository_dir: &Path) -> Result<()> {
+        let staging_root = Path::new(&self.resources_config.repositories_dir).join(".migrations");
+        let checkout = staging_root.join(&migrated.name);
+        let previous = staging_root.join(format!("{}.inline", migrated.name));
+        for stale in [&checkout, &previous] {
+            if stale.exists() {
+                fs::remove_dir_all(stale)?;
+            }
         }
+        fs::create_dir_all(&staging_root)?;
+
+        self.git_client
+            .clone_repository(&migrated.source, &checkout)?;
+        let compose_path = resolve_compose_file(&checkout, &migrated.source.path)?;
+        validate_compose_file(&ComposeFile::from_path(compose_path)?)?;
+
+        fs::rename(repository_dir, &previous)?;
+        if let Err(e) = fs::rename(&checkout, repository_dir) {
+            fs::rename(&previous, repository_dir)?;
+            return Err(e.into());
+        }
+        fs::remove_dir_all(&previous)?;
+        Ok(())
     }
 
-    pub fn create_project(
+    /// All project manifests, for moving the workspace to another host.
+    pub fn export_workspace(&self) -> Result<WorkspaceExport, ProjectUsecaseError> {
+        Ok(WorkspaceExport {
+            version: EXPORT_VERSION,
+            exported_at: Utc::now(),
+            projects: self.project_files()?,
+        })
+    }
+
+    /// Take over the stacks Portainer deployed on this engine. Stacks Portainer cloned from
+    /// git become git-backed projects, and those written in its web editor become inline
+    /// projects holding a copy of the stack's files. As with `import_workspace`, taken
+    /// names are skipped and failures do not stop the other stacks.
+    pub fn import_portainer_stacks(
+        &self,
+        request: &PortainerImportRequest,
+    ) -> Result<Vec<ImportedProject>, ProjectUsecaseError> {
+        let stacks = self
+            .compose_client
+            .list_stacks()
+            .map_err(|e| ProjectUsecaseError::PortainerImportFailed(e.to_string()))?;
+        let existing = self
+            .project_files()?
+            .into_iter()
+            .map(|project_file| project_file.name)
+            .collect::<Vec<_>>();
+
+        Ok(portainer_stacks(&stacks, Path::new(&request.data_dir))
+            .into_iter()
+            .map(|stack| {
+                let name = stack.name.clone();
+                if existing.contains(&name) {
+                    return ImportedProject {
+                        name,
+                        status: ImportStatus::Skipped,
+                        job_id: None,
+                        error: None,
+                    };
+                }
+
+                match self.import_portainer_stack(stack, request.deploy) {
+                    Ok(job) => ImportedProject {
+                        name,
+                        status: ImportStatus::Queued,
+                        job_id: Some(job.id),
+                        error: None,
+                    },
+                    Err(e) => ImportedProject {
+                        name,
+                        status: ImportStatus::Failed,
+                        job_id: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            })
+            .collect())
+    }
+
+    fn import_portainer_stack(
+        &self,
+        stack: PortainerStack,
+        deploy: bool,
+    ) -> Result<Job, ProjectUsecaseError> {
+        if let Ok(source) = self.git_client.describe_checkout(&stack.dir) {
+            let project_file = ProjectFile {
+                name: stack.name,
+                source: GitSource {
+                    path: stack.compose_path,
+                    ..source
+                },
+                ..Default::default()
+            };
+            return self.start_project(project_file, deploy);
+        }
+
+        let compose_file = ComposeFile::from_path(stack.dir.join(&stack.compose_path))
+            .map_err(ValidationError::from)?;
+        let project_file = ProjectFile {
+            source: GitSource {
+                path: stack.compose_path.clone(),
+                ..Default::default()
+            },
+            ..ProjectFile::inline(&stack.name)
+        };
+        self.start_inline_project(
+            project_file,
+            &compose_file,
+            &format!("Project imported from Portainer ({})", stack.dir.display()),
+            |workspace| copy_dir(&stack.dir, workspace),
+            deploy,
+        )
+    }
+
+    /// Recreate the projects in an export. Each is cloned in its own job, and deployed too
+    /// when `deploy` is set. Projects whose name is already taken are skipped, and one
+    /// project failing does not stop the others.
+    pub fn import_workspace(
+        &self,
+        export: WorkspaceExport,
+        deploy: bool,
+    ) -> Result<Vec<ImportedProject>, ProjectUsecaseError> {
+        if export.version != EXPORT_VERSION {
+            return Err(ProjectUsecaseError::UnsupportedExportVersion(
+                export.version,
+            ));
+        }
+
+        self.start_missing_projects(export.projects, deploy)
+    }
+
+    /// Write the secrets of a bootstrap file, then create and deploy those of its projects
+    /// that do not exist yet. A secret that cannot be read stops everything, before a
+    /// project that needs it is deployed without it.
+    pub fn apply_bootstrap(
+        &self,
+        bootstrap: Bootstrap,
+    ) -> Result<Vec<ImportedProject>, ProjectUsecaseError> {
+        let store = self.secrets.store();
+        for secret in &bootstrap.secrets {
+            let failed = |e: anyhow::Error| {
+                ProjectUsecaseError::SecretFailed(format!(
+                    "{} of {}: {}",
+                    secret.name, secret.project, e
+                ))
+            };
+            let value = read_secret_reference(&secret.value).map_err(failed)?;
+            if store.get(&secret.project, &secret.name).ok() == Some(value.clone()) {
+                continue;
+            }
+            store
+                .put(&secret.project, &secret.name, &value)
+                .map_err(failed)?;
+            println!("Secret {} of {} written", secret.name, secret.project);
+        }
+
+        self.start_missing_projects(bootstrap.projects, true)
+    }
+
+    /// Create a project for each environment the manifest declares, skipping those
+    /// created already, so creating it again adds only environments that were added. The
+    /// projects of environments it no longer declares are deleted.
+    pub fn create_environments(
         &self,
         project_file: ProjectFile,
-    ) -> Result<GenericResponse<ResponseStatus>, ProjectUsecaseError> {
+    ) -> Result<Vec<ImportedProject>, ProjectUsecaseError> {
+        let project = project_file.name.clone();
+        let declared = project_file
+            .environments
+            .iter()
+            .map(|environment| environment.name.clone())
+            .collect::<HashSet<_>>();
+        let mut results = self.start_missing_projects(vec![project_file], true)?;
+
+        let removed = self.project_files()?.into_iter().filter(|existing| {
+            existing.deletion.is_none()
+                && existing
+                    .environment_of
+                    .as_ref()
+                    .is_some_and(|of| of.project == project && !declared.contains(&of.environment))
+        });
+        for project_file in removed {
+            let name = project_file.name;
+            results.push(match self.delete_project(&name) {
+                Ok(job) => ImportedProject {
+                    name,
+                    status: ImportStatus::Removed,
+                    job_id: Some(job.id),
+                    error: None,
+                },
+                Err(e) => ImportedProject {
+                    name,
+                    status: ImportStatus::Failed,
+                    job_id: None,
+                    error: Some(e.to_string()),
+                },
+            });
+        }
+        Ok(results)
+    }
+
+    /// Start each of `projects` whose name is not taken yet, as `import_workspace` does.
+    /// A manifest with environments starts a project per environment.
+    fn start_missing_projects(
+        &self,
+        projects: Vec<ProjectFile>,
+        deploy: bool,
+    ) -> Result<Vec<ImportedProject>, ProjectUsecaseError> {
+        let existing = self
+            .project_files()?
+            .into_iter()
+            .map(|project_file| project_file.name)
+            .collect::<Vec<_>>();
+
+        Ok(projects
+            .into_iter()
+            .flat_map(ProjectFile::environment_projects)
+            .map(|project_file| {
+                let name = project_file.name.clone();
+                if existing.contains(&name) {
+                    return ImportedProject {
+                        name,
+                        status: ImportStatus::Skipped,
+                        job_id: None,
+                        error: None,
+                    };
+                }
+
+                match self.start_project(project_file, deploy) {
+                    Ok(job) => ImportedProject {
+                        name,
+                        status: ImportStatus::Queued,
+                        job_id: Some(job.id),
+                        error: None,
+                    },
+                    Err(e) => ImportedProject {
+                        name,
+                        status: ImportStatus::Failed,
+                        job_id: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            })
+            .collect())
+    }
+
+    /// Write the manifest and queue the clone, followed by `compose up` when `deploy`.
+    fn start_project(
+        &self,
+        project_file: ProjectFile,
+        deploy: bool,
+    ) -> Result<Job, ProjectUsecaseError> {
         println!("Creating project: {}", project_file.name);
+        if project_file.inline {
+            return Err(ProjectUsecaseError::CreateProjectFailed(
+                "Inline projects are created from their compose file".to_string(),
+            ));
+        }
+        if !project_file.environments.is_empty() {
+            return Err(ProjectUsecaseError::CreateProjectFailed(
+                "Projects with environments are created once per environment".to_string(),
+            ));
+        }
+        validate_create_project_params(&project_file)?;
+        self.preflight(&project_file)?;
+        let project_file = ProjectFile {
+            creation: Some(Creation::default()),
+            ..project_file
+        };
 
         let git_client = Arc::clone(&self.git_client);
         let compose_client = Arc::clone(&self.compose_client);
+        let secrets = self.secrets.clone();
+        let subnets = self.subnets.clone();
+        let previews = self.previews.clone();
+        let compose_files = self.compose_files.clone();
+        let sops = self.sops.clone();
 
         let (project_path, project_file_path, repository_dir) =
             get_project_and_repository_paths(&self.resources_config, &project_file.name);
@@ -68,62 +811,2529 @@ where
         )
         .map_err(|e| ProjectUsecaseError::CreateProjectFailed(e.to_string()))?;
 
-        let source = project_file.source.clone();
+        let source = self
+            .secrets
+            .resolve_source(&project_file.name, &project_file.source)
+            .map_err(|e| ProjectUsecaseError::CreateProjectFailed(e.to_string()))?;
         let repository_dir = repository_dir.clone();
+        let manifest = project_file.clone();
+        let retry_policy = self.retry_policy(&project_file);
+        let locks = self.locks.clone();
+        let activity_log = self.activity_log.clone();
+        let deployments = self.deployments.clone();
+        let name = project_file.name.clone();
+        record_activity(
+            &activity_log,
+            &name,
+            ActivityKind::Deployment,
+            &format!("Project created from {}@{}", source.url, source.branch),
+        );
+
+        let images = match deploy && self.limits.pull_during_clone {
+            true => known_images(&self.compose_files, &project_file, &repository_dir),
+            false => vec![],
+        };
+        let cancelled_path = project_file_path.clone();
+
+        let job = self.jobs.submit_with_cancel_hook(
+            JobKind::CreateProject,
+            &project_file.name,
+            move || {
+                let _lease = locks.lock(&name, "deployment");
+                let started_at = Utc::now();
+                let cloned = clone_while_pulling(compose_client.as_ref(), &images, || {
+                    retry(&retry_policy, &format!("Cloning {}", name), || {
+                        git_client.clone_repository(&source, &repository_dir)
+                    })
+                });
+                let result = cloned.and_then(|_| match deploy {
+                    true => retry(&retry_policy, &format!("Deploying {}", name), || {
+                        compose_up(
+                            compose_client.as_ref(),
+                            &secrets,
+                            &subnets,
+                            &previews,
+                            &compose_files,
+                            &sops,
+                            &repository_dir,
+                            &manifest,
+                            git_client
+                                .get_current_revision(&repository_dir)
+                                .ok()
+                                .as_deref(),
+                        )
+                    }),
+                    false => Ok(()),
+                });
+                record_creation_outcome(&project_file_path, &result);
+                record_deployment_outcome(&activity_log, &name, &result);
+                if deploy {
+                    let deployment = finished_deployment(
+                        &name,
+                        DeploymentTrigger::Create,
+                        started_at,
+                        git_client.get_current_revision(&repository_dir).ok(),
+                        &result,
+                    );
+                    record_deployment(&deployments, &deployment);
+                }
+                result
+            },
+            move || record_creation_cancelled(&cancelled_path),
+        );
+
+        Ok(job)
+    }
+
+    /// Pull the project's repository and re-apply its compose file in the background.
+    /// Inline projects have nothing to pull, so their compose file is only re-applied.
+    pub fn sync_project(&self, name: &str) -> Result<Job, ProjectUsecaseError> {
+        self.sync_project_triggered(name, DeploymentTrigger::Manual)
+    }
+
+    /// Sync every project `selector` selects. A project that can't be synced, e.g. one
+    /// being deleted, is reported with why instead of failing the others.
+    pub fn sync_selected(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<QueuedJob>, ProjectUsecaseError> {
+        Ok(self
+            .selected_project_files(selector)?
+            .into_iter()
+            .map(|project_file| match self.sync_project(&project_file.name) {
+                Ok(job) => QueuedJob {
+                    project: project_file.name,
+                    job_id: Some(job.id),
+                    error: None,
+                },
+                Err(e) => QueuedJob {
+                    project: project_file.name,
+                    job_id: None,
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect())
+    }
+
+    /// Like `sync_project`, recording `trigger` as what started it in the history.
+    pub fn sync_project_triggered(
+        &self,
+        name: &str,
+        trigger: DeploymentTrigger,
+    ) -> Result<Job, ProjectUsecaseError> {
+        println!("Syncing project: {}", name);
+        let project_file = self.find_project_file(name)?;
+        if project_file.deletion.is_some() {
+            return Err(ProjectUsecaseError::ProjectDeleting(name.to_string()));
+        }
+        self.enforce_disk_quota(&project_file)?;
+
+        let git_client = Arc::clone(&self.git_client);
+        let compose_client = Arc::clone(&self.compose_client);
+        let secrets = self.secrets.clone();
+        let subnets = self.subnets.clone();
+        let previews = self.previews.clone();
+        let compose_files = self.compose_files.clone();
+        let sops = self.sops.clone();
+        let (_, project_file_path, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let source = self
+            .secrets
+            .resolve_source(&project_file.name, &project_file.source)
+            .map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))?;
+        let manifest = project_file.clone();
+        let retry_policy = self.retry_policy(&project_file);
+        let locks = self.locks.clone();
+        let activity_log = self.activity_log.clone();
+        let deployments = self.deployments.clone();
+        let name = project_file.name.clone();
+        record_activity(
+            &activity_log,
+            &name,
+            ActivityKind::Deployment,
+            &match project_file.inline {
+                true => "Sync started for the uploaded compose file".to_string(),
+                false => format!("Sync started for {}@{}", source.url, source.branch),
+            },
+        );
+
+        if project_file.inline {
+            return Ok(self
+                .jobs
+                .submit(JobKind::SyncProject, &project_file.name, move || {
+                    let _lease = locks.lock(&name, "deployment");
+                    let started_at = Utc::now();
+                    let result = retry(&retry_policy, &format!("Deploying {}", name), || {
+                        compose_up(
+                            compose_client.as_ref(),
+                            &secrets,
+                            &subnets,
+                            &previews,
+                            &compose_files,
+                            &sops,
+                            &repository_dir,
+                            &manifest,
+                            None,
+                        )
+                    });
+                    if result.is_ok() {
+                        record_creation_outcome(&project_file_path, &result);
+                    }
+                    record_deployment_outcome(&activity_log, &name, &result);
+                    let deployment = finished_deployment(&name, trigger, started_at, None, &result);
+                    record_deployment(&deployments, &deployment);
+                    result
+                }));
+        }
+
+        let standby = StandbyCheckouts::new(
+            Path::new(&self.resources_config.repositories_dir),
+            &name,
+            self.resources_config.retained_revisions,
+        );
+
+        let job = self
+            .jobs
+            .submit(JobKind::SyncProject, &project_file.name, move || {
+                let _lease = locks.lock(&name, "deployment");
+                let started_at = Utc::now();
+                let previous_revision = git_client.get_current_revision(&repository_dir).ok();
+                let result = retry(&retry_policy, &format!("Pulling {}", name), || {
+                    git_client.pull_repository(&source, &repository_dir)
+                })
+                .and_then(|_| {
+                    retry(&retry_policy, &format!("Deploying {}", name), || {
+                        compose_up(
+                            compose_client.as_ref(),
+                            &secrets,
+                            &subnets,
+                            &previews,
+                            &compose_files,
+                            &sops,
+                            &repository_dir,
+                            &manifest,
+                            git_client
+                                .get_current_revision(&repository_dir)
+                                .ok()
+                                .as_deref(),
+                        )
+                    })
+                });
+                if result.is_ok() {
+                    keep_on_standby(
+                        git_client.as_ref(),
+                        &standby,
+                        &repository_dir,
+                        previous_revision,
+                    );
+                    record_creation_outcome(&project_file_path, &result);
+                }
+                record_deployment_outcome(&activity_log, &name, &result);
+                let deployment = finished_deployment(
+                    &name,
+                    trigger,
+                    started_at,
+                    git_client.get_current_revision(&repository_dir).ok(),
+                    &result,
+                );
+                record_deployment(&deployments, &deployment);
+                result
+            });
+
+        Ok(job)
+    }
+
+    /// What the reconciler and webhooks deploy new commits through. Like
+    /// `sync_project_triggered`, except for projects whose manifest requires approval: their
+    /// new commits are recorded as a pending deployment instead, and no job is queued.
+    pub fn deploy_new_commits(
+        &self,
+        name: &str,
+        trigger: DeploymentTrigger,
+    ) -> Result<Option<Job>, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        if project_file.approval.is_automatic() || project_file.inline {
+            return self.sync_project_triggered(name, trigger).map(Some);
+        }
+        // An approved deployment still under way has not moved the checkout yet.
+        if self.jobs.in_progress(&project_file.name) {
+            return Ok(None);
+        }
+
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let source = self
+            .secrets
+            .resolve_source(&project_file.name, &project_file.source)
+            .map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))?;
+        let remote = self
+            .git_client
+            .get_remote_revision(&source)
+            .map_err(|e| ProjectUsecaseError::ApprovalFailed(e.to_string()))?;
+        let local = self
+            .git_client
+            .get_current_revision(&repository_dir)
+            .map_err(|e| ProjectUsecaseError::ApprovalFailed(e.to_string()))?;
+        if remote == local {
+            return self.sync_project_triggered(name, trigger).map(Some);
+        }
+
+        self.request_approval(&project_file.name, remote, trigger)?;
+        Ok(None)
+    }
+
+    /// Record `revision` as a deployment awaiting approval, superseding the one pending
+    /// before, unless it is the one pending already.
+    fn request_approval(
+        &self,
+        name: &str,
+        revision: String,
+        trigger: DeploymentTrigger,
+    ) -> Result<(), ProjectUsecaseError> {
+        let pending = self
+            .deployments
+            .latest(name, DeploymentOutcome::Pending)
+            .map_err(|e| ProjectUsecaseError::ReadHistoryFailed(e.to_string()))?;
+        if let Some(pending) = pending {
+            if pending.revision.as_deref() == Some(revision.as_str()) {
+                return Ok(());
+            }
+            self.deployments
+                .update(&Deployment {
+                    outcome: DeploymentOutcome::Superseded,
+                    ..pending
+                })
+                .map_err(|e| ProjectUsecaseError::ApprovalFailed(e.to_string()))?;
+        }
+
+        let now = Utc::now();
+        let id = self
+            .deployments
+            .record(&Deployment {
+                id: 0,
+                project: name.to_string(),
+                revision: Some(revision.clone()),
+                trigger,
+                started_at: now,
+                finished_at: now,
+                duration_ms: 0,
+                outcome: DeploymentOutcome::Pending,
+                error: None,
+                rollback_of: None,
+            })
+            .map_err(|e| ProjectUsecaseError::ApprovalFailed(e.to_string()))?;
+        record_activity(
+            &self.activity_log,
+            name,
+            ActivityKind::Deployment,
+            &format!("{} awaits approval as deployment {}", revision, id),
+        );
+        Ok(())
+    }
+
+    /// Check out the revision of the project's deployment `deployment_id` and re-apply its
+    /// compose file in the background, recording a rollback that links to that deployment.
+    /// The next sync moves the project forward again.
+    pub fn rollback_project(
+        &self,
+        name: &str,
+        deployment_id: i64,
+    ) -> Result<Job, ProjectUsecaseError> {
+        println!(
+            "Rolling back project {} to deployment {}",
+            name, deployment_id
+        );
+        let project_file = self.find_project_file(name)?;
+        if project_file.deletion.is_some() {
+            return Err(ProjectUsecaseError::ProjectDeleting(name.to_string()));
+        }
+        if project_file.inline {
+            return Err(ProjectUsecaseError::RollbackUnavailable(format!(
+                "{} has no git history to roll back to",
+                name
+            )));
+        }
+        let target = self
+            .deployments
+            .get(name, deployment_id)
+            .map_err(|e| ProjectUsecaseError::ReadHistoryFailed(e.to_string()))?
+            .ok_or_else(|| {
+                ProjectUsecaseError::DeploymentNotFound(format!("{}/{}", name, deployment_id))
+            })?;
+        if matches!(
+            target.outcome,
+            DeploymentOutcome::Pending | DeploymentOutcome::Superseded
+        ) {
+            return Err(ProjectUsecaseError::RollbackUnavailable(format!(
+                "deployment {} of {} was never deployed",
+                deployment_id, name
+            )));
+        }
+        let Some(revision) = target.revision else {
+            return Err(ProjectUsecaseError::RollbackUnavailable(format!(
+                "deployment {} of {} recorded no revision",
+                deployment_id, name
+            )));
+        };
+
+        self.deploy_revision(
+            &project_file,
+            revision,
+            RevisionDeployment::Rollback(deployment_id),
+        )
+    }
+
+    /// Deploy the revision of the project's pending deployment `deployment_id` in the
+    /// background. The pending deployment is completed in place with the outcome.
+    pub fn approve_deployment(
+        &self,
+        name: &str,
+        deployment_id: i64,
+    ) -> Result<Job, ProjectUsecaseError> {
+        println!("Approving deployment {} of project {}", deployment_id, name);
+        let project_file = self.find_project_file(name)?;
+        if project_file.deletion.is_some() {
+            return Err(ProjectUsecaseError::ProjectDeleting(name.to_string()));
+        }
+        let pending = self
+            .deployments
+            .get(name, deployment_id)
+            .map_err(|e| ProjectUsecaseError::ReadHistoryFailed(e.to_string()))?
+            .ok_or_else(|| {
+                ProjectUsecaseError::DeploymentNotFound(format!("{}/{}", name, deployment_id))
+            })?;
+        if pending.outcome != DeploymentOutcome::Pending {
+            return Err(ProjectUsecaseError::DeploymentNotPending(format!(
+                "deployment {} of {} is {}",
+                deployment_id,
+                name,
+                pending.outcome.as_str()
+            )));
+        }
+        let Some(revision) = pending.revision.clone() else {
+            return Err(ProjectUsecaseError::ApprovalFailed(format!(
+                "deployment {} of {} recorded no revision",
+                deployment_id, name
+            )));
+        };
+
+        let approved = Deployment {
+            outcome: DeploymentOutcome::Approved,
+            ..pending.clone()
+        };
+        self.deployments
+            .update(&approved)
+            .map_err(|e| ProjectUsecaseError::ApprovalFailed(e.to_string()))?;
+        self.deploy_revision(
+            &project_file,
+            revision,
+            RevisionDeployment::Approval(approved),
+        )
+        .inspect_err(|_| update_deployment(&self.deployments, &pending))
+    }
+
+    /// Check out `revision`, from its standby checkout when one is kept, and re-apply the
+    /// project's compose file in the background, recording the deployment as `purpose` asks.
+    fn deploy_revision(
+        &self,
+        project_file: &ProjectFile,
+        revision: String,
+        purpose: RevisionDeployment,
+    ) -> Result<Job, ProjectUsecaseError> {
+        let git_client = Arc::clone(&self.git_client);
+        let compose_client = Arc::clone(&self.compose_client);
+        let secrets = self.secrets.clone();
+        let subnets = self.subnets.clone();
+        let previews = self.previews.clone();
+        let compose_files = self.compose_files.clone();
+        let sops = self.sops.clone();
+        let (_, project_file_path, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let source = self
+            .secrets
+            .resolve_source(&project_file.name, &project_file.source)
+            .map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))?;
+        let manifest = project_file.clone();
+        let retry_policy = self.retry_policy(project_file);
+        let locks = self.locks.clone();
+        let activity_log = self.activity_log.clone();
+        let deployments = self.deployments.clone();
+        let standby = StandbyCheckouts::new(
+            Path::new(&self.resources_config.repositories_dir),
+            &project_file.name,
+            self.resources_config.retained_revisions,
+        );
+        let name = project_file.name.clone();
+        let (kind, message) = match &purpose {
+            RevisionDeployment::Rollback(of) => (
+                JobKind::RollbackProject,
+                format!("Rollback started to {} of deployment {}", revision, of),
+            ),
+            RevisionDeployment::Approval(approved) => (
+                JobKind::DeployApproved,
+                format!(
+                    "Deployment {} of {} approved and started",
+                    approved.id, revision
+                ),
+            ),
+        };
+        record_activity(&activity_log, &name, ActivityKind::Deployment, &message);
+
+        Ok(self.jobs.submit(kind, &project_file.name, move || {
+            let _lease = locks.lock(&name, "deployment");
+            let started_at = Utc::now();
+            let previous_revision = git_client.get_current_revision(&repository_dir).ok();
+            let checkout = match standby.get(&revision) {
+                Some(worktree) => git_client.checkout_worktree(&repository_dir, &worktree),
+                None => retry(&retry_policy, &format!("Checking out {}", revision), || {
+                    git_client.checkout_revision(&source, &repository_dir, &revision)
+                }),
+            };
+            let result = checkout.and_then(|_| {
+                retry(&retry_policy, &format!("Deploying {}", name), || {
+                    compose_up(
+                        compose_client.as_ref(),
+                        &secrets,
+                        &subnets,
+                        &previews,
+                        &compose_files,
+                        &sops,
+                        &repository_dir,
+                        &manifest,
+                        git_client
+                            .get_current_revision(&repository_dir)
+                            .ok()
+                            .as_deref(),
+                    )
+                })
+            });
+            if result.is_ok() {
+                keep_on_standby(
+                    git_client.as_ref(),
+                    &standby,
+                    &repository_dir,
+                    previous_revision,
+                );
+                record_creation_outcome(&project_file_path, &result);
+            }
+            record_deployment_outcome(&activity_log, &name, &result);
+            let deployed = git_client.get_current_revision(&repository_dir).ok();
+            match purpose {
+                RevisionDeployment::Rollback(of) => {
+                    let deployment = Deployment {
+                        rollback_of: Some(of),
+                        ..finished_deployment(
+                            &name,
+                            DeploymentTrigger::Rollback,
+                            started_at,
+                            deployed,
+                            &result,
+                        )
+                    };
+                    record_deployment(&deployments, &deployment);
+                }
+                RevisionDeployment::Approval(approved) => {
+                    let deployment = Deployment {
+                        id: approved.id,
+                        ..finished_deployment(
+                            &name,
+                            approved.trigger,
+                            started_at,
+                            deployed,
+                            &result,
+                        )
+                    };
+                    update_deployment(&deployments, &deployment);
+                }
+            }
+            result
+        }))
+    }
+
+    /// Pull `images` and re-apply the project's compose file in the background, so the
+    /// services running them are recreated.
+    pub fn update_images(
+        &self,
+        name: &str,
+        images: Vec<String>,
+    ) -> Result<Job, ProjectUsecaseError> {
+        println!("Updating images of project: {}", name);
+        let project_file = self.find_project_file(name)?;
+        if project_file.deletion.is_some() {
+            return Err(ProjectUsecaseError::ProjectDeleting(name.to_string()));
+        }
+
+        let git_client = Arc::clone(&self.git_client);
+        let compose_client = Arc::clone(&self.compose_client);
+        let secrets = self.secrets.clone();
+        let subnets = self.subnets.clone();
+        let previews = self.previews.clone();
+        let compose_files = self.compose_files.clone();
+        let sops = self.sops.clone();
+        let (_, project_file_path, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let manifest = project_file.clone();
+        let retry_policy = self.retry_policy(&project_file);
+        let locks = self.locks.clone();
+        let activity_log = self.activity_log.clone();
+        let deployments = self.deployments.clone();
+        let name = project_file.name.clone();
+        record_activity(
+            &activity_log,
+            &name,
+            ActivityKind::Deployment,
+            &format!("New image digests found for {}", images.join(", ")),
+        );
+
+        Ok(self
+            .jobs
+            .submit(JobKind::SyncProject, &project_file.name, move || {
+                let _lease = locks.lock(&name, "deployment");
+                let started_at = Utc::now();
+                let revision = match manifest.inline {
+                    true => None,
+                    false => git_client.get_current_revision(&repository_dir).ok(),
+                };
+                let result = images
+                    .iter()
+                    .try_for_each(|image| {
+                        retry(&retry_policy, &format!("Pulling {}", image), || {
+                            compose_client
+                                .pull_image(image)
+                                .map_err(|e| anyhow!(e.to_string()))
+                        })
+                    })
+                    .and_then(|_| {
+                        retry(&retry_policy, &format!("Deploying {}", name), || {
+                            compose_up(
+                                compose_client.as_ref(),
+                                &secrets,
+                                &subnets,
+                                &previews,
+                                &compose_files,
+                                &sops,
+                                &repository_dir,
+                                &manifest,
+                                revision.as_deref(),
+                            )
+                        })
+                    });
+                if result.is_ok() {
+                    record_creation_outcome(&project_file_path, &result);
+                }
+                record_deployment_outcome(&activity_log, &name, &result);
+                let deployment = finished_deployment(
+                    &name,
+                    DeploymentTrigger::ImageUpdate,
+                    started_at,
+                    revision,
+                    &result,
+                );
+                record_deployment(&deployments, &deployment);
+                result
+            }))
+    }
+
+    /// Deal with the jobs a crash or restart of gfc interrupted: deployments and deletes
+    /// are queued again, and each interrupted job is marked failed with what became of it.
+    /// Rollbacks are not retried, as the revision they rolled back to is not kept, and
+    /// approved deployments go back to pending to be approved again.
+    /// Returns the jobs queued in their place.
+    pub fn resume_interrupted_jobs(&self) -> Vec<Job> {
+        let mut resumed = Vec::new();
+        for job in self.jobs.interrupted() {
+            let requeued = match job.kind {
+                JobKind::CreateProject | JobKind::SyncProject => self
+                    .sync_project_triggered(&job.project, DeploymentTrigger::Startup)
+                    .map(Some),
+                JobKind::DeleteProject => self.delete_project(&job.project).map(Some),
+                JobKind::RollbackProject => Ok(None),
+                JobKind::DeployApproved => {
+                    self.reopen_approval(&job.project);
+                    Ok(None)
+                }
+            };
+            let reason = match &requeued {
+                Ok(Some(requeued)) => format!(
+                    "Interrupted by a restart of gfc, and queued again as job {}",
+                    requeued.id
+                ),
+                Ok(None) if job.kind == JobKind::DeployApproved => {
+                    "Interrupted by a restart of gfc; approve the deployment again to retry"
+                        .to_string()
+                }
+                Ok(None) => "Interrupted by a restart of gfc; roll back again to retry".to_string(),
+                Err(e) => format!(
+                    "Interrupted by a restart of gfc, and could not be queued again: {}",
+                    e
+                ),
+            };
+            self.jobs.fail_interrupted(job, reason);
+            resumed.extend(requeued.ok().flatten());
+        }
+        resumed
+    }
+
+    /// Put the project's approved deployment back to pending, for an approval whose job
+    /// was interrupted.
+    fn reopen_approval(&self, name: &str) {
+        match self.deployments.latest(name, DeploymentOutcome::Approved) {
+            Ok(Some(approved)) => update_deployment(
+                &self.deployments,
+                &Deployment {
+                    outcome: DeploymentOutcome::Pending,
+                    ..approved
+                },
+            ),
+            Ok(None) => {}
+            Err(e) => println!("Failed to reopen the approval of {}: {}", name, e),
+        }
+    }
+
+    /// Tear the project down and remove everything gfc keeps for it, returning the job to
+    /// poll. The project file is marked first and removed last, so a project whose
+    /// teardown fails partway stays listed as `DeleteFailed`, and deleting it again picks
+    /// up where the last attempt stopped.
+    pub fn delete_project(&self, name: &str) -> Result<Job, ProjectUsecaseError> {
+        println!("Deleting project: {}", name);
+        let mut project_file = self.find_project_file(name)?;
+        let (project_dir, project_file_path, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        project_file.deletion = Some(Deletion::default());
+        write_manifest(&project_file_path, &project_file)
+            .map_err(|e| ProjectUsecaseError::DeleteProjectFailed(e.to_string()))?;
+        record_activity(
+            &self.activity_log,
+            &project_file.name,
+            ActivityKind::ManualAction,
+            "Deletion started",
+        );
+
+        let compose_client = Arc::clone(&self.compose_client);
+        let secrets = self.secrets.clone();
+        let standby = StandbyCheckouts::new(
+            Path::new(&self.resources_config.repositories_dir),
+            &project_file.name,
+            self.resources_config.retained_revisions,
+        );
+        let activity_log = self.activity_log.clone();
+        let deployments = self.deployments.clone();
+        let locks = self.locks.clone();
+        let name = project_file.name.clone();
+
+        Ok(self.jobs.submit(JobKind::DeleteProject, &name, move || {
+            let _lease = locks.lock(&project_file.name, "deletion");
+            let result = tear_down(
+                compose_client.as_ref(),
+                &secrets,
+                &standby,
+                &project_file.name,
+                &repository_dir,
+                &project_dir,
+            );
+            if let Err(e) = &result {
+                project_file.deletion = Some(Deletion {
+                    error: Some(e.to_string()),
+                });
+                if let Err(e) = write_manifest(&project_file_path, &project_file) {
+                    println!("Failed to mark {} as not deleted: {}", project_file.name, e);
+                }
+                record_activity(
+                    &activity_log,
+                    &project_file.name,
+                    ActivityKind::ManualAction,
+                    &format!("Deletion failed: {}", e),
+                );
+            } else if let Err(e) = deployments.remove(&project_file.name) {
+                println!(
+                    "Failed to forget deployments of {}: {}",
+                    project_file.name, e
+                );
+            }
+            result
+        }))
+    }
+
+    /// Deploy `head_branch` of `head_url` as the preview of pull request `number` of the
+    /// project, creating the preview with copies of the project's secrets on first use
+    /// and syncing it after that.
+    pub fn preview_pull_request(
+        &self,
+        project_file: &ProjectFile,
+        number: u64,
+        head_url: &str,
+        head_branch: &str,
+    ) -> Result<Job, ProjectUsecaseError> {
+        let name = preview_name(&project_file.name, number);
+        if let Ok(existing) = self.find_project_file(&name) {
+            if !is_preview_of(&existing, &project_file.name) {
+                return Err(ProjectUsecaseError::CreateProjectFailed(format!(
+                    "{} is taken by a project that is not a preview",
+                    name
+                )));
+            }
+            return self.sync_project_triggered(&name, DeploymentTrigger::Webhook);
+        }
+
+        let store = self.secrets.store();
+        let copy_secrets = || -> Result<()> {
+            for secret in store.names(&project_file.name)? {
+                store.put(&name, &secret, &store.get(&project_file.name, &secret)?)?;
+            }
+            Ok(())
+        };
+        copy_secrets().map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))?;
+        let preview = preview_project_file(project_file, number, head_url, head_branch);
+        self.create_project(preview).inspect_err(|_| {
+            if let Err(e) = store.remove_all(&name) {
+                println!("Failed to remove secrets copied to {}: {}", name, e);
+            }
+        })
+    }
 
-        tokio::task::spawn_blocking(move || {
-            let _ = git_client.clone_repository(&source, &repository_dir);
-            let _ = compose_client.up(repository_dir.to_str().unwrap());
+    /// Delete the preview of pull request `number` of the project, if it has one that is
+    /// not being deleted already.
+    pub fn close_preview(
+        &self,
+        project_name: &str,
+        number: u64,
+    ) -> Result<Option<Job>, ProjectUsecaseError> {
+        let name = preview_name(project_name, number);
+        match self.find_project_file(&name) {
+            Ok(preview) if is_preview_of(&preview, project_name) && preview.deletion.is_none() => {
+                Ok(Some(self.delete_project(&name)?))
+            }
+            Ok(_) | Err(ProjectUsecaseError::ProjectNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// How busy each job queue is, for `GET /jobs/queues` and the metrics.
+    pub fn job_queues(&self) -> Vec<QueueStats> {
+        self.jobs.queue_stats()
+    }
+
+    /// The project's latest deployments, newest first.
+    pub fn project_deployments(
+        &self,
+        name: &str,
+        query: &DeploymentsQuery,
+    ) -> Result<GenericResponse<Deployment>, ProjectUsecaseError> {
+        self.find_project_file(name)?;
+        let deployments = self
+            .deployments
+            .list(name, query.limit())
+            .map_err(|e| ProjectUsecaseError::ReadHistoryFailed(e.to_string()))?;
+        Ok(GenericResponse::results(deployments))
+    }
+
+    pub fn job(&self, id: &str) -> Result<Job, ProjectUsecaseError> {
+        self.jobs
+            .get(id)
+            .ok_or_else(|| ProjectUsecaseError::JobNotFound(id.to_string()))
+    }
+
+    /// Cancel a queued or running job, for `DELETE /jobs/{id}`.
+    pub fn cancel_job(&self, id: &str) -> Result<Job, ProjectUsecaseError> {
+        let job = self.job(id)?;
+        if job.status.is_finished() {
+            return Err(ProjectUsecaseError::JobFinished(id.to_string()));
+        }
+        self.jobs
+            .cancel(id)
+            .ok_or_else(|| ProjectUsecaseError::JobNotFound(id.to_string()))
+    }
+
+    pub fn list_projects(
+        &self,
+        deadline: &Deadline,
+    ) -> Result<GenericResponse<Project>, ProjectUsecaseError> {
+        Ok(GenericResponse::results(self.resolve_projects(deadline)?))
+    }
+
+    /// List projects along with an ETag over their manifests and container states, so
+    /// polling clients can skip listings that have not changed. Humanized times are
+    /// covered by the ETag, so they are not served stale.
+    pub fn list_projects_with_etag(
+        &self,
+        deadline: &Deadline,
+        humanize: bool,
+        selector: &LabelSelector,
+    ) -> Result<(GenericResponse<Project>, String), ProjectUsecaseError> {
+        let project_files = self.selected_project_files(selector)?;
+        let mut projects = self
+            .resolve_projects_lazily(deadline, selector)?
+            .collect::<Result<Vec<_>, _>>()?;
+        if humanize {
+            let now = Utc::now();
+            projects
+                .iter_mut()
+                .for_each(|project| project.humanize(now));
+        }
+        let etag = listing_etag(&project_files, &projects);
+        Ok((GenericResponse::results(projects), etag))
+    }
+
+    /// Resolve the status of every project. The git and docker commands run for it are
+    /// killed once `deadline` passes, and it stops there, reporting how far it got.
+    pub fn resolve_projects(
+        &self,
+        deadline: &Deadline,
+    ) -> Result<Vec<Project>, ProjectUsecaseError> {
+        self.resolve_projects_lazily(deadline, &LabelSelector::default())?
+            .collect()
+    }
+
+    /// Like `resolve_projects`, for the projects `selector` selects, resolving each
+    /// project only as the iterator is advanced, so callers can hand out projects as soon
+    /// as they are ready.
+    pub fn resolve_projects_lazily<'a>(
+        &'a self,
+        deadline: &'a Deadline,
+        selector: &LabelSelector,
+    ) -> Result<impl Iterator<Item = Result<Project, ProjectUsecaseError>> + 'a, ProjectUsecaseError>
+    {
+        let project_files = self.selected_project_files(selector)?;
+        let total = project_files.len();
+
+        Ok(project_files
+            .into_iter()
+            .enumerate()
+            .map(move |(resolved, project_file)| {
+                let exceeded = || {
+                    ProjectUsecaseError::DeadlineExceeded(format!(
+                        "resolved {} of {} projects, next was {}",
+                        resolved, total, project_file.name
+                    ))
+                };
+                if deadline.is_expired() {
+                    return Err(exceeded());
+                }
+
+                process::with_time_limit(deadline.remaining(), || self.to_project(&project_file))
+                    .map_err(|e| match deadline.is_expired() {
+                        true => exceeded(),
+                        false => ProjectUsecaseError::ListProjectsFailed(e.to_string()),
+                    })
+            }))
+    }
+
+    pub fn project_activity(
+        &self,
+        name: &str,
+        query: &ActivityQuery,
+    ) -> Result<GenericResponse<ActivityPage>, ProjectUsecaseError> {
+        self.find_project_file(name)?;
+        let entries = self
+            .activity_log
+            .list(name)
+            .map_err(|e| ProjectUsecaseError::ReadActivityFailed(e.to_string()))?;
+
+        Ok(GenericResponse::result(ActivityPage::paginate(
+            entries, query,
+        )))
+    }
+
+    pub fn project_status(
+        &self,
+        deadline: &Deadline,
+        name: &str,
+    ) -> Result<GenericResponse<ProjectStatusDetail>, ProjectUsecaseError> {
+        within_deadline(
+            deadline,
+            || format!("reading the status of {}", name),
+            || self.read_project_status(name),
+        )
+    }
+
+    fn read_project_status(
+        &self,
+        name: &str,
+    ) -> Result<GenericResponse<ProjectStatusDetail>, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        if let Some(status) = project_file.lifecycle_status() {
+            return Ok(GenericResponse::result(ProjectStatusDetail {
+                name: project_file.name,
+                status,
+                containers: vec![],
+                failures: vec![],
+            }));
+        }
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let containers = self
+            .compose_client
+            .inspect_containers(repository_dir.to_str().unwrap())
+            .map_err(|e| ProjectUsecaseError::ReadStatusFailed(e.to_string()))?;
+
+        Ok(GenericResponse::result(ProjectStatusDetail {
+            name: project_file.name,
+            status: build_project_status(&containers),
+            failures: container_failures(&containers),
+            containers,
+        }))
+    }
+
+    /// Just the overall status, without the per-container detail.
+    pub fn project_status_summary(&self, name: &str) -> Result<ProjectStatus, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        match project_file.lifecycle_status() {
+            Some(status) => Ok(status),
+            None => self.container_status_for(&project_file.name),
+        }
+    }
+
+    /// Lifecycle events of the project's containers from now on, until dropped.
+    pub fn project_events(
+        &self,
+        name: &str,
+    ) -> Result<Box<dyn Iterator<Item = ProjectEvent> + Send>, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        self.compose_client
+            .events(&project_file.name)
+            .map_err(|e| ProjectUsecaseError::ReadStatusFailed(e.to_string()))
+    }
+
+    /// The images of the project's services whose registry has a digest that was not
+    /// pulled. Images the registry cannot be asked about, e.g. ones built locally, are
+    /// skipped.
+    pub fn outdated_images(&self, project_file: &ProjectFile) -> Result<Vec<String>> {
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let overrides = extra_compose_files(&repository_dir, &project_file.source);
+        let images = self
+            .compose_client
+            .images(repository_dir.to_str().unwrap(), &overrides)
+            .map_err(|e| anyhow!(e.to_string()))?;
+
+        let mut outdated = vec![];
+        for image in images {
+            let remote = match self.compose_client.remote_image_digest(&image) {
+                Ok(remote) => remote,
+                Err(e) => {
+                    println!("Skipping {} of {}: {}", image, project_file.name, e);
+                    continue;
+                }
+            };
+            let local = self
+                .compose_client
+                .local_image_digests(&image)
+                .map_err(|e| anyhow!(e.to_string()))?;
+            if is_outdated(&local, &remote) {
+                outdated.push(image);
+            }
+        }
+        Ok(outdated)
+    }
+
+    /// Whether the remote branch has moved past the commit checked out. Always false for
+    /// inline projects.
+    pub fn has_remote_changes(&self, project_file: &ProjectFile) -> Result<bool> {
+        if project_file.inline {
+            return Ok(false);
+        }
+
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let source = self
+            .secrets
+            .resolve_source(&project_file.name, &project_file.source)?;
+        let remote = self.git_client.get_remote_revision(&source)?;
+        let local = self.git_client.get_current_revision(&repository_dir)?;
+        Ok(remote != local)
+    }
+
+    /// Whether the commits from `before` to `after` change any of the project's watched
+    /// paths, fetching them into its checkout as needed. Always true for projects watching
+    /// their whole repository, and for those not checked out yet.
+    pub fn is_changed_between(
+        &self,
+        project_file: &ProjectFile,
+        before: &str,
+        after: &str,
+    ) -> Result<bool> {
+        if project_file.watched_paths().iter().any(String::is_empty) {
+            return Ok(true);
+        }
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        if !repository_dir.exists() {
+            return Ok(true);
+        }
+
+        let source = self
+            .secrets
+            .resolve_source(&project_file.name, &project_file.source)?;
+        for revision in [before, after] {
+            self.git_client
+                .fetch_revision(&source, &repository_dir, revision)?;
+        }
+        let changed = self
+            .git_client
+            .changed_files(&repository_dir, before, after)?;
+        Ok(project_file.is_affected_by(&changed))
+    }
+
+    /// What syncing the project would change, fetched from its remote without moving the
+    /// deployed checkout: the new commits, and the difference in its compose
+    /// configuration, rendered at both revisions. The git and docker commands are killed
+    /// once `deadline` passes, and the error then says which step was cut short.
+    pub fn diff_project(
+        &self,
+        deadline: &Deadline,
+        name: &str,
+    ) -> Result<ProjectDiff, ProjectUsecaseError> {
+        let step = Cell::new("resolving revisions");
+        within_deadline(
+            deadline,
+            || format!("diff of {} stopped while {}", name, step.get()),
+            || self.compare_with_remote(name, &step),
+        )
+    }
+
+    fn compare_with_remote(
+        &self,
+        name: &str,
+        step: &Cell<&'static str>,
+    ) -> Result<ProjectDiff, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        if project_file.inline {
+            return Err(ProjectUsecaseError::DiffUnavailable(format!(
+                "{} has no git remote to compare with",
+                name
+            )));
+        }
+        let _lease = self.try_lock(name, "diff")?;
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let source = self
+            .secrets
+            .resolve_source(&project_file.name, &project_file.source)
+            .map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))?;
+        let diff_failed = |e: anyhow::Error| ProjectUsecaseError::DiffFailed(e.to_string());
+
+        let deployed_revision = self
+            .git_client
+            .get_current_revision(&repository_dir)
+            .map_err(diff_failed)?;
+        let remote_revision = self
+            .git_client
+            .get_remote_revision(&source)
+            .map_err(diff_failed)?;
+        if deployed_revision == remote_revision {
+            return Ok(ProjectDiff {
+                deployed_revision,
+                remote_revision,
+                commits: vec![],
+                compose_diff: String::new(),
+            });
+        }
+
+        step.set("fetching the remote revision");
+        self.git_client
+            .fetch_revision(&source, &repository_dir, &remote_revision)
+            .map_err(diff_failed)?;
+        let commits = self
+            .git_client
+            .list_commits(&repository_dir, &deployed_revision, &remote_revision)
+            .map_err(diff_failed)?;
+        step.set("rendering the deployed compose config");
+        let deployed_config =
+            rendered_compose_config(self.compose_client.as_ref(), &repository_dir, &source)
+                .map_err(diff_failed)?;
+
+        step.set("checking out the remote revision");
+        let checkout = TempDir::new().map_err(|e| diff_failed(e.into()))?;
+        let remote_dir = checkout.path().join(&project_file.name);
+        self.git_client
+            .add_worktree(&repository_dir, &remote_revision, &remote_dir)
+            .map_err(diff_failed)?;
+        step.set("rendering the remote compose config");
+        let remote_config =
+            rendered_compose_config(self.compose_client.as_ref(), &remote_dir, &source);
+        if let Err(e) = self
+            .git_client
+            .remove_worktree(&repository_dir, &remote_dir)
+        {
+            println!("Failed to remove {}: {}", remote_dir.display(), e);
+        }
+
+        Ok(ProjectDiff {
+            compose_diff: compose_diff(
+                &deployed_config,
+                &remote_config.map_err(diff_failed)?,
+                &deployed_revision,
+                &remote_revision,
+            ),
+            deployed_revision,
+            remote_revision,
+            commits,
+        })
+    }
+
+    /// Whether the project's repository, or uploaded compose file, is checked out.
+    pub fn has_checkout(&self, project_file: &ProjectFile) -> bool {
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        repository_dir.exists()
+    }
+
+    /// Whether the project's containers drifted from its compose file. See
+    /// [`Project::drifted`].
+    pub fn detect_drift(&self, project_file: &ProjectFile) -> Result<bool, ProjectUsecaseError> {
+        let containers = self.containers_for(&project_file.name)?;
+        let status = build_project_status(&containers);
+        Ok(self.drift_for(project_file, &containers, &status))
+    }
+
+    /// Remove compose networks left behind by projects that are gone: no manifest, no
+    /// stack on the engine and no containers attached. Returns the names removed; a
+    /// network that fails to be removed is skipped.
+    pub fn prune_networks(&self) -> Result<Vec<String>, ProjectUsecaseError> {
+        let mut live_projects = self
+            .project_files()?
+            .into_iter()
+            .map(|project_file| project_file.name)
+            .collect::<HashSet<_>>();
+        live_projects.extend(
+            self.compose_client
+                .list_stacks()
+                .map_err(|e| ProjectUsecaseError::PruneNetworksFailed(e.to_string()))?
+                .into_iter()
+                .map(|stack| stack.name),
+        );
+        let networks = self
+            .compose_client
+            .list_networks()
+            .map_err(|e| ProjectUsecaseError::PruneNetworksFailed(e.to_string()))?;
+
+        let mut removed = Vec::new();
+        for network in dangling_networks(&networks, &live_projects) {
+            match self.compose_client.remove_network(&network.name) {
+                Ok(()) => removed.push(network.name.clone()),
+                Err(e) => println!("Failed to remove network {}: {}", network.name, e),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove what is left of projects whose project file is gone: compose projects
+    /// started from their checkouts, the checkouts themselves, and dangling networks.
+    /// Compose projects started from anywhere else are left alone. Removals that fail are
+    /// logged and left out of the report.
+    pub fn prune_orphans(&self) -> Result<PruneReport, ProjectUsecaseError> {
+        let managed = self
+            .project_files()?
+            .into_iter()
+            .map(|project_file| project_file.name)
+            .collect::<HashSet<_>>();
+        let repositories_dir = Path::new(&self.resources_config.repositories_dir);
+        let repositories_dir = fs::canonicalize(repositories_dir)
+            .or_else(|_| std::path::absolute(repositories_dir))
+            .map_err(|e| ProjectUsecaseError::PruneFailed(e.to_string()))?;
+        let stacks = self
+            .compose_client
+            .list_stacks()
+            .map_err(|e| ProjectUsecaseError::PruneFailed(e.to_string()))?;
+
+        let mut report = PruneReport::default();
+        for stack in orphaned_stacks(&stacks, &managed, &repositories_dir) {
+            match self.compose_client.remove_stack(&stack.name) {
+                Ok(()) => report.stacks.push(stack.name.clone()),
+                Err(e) => println!("Failed to remove compose project {}: {}", stack.name, e),
+            }
+        }
+        let checkouts = orphaned_checkouts(&repositories_dir, &managed)
+            .map_err(|e| ProjectUsecaseError::PruneFailed(e.to_string()))?;
+        for checkout in checkouts {
+            match fs::remove_dir_all(&checkout) {
+                Ok(()) => report.repositories.push(checkout.display().to_string()),
+                Err(e) => println!("Failed to remove {}: {}", checkout.display(), e),
+            }
+        }
+        report.networks = self.prune_networks()?;
+        Ok(report)
+    }
+
+    /// Disk the project's checkouts and named volumes take up, against its quota.
+    pub fn disk_usage(&self, name: &str) -> Result<ProjectDiskUsage, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        self.measure_disk_usage(&project_file)
+    }
+
+    fn measure_disk_usage(
+        &self,
+        project_file: &ProjectFile,
+    ) -> Result<ProjectDiskUsage, ProjectUsecaseError> {
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let standby = StandbyCheckouts::new(
+            Path::new(&self.resources_config.repositories_dir),
+            &project_file.name,
+            self.resources_config.retained_revisions,
+        );
+        let repository_bytes = directory_size(&repository_dir)
+            .map_err(anyhow::Error::from)
+            .and_then(|checkout| Ok(checkout + standby.disk_usage()?))
+            .map_err(|e| ProjectUsecaseError::DiskUsageFailed(e.to_string()))?;
+        let volumes = self
+            .compose_client
+            .volume_usage(&project_file.name)
+            .map_err(|e| ProjectUsecaseError::DiskUsageFailed(e.to_string()))?;
+        let quota = project_file.disk_quota_bytes.or(self.disk_quota.max_bytes);
+        Ok(project_disk_usage(repository_bytes, volumes, quota))
+    }
+
+    /// Refuse to sync a project over its disk quota, or record a warning, as configured.
+    /// A sync goes ahead when the usage can't be measured.
+    fn enforce_disk_quota(&self, project_file: &ProjectFile) -> Result<(), ProjectUsecaseError> {
+        if project_file.disk_quota_bytes.is_none() && self.disk_quota.max_bytes.is_none() {
+            return Ok(());
+        }
+        let usage = match self.measure_disk_usage(project_file) {
+            Ok(usage) if usage.over_quota => usage,
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                println!(
+                    "Failed to check the disk quota of {}: {}",
+                    project_file.name, e
+                );
+                return Ok(());
+            }
+        };
+
+        let message = format!(
+            "{} takes up {} bytes, over its quota of {} bytes",
+            project_file.name,
+            usage.total_bytes,
+            usage.quota_bytes.unwrap_or_default()
+        );
+        match self.disk_quota.enforcement {
+            QuotaEnforcement::Refuse => Err(ProjectUsecaseError::QuotaExceeded(message)),
+            QuotaEnforcement::Warn => {
+                record_activity(
+                    &self.activity_log,
+                    &project_file.name,
+                    ActivityKind::Deployment,
+                    &format!("Syncing although {}", message),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Images created more than `retention` ago that no project on the Docker host uses,
+    /// in any revision it keeps checked out; see [`unused_images`].
+    pub fn unused_images(
+        &self,
+        retention: Duration,
+    ) -> Result<Vec<LocalImage>, ProjectUsecaseError> {
+        let images = self
+            .compose_client
+            .list_images()
+            .map_err(|e| ProjectUsecaseError::PruneFailed(e.to_string()))?;
+        let retention = chrono::Duration::from_std(retention)
+            .map_err(|e| ProjectUsecaseError::PruneFailed(e.to_string()))?;
+        Ok(unused_images(
+            &images,
+            &self.referenced_images()?,
+            Utc::now() - retention,
+        ))
+    }
+
+    /// Remove the images [`Self::unused_images`] finds. Removals that fail, e.g. because a
+    /// container outside gfc still uses the image, are reported rather than fatal.
+    pub fn collect_unused_images(
+        &self,
+        retention: Duration,
+    ) -> Result<ImageGcReport, ProjectUsecaseError> {
+        let mut report = ImageGcReport::default();
+        for image in self.unused_images(retention)? {
+            match self.compose_client.remove_image(&image.reference()) {
+                Ok(()) => report.removed.push(image),
+                Err(e) => report.failed.push(format!("{}: {}", image.reference(), e)),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Images of every project of this workspace and of its neighbours, standby
+    /// checkouts included, so a rollback never has to pull again.
+    fn referenced_images(&self) -> Result<Vec<String>, ProjectUsecaseError> {
+        let mut images = Vec::new();
+        for resources in std::iter::once(&self.resources_config).chain(&self.neighbours) {
+            let project_files = find_all_project_files(Path::new(&resources.projects_dir))
+                .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))?;
+            for project_file in project_files {
+                let (_, _, repository_dir) =
+                    get_project_and_repository_paths(resources, &project_file.name);
+                let standby = StandbyCheckouts::new(
+                    Path::new(&resources.repositories_dir),
+                    &project_file.name,
+                    resources.retained_revisions,
+                );
+                let checkouts = std::iter::once(repository_dir).chain(
+                    standby
+                        .list()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|revision| standby.path_for(&revision)),
+                );
+                for checkout in checkouts {
+                    if let Some(compose_file) = checked_out_compose_file(
+                        &self.compose_files,
+                        &checkout,
+                        &project_file.source.path,
+                    ) {
+                        images.extend(compose_file.images());
+                    }
+                }
+            }
+        }
+        Ok(images)
+    }
+
+    /// Bring the project up when its schedule window opens, or stop it when it closes.
+    pub fn apply_schedule(&self, name: &str, active: bool) -> Result<(), ProjectUsecaseError> {
+        let _lease = self.try_lock(name, "scheduled start or stop")?;
+        let project_file = self.find_project_file(name)?;
+        let (_, _, repository_dir) = get_project_and_repository_paths(&self.resources_config, name);
+        let revision = match project_file.inline {
+            true => None,
+            false => self.git_client.get_current_revision(&repository_dir).ok(),
+        };
+        let (result, action) = match active {
+            true => (
+                compose_up(
+                    self.compose_client.as_ref(),
+                    &self.secrets,
+                    &self.subnets,
+                    &self.previews,
+                    &self.compose_files,
+                    &self.sops,
+                    &repository_dir,
+                    &project_file,
+                    revision.as_deref(),
+                ),
+                "Started by schedule",
+            ),
+            false => (
+                self.compose_client
+                    .stop(repository_dir.to_str().unwrap())
+                    .map_err(|e| anyhow!(e.to_string()))
+                    .and_then(|_| self.secrets.clean(name)),
+                "Stopped by schedule",
+            ),
+        };
+        result.map_err(|e| ProjectUsecaseError::ScheduleFailed(e.to_string()))?;
+
+        record_activity(&self.activity_log, name, ActivityKind::ManualAction, action);
+        Ok(())
+    }
+
+    /// Run one of the project's scheduled actions. A sync is queued as a job like any
+    /// other; the rest run right away.
+    pub fn run_scheduled_action(
+        &self,
+        name: &str,
+        action: ScheduledActionKind,
+    ) -> Result<(), ProjectUsecaseError> {
+        match action {
+            ScheduledActionKind::Sync => self
+                .sync_project_triggered(name, DeploymentTrigger::Schedule)
+                .map(|_| ()),
+            ScheduledActionKind::Start => self.apply_schedule(name, true),
+            ScheduledActionKind::Stop => self.apply_schedule(name, false),
+            ScheduledActionKind::Restart => {
+                let _lease = self.try_lock(name, "scheduled restart")?;
+                self.find_project_file(name)?;
+                let (_, _, repository_dir) =
+                    get_project_and_repository_paths(&self.resources_config, name);
+                self.compose_client
+                    .restart(repository_dir.to_str().unwrap())
+                    .map_err(|e| ProjectUsecaseError::ScheduleFailed(e.to_string()))?;
+                record_activity(
+                    &self.activity_log,
+                    name,
+                    ActivityKind::ManualAction,
+                    "Restarted by schedule",
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// The project's scheduled actions and when each runs next.
+    pub fn scheduled_actions(
+        &self,
+        name: &str,
+    ) -> Result<Vec<ScheduledActionStatus>, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        let now = Local::now();
+        Ok(project_file
+            .scheduled_actions
+            .into_iter()
+            .map(|action| ScheduledActionStatus {
+                next_run_at: action
+                    .schedule()
+                    .ok()
+                    .and_then(|schedule| schedule.next_after(now.naive_local()))
+                    .and_then(|at| at.and_local_timezone(Local).earliest())
+                    .map(|at| {
+                        at.with_timezone(&Utc)
+                            .to_rfc3339_opts(SecondsFormat::Secs, true)
+                    }),
+                action: action.action,
+                cron: action.cron,
+            })
+            .collect())
+    }
+
+    /// A file from the project's checkout, e.g. a config or docs shipped in the repository.
+    /// Paths must stay inside the checkout, symlinks included, and may not reach into
+    /// `.git`.
+    pub fn repository_file(&self, name: &str, path: &str) -> Result<Vec<u8>, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        let invalid = || ProjectUsecaseError::InvalidFilePath(path.to_string());
+        validate_source_path(path).map_err(|_| invalid())?;
+        if reaches_into_git_dir(Path::new(path)) {
+            return Err(invalid());
+        }
+
+        let (_, _, repository_dir) = get_project_and_repository_paths(&self.resources_config, name);
+        let not_found = || ProjectUsecaseError::FileNotFound(path.to_string());
+        let root = fs::canonicalize(&repository_dir).map_err(|_| not_found())?;
+        let file = fs::canonicalize(root.join(path)).map_err(|_| not_found())?;
+        match file.strip_prefix(&root) {
+            Ok(resolved) if !reaches_into_git_dir(resolved) => {}
+            _ => return Err(invalid()),
+        }
+        // Compared once resolved, so a committed symlink can't lead to them either.
+        if deployment_secret_files(&project_file)
+            .iter()
+            .any(|secret| fs::canonicalize(root.join(secret)).is_ok_and(|secret| secret == file))
+        {
+            return Err(ProjectUsecaseError::FileNotServed(path.to_string()));
+        }
+
+        let metadata = fs::metadata(&file).map_err(|_| not_found())?;
+        if !metadata.is_file() {
+            return Err(not_found());
+        }
+        if metadata.len() > MAX_REPOSITORY_FILE_BYTES {
+            return Err(ProjectUsecaseError::FileTooLarge {
+                path: path.to_string(),
+                limit: MAX_REPOSITORY_FILE_BYTES,
+            });
+        }
+
+        fs::read(&file).map_err(|_| not_found())
+    }
+
+    /// Names of the project's stored secrets; values are never returned.
+    pub fn list_secrets(&self, name: &str) -> Result<Vec<String>, ProjectUsecaseError> {
+        self.find_project_file(name)?;
+        self.secrets
+            .store()
+            .names(name)
+            .map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))
+    }
+
+    /// Takes effect on the project's next deployment.
+    pub fn put_secret(
+        &self,
+        name: &str,
+        secret: &str,
+        value: &[u8],
+    ) -> Result<(), ProjectUsecaseError> {
+        self.find_project_file(name)?;
+        self.secrets
+            .store()
+            .put(name, secret, value)
+            .map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))?;
+        record_activity(
+            &self.activity_log,
+            name,
+            ActivityKind::ManualAction,
+            &format!("Secret {} updated", secret),
+        );
+        Ok(())
+    }
+
+    pub fn delete_secret(&self, name: &str, secret: &str) -> Result<(), ProjectUsecaseError> {
+        self.find_project_file(name)?;
+        self.secrets
+            .store()
+            .delete(name, secret)
+            .map_err(|e| ProjectUsecaseError::SecretFailed(e.to_string()))?;
+        record_activity(
+            &self.activity_log,
+            name,
+            ActivityKind::ManualAction,
+            &format!("Secret {} deleted", secret),
+        );
+        Ok(())
+    }
+
+    /// The project's lease for an operation run right away, failing with `ProjectBusy`
+    /// while a job or another request holds it.
+    fn try_lock(
+        &self,
+        name: &str,
+        operation: &'static str,
+    ) -> Result<ProjectLease, ProjectUsecaseError> {
+        self.locks
+            .try_lock(name, operation)
+            .map_err(|holder| ProjectUsecaseError::ProjectBusy {
+                name: name.to_string(),
+                operation: holder.to_string(),
+            })
+    }
+
+    /// Freeze every container in the project, e.g. while its volumes are backed up.
+    pub fn pause_project(
+        &self,
+        name: &str,
+    ) -> Result<GenericResponse<ProjectStatusDetail>, ProjectUsecaseError> {
+        self.set_paused(name, true)?;
+        self.read_project_status(name)
+    }
+
+    pub fn unpause_project(
+        &self,
+        name: &str,
+    ) -> Result<GenericResponse<ProjectStatusDetail>, ProjectUsecaseError> {
+        self.set_paused(name, false)?;
+        self.read_project_status(name)
+    }
+
+    fn set_paused(&self, name: &str, paused: bool) -> Result<(), ProjectUsecaseError> {
+        let _lease = self.try_lock(name, "pause")?;
+        let project_file = self.find_project_file(name)?;
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let path = repository_dir.to_str().unwrap();
+        let (result, action) = match paused {
+            true => (self.compose_client.pause(path), "Project paused"),
+            false => (self.compose_client.unpause(path), "Project unpaused"),
+        };
+        result.map_err(|e| ProjectUsecaseError::PauseFailed(e.to_string()))?;
+
+        record_activity(
+            &self.activity_log,
+            &project_file.name,
+            ActivityKind::ManualAction,
+            action,
+        );
+        Ok(())
+    }
+
+    /// The compose file the project deploys, as currently checked out.
+    pub fn project_compose_file(
+        &self,
+        deadline: &Deadline,
+        name: &str,
+    ) -> Result<ComposeFile, ProjectUsecaseError> {
+        within_deadline(
+            deadline,
+            || format!("reading the compose file of {}", name),
+            || self.load_compose_file(name),
+        )
+    }
+
+    fn load_compose_file(&self, name: &str) -> Result<ComposeFile, ProjectUsecaseError> {
+        let project_file = self.find_project_file(name)?;
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        let compose_path = resolve_compose_file(&repository_dir, &project_file.source.path)?;
+
+        self.compose_files
+            .load(&compose_path)
+            .map_err(|e| ProjectUsecaseError::ReadComposeFileFailed(e.to_string()))
+    }
+
+    /// Run a one-off command, such as a migration, in one of the project's services.
+    pub fn exec_in_service(
+        &self,
+        name: &str,
+        service: &str,
+        request: &ExecRequest,
+    ) -> Result<ExecOutput, ProjectUsecaseError> {
+        if request.command.is_empty() {
+            return Err(ProjectUsecaseError::ExecFailed(
+                "Command must not be empty".to_string(),
+            ));
+        }
+        if self.load_compose_file(name)?.service(service).is_none() {
+            return Err(ProjectUsecaseError::ServiceNotFound(format!(
+                "{}/{}",
+                name, service
+            )));
+        }
+
+        let (_, _, repository_dir) = get_project_and_repository_paths(&self.resources_config, name);
+        record_activity(
+            &self.activity_log,
+            name,
+            ActivityKind::ManualAction,
+            &format!("Exec in {}: {}", service, request.command.join(" ")),
+        );
+        self.compose_client
+            .exec(
+                repository_dir.to_str().unwrap(),
+                service,
+                &request.command,
+                request.timeout(),
+            )
+            .map_err(|e| ProjectUsecaseError::ExecFailed(e.to_string()))
+    }
+
+    /// Versions and disk usage for support requests and dashboards. Parts that cannot be
+    /// read are left empty instead of failing the whole report.
+    pub fn system_info(&self) -> Result<SystemInfo, ProjectUsecaseError> {
+        let disk_usage = [
+            ("projects", &self.resources_config.projects_dir),
+            ("repositories", &self.resources_config.repositories_dir),
+        ]
+        .into_iter()
+        .map(|(name, path)| DirectoryUsage {
+            name: name.to_string(),
+            path: path.clone(),
+            bytes: directory_size(Path::new(path)).ok(),
+        })
+        .collect();
+
+        Ok(SystemInfo {
+            gfc_version: env!("CARGO_PKG_VERSION").to_string(),
+            docker_engine_version: self.compose_client.engine_version().ok(),
+            compose_version: self.compose_client.compose_version().ok(),
+            managed_projects: self.project_files()?.len(),
+            disk_usage,
+        })
+    }
+
+    /// Dry run of `create_project`: every check it makes, plus whether the remote is
+    /// reachable and compose accepts the file. The repository is cloned into a temporary
+    /// directory, so nothing is left in the projects or repositories directories. The git
+    /// and docker commands are killed once `deadline` passes, and the error then says which
+    /// checks had passed.
+    pub fn validate_project(
+        &self,
+        deadline: &Deadline,
+        project_file: &ProjectFile,
+    ) -> Result<ProjectValidation, ProjectUsecaseError> {
+        let mut validation = ProjectValidation::default();
+        if !deadline.is_expired() {
+            process::with_time_limit(deadline.remaining(), || {
+                self.run_validation_checks(project_file, &mut validation)
+            });
+        }
+        let passed = validation
+            .checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Passed)
+            .count();
+        if deadline.is_expired() && passed < VALIDATION_CHECKS.len() {
+            return Err(ProjectUsecaseError::DeadlineExceeded(format!(
+                "passed {} of {} checks, next was {}",
+                passed,
+                VALIDATION_CHECKS.len(),
+                VALIDATION_CHECKS[passed]
+            )));
+        }
+        Ok(validation.finish(VALIDATION_CHECKS))
+    }
+
+    /// Stops at the first failed check, since later ones depend on it.
+    fn run_validation_checks(
+        &self,
+        project_file: &ProjectFile,
+        validation: &mut ProjectValidation,
+    ) -> Option<()> {
+        validation.check(
+            "params",
+            validate_create_project_params(project_file).map_err(|e| e.to_string()),
+        )?;
+        let resolved = ProjectFile {
+            source: validation.check(
+                "remote",
+                self.secrets
+                    .resolve_source(&project_file.name, &project_file.source)
+                    .and_then(|source| self.git_client.check_remote(&source).map(|_| source))
+                    .map_err(|e| e.to_string()),
+            )?,
+            ..project_file.clone()
+        };
+        let (_checkout, compose_path, compose_file) = validation.check(
+            "compose_file",
+            temporary_checkout(self.git_client.as_ref(), &resolved).map_err(|e| e.to_string()),
+        )?;
+        validation.check(
+            "compose_config",
+            self.compose_client
+                .check_config(&compose_path)
+                .map_err(|e| e.to_string()),
+        )?;
+        let collisions = self.existing_projects().map(|existing| {
+            preflight(
+                &project_file.name,
+                Some(&compose_file),
+                &existing,
+                &host_gpus(Some(&compose_file)),
+            )
         });
+        validation.check(
+            "collisions",
+            match collisions {
+                Ok(problems) if problems.is_empty() => Ok(()),
+                Ok(problems) => Err(PreflightReport(problems).to_string()),
+                Err(e) => Err(e.to_string()),
+            },
+        )
+    }
+
+    /// Reject a new project that would collide with one already managed.
+    fn preflight(&self, project_file: &ProjectFile) -> Result<(), ProjectUsecaseError> {
+        let existing = self.existing_projects()?;
+        let compose_file = self.checked_out_compose_file(project_file);
 
-        Ok(GenericResponse::result(ResponseStatus::Success))
+        let problems = preflight(
+            &project_file.name,
+            compose_file.as_ref(),
+            &existing,
+            &host_gpus(compose_file.as_ref()),
+        );
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(ProjectUsecaseError::PreflightFailed(PreflightReport(
+                problems,
+            ))),
+        }
+    }
+
+    /// The projects of this workspace and of its neighbours, which share the Docker host.
+    fn existing_projects(&self) -> Result<Vec<ExistingProject>, ProjectUsecaseError> {
+        let mut existing = self
+            .project_files()?
+            .into_iter()
+            .map(|existing| ExistingProject {
+                compose_file: self.checked_out_compose_file(&existing),
+                name: existing.name,
+            })
+            .collect::<Vec<_>>();
+        for neighbour in &self.neighbours {
+            let project_files = find_all_project_files(Path::new(&neighbour.projects_dir))
+                .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))?;
+            existing.extend(project_files.into_iter().map(|project_file| {
+                let (_, _, repository_dir) =
+                    get_project_and_repository_paths(neighbour, &project_file.name);
+                ExistingProject {
+                    compose_file: checked_out_compose_file(
+                        &self.compose_files,
+                        &repository_dir,
+                        &project_file.source.path,
+                    ),
+                    name: project_file.name,
+                }
+            }));
+        }
+        Ok(existing)
+    }
+
+    fn checked_out_compose_file(&self, project_file: &ProjectFile) -> Option<ComposeFile> {
+        let (_, _, repository_dir) =
+            get_project_and_repository_paths(&self.resources_config, &project_file.name);
+        checked_out_compose_file(
+            &self.compose_files,
+            &repository_dir,
+            &project_file.source.path,
+        )
+    }
+
+    /// The project files whose labels `selector` selects.
+    pub fn selected_project_files(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Vec<ProjectFile>, ProjectUsecaseError> {
+        Ok(self
+            .project_files()?
+            .into_iter()
+            .filter(|project_file| selector.matches(&project_file.labels))
+            .collect())
     }
 
-    pub fn list_projects(&self) -> Result<GenericResponse<Project>, ProjectUsecaseError> {
+    pub fn project_files(&self) -> Result<Vec<ProjectFile>, ProjectUsecaseError> {
         let root_project_path = Path::new(&self.resources_config.projects_dir);
-        let project_files = find_all_project_files(root_project_path)
-            .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))?;
+        find_all_project_files(root_project_path)
+            .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))
+    }
 
-        let projects = project_files
+    pub fn find_project_file(&self, name: &str) -> Result<ProjectFile, ProjectUsecaseError> {
+        self.project_files()?
             .into_iter()
-            .map(|project_file| self.to_project(&project_file))
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))?;
-
-        Ok(GenericResponse::results(projects))
+            .find(|project_file| project_file.name == name)
+            .ok_or_else(|| ProjectUsecaseError::ProjectNotFound(name.to_string()))
     }
 
     fn to_project(&self, project_file: &ProjectFile) -> Result<Project> {
         let name = project_file.name.clone();
         let source = project_file.source.clone();
-        let status = self.container_status_for(&name)?;
+        if let Some(status) = project_file.lifecycle_status() {
+            // The checkout may be gone already, or not cloned yet; whatever set the
+            // status was the last to write the project file.
+            let (_, project_file_path, _) =
+                get_project_and_repository_paths(&self.resources_config, &name);
+            let modified = fs::metadata(project_file_path)?.modified()?;
+            return Ok(Project {
+                name,
+                source,
+                labels: project_file.labels.clone(),
+                status,
+                last_updated_at: Some(rfc3339(DateTime::<Utc>::from(modified))),
+                last_updated_ago: None,
+                drifted: false,
+                environment_of: project_file.environment_of.clone(),
+                warnings: vec![],
+            });
+        }
+        let containers = self.containers_for(&name)?;
+        let status = build_project_status(&containers);
+        let drifted = self.drift_for(project_file, &containers, &status);
         let repository_dir = Path::new(&self.resources_config.repositories_dir).join(&name);
-        let last_updated_at = self
-            .git_client
-            .get_last_commit_timestamp(&repository_dir)?
-            .to_string();
+        let last_updated_at = match project_file.inline {
+            true => fs::metadata(repository_dir.join(&source.path))
+                .and_then(|metadata| metadata.modified())
+                .map(DateTime::<Utc>::from)
+                .map_err(|e| format!("Failed to read when the compose file changed: {}", e)),
+            false => self
+                .git_client
+                .get_last_commit_timestamp(&repository_dir)
+                .map_err(|e| format!("Failed to read the last commit: {}", e)),
+        };
+        let (last_updated_at, warnings) = match last_updated_at {
+            Ok(time) => (Some(rfc3339(time)), vec![]),
+            Err(warning) => (None, vec![warning]),
+        };
 
         Ok(Project {
             name,
             source,
+            labels: project_file.labels.clone(),
             status,
             last_updated_at,
+            last_updated_ago: None,
+            drifted,
+            environment_of: project_file.environment_of.clone(),
+            warnings,
         })
     }
 
-    fn container_status_for(&self, project_name: &str) -> Result<String, ProjectUsecaseError> {
-        let repository_dir = Path::new(&self.resources_config.repositories_dir).join(project_name);
-        let containers = self
+    /// Drift is only reported for projects that are meant to be up. A failed check is
+    /// logged and reported as no drift, so one broken compose file can't fail a listing.
+    fn drift_for(
+        &self,
+        project_file: &ProjectFile,
+        containers: &[Container],
+        status: &ProjectStatus,
+    ) -> bool {
+        if *status == ProjectStatus::Paused
+            || !project_file.is_scheduled_at(Local::now().naive_local())
+        {
+            return false;
+        }
+
+        let repository_dir =
+            Path::new(&self.resources_config.repositories_dir).join(&project_file.name);
+        let mut overrides = extra_compose_files(&repository_dir, &project_file.source);
+        overrides.extend(
+            [
+                self.secrets.override_file(&project_file.name),
+                self.subnets.override_file(&project_file.name),
+                project_file
+                    .inject_provenance
+                    .then(|| {
+                        provenance::override_file(&self.secrets.runtime_dir(&project_file.name))
+                    })
+                    .flatten(),
+                shared_network::override_file(&self.secrets.runtime_dir(&project_file.name))
+                    .filter(|_| !project_file.shared_services.is_empty()),
+                project_file
+                    .preview
+                    .as_ref()
+                    .and_then(|_| self.previews.override_file(&project_file.name)),
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        match self
             .compose_client
+            .config_hashes(repository_dir.to_str().unwrap(), &overrides)
+        {
+            Ok(config_hashes) => has_drifted(&config_hashes, containers),
+            Err(e) => {
+                println!("Failed to check {} for drift: {}", project_file.name, e);
+                false
+            }
+        }
+    }
+
+    fn container_status_for(
+        &self,
+        project_name: &str,
+    ) -> Result<ProjectStatus, ProjectUsecaseError> {
+        let repository_dir = Path::new(&self.resources_config.repositories_dir).join(project_name);
+        self.compose_client
+            .container_states(repository_dir.to_str().unwrap())
+            .map(|states| project_status_from_states(&states))
+            .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))
+    }
+
+    fn containers_for(&self, project_name: &str) -> Result<Vec<Container>, ProjectUsecaseError> {
+        let repository_dir = Path::new(&self.resources_config.repositories_dir).join(project_name);
+        self.compose_client
             .list_containers(repository_dir.to_str().unwrap())
-            .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))?;
+            .map_err(|e| ProjectUsecaseError::ListProjectsFailed(e.to_string()))
+    }
+}
+
+/// `compose up`, with the project's store-backed secrets materialized and its networks
+/// given subnets first, and previews given their ports. `revision` is the commit checked
+/// out, for `inject_provenance`.
+#[allow(clippy::too_many_arguments)]
+fn compose_up<C: ComposeClient>(
+    compose_client: &C,
+    secrets: &ProjectSecrets,
+    subnets: &ProjectSubnets,
+    previews: &PreviewPorts,
+    compose_files: &ComposeFileCache,
+    sops: &Sops,
+    repository_dir: &Path,
+    project_file: &ProjectFile,
+    revision: Option<&str>,
+) -> Result<()> {
+    let name = &project_file.name;
+    let source = &project_file.source;
+    decrypt_sops_files(sops, repository_dir, project_file)?;
+    write_env_file(repository_dir, project_file)?;
+    let mut overrides = extra_compose_files(repository_dir, source);
+    if let Some(compose_file) =
+        checked_out_compose_file(compose_files, repository_dir, &source.path)
+    {
+        overrides.extend(
+            [
+                secrets.materialize(name, &compose_file)?,
+                subnets.materialize(name, &compose_file)?,
+            ]
+            .into_iter()
+            .flatten(),
+        );
+        if project_file.inject_provenance {
+            let environment = provenance::environment(name, revision, Utc::now());
+            overrides.push(provenance::materialize(
+                &secrets.runtime_dir(name),
+                &compose_file,
+                &environment,
+            )?);
+        }
+        if !project_file.shared_services.is_empty() {
+            compose_client
+                .create_network(SHARED_NETWORK)
+                .map_err(|e| anyhow!(e.to_string()))?;
+            overrides.push(shared_network::materialize(
+                &secrets.runtime_dir(name),
+                name,
+                &compose_file,
+                &project_file.shared_services,
+            )?);
+        }
+        if let Some(preview) = &project_file.preview {
+            overrides.push(previews.materialize(name, &compose_file, preview)?);
+        }
+    }
+
+    compose_client
+        .up_with_overrides(repository_dir.to_str().unwrap(), &overrides)
+        .map_err(|e| anyhow!(e.to_string()))
+}
+
+/// Decrypt the project's sops files into the checkout, readable only by the gfc user. The
+/// plaintext never goes into the project file.
+fn decrypt_sops_files(
+    sops: &Sops,
+    repository_dir: &Path,
+    project_file: &ProjectFile,
+) -> Result<()> {
+    for path in &project_file.sops_files {
+        let Some(output) = decrypted_path(path) else {
+            continue;
+        };
+        let plaintext = sops.decrypt(&repository_dir.join(path))?;
+        write_private_file(&repository_dir.join(output), &plaintext)?;
+    }
+    Ok(())
+}
+
+/// Files gfc writes into the checkout before each deployment, with plaintext the repository
+/// itself doesn't hold: the `.env` file of the project's environment and decrypted sops files.
+/// Compose reads them from next to the compose file, so they can't live anywhere else.
+fn deployment_secret_files(project_file: &ProjectFile) -> Vec<String> {
+    std::iter::once(DOTENV_FILE.to_string())
+        .chain(
+            project_file
+                .sops_files
+                .iter()
+                .filter_map(|path| decrypted_path(path)),
+        )
+        .collect()
+}
+
+/// Whether gfc created `project_file` to preview a pull request of `project_name`.
+fn is_preview_of(project_file: &ProjectFile, project_name: &str) -> bool {
+    project_file
+        .preview
+        .as_ref()
+        .is_some_and(|preview| preview.of == project_name)
+}
+
+/// The compose files of `source` after the first, which compose applies on top of it.
+fn extra_compose_files(repository_dir: &Path, source: &GitSource) -> Vec<PathBuf> {
+    source
+        .extra_paths
+        .iter()
+        .map(|path| repository_dir.join(path))
+        .collect()
+}
+
+/// The compose configuration of the checkout in `repository_dir`, with the source's extra
+/// compose files applied but none of the overrides gfc adds when deploying.
+fn rendered_compose_config<C: ComposeClient>(
+    compose_client: &C,
+    repository_dir: &Path,
+    source: &GitSource,
+) -> Result<String> {
+    compose_client
+        .rendered_config(
+            repository_dir.to_str().unwrap(),
+            &extra_compose_files(repository_dir, source),
+        )
+        .map_err(|e| anyhow!(e.to_string()))
+}
+
+/// A unified diff from the compose configuration at `from` to the one at `to`, or an
+/// empty string when they are the same.
+fn compose_diff(from_config: &str, to_config: &str, from: &str, to: &str) -> String {
+    if from_config == to_config {
+        return String::new();
+    }
+    similar::TextDiff::from_lines(from_config, to_config)
+        .unified_diff()
+        .header(from, to)
+        .to_string()
+}
+
+/// Checks run by [`ProjectUsecase::validate_project`], in order.
+const VALIDATION_CHECKS: &[&str] = &[
+    "params",
+    "remote",
+    "compose_file",
+    "compose_config",
+    "collisions",
+];
+
+/// The host's GPUs, only looked up when `compose_file` reserves any. Empty when the
+/// inventory can't be read, e.g. on hosts without NVIDIA drivers.
+fn host_gpus(compose_file: Option<&ComposeFile>) -> Vec<Gpu> {
+    let reserves_gpus = compose_file.is_some_and(|compose_file| {
+        compose_file
+            .device_reservations()
+            .iter()
+            .any(DeviceReservation::is_gpu)
+    });
+    match reserves_gpus {
+        true => list_gpus().unwrap_or_default(),
+        false => vec![],
+    }
+}
+
+fn project_disk_usage(
+    repository_bytes: u64,
+    volumes: Vec<VolumeUsage>,
+    quota_bytes: Option<u64>,
+) -> ProjectDiskUsage {
+    let total_bytes = repository_bytes + volumes.iter().filter_map(|v| v.bytes).sum::<u64>();
+    ProjectDiskUsage {
+        repository_bytes,
+        volumes,
+        total_bytes,
+        quota_bytes,
+        over_quota: quota_bytes.is_some_and(|quota| total_bytes > quota),
+    }
+}
+
+/// The compose file in an existing checkout, if there is one and it parses.
+fn checked_out_compose_file(
+    compose_files: &ComposeFileCache,
+    repository_dir: &Path,
+    source_path: &str,
+) -> Option<ComposeFile> {
+    resolve_compose_file(repository_dir, source_path)
+        .ok()
+        .and_then(|path| compose_files.load(&path).ok())
+}
+
+/// Clone the project into a temporary directory and read its compose file. The checkout
+/// is removed when the returned `TempDir` is dropped.
+fn temporary_checkout<G: GitClient>(
+    git_client: &G,
+    project_file: &ProjectFile,
+) -> Result<(TempDir, PathBuf, ComposeFile)> {
+    let checkout = TempDir::new()?;
+    let repository_dir = checkout.path().join(&project_file.name);
+    git_client.clone_repository(&project_file.source, &repository_dir)?;
+
+    let compose_path = resolve_compose_file(&repository_dir, &project_file.source.path)?;
+    let compose_file = ComposeFile::from_path(&compose_path)?;
+    validate_compose_file(&compose_file)?;
+
+    Ok((checkout, compose_path, compose_file))
+}
+
+/// Images that can be pulled before the clone finishes: the manifest's hints plus those
+/// of a previous revision still checked out in `repository_dir`.
+fn known_images(
+    compose_files: &ComposeFileCache,
+    project_file: &ProjectFile,
+    repository_dir: &Path,
+) -> Vec<String> {
+    let previous =
+        checked_out_compose_file(compose_files, repository_dir, &project_file.source.path)
+            .map(|compose_file| compose_file.images())
+            .unwrap_or_default();
+
+    let mut images = project_file.images.clone();
+    images.extend(
+        previous
+            .into_iter()
+            .filter(|image| !project_file.images.contains(image)),
+    );
+    images
+}
+
+fn read_secret_reference(reference: &SecretReference) -> Result<Vec<u8>> {
+    match reference {
+        SecretReference::FromEnv(variable) => std::env::var(variable)
+            .map(String::into_bytes)
+            .map_err(|e| anyhow!("{}: {}", variable, e)),
+        SecretReference::FromFile(path) => fs::read(path).map_err(|e| anyhow!("{}: {}", path, e)),
+    }
+}
+
+/// Whether none of the `local` digests of an image, as `name@sha256:...`, is `remote`.
+fn is_outdated(local: &[String], remote: &str) -> bool {
+    !local.iter().any(|digest| {
+        digest
+            .rsplit_once('@')
+            .is_some_and(|(_, digest)| digest == remote)
+    })
+}
+
+fn reaches_into_git_dir(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == ".git")
+}
+
+/// Run `work` with the git and docker commands it starts killed once `deadline` passes. A
+/// failure after that is reported as `DeadlineExceeded`, with `progress` saying how far the
+/// work got.
+fn within_deadline<T>(
+    deadline: &Deadline,
+    progress: impl FnOnce() -> String,
+    work: impl FnOnce() -> Result<T, ProjectUsecaseError>,
+) -> Result<T, ProjectUsecaseError> {
+    if deadline.is_expired() {
+        return Err(ProjectUsecaseError::DeadlineExceeded(progress()));
+    }
+
+    process::with_time_limit(deadline.remaining(), work).map_err(|e| match deadline.is_expired() {
+        true => ProjectUsecaseError::DeadlineExceeded(progress()),
+        false => e,
+    })
+}
+
+/// Run `clone` while `images` are pulled next to it. The pulls are cancelled as soon as the
+/// clone fails, as nothing would use the images.
+fn clone_while_pulling<C: ComposeClient + Sync, T>(
+    compose_client: &C,
+    images: &[String],
+    clone: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let pulls = process::Cancellation::default();
+    thread::scope(|scope| {
+        scope.spawn(|| process::with_cancellation(&pulls, || pull_images(compose_client, images)));
+        let cloned = clone();
+        if cloned.is_err() {
+            pulls.cancel();
+        }
+        cloned
+    })
+}
+
+/// Best effort: `compose up` pulls whatever is still missing, so failures are only logged.
+fn pull_images<C: ComposeClient>(compose_client: &C, images: &[String]) {
+    for image in images {
+        if process::is_cancelled() {
+            return;
+        }
+        if let Err(e) = compose_client.pull_image(image) {
+            println!("Failed to pull {}: {}", image, e);
+        }
+    }
+}
+
+/// Keep the revision that was running before a successful sync, unless the sync did not
+/// change it. Failing to do so does not fail the deployment.
+fn keep_on_standby<G: GitClient>(
+    git_client: &G,
+    standby: &StandbyCheckouts,
+    repository_dir: &Path,
+    previous_revision: Option<String>,
+) {
+    let Some(previous_revision) = previous_revision else {
+        return;
+    };
+    let current_revision = git_client.get_current_revision(repository_dir).ok();
+    if current_revision.as_deref() == Some(previous_revision.as_str()) {
+        return;
+    }
+
+    if let Err(e) = standby.keep(git_client, repository_dir, &previous_revision) {
+        println!("Failed to keep {} on standby: {}", previous_revision, e);
+    }
+}
+
+fn record_activity(
+    activity_log: &ActivityLog,
+    project_name: &str,
+    kind: ActivityKind,
+    message: &str,
+) {
+    if let Err(e) = activity_log.record(project_name, kind, message) {
+        println!("Failed to record activity for {}: {}", project_name, e);
+    }
+}
+
+/// A deployment that started at `started_at` and has just finished with `result`.
+fn finished_deployment(
+    project_name: &str,
+    trigger: DeploymentTrigger,
+    started_at: DateTime<Utc>,
+    revision: Option<String>,
+    result: &Result<()>,
+) -> Deployment {
+    let finished_at = Utc::now();
+    let outcome = match result {
+        Ok(()) => DeploymentOutcome::Succeeded,
+        Err(_) if process::is_cancelled() => DeploymentOutcome::Cancelled,
+        Err(_) => DeploymentOutcome::Failed,
+    };
+    Deployment {
+        id: 0,
+        project: project_name.to_string(),
+        revision,
+        trigger,
+        started_at,
+        finished_at,
+        duration_ms: (finished_at - started_at).num_milliseconds().max(0) as u64,
+        outcome,
+        error: result.as_ref().err().map(|e| e.to_string()),
+        rollback_of: None,
+    }
+}
+
+/// Keep the workspace's unfinished jobs next to its deployment history, so those a restart
+/// interrupts can be resumed.
+#[cfg(feature = "sqlite-store")]
+fn stored_jobs(jobs: JobManager, resources_config: &ResourcesConfig) -> JobManager {
+    jobs.with_store(JobStore::new(resources_config.history_db_path()))
+}
+
+#[cfg(not(feature = "sqlite-store"))]
+fn stored_jobs(jobs: JobManager, _resources_config: &ResourcesConfig) -> JobManager {
+    jobs
+}
+
+fn record_deployment(history: &DeploymentHistory, deployment: &Deployment) {
+    if let Err(e) = history.record(deployment) {
+        println!(
+            "Failed to record deployment of {}: {}",
+            deployment.project, e
+        );
+    }
+}
+
+fn update_deployment(history: &DeploymentHistory, deployment: &Deployment) {
+    if let Err(e) = history.update(deployment) {
+        println!(
+            "Failed to update deployment {} of {}: {}",
+            deployment.id, deployment.project, e
+        );
+    }
+}
+
+fn record_deployment_outcome(activity_log: &ActivityLog, project_name: &str, result: &Result<()>) {
+    let message = match result {
+        Ok(()) => "Deployment succeeded".to_string(),
+        Err(e) => format!("Deployment failed: {}", e),
+    };
+    record_activity(
+        activity_log,
+        project_name,
+        ActivityKind::Deployment,
+        &message,
+    );
+}
+
+/// Compose projects of unmanaged names whose compose files are in `repositories_dir`.
+fn orphaned_stacks<'a>(
+    stacks: &'a [ComposeStack],
+    managed: &'a HashSet<String>,
+    repositories_dir: &'a Path,
+) -> impl Iterator<Item = &'a ComposeStack> {
+    stacks.iter().filter(move |stack| {
+        !managed.contains(&stack.name)
+            && stack
+                .config_files
+                .iter()
+                .any(|file| Path::new(file).starts_with(repositories_dir))
+    })
+}
+
+/// Checkouts in `repositories_dir`, and standby checkouts, of unmanaged names.
+fn orphaned_checkouts(repositories_dir: &Path, managed: &HashSet<String>) -> Result<Vec<PathBuf>> {
+    let mut orphans = Vec::new();
+    for dir in [
+        repositories_dir.to_path_buf(),
+        repositories_dir.join(".revisions"),
+    ] {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if path.is_dir() && !name.starts_with('.') && !managed.contains(name.as_ref()) {
+                orphans.push(path);
+            }
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// The steps of a delete. Each is a no-op once done, so a retry only redoes what failed.
+/// Without a compose file compose can't tell which containers and volumes are the
+/// project's, so they are left alone. The project directory, with the project file in it,
+/// goes last.
+fn tear_down<C: ComposeClient>(
+    compose_client: &C,
+    secrets: &ProjectSecrets,
+    standby: &StandbyCheckouts,
+    name: &str,
+    repository_dir: &Path,
+    project_dir: &Path,
+) -> Result<()> {
+    if find_compose_file_name(repository_dir).is_ok() {
+        compose_client
+            .remove(repository_dir.to_str().unwrap())
+            .map_err(|e| anyhow!("Failed to remove containers and volumes: {}", e))?;
+    }
+    secrets
+        .remove(name)
+        .map_err(|e| anyhow!("Failed to remove secrets: {}", e))?;
+    standby
+        .remove_all()
+        .map_err(|e| anyhow!("Failed to remove standby checkouts: {}", e))?;
+    if repository_dir.exists() {
+        fs::remove_dir_all(repository_dir)
+            .map_err(|e| anyhow!("Failed to remove checkout: {}", e))?;
+    }
+    fs::remove_dir_all(project_dir).map_err(|e| anyhow!("Failed to remove project file: {}", e))?;
+    Ok(())
+}
+
+/// Settle the creation marker of the manifest at `project_file_path` once a deployment
+/// finished: drop it after a success, or keep why it failed and whether its job was
+/// cancelled. Manifests without one, or gone because the project was deleted meanwhile,
+/// are left alone.
+fn record_creation_outcome(project_file_path: &Path, result: &Result<()>) {
+    record_creation(
+        project_file_path,
+        match result {
+            Ok(()) => None,
+            Err(e) => Some(Creation {
+                error: Some(e.to_string()),
+                cancelled: process::is_cancelled(),
+            }),
+        },
+    );
+}
 
-        Ok(build_container_status_string(&containers))
+/// Mark a project whose create job was cancelled before it started as cancelled, as
+/// the job itself would have had it been cancelled while running.
+fn record_creation_cancelled(project_file_path: &Path) {
+    record_creation(
+        project_file_path,
+        Some(Creation {
+            error: Some("Cancelled before it started".to_string()),
+            cancelled: true,
+        }),
+    );
+}
+
+/// Replace the creation state of a project still being created with `creation`.
+fn record_creation(project_file_path: &Path, creation: Option<Creation>) {
+    let Ok(mut project_file) = read_project_file(project_file_path) else {
+        return;
+    };
+    if project_file.creation.is_none() {
+        return;
+    }
+
+    project_file.creation = creation;
+    if let Err(e) = write_manifest(project_file_path, &project_file) {
+        println!(
+            "Failed to record how {} was created: {}",
+            project_file.name, e
+        );
     }
 }
 
+fn write_manifest(project_file_path: &Path, project_file: &ProjectFile) -> Result<()> {
+    Ok(fs::write(
+        project_file_path,
+        serde_yaml::to_string(project_file)?,
+    )?)
+}
+
 /// Prepare the project and repository directories and write the project YAML file.
 /// Creates all directories if they do not exist.
+/// Copy the files in `from` into `to`, leaving out any `.git` directory.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            if entry.file_name() != ".git" {
+                copy_dir(&entry.path(), &target)?;
+            }
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
 fn setup_project_workspace(
     project_file: &ProjectFile,
     project_path: &Path,
@@ -148,54 +3358,183 @@ fn get_project_and_repository_paths(
 }
 
 fn find_all_project_files(root_path: &Path) -> Result<Vec<ProjectFile>> {
-    let patterns = [
-        format!("{}/**/*.yml", root_path.display()),
-        format!("{}/**/*.yaml", root_path.display()),
-    ];
+    blocking::debug_assert_may_block("Reading project files");
+    let patterns = MANIFEST_EXTENSIONS
+        .iter()
+        .map(|extension| format!("{}/**/*.{}", root_path.display(), extension))
+        .collect::<Vec<_>>();
 
     let files = patterns
         .iter()
         .flat_map(|pattern| glob(pattern).into_iter().flatten())
         .collect::<Result<Vec<PathBuf>, _>>()?;
 
-    let contents = files
+    let projects = files
+        .iter()
+        .map(|file| read_project_file(file))
+        .collect::<Result<Vec<ProjectFile>>>()?;
+
+    Ok(projects)
+}
+
+fn rfc3339(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Hash of the manifests and resolved projects, independent of the order they were read in.
+fn listing_etag(project_files: &[ProjectFile], projects: &[Project]) -> String {
+    let mut entries = project_files
         .iter()
-        .map(fs::read_to_string)
-        .collect::<Result<Vec<String>, _>>()?;
+        .map(|project_file| serde_json::to_string(project_file).unwrap_or_default())
+        .chain(
+            projects
+                .iter()
+                .map(|project| serde_json::to_string(project).unwrap_or_default()),
+        )
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    let mut hasher = Sha256::new();
+    for entry in entries {
+        hasher.update(entry.as_bytes());
+        hasher.update(b"\n");
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// A service drifted when none of its containers is up, or finished successfully, or
+/// when a container was created from a different configuration than the file's.
+fn has_drifted(config_hashes: &HashMap<String, String>, containers: &[Container]) -> bool {
+    let settled = |container: &Container| match container.state {
+        ContainerState::Exited => container.exit_code == Some(0),
+        ContainerState::Created | ContainerState::Dead | ContainerState::Removing => false,
+        ContainerState::Paused | ContainerState::Restarting | ContainerState::Running => true,
+    };
+    let stopped = config_hashes.keys().any(|service| {
+        !containers
+            .iter()
+            .any(|container| container.service.as_ref() == Some(service) && settled(container))
+    });
+    let outdated =
+        containers.iter().any(
+            |container| match (&container.service, &container.config_hash) {
+                (Some(service), Some(hash)) => config_hashes.get(service) != Some(hash),
+                _ => false,
+            },
+        );
 
-    let projects = contents
+    stopped || outdated
+}
+
+fn dangling_networks<'a>(
+    networks: &'a [ComposeNetwork],
+    live_projects: &HashSet<String>,
+) -> Vec<&'a ComposeNetwork> {
+    networks
         .iter()
-        .map(|content| serde_yaml::from_str(content))
-        .collect::<Result<Vec<ProjectFile>, _>>()?;
+        .filter(|network| network.containers == 0 && !live_projects.contains(&network.project))
+        .collect()
+}
 
-    Ok(projects)
+pub fn read_project_file(path: &Path) -> Result<ProjectFile> {
+    let format = ManifestFormat::from_path(path)
+        .ok_or_else(|| anyhow!("Unsupported manifest format: {}", path.display()))?;
+    let content = fs::read_to_string(path)?;
+    Ok(format.parse(&content)?)
+}
+
+/// OOM kills, and containers restarted at least [`RESTART_LOOP_THRESHOLD`] times.
+fn container_failures(containers: &[Container]) -> Vec<ContainerFailure> {
+    containers
+        .iter()
+        .filter_map(|container| {
+            let reason = match (container.oom_killed, container.restart_count) {
+                (Some(true), _) => "Killed for running out of memory".to_string(),
+                (_, Some(restarts)) if restarts >= RESTART_LOOP_THRESHOLD => {
+                    format!("Died and was restarted {} times", restarts)
+                }
+                _ => return None,
+            };
+            Some(ContainerFailure {
+                container: container.name.clone(),
+                exit_code: container.exit_code,
+                reason,
+            })
+        })
+        .collect()
 }
 
-fn build_container_status_string(containers: &[Container]) -> String {
-    let total = containers.len();
-    let running = containers
+pub(crate) fn build_project_status(containers: &[Container]) -> ProjectStatus {
+    let states = containers
         .iter()
-        .filter(|c| c.state == ContainerState::Running)
+        .map(|container| container.state)
+        .collect::<Vec<_>>();
+    project_status_from_states(&states)
+}
+
+pub(crate) fn project_status_from_states(states: &[ContainerState]) -> ProjectStatus {
+    let total = states.len();
+    let running = states
+        .iter()
+        .filter(|state| **state == ContainerState::Running)
         .count();
+    let paused = states.contains(&ContainerState::Paused);
 
-    match running {
-        0 => "Exited".to_string(),
-        _ => format!("Running ({}/{})", running, total),
+    match (running, paused) {
+        (0, true) => ProjectStatus::Paused,
+        (0, false) => ProjectStatus::Exited,
+        _ => ProjectStatus::Running { running, total },
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::models::docker_compose::{Container, ContainerState};
-    use crate::usecases::project::build_container_status_string;
+    use std::collections::{HashMap, HashSet};
+    use std::process::Command;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use anyhow::anyhow;
+
+    use crate::models::bootstrap::SecretReference;
+    use crate::models::docker_compose::{
+        ComposeNetwork, ComposeStack, Container, ContainerState, VolumeUsage,
+    };
+    use crate::models::git::GitSource;
+    use crate::models::project::{Creation, Project, ProjectFile, ProjectStatus};
+    use crate::repositories::docker_compose_client::{DockerComposeError, MockDockerComposeClient};
+    use crate::repositories::process::CommandTimeout;
+    use crate::usecases::project::{
+        build_project_status, clone_while_pulling, compose_diff, container_failures,
+        dangling_networks, has_drifted, is_outdated, listing_etag, orphaned_checkouts,
+        orphaned_stacks, project_disk_usage, read_project_file, read_secret_reference,
+        record_creation_outcome, write_manifest,
+    };
+
+    fn build_container_status_string(containers: &[Container]) -> String {
+        build_project_status(containers).to_string()
+    }
 
     fn make_container(name: &str, state: ContainerState) -> Container {
         Container {
             name: name.to_string(),
             state,
+            health: None,
+            exit_code: None,
+            restart_count: None,
+            oom_killed: None,
+            service: Some(name.to_string()),
+            config_hash: Some(format!("{}-hash", name)),
         }
     }
 
+    fn config_hashes(services: &[&str]) -> HashMap<String, String> {
+        services
+            .iter()
+            .map(|service| (service.to_string(), format!("{}-hash", service)))
+            .collect()
+    }
+
     #[test]
     fn given_two_running_containers_when_build_container_status_string_then_return_running_two_out_of_two(
     ) {
@@ -254,4 +3593,299 @@ mod tests {
 
         assert_eq!(actual, "Running (2/3)");
     }
+
+    #[test]
+    fn given_all_paused_containers_when_build_container_status_string_then_return_paused() {
+        let containers = vec![
+            make_container("service1", ContainerState::Paused),
+            make_container("service2", ContainerState::Paused),
+        ];
+
+        let actual = build_container_status_string(&containers);
+
+        assert_eq!(actual, "Paused");
+    }
+
+    #[test]
+    fn given_volumes_of_unknown_size_when_project_disk_usage_then_count_only_known_sizes() {
+        let volumes = vec![
+            VolumeUsage {
+                name: "web_data".to_string(),
+                bytes: Some(600),
+            },
+            VolumeUsage {
+                name: "web_nfs".to_string(),
+                bytes: None,
+            },
+        ];
+
+        let actual = project_disk_usage(500, volumes, Some(1000));
+
+        assert_eq!(actual.total_bytes, 1100);
+        assert!(actual.over_quota);
+        assert!(!project_disk_usage(500, vec![], None).over_quota);
+    }
+
+    #[test]
+    fn given_same_projects_in_another_order_when_listing_etag_then_return_same_etag() {
+        let project = |name: &str, status| Project {
+            name: name.to_string(),
+            source: GitSource::default(),
+            labels: Default::default(),
+            status,
+            last_updated_at: Some("2024-01-01T00:00:00Z".to_string()),
+            last_updated_ago: None,
+            drifted: false,
+            environment_of: None,
+            warnings: vec![],
+        };
+        let web = project(
+            "web",
+            ProjectStatus::Running {
+                running: 1,
+                total: 1,
+            },
+        );
+        let db = project("db", ProjectStatus::Exited);
+
+        let forward = listing_etag(&[], &[web.clone(), db.clone()]);
+        let backward = listing_etag(&[], &[db.clone(), web]);
+        let stopped = listing_etag(&[], &[db, project("web", ProjectStatus::Exited)]);
+
+        assert_eq!(forward, backward);
+        assert_ne!(forward, stopped);
+    }
+
+    #[test]
+    fn given_networks_of_live_and_removed_projects_when_finding_dangling_networks_then_return_unused_ones_of_removed_projects(
+    ) {
+        let network = |name: &str, project: &str, containers| ComposeNetwork {
+            name: name.to_string(),
+            project: project.to_string(),
+            containers,
+        };
+        let networks = vec![
+            network("web_default", "web", 0),
+            network("old_default", "old", 0),
+            network("old_backend", "old", 1),
+        ];
+        let live_projects = HashSet::from(["web".to_string()]);
+
+        let actual = dangling_networks(&networks, &live_projects);
+
+        assert_eq!(actual, vec![&networks[1]]);
+    }
+
+    #[test]
+    fn given_stacks_and_checkouts_of_removed_projects_when_finding_orphans_then_return_only_those_gfc_started(
+    ) {
+        let root = tempfile::TempDir::new().unwrap();
+        for dir in ["web", "old", ".revisions/old", ".revisions/web"] {
+            std::fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        let stack = |name: &str, config_file: &std::path::Path| ComposeStack {
+            name: name.to_string(),
+            status: "running(1)".to_string(),
+            config_files: vec![config_file.display().to_string()],
+        };
+        let stacks = vec![
+            stack("web", &root.path().join("web/docker-compose.yml")),
+            stack("old", &root.path().join("old/docker-compose.yml")),
+            stack(
+                "other",
+                std::path::Path::new("/opt/other/docker-compose.yml"),
+            ),
+        ];
+        let managed = HashSet::from(["web".to_string()]);
+
+        let stacks = orphaned_stacks(&stacks, &managed, root.path()).collect::<Vec<_>>();
+        let checkouts = orphaned_checkouts(root.path(), &managed).unwrap();
+
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].name, "old");
+        assert_eq!(
+            checkouts,
+            vec![root.path().join(".revisions/old"), root.path().join("old")]
+        );
+    }
+
+    #[test]
+    fn given_project_being_created_when_record_creation_outcome_then_keep_failure_until_success() {
+        let root = tempfile::TempDir::new().unwrap();
+        let path = root.path().join("project.yaml");
+        let project_file = ProjectFile {
+            name: "app".to_string(),
+            creation: Some(Creation::default()),
+            ..Default::default()
+        };
+        write_manifest(&path, &project_file).unwrap();
+        assert_eq!(
+            project_file.lifecycle_status(),
+            Some(ProjectStatus::CreationInProgress)
+        );
+
+        record_creation_outcome(&path, &Err(anyhow::anyhow!("clone failed")));
+        let failed = read_project_file(&path).unwrap();
+        record_creation_outcome(&path, &Ok(()));
+        let deployed = read_project_file(&path).unwrap();
+
+        assert_eq!(
+            failed.lifecycle_status(),
+            Some(ProjectStatus::DeploymentFailed {
+                reason: "clone failed".to_string()
+            })
+        );
+        assert_eq!(deployed.lifecycle_status(), None);
+    }
+
+    #[test]
+    fn given_cancelled_job_when_record_creation_outcome_then_project_is_cancelled() {
+        let root = tempfile::TempDir::new().unwrap();
+        let path = root.path().join("project.yaml");
+        let project_file = ProjectFile {
+            name: "app".to_string(),
+            creation: Some(Creation::default()),
+            ..Default::default()
+        };
+        write_manifest(&path, &project_file).unwrap();
+        let cancellation = crate::repositories::process::Cancellation::default();
+        cancellation.cancel();
+
+        crate::repositories::process::with_cancellation(&cancellation, || {
+            record_creation_outcome(&path, &Err(anyhow::anyhow!("git was killed")))
+        });
+
+        assert_eq!(
+            read_project_file(&path).unwrap().lifecycle_status(),
+            Some(ProjectStatus::Cancelled)
+        );
+    }
+
+    #[test]
+    fn given_containers_matching_compose_file_when_has_drifted_then_return_false() {
+        let mut migrate = make_container("migrate", ContainerState::Exited);
+        migrate.exit_code = Some(0);
+        let containers = vec![make_container("web", ContainerState::Running), migrate];
+
+        assert!(!has_drifted(
+            &config_hashes(&["web", "migrate"]),
+            &containers
+        ));
+    }
+
+    #[test]
+    fn given_stopped_or_missing_service_when_has_drifted_then_return_true() {
+        let mut web = make_container("web", ContainerState::Exited);
+        web.exit_code = Some(137);
+
+        assert!(has_drifted(&config_hashes(&["web"]), &[web]));
+        assert!(has_drifted(
+            &config_hashes(&["web", "worker"]),
+            &[make_container("web", ContainerState::Running)]
+        ));
+    }
+
+    #[test]
+    fn given_container_created_from_another_configuration_when_has_drifted_then_return_true() {
+        let mut web = make_container("web", ContainerState::Running);
+        web.config_hash = Some("stale-hash".to_string());
+
+        assert!(has_drifted(&config_hashes(&["web"]), &[web]));
+    }
+
+    #[test]
+    fn given_oom_killed_and_restarting_containers_when_container_failures_then_report_both() {
+        let oom_killed = Container {
+            oom_killed: Some(true),
+            exit_code: Some(137),
+            ..make_container("web-1", ContainerState::Exited)
+        };
+        let restarting = Container {
+            restart_count: Some(5),
+            exit_code: Some(1),
+            ..make_container("worker-1", ContainerState::Restarting)
+        };
+        let recovered = Container {
+            restart_count: Some(1),
+            ..make_container("db-1", ContainerState::Running)
+        };
+
+        let actual = container_failures(&[oom_killed, restarting, recovered]);
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].container, "web-1");
+        assert_eq!(actual[0].exit_code, Some(137));
+        assert_eq!(actual[0].reason, "Killed for running out of memory");
+        assert_eq!(actual[1].reason, "Died and was restarted 5 times");
+    }
+
+    #[test]
+    fn given_local_digests_when_is_outdated_then_compare_with_remote_digest() {
+        let local = vec![
+            "registry.example.com:5000/web@sha256:aaa".to_string(),
+            "web@sha256:bbb".to_string(),
+        ];
+
+        assert!(!is_outdated(&local, "sha256:aaa"));
+        assert!(is_outdated(&local, "sha256:ccc"));
+        assert!(is_outdated(&[], "sha256:aaa"));
+    }
+
+    #[test]
+    fn given_changed_image_when_compose_diff_then_show_the_changed_lines() {
+        let deployed = "services:\n  web:\n    image: nginx:1.25\n    ports:\n      - 80:80\n";
+        let remote = "services:\n  web:\n    image: nginx:1.27\n    ports:\n      - 80:80\n";
+
+        let actual = compose_diff(deployed, remote, "0123abc", "4567def");
+
+        assert!(
+            actual.starts_with("--- 0123abc\n+++ 4567def\n"),
+            "{}",
+            actual
+        );
+        assert!(actual.contains("\n-    image: nginx:1.25\n+    image: nginx:1.27\n"));
+        assert_eq!(compose_diff(deployed, deployed, "0123abc", "0123abc"), "");
+    }
+
+    #[test]
+    fn given_file_or_missing_variable_when_read_secret_reference_then_read_file_or_fail() {
+        let root = tempfile::TempDir::new().unwrap();
+        let path = root.path().join("db_password");
+        std::fs::write(&path, "hunter2").unwrap();
+
+        let file = read_secret_reference(&SecretReference::FromFile(path.display().to_string()));
+        let missing = read_secret_reference(&SecretReference::FromEnv(
+            "GFC_TEST_UNSET_BOOTSTRAP_SECRET".to_string(),
+        ));
+
+        assert_eq!(file.unwrap(), b"hunter2");
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn given_failing_clone_when_clone_while_pulling_then_kill_the_pulls() {
+        let (started, pull_started) = mpsc::channel();
+        let mut compose_client = MockDockerComposeClient::new();
+        compose_client
+            .expect_pull_image()
+            .times(1)
+            .returning(move |image| {
+                started.send(()).unwrap();
+                Command::new("sleep")
+                    .arg("30")
+                    .status_within(Duration::from_secs(60))
+                    .map(|_| ())
+                    .map_err(|_| DockerComposeError::Cancelled(image.to_string()))
+            });
+        let images = vec!["nginx:1.27".to_string(), "redis:7".to_string()];
+        let started_at = Instant::now();
+
+        let actual = clone_while_pulling(&compose_client, &images, || {
+            pull_started.recv().unwrap();
+            Err::<(), _>(anyhow!("Repository not found"))
+        });
+
+        assert!(actual.is_err());
+        assert!(started_at.elapsed() < Duration::from_secs(10));
+    }
 }
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::models::compose_file::ComposeFile;
+
+const OVERRIDE_FILE: &str = "provenance.override.yml";
+
+/// The variables set in every service of a project with `inject_provenance`. Inline
+/// projects have no revision, so they get no `GFC_REVISION`.
+pub fn environment(
+    project_name: &str,
+    revision: Option<&str>,
+    deployed_at: DateTime<Utc>,
+) -> BTreeMap<String, String> {
+    let mut environment = BTreeMap::from([
+        ("GFC_PROJECT".to_string(), project_name.to_string()),
+        (
+            "GFC_DEPLOYED_AT".to_string(),
+            deployed_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        ),
+    ]);
+    if let Some(revision) = revision {
+        environment.insert("GFC_REVISION".to_string(), revision.to_string());
+    }
+    environment
+}
+
+/// Write the override file adding `environment` to every service of `compose_file` into
+/// the project's runtime directory `dir`, and return it. Must run after secrets are
+/// materialized, which clears that directory.
+pub fn materialize(
+    dir: &Path,
+    compose_file: &ComposeFile,
+    environment: &BTreeMap<String, String>,
+) -> Result<PathBuf> {
+    let variables = environment
+        .iter()
+        .map(|(key, value)| (Value::from(key.as_str()), Value::from(value.as_str())))
+        .collect::<Mapping>();
+    let services = compose_file
+        .service_names()
+        .into_iter()
+        .map(|service| {
+            let mut definition = Mapping::new();
+            definition.insert(
+                Value::from("environment"),
+                Value::Mapping(variables.clone()),
+            );
+            (Value::from(service), Value::Mapping(definition))
+        })
+        .collect::<Mapping>();
+    let mut document = Mapping::new();
+    document.insert(Value::from("services"), Value::Mapping(services));
+
+    fs::create_dir_all(dir)?;
+    let override_path = dir.join(OVERRIDE_FILE);
+    fs::write(&override_path, serde_yaml::to_string(&document)?)?;
+    Ok(fs::canonicalize(override_path)?)
+}
+
+/// The override file written by the last `materialize` into `dir`, while the project is
+/// up.
+pub fn override_file(dir: &Path) -> Option<PathBuf> {
+    fs::canonicalize(dir.join(OVERRIDE_FILE)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn given_git_project_when_materialized_then_set_variables_in_every_service() {
+        let root = tempfile::TempDir::new().unwrap();
+        let compose_file = ComposeFile::parse(
+            "services:\n  web:\n    image: nginx\n    environment:\n      - MODE=prod\n  db:\n    image: postgres\n",
+        )
+        .unwrap();
+        let deployed_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let environment = environment("shop", Some("0123abc"), deployed_at);
+
+        let path = materialize(root.path(), &compose_file, &environment).unwrap();
+
+        let actual: Value = serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        for service in ["web", "db"] {
+            let variables = &actual["services"][service]["environment"];
+            assert_eq!(variables["GFC_PROJECT"], Value::from("shop"));
+            assert_eq!(variables["GFC_REVISION"], Value::from("0123abc"));
+            assert_eq!(
+                variables["GFC_DEPLOYED_AT"],
+                Value::from("2024-01-01T12:00:00Z")
+            );
+        }
+        assert!(override_file(root.path()).is_some());
+    }
+}
@@ -0,0 +1,210 @@
+use chrono::Local;
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use crate::models::deployment::DeploymentTrigger;
+use crate::models::project::{ProjectFile, ProjectStatus};
+use crate::repositories::blocking;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::ProjectUsecase;
+
+/// Keeps projects in step with their git remotes: on every tick, projects whose branch
+/// has new commits are synced, through the same jobs as `POST /projects/{name}/sync`.
+/// With drift correction on, so are projects whose containers drifted from their
+/// compose file.
+///
+/// Paused projects and those outside their schedule window are left alone, as is a
+/// project whose previous sync is still running. Projects whose manifest requires
+/// approval get a pending deployment of their new commits instead. Each tick also removes
+/// compose networks that outlived their project, before they exhaust the engine's address
+/// pools.
+pub struct Reconciler<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    project_usecase: ProjectUsecase<C, G>,
+    interval: Duration,
+    correct_drift: bool,
+    /// The last sync job queued for each project.
+    syncs: HashMap<String, String>,
+}
+
+impl<C, G> Reconciler<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    pub fn new(project_usecase: ProjectUsecase<C, G>, interval: Duration) -> Self {
+        Self {
+            project_usecase,
+            interval,
+            correct_drift: false,
+            syncs: HashMap::new(),
+        }
+    }
+
+    pub fn with_drift_correction(self, correct_drift: bool) -> Self {
+        Self {
+            correct_drift,
+            ..self
+        }
+    }
+
+    /// Reconcile on a dedicated thread. Must be called from within a Tokio runtime, which
+    /// the sync jobs are submitted to.
+    pub fn spawn(mut self) -> thread::JoinHandle<()> {
+        let runtime = tokio::runtime::Handle::current();
+        thread::spawn(move || {
+            let _runtime = runtime.enter();
+            blocking::allow(|| loop {
+                self.tick();
+                thread::sleep(self.interval);
+            })
+        })
+    }
+
+    pub fn tick(&mut self) {
+        let project_files = match self.project_usecase.project_files() {
+            Ok(project_files) => project_files,
+            Err(e) => {
+                println!("Failed to list projects to reconcile: {}", e);
+                return;
+            }
+        };
+
+        for project_file in project_files {
+            if self.sync_in_progress(&project_file.name)
+                || !wants_deployment(&self.project_usecase, &project_file)
+            {
+                continue;
+            }
+
+            match self.needs_sync(&project_file) {
+                Ok(false) => continue,
+                Ok(true) => {}
+                Err(e) => {
+                    println!("Failed to check {} for changes: {}", project_file.name, e);
+                    continue;
+                }
+            }
+
+            match self
+                .project_usecase
+                .deploy_new_commits(&project_file.name, DeploymentTrigger::Reconciler)
+            {
+                Ok(Some(job)) => {
+                    self.syncs.insert(project_file.name, job.id);
+                }
+                Ok(None) => {}
+                Err(e) => println!("Failed to sync {}: {}", project_file.name, e),
+            }
+        }
+
+        match self.project_usecase.prune_orphans() {
+            Ok(report) if !report.is_empty() => println!("Pruned orphaned resources: {:?}", report),
+            Ok(_) => {}
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    fn needs_sync(&self, project_file: &ProjectFile) -> anyhow::Result<bool> {
+        if self.project_usecase.has_remote_changes(project_file)? {
+            return Ok(true);
+        }
+        if !self.correct_drift {
+            return Ok(false);
+        }
+
+        let drifted = self.project_usecase.detect_drift(project_file)?;
+        if drifted {
+            println!("Correcting drift of {}", project_file.name);
+        }
+        Ok(drifted)
+    }
+
+    fn sync_in_progress(&self, name: &str) -> bool {
+        self.syncs
+            .get(name)
+            .and_then(|id| self.project_usecase.job(id).ok())
+            .is_some_and(|job| !job.status.is_finished())
+    }
+}
+
+/// Bring every project back to what its manifest describes, once, e.g. after a host
+/// reboot or on a fresh install restored from manifests: projects whose checkout is
+/// missing are cloned again, and those whose containers are missing or drifted from their
+/// compose file are redeployed. Projects the reconciler would leave alone are left alone
+/// here too, as are those with a job already under way. Returns the projects whose sync was queued.
+pub fn converge<C, G>(project_usecase: &ProjectUsecase<C, G>) -> Vec<String>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let project_files = match project_usecase.project_files() {
+        Ok(project_files) => project_files,
+        Err(e) => {
+            println!("Failed to list projects to converge: {}", e);
+            return vec![];
+        }
+    };
+
+    let mut synced = Vec::new();
+    for project_file in project_files {
+        if project_usecase.jobs.in_progress(&project_file.name)
+            || !wants_deployment(project_usecase, &project_file)
+        {
+            continue;
+        }
+        let diverged = match project_usecase.has_checkout(&project_file) {
+            false if project_file.inline => {
+                println!(
+                    "Cannot converge {}: its uploaded compose file is gone",
+                    project_file.name
+                );
+                continue;
+            }
+            false => true,
+            true => match project_usecase.detect_drift(&project_file) {
+                Ok(drifted) => drifted,
+                Err(e) => {
+                    println!("Failed to check {} for drift: {}", project_file.name, e);
+                    continue;
+                }
+            },
+        };
+        if !diverged {
+            continue;
+        }
+
+        match project_usecase.sync_project_triggered(&project_file.name, DeploymentTrigger::Startup)
+        {
+            Ok(_) => synced.push(project_file.name),
+            Err(e) => println!("Failed to converge {}: {}", project_file.name, e),
+        }
+    }
+    synced
+}
+
+/// Whether a background deployment of the project may start now: it is inside its
+/// schedule window, and not paused, being created or being deleted.
+pub(crate) fn wants_deployment<C, G>(
+    project_usecase: &ProjectUsecase<C, G>,
+    project_file: &ProjectFile,
+) -> bool
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    let in_window = project_file.is_scheduled_at(Local::now().naive_local());
+    let paused = matches!(
+        project_usecase.project_status_summary(&project_file.name),
+        Ok(ProjectStatus::Paused)
+    );
+
+    let creating = project_file.creation_status() == Some(ProjectStatus::CreationInProgress);
+
+    in_window && !paused && !creating && project_file.deletion.is_none()
+}
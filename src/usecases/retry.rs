@@ -0,0 +1,96 @@
+use anyhow::Result;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::Duration;
+
+use crate::models::retry::RetryPolicy;
+use crate::repositories::process;
+
+/// Run `operation` until it succeeds or `policy` runs out of attempts, returning the last
+/// error. A cancelled job isn't retried. `what` names the operation in the log, e.g.
+/// `Cloning app`.
+pub fn retry<T>(
+    policy: &RetryPolicy,
+    what: &str,
+    mut operation: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= max_attempts || process::is_cancelled() => return Err(e),
+            Err(e) => {
+                let delay = with_jitter(policy, policy.backoff(attempt));
+                println!(
+                    "{} failed (attempt {} of {}), retrying in {:?}: {}",
+                    what, attempt, max_attempts, delay, e
+                );
+                thread::sleep(delay);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn with_jitter(policy: &RetryPolicy, backoff: Duration) -> Duration {
+    if !policy.jitter || backoff.is_zero() {
+        return backoff;
+    }
+    // Every `RandomState` is seeded randomly, which is all the randomness this needs.
+    let random = RandomState::new().build_hasher().finish();
+    let half = backoff / 2;
+    half + Duration::from_nanos(random % (half.as_nanos() as u64 + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn immediate(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn given_operation_failing_twice_when_retry_then_return_its_first_success() {
+        let mut attempts = 0;
+
+        let actual = retry(&immediate(3), "Cloning app", || {
+            attempts += 1;
+            match attempts {
+                3 => Ok(attempts),
+                _ => Err(anyhow!("connection reset")),
+            }
+        });
+
+        assert_eq!(actual.unwrap(), 3);
+    }
+
+    #[test]
+    fn given_operation_that_keeps_failing_when_retry_then_stop_after_max_attempts() {
+        let mut attempts = 0;
+
+        let actual: Result<()> = retry(&immediate(2), "Cloning app", || {
+            attempts += 1;
+            Err(anyhow!("connection reset"))
+        });
+
+        assert!(actual.is_err());
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn given_jitter_when_with_jitter_then_wait_between_half_and_all_of_the_backoff() {
+        let backoff = Duration::from_millis(1000);
+
+        let actual = with_jitter(&RetryPolicy::default(), backoff);
+
+        assert!(actual >= backoff / 2 && actual <= backoff);
+    }
+}
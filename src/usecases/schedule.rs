@@ -0,0 +1,128 @@
+use chrono::{Local, NaiveDateTime};
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use crate::models::project::ProjectFile;
+use crate::repositories::blocking;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::ProjectUsecase;
+
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Starts and stops projects that have an active schedule, and runs their scheduled
+/// actions. Projects are only touched when their window opens or closes, so starting a
+/// stack by hand outside its window keeps it up until the next transition.
+pub struct Scheduler<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    project_usecase: ProjectUsecase<C, G>,
+    /// Whether each project's window was open at the last tick.
+    last_active: HashMap<String, bool>,
+    /// Actions due since the last tick run on this one. Nothing runs on the first tick,
+    /// so a restart of gfc doesn't catch up on actions it missed while down.
+    last_tick: Option<NaiveDateTime>,
+}
+
+impl<C, G> Scheduler<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    pub fn new(project_usecase: ProjectUsecase<C, G>) -> Self {
+        Self {
+            project_usecase,
+            last_active: HashMap::new(),
+            last_tick: None,
+        }
+    }
+
+    /// Check schedules once a minute on a dedicated thread. Must be called from within a
+    /// Tokio runtime, which scheduled syncs are submitted to.
+    pub fn spawn(mut self) -> thread::JoinHandle<()> {
+        let runtime = tokio::runtime::Handle::current();
+        thread::spawn(move || {
+            let _runtime = runtime.enter();
+            blocking::allow(|| loop {
+                self.tick();
+                thread::sleep(SCHEDULE_INTERVAL);
+            })
+        })
+    }
+
+    pub fn tick(&mut self) {
+        let now = Local::now().naive_local();
+        let last_tick = self.last_tick.replace(now);
+        let project_files = match self.project_usecase.project_files() {
+            Ok(project_files) => project_files,
+            Err(e) => {
+                println!("Failed to read project schedules: {}", e);
+                return;
+            }
+        };
+
+        for project_file in project_files {
+            if let Some(last_tick) = last_tick {
+                self.run_due_actions(&project_file, last_tick, now);
+            }
+            self.apply_window(project_file, now);
+        }
+    }
+
+    fn apply_window(&mut self, project_file: ProjectFile, now: NaiveDateTime) {
+        let Some(schedule) = &project_file.schedule else {
+            self.last_active.remove(&project_file.name);
+            return;
+        };
+        let active = match schedule.is_active_at(now) {
+            Ok(active) => active,
+            Err(e) => {
+                println!("Invalid schedule for {}: {}", project_file.name, e);
+                return;
+            }
+        };
+        if self.last_active.get(&project_file.name) == Some(&active) {
+            return;
+        }
+
+        match self
+            .project_usecase
+            .apply_schedule(&project_file.name, active)
+        {
+            Ok(()) => {
+                self.last_active.insert(project_file.name, active);
+            }
+            Err(e) => println!("Failed to apply schedule for {}: {}", project_file.name, e),
+        }
+    }
+
+    fn run_due_actions(
+        &self,
+        project_file: &ProjectFile,
+        after: NaiveDateTime,
+        now: NaiveDateTime,
+    ) {
+        for action in &project_file.scheduled_actions {
+            match action.schedule() {
+                Ok(schedule) if schedule.fires_between(after, now) => {}
+                Ok(_) => continue,
+                Err(e) => {
+                    println!("Invalid scheduled action for {}: {}", project_file.name, e);
+                    continue;
+                }
+            }
+            if let Err(e) = self
+                .project_usecase
+                .run_scheduled_action(&project_file.name, action.action)
+            {
+                println!(
+                    "Failed to run scheduled {:?} of {}: {}",
+                    action.action, project_file.name, e
+                );
+            }
+        }
+    }
+}
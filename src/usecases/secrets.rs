@@ -0,0 +1,236 @@
+use anyhow::Result;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::models::compose_file::ComposeFile;
+use crate::models::git::{GitSource, SshKey};
+use crate::repositories::secret_store::{create_private_dir, write_private_file, SecretStore};
+
+const OVERRIDE_FILE: &str = "secrets.override.yml";
+/// Unsealed deploy keys, apart from the project directories `clean` removes.
+const KEYS_DIR: &str = ".keys";
+
+/// Hands the secrets a compose file asks of gfc to `compose up`. Each one is written to
+/// a file in the project's runtime directory, and an override file points the compose
+/// secret at it. The files only exist while the project is up.
+#[derive(Debug, Clone)]
+pub struct ProjectSecrets {
+    store: SecretStore,
+    runtime_dir: PathBuf,
+}
+
+impl ProjectSecrets {
+    pub fn new<P: AsRef<Path>>(store: SecretStore, runtime_dir: P) -> Self {
+        Self {
+            store,
+            runtime_dir: runtime_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn store(&self) -> &SecretStore {
+        &self.store
+    }
+
+    /// Where files the project needs while it is up are written, removed by `clean`.
+    pub fn runtime_dir(&self, project_name: &str) -> PathBuf {
+        self.runtime_dir.join(project_name)
+    }
+
+    /// Write out the store-backed secrets of `compose_file` and return the override file
+    /// to pass to compose, or `None` when it has none. Fails if a secret is not stored.
+    pub fn materialize(
+        &self,
+        project_name: &str,
+        compose_file: &ComposeFile,
+    ) -> Result<Option<PathBuf>> {
+        let names = store_backed_secrets(compose_file);
+        self.clean(project_name)?;
+        if names.is_empty() {
+            return Ok(None);
+        }
+
+        let dir = self.runtime_dir.join(project_name);
+        let secrets_dir = dir.join("secrets");
+        create_private_dir(&secrets_dir)?;
+
+        let mut secrets = Mapping::new();
+        for name in names {
+            let value = self.store.get(project_name, &name)?;
+            let path = fs::canonicalize(&secrets_dir)?.join(&name);
+            write_private_file(&path, &value)?;
+
+            let mut definition = Mapping::new();
+            definition.insert(Value::from("file"), Value::from(path.display().to_string()));
+            secrets.insert(Value::from(name), Value::Mapping(definition));
+        }
+
+        let mut document = Mapping::new();
+        document.insert(Value::from("secrets"), Value::Mapping(secrets));
+        let override_path = dir.join(OVERRIDE_FILE);
+        fs::write(&override_path, serde_yaml::to_string(&document)?)?;
+        Ok(Some(fs::canonicalize(override_path)?))
+    }
+
+    /// The override file written by the last `materialize`, if the project is up with
+    /// store-backed secrets.
+    pub fn override_file(&self, project_name: &str) -> Option<PathBuf> {
+        let path = self.runtime_dir.join(project_name).join(OVERRIDE_FILE);
+        fs::canonicalize(path).ok()
+    }
+
+    /// `source` as the git client gets it: a deploy key stored as a project secret is
+    /// replaced by the absolute path of the stored file, or of an unsealed copy in the
+    /// runtime directory when the store is sealed. git runs in other directories, so the
+    /// path must not be relative.
+    pub fn resolve_source(&self, project_name: &str, source: &GitSource) -> Result<GitSource> {
+        let ssh_key = match &source.ssh_key {
+            Some(SshKey::Secret(name)) if self.store.is_sealed() => {
+                let dir = self.runtime_dir.join(KEYS_DIR).join(project_name);
+                create_private_dir(&dir)?;
+                let path = std::path::absolute(dir.join(name))?;
+                write_private_file(&path, &self.store.get(project_name, name)?)?;
+                Some(SshKey::Path(path.display().to_string()))
+            }
+            Some(SshKey::Secret(name)) => {
+                let path = std::path::absolute(self.store.path_for(project_name, name)?)?;
+                Some(SshKey::Path(path.display().to_string()))
+            }
+            ssh_key => ssh_key.clone(),
+        };
+        Ok(GitSource {
+            ssh_key,
+            ..source.clone()
+        })
+    }
+
+    /// Remove the project's materialized secrets, e.g. once its containers are stopped.
+    pub fn clean(&self, project_name: &str) -> Result<()> {
+        let dir = self.runtime_dir.join(project_name);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Remove everything kept for a deleted project: materialized secrets, unsealed
+    /// deploy keys and the stored values.
+    pub fn remove(&self, project_name: &str) -> Result<()> {
+        self.clean(project_name)?;
+        let keys_dir = self.runtime_dir.join(KEYS_DIR).join(project_name);
+        if keys_dir.exists() {
+            fs::remove_dir_all(keys_dir)?;
+        }
+        self.store.remove_all(project_name)
+    }
+}
+
+/// Top-level secrets that declare neither a `file` nor an `environment` source, which
+/// gfc fills in from its store.
+pub fn store_backed_secrets(compose_file: &ComposeFile) -> Vec<String> {
+    compose_file
+        .document()
+        .get("secrets")
+        .and_then(Value::as_mapping)
+        .into_iter()
+        .flatten()
+        .filter(|(_, definition)| {
+            definition.get("file").is_none() && definition.get("environment").is_none()
+        })
+        .filter_map(|(name, _)| name.as_str().map(str::to_string))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::credentials::CredentialCipher;
+    use tempfile::TempDir;
+
+    const COMPOSE: &str = "services:\n  db:\n    image: postgres\n    secrets: [db_password, tls_cert]\nsecrets:\n  db_password: {}\n  tls_cert:\n    file: ./cert.pem\n";
+
+    #[test]
+    fn given_secret_without_source_when_store_backed_secrets_then_return_only_that_one() {
+        let compose_file = ComposeFile::parse(COMPOSE).unwrap();
+
+        let actual = store_backed_secrets(&compose_file);
+
+        assert_eq!(actual, vec!["db_password"]);
+    }
+
+    #[test]
+    fn given_stored_secret_when_materialize_then_override_points_at_secret_file() {
+        let root = TempDir::new().unwrap();
+        let store = SecretStore::new(root.path().join("secrets"));
+        store.put("demo", "db_password", b"hunter2").unwrap();
+        let secrets = ProjectSecrets::new(store, root.path().join("runtime"));
+        let compose_file = ComposeFile::parse(COMPOSE).unwrap();
+
+        let override_path = secrets.materialize("demo", &compose_file).unwrap().unwrap();
+        let override_file = ComposeFile::from_path(&override_path).unwrap();
+        let secret_path = override_file.document()["secrets"]["db_password"]["file"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        assert_eq!(fs::read(&secret_path).unwrap(), b"hunter2");
+        secrets.clean("demo").unwrap();
+        assert!(!Path::new(&secret_path).exists());
+    }
+
+    #[test]
+    fn given_missing_secret_when_materialize_then_return_error() {
+        let root = TempDir::new().unwrap();
+        let secrets = ProjectSecrets::new(
+            SecretStore::new(root.path().join("secrets")),
+            root.path().join("runtime"),
+        );
+        let compose_file = ComposeFile::parse(COMPOSE).unwrap();
+
+        assert!(secrets.materialize("demo", &compose_file).is_err());
+    }
+
+    #[test]
+    fn given_ssh_key_secret_when_resolve_source_then_key_points_at_stored_file() {
+        let root = TempDir::new().unwrap();
+        let store = SecretStore::new(root.path().join("secrets"));
+        store.put("demo", "deploy_key", b"-----BEGIN").unwrap();
+        let secrets = ProjectSecrets::new(store, root.path().join("runtime"));
+        let source = GitSource {
+            url: "git@example.com:demo.git".to_string(),
+            ssh_key: Some(SshKey::Secret("deploy_key".to_string())),
+            ..Default::default()
+        };
+
+        let actual = secrets.resolve_source("demo", &source).unwrap();
+
+        let Some(SshKey::Path(path)) = actual.ssh_key else {
+            panic!("expected a key path");
+        };
+        assert_eq!(fs::read(path).unwrap(), b"-----BEGIN");
+    }
+
+    #[test]
+    fn given_sealed_store_when_resolve_source_then_key_points_at_unsealed_copy() {
+        let root = TempDir::new().unwrap();
+        let store = SecretStore::new(root.path().join("secrets"))
+            .with_cipher(Some(CredentialCipher::new([7; 32])));
+        store.put("demo", "deploy_key", b"-----BEGIN").unwrap();
+        let secrets = ProjectSecrets::new(store, root.path().join("runtime"));
+        let source = GitSource {
+            ssh_key: Some(SshKey::Secret("deploy_key".to_string())),
+            ..Default::default()
+        };
+
+        let actual = secrets.resolve_source("demo", &source).unwrap();
+
+        let Some(SshKey::Path(path)) = actual.ssh_key else {
+            panic!("expected a key path");
+        };
+        assert_eq!(fs::read(path).unwrap(), b"-----BEGIN");
+        assert_ne!(
+            fs::read(root.path().join("secrets/demo/deploy_key")).unwrap(),
+            b"-----BEGIN"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::{anyhow, Result};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::models::compose_file::ComposeFile;
+use crate::models::network::SharedService;
+
+/// The network services of different projects meet on. gfc creates it outside of any
+/// compose project, so pruning the networks of removed projects never touches it.
+pub const SHARED_NETWORK: &str = "gfc-shared";
+
+const OVERRIDE_FILE: &str = "shared-network.override.yml";
+
+/// Write the override file attaching `shared` services of `compose_file` to the shared
+/// network with their aliases into the project's runtime directory `dir`, and return it.
+/// Must run after secrets are materialized, which clears that directory.
+pub fn materialize(
+    dir: &Path,
+    project_name: &str,
+    compose_file: &ComposeFile,
+    shared: &[SharedService],
+) -> Result<PathBuf> {
+    let mut services = Mapping::new();
+    for shared_service in shared {
+        let service = compose_file
+            .service(&shared_service.service)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Service {} to share is not in the compose file",
+                    shared_service.service
+                )
+            })?;
+        if service.get("network_mode").is_some() {
+            return Err(anyhow!(
+                "Service {} sets network_mode, so it cannot join {}",
+                shared_service.service,
+                SHARED_NETWORK
+            ));
+        }
+
+        let aliases = match shared_service.aliases.is_empty() {
+            true => vec![format!("{}-{}", project_name, shared_service.service)],
+            false => shared_service.aliases.clone(),
+        };
+        let mut attachment = Mapping::new();
+        attachment.insert(
+            Value::from("aliases"),
+            Value::Sequence(aliases.into_iter().map(Value::from).collect()),
+        );
+        let mut networks = Mapping::new();
+        networks.insert(Value::from(SHARED_NETWORK), Value::Mapping(attachment));
+        // Listing a network drops the implicit `default`, which the service must keep.
+        if service.get("networks").is_none() {
+            networks.insert(Value::from("default"), Value::Mapping(Mapping::new()));
+        }
+        let mut definition = Mapping::new();
+        definition.insert(Value::from("networks"), Value::Mapping(networks));
+        services.insert(
+            Value::from(shared_service.service.as_str()),
+            Value::Mapping(definition),
+        );
+    }
+
+    let mut network = Mapping::new();
+    network.insert(Value::from("name"), Value::from(SHARED_NETWORK));
+    network.insert(Value::from("external"), Value::from(true));
+    let mut networks = Mapping::new();
+    networks.insert(Value::from(SHARED_NETWORK), Value::Mapping(network));
+    let mut document = Mapping::new();
+    document.insert(Value::from("networks"), Value::Mapping(networks));
+    document.insert(Value::from("services"), Value::Mapping(services));
+
+    fs::create_dir_all(dir)?;
+    let override_path = dir.join(OVERRIDE_FILE);
+    fs::write(&override_path, serde_yaml::to_string(&document)?)?;
+    Ok(fs::canonicalize(override_path)?)
+}
+
+/// The override file written by the last `materialize` into `dir`, while the project is
+/// up.
+pub fn override_file(dir: &Path) -> Option<PathBuf> {
+    fs::canonicalize(dir.join(OVERRIDE_FILE)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared(service: &str, aliases: &[&str]) -> SharedService {
+        SharedService {
+            service: service.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn given_shared_services_when_materialized_then_attach_them_with_aliases() {
+        let root = tempfile::TempDir::new().unwrap();
+        let compose_file = ComposeFile::parse(
+            "services:\n  api:\n    image: api\n  db:\n    image: postgres\n    networks: [backend]\nnetworks:\n  backend: {}\n",
+        )
+        .unwrap();
+
+        let path = materialize(
+            root.path(),
+            "shop",
+            &compose_file,
+            &[shared("api", &[]), shared("db", &["shop-db", "orders-db"])],
+        )
+        .unwrap();
+
+        let actual: Value = serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(
+            actual["networks"][SHARED_NETWORK]["external"],
+            Value::from(true)
+        );
+        let api = &actual["services"]["api"]["networks"];
+        assert_eq!(api[SHARED_NETWORK]["aliases"][0], Value::from("shop-api"));
+        assert!(api.get("default").is_some());
+        let db = &actual["services"]["db"]["networks"];
+        assert_eq!(db[SHARED_NETWORK]["aliases"][1], Value::from("orders-db"));
+        assert!(db.get("default").is_none());
+    }
+
+    #[test]
+    fn given_unknown_service_when_materialized_then_fail() {
+        let root = tempfile::TempDir::new().unwrap();
+        let compose_file = ComposeFile::parse("services:\n  api:\n    image: api\n").unwrap();
+
+        let actual = materialize(root.path(), "shop", &compose_file, &[shared("web", &[])]);
+
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::repositories::git::GitClient;
+use crate::usecases::system::directory_size;
+
+/// Checkouts of previously deployed revisions, kept as git worktrees of the project's
+/// repository so rolling back to one of them needs no fetch, even from a shallow clone.
+#[derive(Debug, Clone)]
+pub struct StandbyCheckouts {
+    root: PathBuf,
+    retained: usize,
+}
+
+impl StandbyCheckouts {
+    /// Standby checkouts live under `<repositories_dir>/.revisions/<project>`. Project names
+    /// cannot start with a dot, so this never collides with a project's own repository.
+    pub fn new(repositories_dir: &Path, project_name: &str, retained: usize) -> Self {
+        Self {
+            root: repositories_dir.join(".revisions").join(project_name),
+            retained,
+        }
+    }
+
+    pub fn path_for(&self, revision: &str) -> PathBuf {
+        self.root.join(revision)
+    }
+
+    /// The checkout of `revision`, if it is on standby.
+    pub fn get(&self, revision: &str) -> Option<PathBuf> {
+        Some(self.path_for(revision)).filter(|path| path.is_dir())
+    }
+
+    /// Bytes the checkouts take up together.
+    pub fn disk_usage(&self) -> Result<u64> {
+        Ok(directory_size(&self.root)?)
+    }
+
+    /// Drop every checkout, e.g. once the project is deleted.
+    pub fn remove_all(&self) -> Result<()> {
+        if self.root.exists() {
+            fs::remove_dir_all(&self.root)?;
+        }
+        Ok(())
+    }
+
+    /// Revisions on standby, most recently kept first.
+    pub fn list(&self) -> Result<Vec<String>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+            .collect())
+    }
+
+    /// Keep `revision` of `repository_dir` on standby and drop the oldest checkouts beyond
+    /// the retention limit.
+    pub fn keep<G: GitClient>(
+        &self,
+        git_client: &G,
+        repository_dir: &Path,
+        revision: &str,
+    ) -> Result<()> {
+        if self.retained == 0 {
+            return Ok(());
+        }
+
+        let target = self.path_for(revision);
+        if !target.exists() {
+            fs::create_dir_all(&self.root)?;
+            git_client.add_worktree(repository_dir, revision, &target)?;
+        }
+
+        for expired in self.entries()?.into_iter().skip(self.retained) {
+            git_client.remove_worktree(repository_dir, &expired)?;
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<PathBuf>> {
+        if !self.root.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut entries = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+                Some((modified, entry.path()))
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(modified, _)| Reverse(*modified));
+
+        Ok(entries.into_iter().map(|(_, path)| path).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::git::GitClientImpl;
+    use std::process::Command;
+
+    fn commit(dir: &Path, message: &str) -> String {
+        let git = |args: &[&str]| {
+            let output = Command::new("git")
+                .current_dir(dir)
+                .args(["-c", "user.name=gfc", "-c", "user.email=gfc@example.com"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        if !dir.join(".git").exists() {
+            git(&["init", "--quiet", "--initial-branch", "main"]);
+        }
+        git(&["commit", "--quiet", "--allow-empty", "--message", message]);
+        git(&["rev-parse", "HEAD"])
+    }
+
+    #[test]
+    fn given_more_revisions_than_retained_when_keep_then_drop_the_oldest() {
+        let root = tempfile::TempDir::new().unwrap();
+        let repository_dir = root.path().join("web");
+        fs::create_dir_all(&repository_dir).unwrap();
+        let revisions =
+            ["first", "second", "third"].map(|message| commit(&repository_dir, message));
+        let standby = StandbyCheckouts::new(root.path(), "web", 2);
+        let client = GitClientImpl::default();
+
+        for revision in &revisions {
+            standby.keep(&client, &repository_dir, revision).unwrap();
+        }
+
+        assert_eq!(
+            standby.list().unwrap(),
+            [revisions[2].clone(), revisions[1].clone()]
+        );
+        assert_eq!(standby.get(&revisions[0]), None);
+        assert_eq!(
+            standby.get(&revisions[1]),
+            Some(root.path().join(".revisions/web").join(&revisions[1]))
+        );
+    }
+
+    #[test]
+    fn given_no_retained_revisions_when_keep_then_check_nothing_out() {
+        let root = tempfile::TempDir::new().unwrap();
+        let repository_dir = root.path().join("web");
+        fs::create_dir_all(&repository_dir).unwrap();
+        let revision = commit(&repository_dir, "first");
+        let standby = StandbyCheckouts::new(root.path(), "web", 0);
+
+        standby
+            .keep(&GitClientImpl::default(), &repository_dir, &revision)
+            .unwrap();
+
+        assert!(standby.list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Result};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::config::AddressPool;
+use crate::models::compose_file::ComposeFile;
+use crate::models::network::Subnet;
+
+/// Kept next to the project file, so allocations go away with the project.
+const ALLOCATIONS_FILE: &str = "subnets.lock";
+const OVERRIDE_FILE: &str = "networks.override.yml";
+
+/// Gives the networks compose creates for a project subnets from the configured address
+/// pools. A network keeps its subnet across deployments, and no two networks share one.
+/// Like materialized secrets, the override file assigning them lives in the project's
+/// runtime directory.
+#[derive(Debug, Clone)]
+pub struct ProjectSubnets {
+    pools: Vec<AddressPool>,
+    projects_dir: PathBuf,
+    runtime_dir: PathBuf,
+    /// Projects directories of other workspaces on the same Docker host, whose subnets
+    /// are taken as well.
+    neighbours: Vec<PathBuf>,
+    /// Held while allocating, so concurrent deployments don't pick the same subnet.
+    allocating: Arc<Mutex<()>>,
+}
+
+impl ProjectSubnets {
+    pub fn new<P: AsRef<Path>>(pools: Vec<AddressPool>, projects_dir: P, runtime_dir: P) -> Self {
+        Self {
+            pools,
+            projects_dir: projects_dir.as_ref().to_path_buf(),
+            runtime_dir: runtime_dir.as_ref().to_path_buf(),
+            neighbours: vec![],
+            allocating: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn with_pools(self, pools: Vec<AddressPool>) -> Self {
+        Self { pools, ..self }
+    }
+
+    /// Also keep clear of the subnets of projects in `neighbours`, allocating under a lock
+    /// shared with them.
+    pub fn with_neighbours(self, neighbours: Vec<PathBuf>, allocating: Arc<Mutex<()>>) -> Self {
+        Self {
+            neighbours,
+            allocating,
+            ..self
+        }
+    }
+
+    /// Allocate subnets for the networks `compose_file` creates and return the override
+    /// file assigning them, or `None` when no pools are configured or it creates none.
+    /// Must run after secrets are materialized, which clears the runtime directory.
+    pub fn materialize(
+        &self,
+        project_name: &str,
+        compose_file: &ComposeFile,
+    ) -> Result<Option<PathBuf>> {
+        let networks = compose_file.created_networks();
+        if self.pools.is_empty() || networks.is_empty() {
+            return Ok(None);
+        }
+
+        let subnets = self.allocate(project_name, &networks)?;
+        let dir = self.runtime_dir.join(project_name);
+        fs::create_dir_all(&dir)?;
+        let override_path = dir.join(OVERRIDE_FILE);
+        fs::write(
+            &override_path,
+            serde_yaml::to_string(&network_override(&subnets))?,
+        )?;
+        Ok(Some(fs::canonicalize(override_path)?))
+    }
+
+    /// The override file written by the last `materialize`, while the project is up.
+    pub fn override_file(&self, project_name: &str) -> Option<PathBuf> {
+        let path = self.runtime_dir.join(project_name).join(OVERRIDE_FILE);
+        fs::canonicalize(path).ok()
+    }
+
+    fn allocate(
+        &self,
+        project_name: &str,
+        networks: &[String],
+    ) -> Result<BTreeMap<String, Subnet>> {
+        let _allocating = self.allocating.lock().unwrap_or_else(|e| e.into_inner());
+        let mut allocations = self.allocations(project_name)?;
+        let mut taken = self.taken_subnets()?;
+
+        for network in networks {
+            if allocations.contains_key(network) {
+                continue;
+            }
+            let subnet = self
+                .pools
+                .iter()
+                .flat_map(|pool| pool.base.split(pool.size))
+                .find(|candidate| !taken.iter().any(|subnet| subnet.overlaps(candidate)))
+                .ok_or_else(|| anyhow!("No free subnet left for network {}", network))?;
+            taken.push(subnet);
+            allocations.insert(network.clone(), subnet);
+        }
+
+        let path = self.projects_dir.join(project_name).join(ALLOCATIONS_FILE);
+        fs::create_dir_all(self.projects_dir.join(project_name))?;
+        fs::write(path, serde_yaml::to_string(&allocations)?)?;
+
+        Ok(networks
+            .iter()
+            .filter_map(|network| Some((network.clone(), *allocations.get(network)?)))
+            .collect())
+    }
+
+    fn allocations(&self, project_name: &str) -> Result<BTreeMap<String, Subnet>> {
+        allocations_in(&self.projects_dir, project_name)
+    }
+
+    /// Subnets allocated to any project, in this workspace or a neighbouring one.
+    fn taken_subnets(&self) -> Result<Vec<Subnet>> {
+        let mut taken = Vec::new();
+        for projects_dir in std::iter::once(&self.projects_dir).chain(&self.neighbours) {
+            if !projects_dir.exists() {
+                continue;
+            }
+            for entry in fs::read_dir(projects_dir)? {
+                let project_name = entry?.file_name().to_string_lossy().to_string();
+                taken.extend(allocations_in(projects_dir, &project_name)?.into_values());
+            }
+        }
+        Ok(taken)
+    }
+}
+
+fn allocations_in(projects_dir: &Path, project_name: &str) -> Result<BTreeMap<String, Subnet>> {
+    let path = projects_dir.join(project_name).join(ALLOCATIONS_FILE);
+    match path.exists() {
+        true => Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?),
+        false => Ok(BTreeMap::new()),
+    }
+}
+
+fn network_override(subnets: &BTreeMap<String, Subnet>) -> Mapping {
+    let networks = subnets
+        .iter()
+        .map(|(name, subnet)| {
+            let mut config = Mapping::new();
+            config.insert(Value::from("subnet"), Value::from(subnet.to_string()));
+            let mut ipam = Mapping::new();
+            ipam.insert(
+                Value::from("config"),
+                Value::Sequence(vec![Value::Mapping(config)]),
+            );
+            let mut network = Mapping::new();
+            network.insert(Value::from("ipam"), Value::Mapping(ipam));
+            (Value::from(name.as_str()), Value::Mapping(network))
+        })
+        .collect::<Mapping>();
+
+    let mut document = Mapping::new();
+    document.insert(Value::from("networks"), Value::Mapping(networks));
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const COMPOSE: &str = "services:\n  web:\n    image: nginx\n";
+
+    fn subnets(root: &TempDir, pools: &[(&str, u8)]) -> ProjectSubnets {
+        let pools = pools
+            .iter()
+            .map(|(base, size)| AddressPool {
+                base: base.parse().unwrap(),
+                size: *size,
+            })
+            .collect();
+        ProjectSubnets::new(
+            pools,
+            root.path().join("projects"),
+            root.path().join("runtime"),
+        )
+    }
+
+    fn materialized_subnet(subnets: &ProjectSubnets, project_name: &str) -> Result<String> {
+        let compose_file = ComposeFile::parse(COMPOSE).unwrap();
+        let override_path = subnets.materialize(project_name, &compose_file)?.unwrap();
+        let override_file = ComposeFile::from_path(override_path).unwrap();
+        Ok(
+            override_file.document()["networks"]["default"]["ipam"]["config"][0]["subnet"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+        )
+    }
+
+    #[test]
+    fn given_two_projects_when_materialize_then_each_keeps_its_own_subnet() {
+        let root = TempDir::new().unwrap();
+        let subnets = subnets(&root, &[("10.200.0.0/23", 24)]);
+
+        assert_eq!(
+            materialized_subnet(&subnets, "web").unwrap(),
+            "10.200.0.0/24"
+        );
+        assert_eq!(
+            materialized_subnet(&subnets, "api").unwrap(),
+            "10.200.1.0/24"
+        );
+        assert_eq!(
+            materialized_subnet(&subnets, "web").unwrap(),
+            "10.200.0.0/24"
+        );
+        assert!(materialized_subnet(&subnets, "db").is_err());
+    }
+
+    #[test]
+    fn given_no_pools_when_materialize_then_return_none() {
+        let root = TempDir::new().unwrap();
+        let compose_file = ComposeFile::parse(COMPOSE).unwrap();
+
+        let actual = subnets(&root, &[])
+            .materialize("web", &compose_file)
+            .unwrap();
+
+        assert_eq!(actual, None);
+    }
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Total size of the files under `path`. Symlinks are counted as links, not followed, so
+/// checkouts that point elsewhere are not counted twice. A missing directory is empty.
+pub fn directory_size(path: &Path) -> io::Result<u64> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    fs::read_dir(path)?.try_fold(
+        0,
+        |total, entry| Ok(total + directory_size(&entry?.path())?),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn given_nested_files_when_directory_size_then_return_total_bytes() {
+        let root = TempDir::new().unwrap();
+        fs::create_dir(root.path().join("nested")).unwrap();
+        fs::write(root.path().join("a.txt"), "12345").unwrap();
+        fs::write(root.path().join("nested/b.txt"), "123").unwrap();
+
+        let actual = directory_size(root.path()).unwrap();
+
+        assert_eq!(actual, 8);
+    }
+
+    #[test]
+    fn given_missing_directory_when_directory_size_then_return_zero() {
+        let root = TempDir::new().unwrap();
+
+        let actual = directory_size(&root.path().join("missing")).unwrap();
+
+        assert_eq!(actual, 0);
+    }
+}
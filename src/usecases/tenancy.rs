@@ -0,0 +1,179 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+use crate::config::TenancyConfig;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::ProjectUsecase;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TenancyError {
+    #[error("A bearer token is required")]
+    MissingToken,
+    #[error("Token is not valid")]
+    InvalidToken,
+    #[error("Only the operator can do this")]
+    OperatorOnly,
+}
+
+/// Who a request acts for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// The main workspace and host-wide endpoints. Every request is the operator's while
+    /// no tenants are configured.
+    Operator,
+    Tenant(String),
+}
+
+/// Tells workspaces apart wherever they share something keyed by name, e.g. `tenant/team-a`.
+impl fmt::Display for Caller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Caller::Operator => write!(f, "operator"),
+            Caller::Tenant(name) => write!(f, "tenant/{}", name),
+        }
+    }
+}
+
+/// Tells callers apart by their API tokens. Tokens are only kept hashed, and compared as
+/// hashes, so how long a comparison takes says nothing about the tokens themselves.
+#[derive(Debug, Clone, Default)]
+pub struct Tenancy {
+    enabled: bool,
+    operator: Option<[u8; 32]>,
+    tenants: Vec<([u8; 32], String)>,
+}
+
+impl Tenancy {
+    /// Tokens that cannot be resolved reach nothing; [`TenancyConfig::check`] reports them.
+    pub fn new(config: &TenancyConfig) -> Self {
+        Self {
+            enabled: config.is_enabled(),
+            operator: config.operator_token().ok().map(|token| hash(&token)),
+            tenants: config
+                .tenants
+                .iter()
+                .filter_map(|tenant| Some((hash(&tenant.token().ok()?), tenant.name.clone())))
+                .collect(),
+        }
+    }
+
+    pub fn authenticate(&self, token: Option<&str>) -> Result<Caller, TenancyError> {
+        if !self.enabled {
+            return Ok(Caller::Operator);
+        }
+        let token = hash(token.ok_or(TenancyError::MissingToken)?);
+        if self.operator == Some(token) {
+            return Ok(Caller::Operator);
+        }
+        self.tenants
+            .iter()
+            .find(|(tenant_token, _)| *tenant_token == token)
+            .map(|(_, name)| Caller::Tenant(name.clone()))
+            .ok_or(TenancyError::InvalidToken)
+    }
+}
+
+fn hash(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// The main workspace and one per tenant, each with projects, jobs and secrets of its own.
+#[derive(Debug, Clone)]
+pub struct Workspaces<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    main: ProjectUsecase<C, G>,
+    tenants: HashMap<String, ProjectUsecase<C, G>>,
+}
+
+impl<C, G> Workspaces<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+    ProjectUsecase<C, G>: Clone,
+{
+    pub fn new(main: ProjectUsecase<C, G>, tenants: HashMap<String, ProjectUsecase<C, G>>) -> Self {
+        Self { main, tenants }
+    }
+
+    pub fn main(&self) -> &ProjectUsecase<C, G> {
+        &self.main
+    }
+
+    /// The workspace `caller` acts on.
+    pub fn of(&self, caller: &Caller) -> Result<ProjectUsecase<C, G>, TenancyError> {
+        match caller {
+            Caller::Operator => Ok(self.main.clone()),
+            Caller::Tenant(name) => self
+                .tenants
+                .get(name)
+                .cloned()
+                .ok_or(TenancyError::InvalidToken),
+        }
+    }
+
+    /// Every workspace, the main one first, for background work such as reconciling.
+    pub fn all(&self) -> impl Iterator<Item = &ProjectUsecase<C, G>> {
+        std::iter::once(&self.main).chain(self.tenants.values())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TenantConfig;
+
+    fn tenancy_config() -> TenancyConfig {
+        TenancyConfig {
+            operator_token: Some("operator-token".to_string()),
+            operator_token_env: None,
+            tenants: vec![TenantConfig {
+                name: "team-a".to_string(),
+                projects_dir: "/tmp/team-a/projects".to_string(),
+                repositories_dir: "/tmp/team-a/repositories".to_string(),
+                token: Some("team-a-token".to_string()),
+                token_env: None,
+                webhooks: Default::default(),
+            }],
+        }
+    }
+
+    #[test]
+    fn given_no_tenants_when_authenticated_without_token_then_caller_is_operator() {
+        let tenancy = Tenancy::new(&TenancyConfig::default());
+
+        assert_eq!(tenancy.authenticate(None), Ok(Caller::Operator));
+    }
+
+    #[test]
+    fn given_tenants_when_authenticated_then_token_decides_caller() {
+        let tenancy = Tenancy::new(&tenancy_config());
+
+        assert_eq!(
+            tenancy.authenticate(Some("team-a-token")),
+            Ok(Caller::Tenant("team-a".to_string()))
+        );
+        assert_eq!(
+            tenancy.authenticate(Some("operator-token")),
+            Ok(Caller::Operator)
+        );
+        assert_eq!(
+            tenancy.authenticate(Some("guess")),
+            Err(TenancyError::InvalidToken)
+        );
+        assert_eq!(tenancy.authenticate(None), Err(TenancyError::MissingToken));
+    }
+
+    #[test]
+    fn given_tenant_without_token_when_checked_then_fail() {
+        let mut config = tenancy_config();
+        config.tenants[0].token = None;
+
+        assert!(config.check().is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::models::usage::UsageStats;
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::repositories::usage_reporter::UsageReporter;
+use crate::usecases::project::ProjectUsecase;
+
+/// Sends an anonymous [`UsageStats`] report through `reporter` on startup and then once
+/// per interval. Only started when `usage_stats.enabled` is set; a report that fails to
+/// send is dropped.
+pub struct UsageStatsReporter<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    project_usecase: ProjectUsecase<C, G>,
+    reporter: Box<dyn UsageReporter>,
+    backend: &'static str,
+    interval: Duration,
+}
+
+impl<C, G> UsageStatsReporter<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    pub fn new(
+        project_usecase: ProjectUsecase<C, G>,
+        reporter: Box<dyn UsageReporter>,
+        backend: &'static str,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            project_usecase,
+            reporter,
+            backend,
+            interval,
+        }
+    }
+
+    /// Report on a dedicated thread.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || loop {
+            self.tick();
+            thread::sleep(self.interval);
+        })
+    }
+
+    pub fn tick(&self) {
+        let projects = match self.project_usecase.project_files() {
+            Ok(project_files) => project_files.len(),
+            Err(e) => {
+                println!("Failed to count projects for the usage report: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = self
+            .reporter
+            .report(&UsageStats::new(projects, self.backend))
+        {
+            println!("Failed to send usage report: {}", e);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+use crate::models::compose_file::{ComposeFile, ComposeFileError};
+use crate::models::git::SshKey;
+use crate::models::project::ProjectFile;
+use crate::models::schedule::ScheduleError;
+use crate::models::selector::is_valid_label_key;
+use crate::repositories::docker_compose_client::find_compose_file_name;
+use crate::repositories::secret_store::validate_secret_name;
+use crate::repositories::sops::decrypted_path;
+use crate::usecases::environment::{is_valid_variable_name, DOTENV_FILE};
+
+#[derive(Debug, Error)]
+pub enum ValidationError {
+    #[error("Invalid project name '{0}': use lowercase letters, digits, '-' or '_', starting with a letter or digit")]
+    InvalidProjectName(String),
+    #[error("Source url must not be empty")]
+    EmptySourceUrl,
+    #[error("Source branch must not be empty")]
+    EmptySourceBranch,
+    #[error("Invalid tag pattern '{0}'")]
+    InvalidTagPattern(String),
+    #[error("Invalid deploy key secret name '{0}'")]
+    InvalidSshKeySecret(String),
+    #[error("Source path must be relative and stay inside the repository: {0}")]
+    InvalidSourcePath(String),
+    #[error("Invalid environment variable '{0}': names use letters, digits or '_', and values must fit on one line")]
+    InvalidEnvironmentVariable(String),
+    #[error("env_file must name a file other than the .env gfc writes: {0}")]
+    InvalidEnvFile(String),
+    #[error("sops file must have 'enc' in its name, e.g. prod.enc.env: {0}")]
+    InvalidSopsFile(String),
+    #[error("Compose file not found at {0}")]
+    ComposeFileNotFound(String),
+    #[error("Invalid compose file: {0}")]
+    InvalidComposeFile(#[from] ComposeFileError),
+    #[error("Compose file does not define any services")]
+    NoServices,
+    #[error("Service '{0}' has neither an image nor a build section")]
+    ServiceWithoutImage(String),
+    #[error("Invalid schedule: {0}")]
+    InvalidSchedule(#[from] ScheduleError),
+    #[error("Invalid label '{0}': keys must not be empty or contain whitespace, ',', '=' or '!'")]
+    InvalidLabel(String),
+}
+
+/// Checks a project file before anything is written to disk. The project name becomes a
+/// directory name and the compose project name, so it follows compose's naming rules.
+pub fn validate_create_project_params(project_file: &ProjectFile) -> Result<(), ValidationError> {
+    validate_project_name(&project_file.name)?;
+
+    let source = &project_file.source;
+    // Nothing is cloned for inline projects, so only the compose file path matters.
+    if !project_file.inline {
+        if source.url.trim().is_empty() {
+            return Err(ValidationError::EmptySourceUrl);
+        }
+        match &source.tag_pattern {
+            Some(pattern) if glob::Pattern::new(pattern).is_err() => {
+                return Err(ValidationError::InvalidTagPattern(pattern.clone()));
+            }
+            Some(_) => {}
+            None if source.branch.trim().is_empty() => {
+                return Err(ValidationError::EmptySourceBranch);
+            }
+            None => {}
+        }
+        if let Some(SshKey::Secret(name)) = &source.ssh_key {
+            validate_secret_name(name)
+                .map_err(|_| ValidationError::InvalidSshKeySecret(name.clone()))?;
+        }
+    }
+    validate_source_path(&source.path)?;
+    for path in &source.extra_paths {
+        validate_source_path(path)?;
+    }
+    for path in &project_file.watch_paths {
+        validate_source_path(path)?;
+    }
+    for path in &project_file.sops_files {
+        validate_source_path(path)?;
+        if decrypted_path(path).is_none() {
+            return Err(ValidationError::InvalidSopsFile(path.clone()));
+        }
+    }
+    validate_environment(project_file)?;
+    if let Some(key) = project_file
+        .labels
+        .keys()
+        .find(|key| !is_valid_label_key(key))
+    {
+        return Err(ValidationError::InvalidLabel(key.clone()));
+    }
+    if let Some(schedule) = &project_file.schedule {
+        schedule.validate()?;
+    }
+    for action in &project_file.scheduled_actions {
+        action.schedule()?;
+    }
+
+    Ok(())
+}
+
+pub fn validate_compose_file(compose_file: &ComposeFile) -> Result<(), ValidationError> {
+    let services = compose_file.service_names();
+    if services.is_empty() {
+        return Err(ValidationError::NoServices);
+    }
+
+    services
+        .into_iter()
+        .find(|name| {
+            compose_file
+                .service(name)
+                .map(|service| service.get("image").is_none() && service.get("build").is_none())
+                .unwrap_or(true)
+        })
+        .map_or(Ok(()), |name| {
+            Err(ValidationError::ServiceWithoutImage(name))
+        })
+}
+
+/// Resolve the compose file for `source_path` inside `repository_dir`. The path may point
+/// at the file itself or at a directory containing one of the supported compose file names.
+pub fn resolve_compose_file(
+    repository_dir: &Path,
+    source_path: &str,
+) -> Result<PathBuf, ValidationError> {
+    let candidate = repository_dir.join(source_path);
+    if candidate.is_file() {
+        return Ok(candidate);
+    }
+
+    find_compose_file_name(&candidate)
+        .map(|name| candidate.join(name))
+        .map_err(|_| ValidationError::ComposeFileNotFound(candidate.display().to_string()))
+}
+
+fn validate_project_name(name: &str) -> Result<(), ValidationError> {
+    let mut chars = name.chars();
+    let valid_first = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    let valid_rest =
+        chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+
+    match valid_first && valid_rest {
+        true => Ok(()),
+        false => Err(ValidationError::InvalidProjectName(name.to_string())),
+    }
+}
+
+fn validate_environment(project_file: &ProjectFile) -> Result<(), ValidationError> {
+    if let Some((name, _)) = project_file
+        .environment
+        .iter()
+        .find(|(name, value)| !is_valid_variable_name(name) || value.contains(['\n', '\r']))
+    {
+        return Err(ValidationError::InvalidEnvironmentVariable(name.clone()));
+    }
+    if let Some(path) = &project_file.env_file {
+        validate_source_path(path)?;
+        let is_dotenv = Path::new(path)
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .eq([Component::Normal(DOTENV_FILE.as_ref())]);
+        if is_dotenv {
+            return Err(ValidationError::InvalidEnvFile(path.clone()));
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_source_path(path: &str) -> Result<(), ValidationError> {
+    let escapes_repository = Path::new(path)
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+
+    match escapes_repository {
+        true => Err(ValidationError::InvalidSourcePath(path.to_string())),
+        false => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::git::GitSource;
+
+    fn make_project_file(name: &str, path: &str) -> ProjectFile {
+        ProjectFile {
+            name: name.to_string(),
+            source: GitSource {
+                url: "https://github.com/fpiyapol/gfc.git".to_string(),
+                branch: "main".to_string(),
+                path: path.to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn given_valid_project_file_when_validate_then_return_ok() {
+        let project_file = make_project_file("my-app_1", "deploy/docker-compose.yml");
+
+        let actual = validate_create_project_params(&project_file);
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn given_uppercase_or_traversing_name_when_validate_then_return_invalid_project_name() {
+        for name in ["MyApp", "../etc", "", "-app"] {
+            let project_file = make_project_file(name, "docker-compose.yml");
+
+            let actual = validate_create_project_params(&project_file);
+
+            assert!(matches!(
+                actual,
+                Err(ValidationError::InvalidProjectName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn given_inline_project_without_remote_when_validate_then_return_ok() {
+        let project_file = ProjectFile::inline("uploaded");
+
+        let actual = validate_create_project_params(&project_file);
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn given_path_outside_repository_when_validate_then_return_invalid_source_path() {
+        for path in ["../docker-compose.yml", "/etc/compose.yml"] {
+            let project_file = make_project_file("app", path);
+
+            let actual = validate_create_project_params(&project_file);
+
+            assert!(matches!(actual, Err(ValidationError::InvalidSourcePath(_))));
+        }
+    }
+
+    #[test]
+    fn given_invalid_environment_when_validate_then_return_error() {
+        let mut project_file = make_project_file("demo", "docker-compose.yml");
+        project_file
+            .environment
+            .insert("DB-HOST".to_string(), "db".to_string());
+
+        let actual = validate_create_project_params(&project_file);
+
+        assert!(matches!(
+            actual,
+            Err(ValidationError::InvalidEnvironmentVariable(name)) if name == "DB-HOST"
+        ));
+
+        let project_file = ProjectFile {
+            env_file: Some("./.env".to_string()),
+            ..make_project_file("demo", "docker-compose.yml")
+        };
+
+        let actual = validate_create_project_params(&project_file);
+
+        assert!(matches!(actual, Err(ValidationError::InvalidEnvFile(_))));
+    }
+
+    #[test]
+    fn given_service_without_image_when_validate_compose_file_then_return_error() {
+        let compose_file =
+            ComposeFile::parse("services:\n  web:\n    image: nginx\n  job:\n    command: run\n")
+                .unwrap();
+
+        let actual = validate_compose_file(&compose_file);
+
+        assert!(matches!(
+            actual,
+            Err(ValidationError::ServiceWithoutImage(name)) if name == "job"
+        ));
+    }
+
+    #[test]
+    fn given_compose_file_without_services_when_validate_compose_file_then_return_no_services() {
+        let compose_file = ComposeFile::parse("x-common:\n  restart: always\n").unwrap();
+
+        let actual = validate_compose_file(&compose_file);
+
+        assert!(matches!(actual, Err(ValidationError::NoServices)));
+    }
+}
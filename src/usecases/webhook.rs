@@ -0,0 +1,704 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use thiserror::Error;
+
+use crate::config::{WebhookRule, WebhooksConfig};
+use crate::models::deployment::DeploymentTrigger;
+use crate::models::preview::preview_name;
+use crate::models::project::ProjectFile;
+use crate::models::webhook::{
+    GiteaPullRequestEvent, GiteaPushEvent, GithubPullRequestEvent, GithubPushEvent,
+    GitlabMergeRequestEvent, GitlabPushEvent, PullRequestAction, PullRequestEvent, PushEvent,
+    WebhookMatch,
+};
+use crate::repositories::compose_client::ComposeClient;
+use crate::repositories::git::GitClient;
+use crate::usecases::project::{ProjectUsecase, ProjectUsecaseError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("Webhook is not configured: {0}")]
+    NotConfigured(String),
+    #[error("Missing signature header")]
+    MissingSignature,
+    #[error("Token does not match")]
+    InvalidToken,
+    #[error("Signature does not match payload")]
+    InvalidSignature,
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+    #[error("Unknown forge '{0}': use github, gitlab or gitea")]
+    UnknownForge(String),
+    #[error(transparent)]
+    Project(#[from] ProjectUsecaseError),
+    #[error("Webhook handling was interrupted: {0}")]
+    Interrupted(#[from] tokio::task::JoinError),
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookUsecase<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    pub project_usecase: ProjectUsecase<C, G>,
+    pub webhooks_config: WebhooksConfig,
+}
+
+impl<C, G> WebhookUsecase<C, G>
+where
+    C: ComposeClient + Send + Sync,
+    G: GitClient + Send + Sync,
+{
+    pub fn new(project_usecase: ProjectUsecase<C, G>, webhooks_config: WebhooksConfig) -> Self {
+        Self {
+            project_usecase,
+            webhooks_config,
+        }
+    }
+
+    /// Verify a GitHub delivery and sync every project tracking the pushed branch, or
+    /// preview a pull request. Returns the names of the synced projects; events other
+    /// than `push` and `pull_request` sync nothing.
+    pub fn handle_github(
+        &self,
+        event: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.verify_github(signature, body)?;
+
+        match event {
+            "push" => {
+                let push_event: GithubPushEvent = serde_json::from_slice(body)?;
+                self.sync_matching_projects(&push_event.into())
+            }
+            "pull_request" => {
+                let pull_request: GithubPullRequestEvent = serde_json::from_slice(body)?;
+                self.preview_pull_request(&pull_request.into())
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Verify a GitLab delivery and sync every project tracking the pushed branch or tag,
+    /// or preview a merge request.
+    pub fn handle_gitlab(
+        &self,
+        token: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.verify_gitlab(token)?;
+
+        let event: serde_json::Value = serde_json::from_slice(body)?;
+        match event["object_kind"].as_str() {
+            Some("push" | "tag_push") => {
+                let push_event: GitlabPushEvent = serde_json::from_value(event)?;
+                self.sync_matching_projects(&push_event.into())
+            }
+            Some("merge_request") => {
+                let merge_request: GitlabMergeRequestEvent = serde_json::from_value(event)?;
+                self.preview_pull_request(&merge_request.into())
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Verify a Gitea or Forgejo delivery and sync every project tracking the pushed branch,
+    /// or preview a pull request.
+    pub fn handle_gitea(
+        &self,
+        event: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.verify_gitea(signature, body)?;
+
+        match event {
+            "push" => {
+                let push_event: GiteaPushEvent = serde_json::from_slice(body)?;
+                self.sync_matching_projects(&push_event.into())
+            }
+            "pull_request" => {
+                let pull_request: GiteaPullRequestEvent = serde_json::from_slice(body)?;
+                self.preview_pull_request(&pull_request.into())
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Verify a signed delivery for a single project against the secret in its manifest.
+    /// The payload itself is not interpreted.
+    pub fn handle_generic(
+        &self,
+        project_name: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.verify_generic(project_name, signature, body)?;
+
+        self.project_usecase
+            .deploy_new_commits(project_name, DeploymentTrigger::Webhook)?;
+        Ok(vec![project_name.to_string()])
+    }
+
+    /// Check a GitHub delivery against this workspace's secret.
+    pub fn verify_github(&self, signature: Option<&str>, body: &[u8]) -> Result<(), WebhookError> {
+        let github = self
+            .webhooks_config
+            .github
+            .as_ref()
+            .ok_or_else(|| WebhookError::NotConfigured("github".to_string()))?;
+        let signature = signature.ok_or(WebhookError::MissingSignature)?;
+        verify_github_signature(&github.secret, body, signature)
+    }
+
+    /// Check a GitLab delivery's token against this workspace's secret.
+    pub fn verify_gitlab(&self, token: Option<&str>) -> Result<(), WebhookError> {
+        let gitlab = self
+            .webhooks_config
+            .gitlab
+            .as_ref()
+            .ok_or_else(|| WebhookError::NotConfigured("gitlab".to_string()))?;
+        let token = token.ok_or(WebhookError::MissingSignature)?;
+        match constant_time_eq(gitlab.secret.as_bytes(), token.as_bytes()) {
+            true => Ok(()),
+            false => Err(WebhookError::InvalidToken),
+        }
+    }
+
+    /// Check a Gitea or Forgejo delivery against this workspace's secret.
+    pub fn verify_gitea(&self, signature: Option<&str>, body: &[u8]) -> Result<(), WebhookError> {
+        let gitea = self
+            .webhooks_config
+            .gitea
+            .as_ref()
+            .ok_or_else(|| WebhookError::NotConfigured("gitea".to_string()))?;
+        let signature = signature.ok_or(WebhookError::MissingSignature)?;
+        verify_hmac_sha256(&gitea.secret, body, signature)
+    }
+
+    /// Check a generic delivery against the secret of the project of this workspace it
+    /// is for. A project that doesn't exist, or has no webhook, fails like a bad
+    /// signature, so unsigned requests can't tell which projects there are.
+    pub fn verify_generic(
+        &self,
+        project_name: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<(), WebhookError> {
+        let signature = signature.ok_or(WebhookError::MissingSignature)?;
+        let project_file = match self.project_usecase.find_project_file(project_name) {
+            Ok(project_file) => project_file,
+            Err(ProjectUsecaseError::ProjectNotFound(_)) => {
+                return Err(WebhookError::InvalidSignature)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let webhook = project_file
+            .webhook
+            .as_ref()
+            .ok_or(WebhookError::InvalidSignature)?;
+        verify_github_signature(&webhook.secret, body, signature)
+    }
+
+    /// The projects a push payload from `forge` would sync, without verifying or syncing
+    /// anything, to try out `rules`.
+    pub fn dry_run(&self, forge: &str, body: &[u8]) -> Result<Vec<WebhookMatch>, WebhookError> {
+        let push_event: PushEvent = match forge {
+            "github" => serde_json::from_slice::<GithubPushEvent>(body)?.into(),
+            "gitea" => serde_json::from_slice::<GiteaPushEvent>(body)?.into(),
+            "gitlab" => serde_json::from_slice::<GitlabPushEvent>(body)?.into(),
+            _ => return Err(WebhookError::UnknownForge(forge.to_string())),
+        };
+        self.matching_projects(&push_event)
+    }
+
+    /// Sync the projects tracking the pushed ref, skipping those whose watched paths the
+    /// push left untouched, e.g. the other projects of a monorepo.
+    fn sync_matching_projects(&self, push_event: &PushEvent) -> Result<Vec<String>, WebhookError> {
+        self.matching_projects(push_event)?
+            .into_iter()
+            .filter(|matched| self.is_changed_by(&matched.project, push_event))
+            .map(|matched| {
+                self.project_usecase
+                    .deploy_new_commits(&matched.project, DeploymentTrigger::Webhook)?;
+                Ok::<_, WebhookError>(matched.project)
+            })
+            .collect()
+    }
+
+    /// Whether the push changed anything the project watches. When that can't be told,
+    /// e.g. for a push that created the branch, the project counts as changed.
+    fn is_changed_by(&self, project_name: &str, push_event: &PushEvent) -> bool {
+        let Some(commits) = &push_event.commits else {
+            return true;
+        };
+        let Ok(project_file) = self.project_usecase.find_project_file(project_name) else {
+            return true;
+        };
+        match self.project_usecase.is_changed_between(
+            &project_file,
+            &commits.before,
+            &commits.after,
+        ) {
+            Ok(true) => true,
+            Ok(false) => {
+                println!(
+                    "Skipping {}: the push changed none of its watched paths",
+                    project_name
+                );
+                false
+            }
+            Err(e) => {
+                println!(
+                    "Failed to find the paths the push changed, syncing {} anyway: {}",
+                    project_name, e
+                );
+                true
+            }
+        }
+    }
+
+    /// Create, sync or delete the previews of the pull request for every project with
+    /// previews that tracks the branch it targets. Returns the names of the previews.
+    /// Pull requests from a fork that is gone can't be cloned, so they are only closed.
+    fn preview_pull_request(
+        &self,
+        pull_request: &PullRequestEvent,
+    ) -> Result<Vec<String>, WebhookError> {
+        let mut previews = Vec::new();
+        for project_file in self.previewed_projects(pull_request)? {
+            let name = &project_file.name;
+            let previewed = match (pull_request.action, &pull_request.head_url) {
+                (PullRequestAction::Opened | PullRequestAction::Updated, Some(head_url)) => {
+                    self.project_usecase.preview_pull_request(
+                        &project_file,
+                        pull_request.number,
+                        head_url,
+                        &pull_request.head_branch,
+                    )?;
+                    true
+                }
+                (PullRequestAction::Closed, _) => self
+                    .project_usecase
+                    .close_preview(name, pull_request.number)?
+                    .is_some(),
+                _ => false,
+            };
+            if previewed {
+                previews.push(preview_name(name, pull_request.number));
+            }
+        }
+        Ok(previews)
+    }
+
+    /// Projects with previews whose repository and branch the pull request targets.
+    fn previewed_projects(
+        &self,
+        pull_request: &PullRequestEvent,
+    ) -> Result<Vec<ProjectFile>, WebhookError> {
+        Ok(self
+            .project_usecase
+            .project_files()?
+            .into_iter()
+            .filter(|project_file| {
+                project_file.previews.is_some()
+                    && project_file.preview.is_none()
+                    && !project_file.inline
+                    && project_file.deletion.is_none()
+                    && project_file.source.branch == pull_request.base_branch
+                    && pull_request.repository_urls.iter().any(|url| {
+                        normalize_repository_url(url)
+                            == normalize_repository_url(&project_file.source.url)
+                    })
+            })
+            .collect())
+    }
+
+    /// Projects being deleted are left out, so they don't fail the delivery for the others.
+    /// Previews follow their pull request's events instead of pushes to its branch.
+    fn matching_projects(&self, push_event: &PushEvent) -> Result<Vec<WebhookMatch>, WebhookError> {
+        if push_event.deleted {
+            return Ok(vec![]);
+        }
+        let project_files = self.project_usecase.project_files()?;
+
+        Ok(project_files
+            .iter()
+            .filter(|project_file| {
+                !project_file.inline
+                    && project_file.deletion.is_none()
+                    && project_file.preview.is_none()
+            })
+            .filter_map(|project_file| {
+                let matched_by = match matches_push_event(project_file, push_event) {
+                    true => "source".to_string(),
+                    false => matching_rule(&self.webhooks_config.rules, project_file, push_event)?
+                        .repository
+                        .clone(),
+                };
+                Some(WebhookMatch {
+                    project: project_file.name.clone(),
+                    matched_by,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Webhooks of the main workspace and of every tenant's. A forge delivery goes to the
+/// workspace whose secret it is signed with, so each tenant needs secrets of its own, and
+/// a generic one to the workspace whose project of that name it is signed for.
+#[derive(Debug, Clone)]
+pub struct WebhookWorkspaces<C, G>
+where
+    C: ComposeClient + Send + Sync + 'static,
+    G: GitClient + Send + Sync + 'static,
+{
+    main: WebhookUsecase<C, G>,
+    tenants: Vec<WebhookUsecase<C, G>>,
+}
+
+impl<C, G> WebhookWorkspaces<C, G>
+where
+    C: ComposeClient + Send + Sync,
+    G: GitClient + Send + Sync,
+{
+    pub fn new(main: WebhookUsecase<C, G>, tenants: Vec<WebhookUsecase<C, G>>) -> Self {
+        Self { main, tenants }
+    }
+
+    pub fn main(&self) -> &WebhookUsecase<C, G> {
+        &self.main
+    }
+
+    pub fn handle_github(
+        &self,
+        event: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.resolve(|workspace| workspace.verify_github(signature, body))?
+            .handle_github(event, signature, body)
+    }
+
+    pub fn handle_gitlab(
+        &self,
+        token: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.resolve(|workspace| workspace.verify_gitlab(token))?
+            .handle_gitlab(token, body)
+    }
+
+    pub fn handle_gitea(
+        &self,
+        event: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.resolve(|workspace| workspace.verify_gitea(signature, body))?
+            .handle_gitea(event, signature, body)
+    }
+
+    pub fn handle_generic(
+        &self,
+        project_name: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<Vec<String>, WebhookError> {
+        self.resolve(|workspace| workspace.verify_generic(project_name, signature, body))?
+            .handle_generic(project_name, signature, body)
+    }
+
+    /// The first workspace, the main one first, the delivery verifies against. When none
+    /// does, the error of the workspace that got furthest: a bad signature over a missing
+    /// project, and that over a webhook that isn't configured.
+    fn resolve(
+        &self,
+        verify: impl Fn(&WebhookUsecase<C, G>) -> Result<(), WebhookError>,
+    ) -> Result<&WebhookUsecase<C, G>, WebhookError> {
+        let progress = |error: &WebhookError| match error {
+            WebhookError::NotConfigured(_) => 0,
+            WebhookError::Project(_) => 1,
+            _ => 2,
+        };
+        let mut rejected: Option<WebhookError> = None;
+        for workspace in std::iter::once(&self.main).chain(&self.tenants) {
+            match verify(workspace) {
+                Ok(()) => return Ok(workspace),
+                Err(e)
+                    if rejected
+                        .as_ref()
+                        .is_some_and(|r| progress(r) >= progress(&e)) => {}
+                Err(e) => rejected = Some(e),
+            }
+        }
+        Err(rejected.expect("the main workspace is always tried"))
+    }
+}
+
+/// Check an `X-Hub-Signature-256` style header (`sha256=<hex hmac of the body>`).
+pub fn verify_github_signature(
+    secret: &str,
+    body: &[u8],
+    signature: &str,
+) -> Result<(), WebhookError> {
+    let digest = signature
+        .strip_prefix("sha256=")
+        .ok_or(WebhookError::InvalidSignature)?;
+    verify_hmac_sha256(secret, body, digest)
+}
+
+/// Check a hex encoded HMAC-SHA256 of the body, as sent by Gitea and Forgejo.
+pub fn verify_hmac_sha256(secret: &str, body: &[u8], digest: &str) -> Result<(), WebhookError> {
+    let digest = hex::decode(digest).map_err(|_| WebhookError::InvalidSignature)?;
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .map_err(|_| WebhookError::InvalidSignature)?;
+    mac.update(body);
+    mac.verify_slice(&digest)
+        .map_err(|_| WebhookError::InvalidSignature)
+}
+
+/// Compare secrets without leaking how many leading bytes matched through timing.
+fn constant_time_eq(expected: &[u8], actual: &[u8]) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .iter()
+            .zip(actual)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// A project's `branch` is passed to `git clone --branch`, so it may name a tag as well.
+/// Projects with a `tag_pattern` match pushes of the tags it selects.
+fn matches_push_event(project_file: &ProjectFile, push_event: &PushEvent) -> bool {
+    let source = &project_file.source;
+    let same_branch = match (&source.tag_pattern, push_event.ref_name.as_deref()) {
+        (Some(_), Some(ref_name)) => source.matches_tag(ref_name),
+        (None, ref_name) => ref_name == Some(source.branch.as_str()),
+        (Some(_), None) => false,
+    };
+    let same_repository = push_event
+        .repository_urls
+        .iter()
+        .any(|url| normalize_repository_url(url) == normalize_repository_url(&source.url));
+
+    !project_file.inline && same_branch && same_repository
+}
+
+/// The first rule that sends `push_event` to the project. Invalid patterns match nothing.
+fn matching_rule<'a>(
+    rules: &'a [WebhookRule],
+    project_file: &ProjectFile,
+    push_event: &PushEvent,
+) -> Option<&'a WebhookRule> {
+    let matches = |pattern: &str, value: &str| {
+        glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(value))
+    };
+
+    rules.iter().find(|rule| {
+        rule.projects.contains(&project_file.name)
+            && push_event
+                .repository_urls
+                .iter()
+                .any(|url| matches(&rule.repository, url))
+            && match (&rule.branch, &push_event.ref_name) {
+                (None, _) => true,
+                (Some(branch), Some(ref_name)) => matches(branch, ref_name),
+                (Some(_), None) => false,
+            }
+    })
+}
+
+/// The host and path of a repository URL, so the HTTPS, SSH and web URLs of a repository
+/// compare equal: `https://user@GitHub.com/org/app.git`, `ssh://git@github.com:22/org/app`
+/// and `git@github.com:org/app.git` all become `github.com/org/app`. Forges treat paths
+/// case-insensitively, so the whole URL is lowercased.
+fn normalize_repository_url(url: &str) -> String {
+    let url = url.trim().to_lowercase();
+    let (authority, path) = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').unwrap_or((rest, "")),
+        // scp-like syntax, `git@host:path`.
+        None => url.split_once(':').unwrap_or((url.as_str(), "")),
+    };
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split_once(':').map_or(host, |(host, _)| host);
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    format!("{}/{}", host, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::git::GitSource;
+    use crate::models::webhook::ref_name;
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    fn make_project_file(url: &str, branch: &str) -> ProjectFile {
+        ProjectFile {
+            name: "app".to_string(),
+            source: GitSource {
+                url: url.to_string(),
+                branch: branch.to_string(),
+                path: "docker-compose.yml".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn given_signature_of_body_when_verify_then_return_ok() {
+        let body = br#"{"ref":"refs/heads/main"}"#;
+
+        let actual = verify_github_signature("secret", body, &sign("secret", body));
+
+        assert!(actual.is_ok());
+    }
+
+    #[test]
+    fn given_signature_with_other_secret_when_verify_then_return_invalid_signature() {
+        let body = br#"{"ref":"refs/heads/main"}"#;
+
+        let actual = verify_github_signature("secret", body, &sign("other", body));
+
+        assert!(matches!(actual, Err(WebhookError::InvalidSignature)));
+    }
+
+    #[test]
+    fn given_push_to_tracked_branch_when_match_then_return_true() {
+        let project_file = make_project_file("https://github.com/fpiyapol/gfc.git", "main");
+        let push_event = PushEvent {
+            repository_urls: vec!["https://github.com/fpiyapol/gfc".to_string()],
+            ref_name: Some("main".to_string()),
+            deleted: false,
+            commits: None,
+        };
+
+        assert!(matches_push_event(&project_file, &push_event));
+    }
+
+    #[test]
+    fn given_push_to_other_branch_when_match_then_return_false() {
+        let project_file = make_project_file("https://github.com/fpiyapol/gfc.git", "main");
+        let push_event = PushEvent {
+            repository_urls: vec!["https://github.com/fpiyapol/gfc.git".to_string()],
+            ref_name: Some("develop".to_string()),
+            deleted: false,
+            commits: None,
+        };
+
+        assert!(!matches_push_event(&project_file, &push_event));
+    }
+
+    #[test]
+    fn given_https_ssh_and_web_urls_of_repository_when_normalized_then_compare_equal() {
+        let urls = [
+            "https://github.com/fpiyapol/gfc.git",
+            "https://token@GitHub.com/fpiyapol/gfc/",
+            "ssh://git@github.com:22/fpiyapol/gfc.git",
+            "git@github.com:fpiyapol/gfc.git",
+            "http://github.com/FPiyapol/gfc",
+        ];
+
+        for url in urls {
+            assert_eq!(normalize_repository_url(url), "github.com/fpiyapol/gfc");
+        }
+        assert_ne!(
+            normalize_repository_url("https://github.com/fpiyapol/gfc-web.git"),
+            "github.com/fpiyapol/gfc"
+        );
+    }
+
+    #[test]
+    fn given_push_over_ssh_url_when_project_cloned_over_https_then_return_true() {
+        let project_file = make_project_file("https://github.com/fpiyapol/gfc.git", "main");
+        let push_event = PushEvent {
+            repository_urls: vec!["ssh://git@github.com/fpiyapol/gfc.git".to_string()],
+            ref_name: Some("main".to_string()),
+            deleted: false,
+            commits: None,
+        };
+
+        assert!(matches_push_event(&project_file, &push_event));
+    }
+
+    #[test]
+    fn given_gitlab_push_deleting_branch_when_parsed_then_mark_it_deleted() {
+        let body = br#"{"object_kind":"push","ref":"refs/heads/main","after":"0000000000000000000000000000000000000000","project":{"git_http_url":"https://gitlab.com/fpiyapol/gfc.git","git_ssh_url":"git@gitlab.com:fpiyapol/gfc.git","web_url":"https://gitlab.com/fpiyapol/gfc"}}"#;
+
+        let actual: PushEvent = serde_json::from_slice::<GitlabPushEvent>(body)
+            .unwrap()
+            .into();
+
+        assert!(actual.deleted);
+    }
+
+    #[test]
+    fn given_gitea_pull_request_from_fork_when_parsed_then_track_the_fork_branch() {
+        let body = br#"{"action":"synchronized","number":3,"pull_request":{"head":{"ref":"fix","repo":{"clone_url":"https://gitea.example.com/someone/gfc.git","ssh_url":"git@gitea.example.com:someone/gfc.git","html_url":"https://gitea.example.com/someone/gfc"}},"base":{"ref":"main","repo":null}},"repository":{"clone_url":"https://gitea.example.com/fpiyapol/gfc.git","ssh_url":"git@gitea.example.com:fpiyapol/gfc.git","html_url":"https://gitea.example.com/fpiyapol/gfc"}}"#;
+
+        let actual: PullRequestEvent = serde_json::from_slice::<GiteaPullRequestEvent>(body)
+            .unwrap()
+            .into();
+
+        assert_eq!(actual.action, PullRequestAction::Updated);
+        assert_eq!(actual.number, 3);
+        assert_eq!(actual.base_branch, "main");
+        assert_eq!(
+            actual.head_url.as_deref(),
+            Some("https://gitea.example.com/someone/gfc.git")
+        );
+        assert_eq!(actual.head_branch, "fix");
+    }
+
+    #[test]
+    fn given_tag_push_when_project_tracks_tag_then_return_true() {
+        let project_file = make_project_file("git@gitlab.com:fpiyapol/gfc.git", "v1.0.0");
+        let push_event = PushEvent {
+            repository_urls: vec!["git@gitlab.com:fpiyapol/gfc.git".to_string()],
+            ref_name: ref_name("refs/tags/v1.0.0"),
+            deleted: false,
+            commits: None,
+        };
+
+        assert!(matches_push_event(&project_file, &push_event));
+    }
+
+    #[test]
+    fn given_rule_for_mirror_when_matching_rule_then_return_it_for_listed_projects_only() {
+        let rules = vec![WebhookRule {
+            repository: "https://github.com/fpiyapol/*".to_string(),
+            branch: Some("release/*".to_string()),
+            projects: vec!["app".to_string()],
+        }];
+        let project_file = make_project_file("https://git.example.com/mirror/gfc.git", "main");
+        let push = |ref_name: &str| PushEvent {
+            repository_urls: vec!["https://github.com/fpiyapol/gfc.git".to_string()],
+            ref_name: Some(ref_name.to_string()),
+            deleted: false,
+            commits: None,
+        };
+        let other_project = ProjectFile {
+            name: "other".to_string(),
+            ..project_file.clone()
+        };
+
+        assert!(matching_rule(&rules, &project_file, &push("release/1.0")).is_some());
+        assert!(matching_rule(&rules, &project_file, &push("main")).is_none());
+        assert!(matching_rule(&rules, &other_project, &push("release/1.0")).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const MANIFEST: &str = "name: web\nsource:\n  url: https://github.com/fpiyapol/web.git\n  branch: main\n  path: docker-compose.yml\n";
+
+fn gfc_validate(file: &Path, args: &[&str]) -> Result<Output> {
+    Ok(Command::new(env!("CARGO_BIN_EXE_gfc"))
+        .arg("validate")
+        .arg("--file")
+        .arg(file)
+        .args(args)
+        .output()?)
+}
+
+fn stdout_json(output: &Output) -> Result<serde_json::Value> {
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+#[test]
+fn given_valid_manifest_and_compose_file_when_validated_then_exit_zero() -> Result<()> {
+    let root = TempDir::new()?;
+    let manifest = root.path().join("project.yaml");
+    fs::write(&manifest, MANIFEST)?;
+    fs::write(
+        root.path().join("docker-compose.yml"),
+        "services:\n  web:\n    image: nginx:1.27\n",
+    )?;
+
+    let output = gfc_validate(
+        &manifest,
+        &["--compose", root.path().to_str().unwrap(), "-o", "json"],
+    )?;
+
+    assert_eq!(output.status.code(), Some(0));
+    let report = stdout_json(&output)?;
+    assert_eq!(report["valid"], true);
+    assert_eq!(report["code"], serde_json::Value::Null);
+    assert!(report["compose_file"]
+        .as_str()
+        .unwrap()
+        .ends_with("docker-compose.yml"));
+    Ok(())
+}
+
+#[test]
+fn given_invalid_manifest_when_validated_then_exit_with_validation_code() -> Result<()> {
+    let root = TempDir::new()?;
+    let manifest = root.path().join("project.yaml");
+    fs::write(&manifest, MANIFEST.replace("name: web", "name: Not A Name"))?;
+
+    let output = gfc_validate(&manifest, &["-o", "json"])?;
+
+    assert_eq!(output.status.code(), Some(3));
+    let report = stdout_json(&output)?;
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["code"], "validation");
+    assert!(report["error"].is_string());
+    Ok(())
+}
+
+#[test]
+fn given_missing_manifest_when_validated_then_exit_with_io_code() -> Result<()> {
+    let root = TempDir::new()?;
+
+    let output = gfc_validate(&root.path().join("missing.yaml"), &["-o", "yaml"])?;
+
+    assert_eq!(output.status.code(), Some(8));
+    let report: serde_yaml::Value = serde_yaml::from_slice(&output.stdout)?;
+    assert_eq!(report["code"], "io");
+    Ok(())
+}
+
+#[test]
+fn given_missing_compose_file_when_validated_as_table_then_print_each_file() -> Result<()> {
+    let root = TempDir::new()?;
+    let manifest = root.path().join("project.yaml");
+    fs::write(&manifest, MANIFEST)?;
+
+    let passed = gfc_validate(&manifest, &[])?;
+    let failed = gfc_validate(&manifest, &["--compose", root.path().to_str().unwrap()])?;
+
+    assert_eq!(passed.status.code(), Some(0));
+    let table = String::from_utf8(passed.stdout)?;
+    let lines = table.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0].split_whitespace().collect::<Vec<_>>(),
+        ["FILE", "STATUS"]
+    );
+    assert!(lines[1].ends_with("valid"));
+    assert!(lines[2].starts_with('-') && lines[2].ends_with("skipped"));
+    assert_eq!(failed.status.code(), Some(3));
+    assert!(String::from_utf8(failed.stdout)?.contains("invalid ("));
+    Ok(())
+}
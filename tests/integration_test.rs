@@ -1,17 +1,23 @@
 use anyhow::Result;
 
+#[cfg(feature = "docker-api")]
+use gfc::models::container_client::CreateContainerConfig;
 use gfc::models::docker_compose::ContainerState;
 use gfc::repositories::compose_client::ComposeClient;
+#[cfg(feature = "docker-api")]
 use gfc::repositories::container_client::ContainerClient;
+#[cfg(feature = "docker-api")]
 use gfc::repositories::docker_client::DockerClient;
 use gfc::repositories::docker_compose_client::{DockerComposeClient, DockerComposeError};
 
+#[cfg(feature = "docker-api")]
 #[test]
 fn create_docker_client() {
     let docker_client = DockerClient::new();
     assert!(docker_client.is_ok());
 }
 
+#[cfg(feature = "docker-api")]
 #[tokio::test]
 async fn create_and_remove_container() -> Result<()> {
     let docker_client = DockerClient::new()?;
@@ -20,7 +26,13 @@ async fn create_and_remove_container() -> Result<()> {
     let image = "hello-world:latest";
 
     let created_image = docker_client.create_image(image).await;
-    let created_container = docker_client.create_container(name, image).await;
+    let created_container = docker_client
+        .create_container(&CreateContainerConfig {
+            name: name.to_string(),
+            image: image.to_string(),
+            ..Default::default()
+        })
+        .await;
     let removed_container = docker_client.remove_container(name).await;
 
     assert!(created_image.is_ok());
@@ -30,13 +42,13 @@ async fn create_and_remove_container() -> Result<()> {
     Ok(())
 }
 
-#[tokio::test]
-async fn docker_compose_up_and_down() -> Result<()> {
+#[test]
+fn docker_compose_up_and_down() -> Result<()> {
     let docker_compose_client = DockerComposeClient::new()?;
     let project = "resources/for-test-a";
 
-    let up_result = docker_compose_client.up(&project);
-    let status = docker_compose_client.list_containers(&project);
+    let up_result = docker_compose_client.up(project);
+    let status = docker_compose_client.list_containers(project);
 
     assert!(up_result.is_ok());
     assert!(status.is_ok());
@@ -45,29 +57,29 @@ async fn docker_compose_up_and_down() -> Result<()> {
         .iter()
         .all(|s| s.state == ContainerState::Running));
 
-    let down_result = docker_compose_client.down(&project);
+    let down_result = docker_compose_client.down(project);
     assert!(down_result.is_ok());
 
     Ok(())
 }
 
-#[tokio::test]
-async fn docker_compose_execute_error() -> Result<()> {
+#[test]
+fn docker_compose_execute_error() -> Result<()> {
     let docker_compose_client = DockerComposeClient::new()?;
     let project = "resources/non-exist-project";
 
-    let up_result = docker_compose_client.up(&project);
-    let status = docker_compose_client.list_containers(&project);
+    let up_result = docker_compose_client.up(project);
+    let status = docker_compose_client.list_containers(project);
 
-    assert!(match up_result {
-        Err(DockerComposeError::DockerComposeFileDoesNotExist) => true,
-        _ => false,
-    });
+    assert!(matches!(
+        up_result,
+        Err(DockerComposeError::DockerComposeFileDoesNotExist)
+    ));
 
-    assert!(match status {
-        Err(DockerComposeError::DockerComposeFileDoesNotExist) => true,
-        _ => false,
-    });
+    assert!(matches!(
+        status,
+        Err(DockerComposeError::DockerComposeFileDoesNotExist)
+    ));
 
     Ok(())
 }